@@ -0,0 +1,103 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use parking_lot::RwLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use tinyvector::database::{Database, EmbeddingRecord, IndexType};
+use tinyvector::hnsw::HnswConfig;
+use tinyvector::similarity::Distance;
+
+const DIMENSION: usize = 128;
+
+fn record(id: usize) -> EmbeddingRecord {
+    EmbeddingRecord {
+        id: id.to_string(),
+        embedding: (0..DIMENSION).map(|i| (i * id) as f32 % 7.).collect(),
+        ..Default::default()
+    }
+}
+
+/// Table `a` has a HNSW index so its writes hold the lock for a while, `b` is scanned.
+fn database() -> Database {
+    let db = Database::zero();
+    let index = IndexType::Hnsw(HnswConfig::default());
+    db.create_table_with_index("a".to_string(), DIMENSION, index).unwrap();
+    db.create_table("b".to_string(), DIMENSION).unwrap();
+    db.insert_records("b".to_string(), (0..10_000).map(record).collect())
+        .unwrap();
+    db
+}
+
+/// Upsert batches into table `a` until `stop` is set, `write` wraps every batch.
+fn spawn_writer<F>(stop: Arc<AtomicBool>, write: F) -> thread::JoinHandle<()>
+where
+    F: Fn(Vec<EmbeddingRecord>) + Send + 'static,
+{
+    thread::spawn(move || {
+        while !stop.load(Ordering::Relaxed) {
+            write((0..100).map(record).collect());
+        }
+    })
+}
+
+/// Latency of `op` on table `b`, idle and while table `a` takes writes, with the per-table
+/// locks and with the former single lock around the whole database. `exclusive` ops took that
+/// lock for writing.
+fn bench_during_writes<F>(c: &mut Criterion, name: &str, exclusive: bool, op: F)
+where
+    F: Fn(&Database),
+{
+    let mut group = c.benchmark_group(name);
+
+    let db = Arc::new(database());
+    group.bench_function("idle", |bench| bench.iter(|| op(&db)));
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let writer = {
+        let db = db.clone();
+        spawn_writer(stop.clone(), move |records| {
+            db.upsert_records("a".to_string(), records).unwrap();
+        })
+    };
+    group.bench_function("writes_to_a/table_lock", |bench| bench.iter(|| op(&db)));
+    stop.store(true, Ordering::Relaxed);
+    writer.join().unwrap();
+
+    // The former layout, one lock around the whole database.
+    let db = Arc::new(RwLock::new(database()));
+    let stop = Arc::new(AtomicBool::new(false));
+    let writer = {
+        let db = db.clone();
+        spawn_writer(stop.clone(), move |records| {
+            db.write().upsert_records("a".to_string(), records).unwrap();
+        })
+    };
+    group.bench_function("writes_to_a/database_lock", |bench| {
+        bench.iter(|| match exclusive {
+            true => op(&db.write()),
+            false => op(&db.read()),
+        })
+    });
+    stop.store(true, Ordering::Relaxed);
+    writer.join().unwrap();
+    group.finish();
+}
+
+/// Query latency on table `b` while table `a` takes writes.
+fn bench_query_during_writes(c: &mut Criterion) {
+    let query = record(42).embedding;
+    bench_during_writes(c, "query_b", false, |db| {
+        let hits = db.query_record("b".to_string(), black_box(&query), 10, Distance::Cosine);
+        black_box(hits.unwrap());
+    });
+}
+
+/// Upsert latency on table `b` while table `a` takes writes.
+fn bench_upsert_during_writes(c: &mut Criterion) {
+    bench_during_writes(c, "upsert_b", true, |db| {
+        db.upsert_records("b".to_string(), vec![record(42)]).unwrap();
+    });
+}
+
+criterion_group!(benches, bench_query_during_writes, bench_upsert_during_writes);
+criterion_main!(benches);
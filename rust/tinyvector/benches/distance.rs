@@ -0,0 +1,68 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use tinyvector::similarity::{cosine, cosine_from_dot, dot_product, euclidean, norm};
+
+/// The scalar folds the kernels replaced, kept as the baseline.
+mod baseline {
+    pub fn euclidean(a: &[f32], b: &[f32]) -> f32 {
+        a.iter()
+            .zip(b)
+            .fold(0.0, |acc, (x, y)| {
+                let diff = x - y;
+                acc + diff * diff
+            })
+            .sqrt()
+    }
+
+    pub fn dot_product(a: &[f32], b: &[f32]) -> f32 {
+        a.iter().zip(b).fold(0.0, |acc, (x, y)| acc + x * y)
+    }
+
+    pub fn cosine(a: &[f32], b: &[f32]) -> f32 {
+        let a = tinyvector::similarity::normalize(a);
+        let b = tinyvector::similarity::normalize(b);
+        a.iter().zip(b).fold(0.0, |acc, (x, y)| acc + x * y)
+    }
+}
+
+fn vector(seed: f32, dimension: usize) -> Vec<f32> {
+    (0..dimension).map(|i| (i as f32 * seed).sin()).collect()
+}
+
+fn bench_kernels(c: &mut Criterion) {
+    // OpenAI embeddings have 1536 dimensions.
+    for dimension in [128, 1536] {
+        let a = vector(0.37, dimension);
+        let b = vector(0.11, dimension);
+        let mut group = c.benchmark_group(format!("distance/{dimension}"));
+
+        group.bench_function(BenchmarkId::new("cosine", "baseline"), |bench| {
+            bench.iter(|| baseline::cosine(black_box(&a), black_box(&b)))
+        });
+        group.bench_function(BenchmarkId::new("cosine", "simd"), |bench| {
+            bench.iter(|| cosine(black_box(&a), black_box(&b)))
+        });
+        // What the table scan does, the norm of the record is cached at insert time.
+        let (norm_a, norm_b) = (norm(&a), norm(&b));
+        group.bench_function(BenchmarkId::new("cosine", "simd_cached_norms"), |bench| {
+            bench.iter(|| cosine_from_dot(dot_product(black_box(&a), black_box(&b)), norm_a, norm_b))
+        });
+
+        group.bench_function(BenchmarkId::new("dot_product", "baseline"), |bench| {
+            bench.iter(|| baseline::dot_product(black_box(&a), black_box(&b)))
+        });
+        group.bench_function(BenchmarkId::new("dot_product", "simd"), |bench| {
+            bench.iter(|| dot_product(black_box(&a), black_box(&b)))
+        });
+
+        group.bench_function(BenchmarkId::new("euclidean", "baseline"), |bench| {
+            bench.iter(|| baseline::euclidean(black_box(&a), black_box(&b)))
+        });
+        group.bench_function(BenchmarkId::new("euclidean", "simd"), |bench| {
+            bench.iter(|| euclidean(black_box(&a), black_box(&b)))
+        });
+        group.finish();
+    }
+}
+
+criterion_group!(benches, bench_kernels);
+criterion_main!(benches);
@@ -0,0 +1,439 @@
+//! Typed client of the tinyvector HTTP API, sharing the request and response types of the
+//! server through `tinyvector-types`.
+
+use reqwest::{Method, RequestBuilder, StatusCode, Url};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::time::Duration;
+use tinyvector_types::api::{ApiError, BaseHttpResponse};
+use tinyvector_types::database::{
+    CombinedRecord, EmbeddingRecord, HybridRecord, MemoryUsage, RecordPage, ScoredRecord,
+    TableContent, TableStats,
+};
+use tinyvector_types::dto::*;
+use tinyvector_types::embedding::EmbeddingBinding;
+use tinyvector_types::explain::QueryExplain;
+use tinyvector_types::system::{ReplicationStatus, SnapshotInfo};
+use tracing::debug;
+
+pub use tinyvector_types::database::DBError;
+pub use tinyvector_types::dto;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// The server rejected the request.
+    #[error(transparent)]
+    Db(#[from] DBError),
+    /// An error of the HTTP layer with no `DBError` counterpart, e.g. `route_not_found`.
+    #[error("{}: {}", .0.code, .0.message)]
+    Api(ApiError),
+    #[error("Request failed: {0}")]
+    Http(#[from] reqwest::Error),
+    /// A response that is not an API response, e.g. from a proxy.
+    #[error("Unexpected response {status}: {body}")]
+    Unexpected { status: StatusCode, body: String },
+    /// An import stopped by `source` after writing `imported` records, which stay in the table.
+    #[error("Import failed after {imported} records: {source}")]
+    Import { imported: usize, source: Box<Error> },
+}
+
+impl From<ApiError> for Error {
+    fn from(e: ApiError) -> Self {
+        match e.to_db_error() {
+            Some(e) => Error::Db(e),
+            None => Error::Api(e),
+        }
+    }
+}
+
+impl Error {
+    /// Whether sending the request again may succeed. Requests that may have been applied
+    /// are only retried when `idempotent`.
+    fn is_transient(&self, idempotent: bool) -> bool {
+        let status = match self {
+            Error::Http(e) if e.is_connect() => return true,
+            Error::Http(e) => return idempotent && (e.is_timeout() || e.is_request()),
+            Error::Db(_) => return false,
+            Error::Api(e) => e.status,
+            Error::Unexpected { status, .. } => *status,
+            Error::Import { .. } => return false,
+        };
+        match status {
+            StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE => true,
+            StatusCode::BAD_GATEWAY | StatusCode::GATEWAY_TIMEOUT => idempotent,
+            _ => false,
+        }
+    }
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+pub struct ClientBuilder {
+    base_url: String,
+    api_key: Option<String>,
+    timeout: Duration,
+    max_retries: u32,
+    retry_backoff: Duration,
+    pool_max_idle_per_host: usize,
+}
+
+impl ClientBuilder {
+    /// Sent as `Authorization: Bearer <key>`.
+    pub fn api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    /// Timeout of a single attempt.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Attempts after the first one, 0 disables retries.
+    pub fn max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Wait before the first retry, doubled for every following one.
+    pub fn retry_backoff(mut self, retry_backoff: Duration) -> Self {
+        self.retry_backoff = retry_backoff;
+        self
+    }
+
+    /// Idle connections kept open to the server.
+    pub fn pool_max_idle_per_host(mut self, max_idle: usize) -> Self {
+        self.pool_max_idle_per_host = max_idle;
+        self
+    }
+
+    pub fn build(self) -> Result<Client> {
+        let mut headers = reqwest::header::HeaderMap::new();
+        if let Some(api_key) = &self.api_key {
+            let value = format!("Bearer {}", api_key).parse().map_err(|_| {
+                DBError::InvalidInput("The API key is not a valid header".to_string())
+            })?;
+            headers.insert(reqwest::header::AUTHORIZATION, value);
+        }
+        let http = reqwest::Client::builder()
+            .default_headers(headers)
+            .timeout(self.timeout)
+            .pool_max_idle_per_host(self.pool_max_idle_per_host)
+            .build()?;
+        let base_url = Url::parse(&self.base_url)
+            .ok()
+            .filter(|url| !url.cannot_be_a_base())
+            .ok_or_else(|| DBError::InvalidInput(format!("Invalid url {}", self.base_url)))?;
+        Ok(Client {
+            http,
+            base_url,
+            max_retries: self.max_retries,
+            retry_backoff: self.retry_backoff,
+        })
+    }
+}
+
+/// Cheap to clone, the clones share their connection pool.
+#[derive(Debug, Clone)]
+pub struct Client {
+    http: reqwest::Client,
+    base_url: Url,
+    max_retries: u32,
+    retry_backoff: Duration,
+}
+
+impl Client {
+    /// A client of the server at `base_url`, e.g. `http://localhost:3000`.
+    pub fn builder(base_url: impl Into<String>) -> ClientBuilder {
+        ClientBuilder {
+            base_url: base_url.into(),
+            api_key: None,
+            timeout: Duration::from_secs(30),
+            max_retries: 3,
+            retry_backoff: Duration::from_millis(100),
+            pool_max_idle_per_host: usize::MAX,
+        }
+    }
+
+    pub fn new(base_url: impl Into<String>) -> Result<Self> {
+        Self::builder(base_url).build()
+    }
+
+    /// Url of the path made of `segments`, each of them percent-encoded.
+    fn url(&self, segments: &[&str]) -> Url {
+        let mut url = self.base_url.clone();
+        url.path_segments_mut()
+            .expect("checked by the builder")
+            .pop_if_empty()
+            .extend(segments);
+        url
+    }
+
+    /// Send the request built by `request`, again after transient failures.
+    async fn send<T, F>(&self, idempotent: bool, request: F) -> Result<T>
+    where
+        T: DeserializeOwned,
+        F: Fn(&reqwest::Client) -> RequestBuilder,
+    {
+        let mut attempt = 0;
+        loop {
+            match self.try_send(request(&self.http)).await {
+                Err(e) if attempt < self.max_retries && e.is_transient(idempotent) => {
+                    let backoff = self.retry_backoff * 2u32.saturating_pow(attempt);
+                    debug!("Retrying in {:?} after {}", backoff, e);
+                    tokio::time::sleep(backoff).await;
+                    attempt += 1;
+                }
+                res => return res,
+            }
+        }
+    }
+
+    async fn try_send<T: DeserializeOwned>(&self, request: RequestBuilder) -> Result<T> {
+        let response = request.send().await?;
+        let status = response.status();
+        let body = response.bytes().await?;
+        match serde_json::from_slice::<BaseHttpResponse<Result<T, ApiError>>>(&body) {
+            Ok(body) => body.into_result().map_err(|mut e| {
+                e.status = status;
+                e.into()
+            }),
+            Err(_) => Err(Error::Unexpected {
+                status,
+                body: String::from_utf8_lossy(&body).into_owned(),
+            }),
+        }
+    }
+
+    async fn json<B, T>(
+        &self,
+        method: Method,
+        segments: &[&str],
+        idempotent: bool,
+        body: &B,
+    ) -> Result<T>
+    where
+        B: Serialize,
+        T: DeserializeOwned,
+    {
+        let url = self.url(segments);
+        self.send(idempotent, |http| http.request(method.clone(), url.clone()).json(body))
+            .await
+    }
+
+    async fn get<T: DeserializeOwned>(&self, segments: &[&str]) -> Result<T> {
+        let url = self.url(segments);
+        self.send(true, |http| http.get(url.clone())).await
+    }
+
+    async fn get_with<Q, T>(&self, segments: &[&str], query: &Q) -> Result<T>
+    where
+        Q: Serialize,
+        T: DeserializeOwned,
+    {
+        let url = self.url(segments);
+        self.send(true, |http| http.get(url.clone()).query(query)).await
+    }
+
+    pub async fn create_table(&self, request: &CreateTableRequest) -> Result<()> {
+        self.json(Method::POST, &["db", "create_table"], false, request).await
+    }
+
+    pub async fn drop_table(&self, table_name: &str) -> Result<()> {
+        let url = self.url(&["db", "drop_table", table_name]);
+        self.send(false, |http| http.delete(url.clone())).await
+    }
+
+    /// Bind the table to an embedding model, `None` unbinds it.
+    pub async fn bind_embedding(
+        &self,
+        table_name: &str,
+        embedding: Option<EmbeddingBinding>,
+    ) -> Result<()> {
+        let segments = ["db", "tables", table_name, "embedding"];
+        let request = BindEmbeddingRequest { embedding };
+        self.json(Method::PUT, &segments, true, &request).await
+    }
+
+    pub async fn insert_record(&self, table_name: &str, record: EmbeddingRecord) -> Result<()> {
+        let request = InsertRecordRequest {
+            table_name: table_name.to_string(),
+            record,
+        };
+        self.json(Method::POST, &["db", "insert_record"], false, &request).await
+    }
+
+    pub async fn insert_records(
+        &self,
+        table_name: &str,
+        records: Vec<EmbeddingRecord>,
+    ) -> Result<()> {
+        let request = InsertRecordsRequest {
+            table_name: table_name.to_string(),
+            records,
+        };
+        self.json(Method::POST, &["db", "insert_records"], false, &request).await
+    }
+
+    pub async fn upsert_record(&self, table_name: &str, record: EmbeddingRecord) -> Result<()> {
+        let request = InsertRecordRequest {
+            table_name: table_name.to_string(),
+            record,
+        };
+        self.json(Method::POST, &["db", "upsert_record"], true, &request).await
+    }
+
+    pub async fn upsert_records(
+        &self,
+        table_name: &str,
+        records: Vec<EmbeddingRecord>,
+    ) -> Result<()> {
+        let request = InsertRecordsRequest {
+            table_name: table_name.to_string(),
+            records,
+        };
+        self.json(Method::POST, &["db", "upsert_records"], true, &request).await
+    }
+
+    /// Import a file in the format named by `request`, see `/db/import/:table_name`. A failure
+    /// after some batches were written is an `Error::Import`.
+    pub async fn import(
+        &self,
+        table_name: &str,
+        request: &ImportRequest,
+        data: Vec<u8>,
+    ) -> Result<ImportResponse> {
+        let url = self.url(&["db", "import", table_name]);
+        let response: ImportResponse = self
+            .send(request.upsert, |http| {
+                http.post(url.clone()).query(request).body(data.clone())
+            })
+            .await?;
+        match response.error {
+            Some(e) if response.imported > 0 => Err(Error::Import {
+                imported: response.imported,
+                source: Box::new(e.into()),
+            }),
+            Some(e) => Err(e.into()),
+            None => Ok(response),
+        }
+    }
+
+    pub async fn delete_record(&self, table_name: &str, id: &str) -> Result<()> {
+        let url = self.url(&["db", "delete_record", table_name, id]);
+        self.send(false, |http| http.delete(url.clone())).await
+    }
+
+    pub async fn query_record(&self, request: &QueryRecordRequest) -> Result<Vec<ScoredRecord>> {
+        Ok(self.explain_query(request).await?.0)
+    }
+
+    /// The hits along with the report of the query, `None` unless `request.explain` is set.
+    pub async fn explain_query(
+        &self,
+        request: &QueryRecordRequest,
+    ) -> Result<(Vec<ScoredRecord>, Option<QueryExplain>)> {
+        let response = self.json(Method::POST, &["db", "query_record"], true, request).await?;
+        Ok(match response {
+            QueryRecordResponse::Hits(hits) => (hits, None),
+            QueryRecordResponse::Explained { hits, explain } => (hits, Some(explain)),
+        })
+    }
+
+    pub async fn query_fields(
+        &self,
+        request: &QueryFieldsRequest,
+    ) -> Result<Vec<CombinedRecord>> {
+        self.json(Method::POST, &["db", "query_fields"], true, request).await
+    }
+
+    pub async fn hybrid_query(&self, request: &HybridQueryRequest) -> Result<Vec<HybridRecord>> {
+        self.json(Method::POST, &["db", "hybrid_query"], true, request).await
+    }
+
+    pub async fn list_tables(&self) -> Result<Vec<String>> {
+        self.get(&["db", "tables"]).await
+    }
+
+    /// Every record of the table.
+    pub async fn get_table(&self, table_name: &str) -> Result<TableContent> {
+        let query = QueryTableRequest {
+            table_name: table_name.to_string(),
+        };
+        self.get_with(&["db", "query_table"], &query).await
+    }
+
+    /// Every table along with its records, meant for debugging.
+    pub async fn get_entire_db(&self) -> Result<HashMap<String, TableContent>> {
+        self.get(&["db", "get_entire_db"]).await
+    }
+
+    pub async fn memory_usage(&self, table_name: &str) -> Result<MemoryUsage> {
+        let query = QueryTableRequest {
+            table_name: table_name.to_string(),
+        };
+        self.get_with(&["db", "memory_usage"], &query).await
+    }
+
+    pub async fn table_stats(&self, table_name: &str) -> Result<TableStats> {
+        self.get(&["db", "tables", table_name, "stats"]).await
+    }
+
+    pub async fn get_record(&self, table_name: &str, id: &str) -> Result<EmbeddingRecord> {
+        self.get(&["db", "tables", table_name, "records", id]).await
+    }
+
+    /// A page of records in id order, pass its `next_cursor` on to get the next one.
+    pub async fn list_records(
+        &self,
+        table_name: &str,
+        request: &ListRecordsRequest,
+    ) -> Result<RecordPage> {
+        self.get_with(&["db", "tables", table_name, "records"], request).await
+    }
+
+    pub async fn snapshot(&self) -> Result<SnapshotInfo> {
+        self.json(Method::POST, &["system", "snapshot"], false, &()).await
+    }
+
+    pub async fn list_snapshots(&self) -> Result<Vec<SnapshotInfo>> {
+        self.get(&["system", "snapshots"]).await
+    }
+
+    pub async fn restore(&self, name: &str) -> Result<RestoreResponse> {
+        let request = RestoreRequest {
+            name: name.to_string(),
+        };
+        self.json(Method::POST, &["system", "restore"], false, &request).await
+    }
+
+    pub async fn replication_status(&self) -> Result<ReplicationStatus> {
+        self.get(&["system", "replication"]).await
+    }
+
+    pub async fn promote(&self) -> Result<ReplicationStatus> {
+        self.json(Method::POST, &["system", "promote"], false, &()).await
+    }
+
+    /// The metrics in the Prometheus text format.
+    pub async fn metrics(&self) -> Result<String> {
+        let response = self.http.get(self.url(&["metrics"])).send().await?;
+        let status = response.status();
+        let body = response.text().await?;
+        if status.is_success() {
+            return Ok(body);
+        }
+        match serde_json::from_str::<BaseHttpResponse<Result<(), ApiError>>>(&body) {
+            Ok(response) => match response.into_result() {
+                Err(mut e) => {
+                    e.status = status;
+                    Err(e.into())
+                }
+                Ok(()) => Err(Error::Unexpected { status, body }),
+            },
+            Err(_) => Err(Error::Unexpected { status, body }),
+        }
+    }
+}
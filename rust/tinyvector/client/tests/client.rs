@@ -0,0 +1,215 @@
+use axum::http::StatusCode;
+use axum::routing::post;
+use axum::Router;
+use serde_json::json;
+use std::net::{SocketAddr, TcpListener};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tinyvector::auth::{ApiKey, AuthSettings, Scope};
+use tinyvector::config::Config;
+use tinyvector::database::EmbeddingRecord;
+use tinyvector::similarity::Distance;
+use tinyvector_client::dto::*;
+use tinyvector_client::{Client, DBError, Error};
+
+fn record(id: &str, embedding: Vec<f32>) -> EmbeddingRecord {
+    EmbeddingRecord {
+        id: id.to_string(),
+        embedding,
+        ..Default::default()
+    }
+}
+
+fn query(table_name: &str, query_embedding: Vec<f32>, top_k: usize) -> QueryRecordRequest {
+    QueryRecordRequest {
+        table_name: table_name.to_string(),
+        query_embedding,
+        query_text: None,
+        top_k,
+        distance: Some(Distance::Cosine),
+        exact: false,
+        ef_search: None,
+        filter: None,
+        min_score: None,
+        max_distance: None,
+        rerank: None,
+        explain: false,
+    }
+}
+
+fn key(key: &str, scope: Scope) -> ApiKey {
+    ApiKey {
+        name: None,
+        key: key.to_string(),
+        scope,
+        tables: None,
+    }
+}
+
+/// Start the server on a free port, it runs until the test ends.
+fn start_server(storage_dir: &std::path::Path) -> String {
+    let config = Config {
+        storage_dir: storage_dir.to_path_buf(),
+        auth: AuthSettings {
+            file: storage_dir.join("auth.toml"),
+            keys: vec![key("admin-key", Scope::Admin), key("reader", Scope::Read)],
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let listener = TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], 0))).unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(tinyvector::server::serve(config, listener));
+    base_url
+}
+
+#[tokio::test]
+async fn test_client_against_server() {
+    let dir = tempfile::tempdir().unwrap();
+    let base_url = start_server(dir.path());
+    let client = Client::builder(&base_url).api_key("admin-key").build().unwrap();
+
+    let create = CreateTableRequest {
+        table_name: "my table".to_string(),
+        dimension: 2,
+        options: Default::default(),
+        embedding: None,
+        fields: Default::default(),
+    };
+    client.create_table(&create).await.unwrap();
+    let e = client.create_table(&create).await.unwrap_err();
+    assert!(matches!(e, Error::Db(DBError::TableAlreadyExists)), "{:?}", e);
+    assert_eq!(vec!["my table"], client.list_tables().await.unwrap());
+
+    let records = vec![record("a", vec![1., 0.]), record("b/1", vec![0., 1.])];
+    client.insert_records("my table", records).await.unwrap();
+    client.upsert_record("my table", record("a", vec![1., 0.1])).await.unwrap();
+    let e = client.insert_record("my table", record("c", vec![1.])).await.unwrap_err();
+    assert!(matches!(e, Error::Db(DBError::DimensionMismatch)), "{:?}", e);
+
+    let hits = client.query_record(&query("my table", vec![1., 0.], 1)).await.unwrap();
+    assert_eq!("a", hits[0].record.id);
+    let request = QueryRecordRequest {
+        explain: true,
+        ..query("my table", vec![1., 0.], 1)
+    };
+    let (hits, explain) = client.explain_query(&request).await.unwrap();
+    assert_eq!("a", hits[0].record.id);
+    assert_eq!(2, explain.unwrap().candidates);
+    // Table names and ids are escaped in paths.
+    let record = client.get_record("my table", "b/1").await.unwrap();
+    assert_eq!(vec![0., 1.], record.embedding);
+    assert_eq!(2, client.table_stats("my table").await.unwrap().count);
+    let page = client
+        .list_records(
+            "my table",
+            &ListRecordsRequest {
+                cursor: None,
+                limit: 1,
+                include_embedding: false,
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(1, page.records.len());
+    assert_eq!(Some("a".to_string()), page.next_cursor);
+
+    // The batch before the bad record stays, the error reports it.
+    let import = ImportRequest {
+        format: Default::default(),
+        upsert: false,
+        batch_size: 1,
+        id_prefix: String::new(),
+    };
+    let data = concat!(
+        r#"{"id": "i1", "embedding": [0.5, 0.5]}"#,
+        "\n",
+        r#"{"id": "i2", "embedding": [0.5]}"#,
+        "\n",
+    );
+    let e = client.import("my table", &import, data.into()).await.unwrap_err();
+    let Error::Import { imported, source } = &e else {
+        panic!("{:?}", e);
+    };
+    assert_eq!(1, *imported);
+    assert!(matches!(**source, Error::Db(DBError::DimensionMismatch)), "{:?}", e);
+    client.get_record("my table", "i1").await.unwrap();
+    client.delete_record("my table", "i1").await.unwrap();
+
+    client.delete_record("my table", "b/1").await.unwrap();
+    let e = client.get_record("my table", "b/1").await.unwrap_err();
+    assert!(matches!(e, Error::Db(DBError::RecordDoesNotExist)), "{:?}", e);
+    let e = client.query_record(&query("missing", vec![1., 0.], 1)).await.unwrap_err();
+    assert!(matches!(e, Error::Db(DBError::TableDoesNotExist)), "{:?}", e);
+
+    let reader = Client::builder(&base_url).api_key("reader").build().unwrap();
+    let hits = reader.query_record(&query("my table", vec![1., 0.], 5)).await.unwrap();
+    assert_eq!(1, hits.len());
+    let e = reader.drop_table("my table").await.unwrap_err();
+    assert!(matches!(e, Error::Db(DBError::PermissionDenied(_))), "{:?}", e);
+    let e = Client::new(&base_url).unwrap().list_tables().await.unwrap_err();
+    assert!(matches!(e, Error::Db(DBError::Unauthenticated)), "{:?}", e);
+
+    let status = client.replication_status().await.unwrap();
+    assert_eq!(client.snapshot().await.unwrap().seq, status.seq);
+    assert!(client.metrics().await.unwrap().contains("tinyvector_records"));
+    client.drop_table("my table").await.unwrap();
+}
+
+/// Serve `/db/*` answering `status` to the first `failures` requests, returns the base url and
+/// the number of requests received.
+async fn flaky_server(failures: usize, status: StatusCode) -> (String, Arc<AtomicUsize>) {
+    let received = Arc::new(AtomicUsize::new(0));
+    let counter = received.clone();
+    let app = Router::new().route(
+        "/db/*path",
+        post(move || {
+            let counter = counter.clone();
+            async move {
+                if counter.fetch_add(1, Ordering::SeqCst) < failures {
+                    return (status, "busy".to_string());
+                }
+                let body = json!({"result": {"Ok": []}, "success": true, "result_code": 0});
+                (StatusCode::OK, body.to_string())
+            }
+        }),
+    );
+    let server = axum::Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0)))
+        .serve(app.into_make_service());
+    let base_url = format!("http://{}", server.local_addr());
+    tokio::spawn(server);
+    (base_url, received)
+}
+
+#[tokio::test]
+async fn test_retries() {
+    let (base_url, received) = flaky_server(2, StatusCode::SERVICE_UNAVAILABLE).await;
+    let client = Client::builder(base_url)
+        .retry_backoff(Duration::from_millis(1))
+        .build()
+        .unwrap();
+    assert!(client.query_record(&query("t", vec![1.], 1)).await.unwrap().is_empty());
+    assert_eq!(3, received.load(Ordering::SeqCst));
+
+    // A write that may have been applied is not sent again.
+    let (base_url, received) = flaky_server(1, StatusCode::BAD_GATEWAY).await;
+    let client = Client::builder(base_url)
+        .retry_backoff(Duration::from_millis(1))
+        .build()
+        .unwrap();
+    let e = client.insert_record("t", record("a", vec![1.])).await.unwrap_err();
+    assert!(matches!(e, Error::Unexpected { status: StatusCode::BAD_GATEWAY, .. }), "{:?}", e);
+    assert_eq!(1, received.load(Ordering::SeqCst));
+    assert!(client.query_record(&query("t", vec![1.], 1)).await.is_ok());
+
+    // Give up after `max_retries`.
+    let (base_url, received) = flaky_server(10, StatusCode::SERVICE_UNAVAILABLE).await;
+    let client = Client::builder(base_url)
+        .max_retries(2)
+        .retry_backoff(Duration::from_millis(1))
+        .build()
+        .unwrap();
+    assert!(client.query_record(&query("t", vec![1.], 1)).await.is_err());
+    assert_eq!(3, received.load(Ordering::SeqCst));
+}
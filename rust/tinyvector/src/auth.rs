@@ -0,0 +1,313 @@
+use crate::database::DBError;
+use crate::routes::helper::respond;
+use anyhow::{bail, Context, Result};
+use axum::http::{header, Request};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum::Extension;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tracing::{info, warn};
+
+/// Default location of the API key file.
+pub const DEFAULT_AUTH_FILE: &str = "./auth.toml";
+
+/// What a key may do, every scope includes the ones below it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Scope {
+    /// Queries and reads.
+    Read,
+    /// Record inserts, upserts, imports and deletes.
+    Write,
+    /// Table creation and removal, whole database dumps and server shutdown.
+    Admin,
+}
+
+/// An entry of the key file.
+///
+/// ```toml
+/// [[keys]]
+/// name = "search-frontend"
+/// key = "..."
+/// scope = "read"
+/// tables = ["docs"]
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct ApiKey {
+    /// Shown in the logs instead of the key.
+    #[serde(default)]
+    pub name: Option<String>,
+    pub key: String,
+    pub scope: Scope,
+    /// Tables the key is limited to, every table when absent.
+    #[serde(default)]
+    pub tables: Option<Vec<String>>,
+}
+
+/// Layout of the key file.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct AuthConfig {
+    #[serde(default)]
+    pub keys: Vec<ApiKey>,
+}
+
+/// The `[auth]` section of the server config.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "snake_case")]
+pub struct AuthSettings {
+    /// Key file, read if it exists.
+    pub file: PathBuf,
+    /// Keys given inline, on top of the ones of the file.
+    pub keys: Vec<ApiKey>,
+    /// Serve every request without a key, the server refuses to start without keys otherwise.
+    pub disabled: bool,
+}
+
+impl Default for AuthSettings {
+    fn default() -> Self {
+        Self {
+            file: PathBuf::from(DEFAULT_AUTH_FILE),
+            keys: Vec::new(),
+            disabled: false,
+        }
+    }
+}
+
+/// The caller of a request, resolved from its API key.
+#[derive(Debug, Clone)]
+pub struct Principal {
+    pub name: String,
+    pub scope: Scope,
+    /// `None` when the key is not limited to some tables.
+    pub tables: Option<HashSet<String>>,
+}
+
+impl Principal {
+    /// Everything is allowed while authentication is disabled.
+    fn anonymous() -> Self {
+        Self {
+            name: "anonymous".to_string(),
+            scope: Scope::Admin,
+            tables: None,
+        }
+    }
+
+    /// Check that the caller has `scope` on `table`, `None` standing for the whole database.
+    pub fn authorize(&self, scope: Scope, table: Option<&str>) -> Result<(), DBError> {
+        if self.scope < scope {
+            return Err(DBError::PermissionDenied(format!(
+                "{} has {:?} access, {:?} is required",
+                self.name, self.scope, scope
+            )));
+        }
+        let allowed = match (&self.tables, table) {
+            (None, _) => true,
+            (Some(tables), Some(table)) => tables.contains(table),
+            (Some(_), None) => false,
+        };
+        if !allowed {
+            return Err(DBError::PermissionDenied(format!(
+                "{} has no access to {}",
+                self.name,
+                table.unwrap_or("every table")
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// The known API keys, authentication is disabled when there are none.
+#[derive(Debug, Clone, Default)]
+pub struct Auth {
+    keys: Option<Arc<HashMap<String, Principal>>>,
+}
+
+impl Auth {
+    pub fn new(config: AuthConfig) -> Self {
+        let keys = config
+            .keys
+            .into_iter()
+            .enumerate()
+            .map(|(i, key)| {
+                let principal = Principal {
+                    name: key.name.unwrap_or_else(|| format!("key #{}", i)),
+                    scope: key.scope,
+                    tables: key.tables.map(|tables| tables.into_iter().collect()),
+                };
+                (key.key, principal)
+            })
+            .collect();
+        Self {
+            keys: Some(Arc::new(keys)),
+        }
+    }
+
+    /// Load the key file along with the inline keys, failing without any unless authentication
+    /// is explicitly disabled.
+    pub fn load(settings: &AuthSettings) -> Result<Self> {
+        if settings.disabled {
+            warn!("Authentication is disabled, every request is served");
+            return Ok(Self::default());
+        }
+        let mut config = Self::read_file(&settings.file)?.unwrap_or_default();
+        config.keys.extend(settings.keys.iter().cloned());
+        if config.keys.is_empty() {
+            bail!(
+                "No API keys in {:?} nor inline, configure some or set `auth.disabled = true`",
+                settings.file
+            );
+        }
+        info!("Loaded {} API keys", config.keys.len());
+        Ok(Self::new(config))
+    }
+
+    fn read_file(path: &Path) -> Result<Option<AuthConfig>> {
+        let data = match fs::read_to_string(path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let config =
+            toml::from_str(&data).with_context(|| format!("Invalid API key file {:?}", path))?;
+        Ok(Some(config))
+    }
+
+    pub(crate) fn principal(&self, key: Option<&str>) -> Option<Principal> {
+        match &self.keys {
+            None => Some(Principal::anonymous()),
+            Some(keys) => keys.get(key?).cloned(),
+        }
+    }
+
+    pub fn extension(&self) -> Extension<Auth> {
+        Extension(self.clone())
+    }
+}
+
+/// Middleware resolving the `Authorization: Bearer <key>` or `x-api-key` header into a
+/// `Principal` extension for the handlers, requests without a known key are rejected.
+pub async fn authenticate<B>(
+    Extension(auth): Extension<Auth>,
+    mut request: Request<B>,
+    next: Next<B>,
+) -> Response {
+    let headers = request.headers();
+    let key = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .or_else(|| headers.get("x-api-key").and_then(|v| v.to_str().ok()));
+
+    let Some(principal) = auth.principal(key) else {
+        return respond::<(), _>(Err(DBError::Unauthenticated)).into_response();
+    };
+    request.extensions_mut().insert(principal);
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::StatusCode;
+    use axum::routing::get;
+    use axum::{middleware, Router};
+    use tower::ServiceExt;
+
+    fn auth() -> Auth {
+        let config: AuthConfig = toml::from_str(
+            r#"
+            [[keys]]
+            name = "ops"
+            key = "admin-key"
+            scope = "admin"
+
+            [[keys]]
+            key = "docs-writer"
+            scope = "write"
+            tables = ["docs"]
+            "#,
+        )
+        .unwrap();
+        Auth::new(config)
+    }
+
+    #[test]
+    fn test_authorize() {
+        let auth = auth();
+        let admin = auth.principal(Some("admin-key")).unwrap();
+        assert_eq!(Ok(()), admin.authorize(Scope::Admin, None));
+
+        let writer = auth.principal(Some("docs-writer")).unwrap();
+        assert_eq!("key #1", writer.name);
+        assert_eq!(Ok(()), writer.authorize(Scope::Read, Some("docs")));
+        assert_eq!(Ok(()), writer.authorize(Scope::Write, Some("docs")));
+        assert!(writer.authorize(Scope::Admin, Some("docs")).is_err());
+        assert!(writer.authorize(Scope::Read, Some("other")).is_err());
+        assert!(writer.authorize(Scope::Read, None).is_err());
+
+        assert!(auth.principal(Some("unknown")).is_none());
+        assert!(auth.principal(None).is_none());
+        assert!(Auth::default().principal(None).is_some());
+    }
+
+    #[test]
+    fn test_load_fails_closed() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut settings = AuthSettings {
+            file: dir.path().join("auth.toml"),
+            ..Default::default()
+        };
+        assert!(Auth::load(&settings).is_err());
+        fs::write(&settings.file, "keys = []").unwrap();
+        assert!(Auth::load(&settings).is_err());
+
+        fs::write(&settings.file, "[[keys]]\nkey = \"k\"\nscope = \"read\"").unwrap();
+        let auth = Auth::load(&settings).unwrap();
+        assert!(auth.principal(None).is_none());
+        assert!(auth.principal(Some("k")).is_some());
+
+        settings.disabled = true;
+        assert!(Auth::load(&settings).unwrap().principal(None).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_authenticate_middleware() {
+        let app = Router::new()
+            .route(
+                "/whoami",
+                get(|Extension(principal): Extension<Principal>| async move { principal.name }),
+            )
+            .route_layer(middleware::from_fn(authenticate))
+            .layer(auth().extension());
+
+        let request = |header: Option<(&str, &str)>| {
+            let mut builder = Request::builder().uri("/whoami");
+            if let Some((name, value)) = header {
+                builder = builder.header(name, value);
+            }
+            builder.body(Body::empty()).unwrap()
+        };
+
+        let response = app.clone().oneshot(request(None)).await.unwrap();
+        assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+        let response = app.clone().oneshot(request(Some(("x-api-key", "wrong")))).await.unwrap();
+        assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+
+        let response = app
+            .clone()
+            .oneshot(request(Some(("authorization", "Bearer admin-key"))))
+            .await
+            .unwrap();
+        assert_eq!(StatusCode::OK, response.status());
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        assert_eq!(&b"ops"[..], &body[..]);
+    }
+}
@@ -0,0 +1,737 @@
+use crate::auth::{Auth, Principal, Scope};
+use crate::database::{
+    DBError, Database, EmbeddingRecord, ScoredRecord, SearchOptions, TableOptions, VectorField,
+};
+use crate::embedding::{Embedder, EmbeddingBinding};
+use crate::metadata::Filter;
+use crate::replication::{self, Frame};
+use crate::routes::helper::{self, ApiError};
+use crate::similarity::Distance;
+use crate::telemetry::next_request_id;
+use anyhow::{bail, Result};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader, BufWriter};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::{TcpListener, TcpStream, ToSocketAddrs};
+use tracing::{debug, info, info_span, warn, Instrument, Span};
+
+/// Largest frame accepted, a batch of about ten thousand 1536 dimensional vectors.
+pub const MAX_FRAME_SIZE: usize = 64 << 20;
+/// Largest frame accepted before the connection authenticated, enough for `Authenticate`.
+const UNAUTHENTICATED_FRAME_SIZE: usize = 4 << 10;
+
+/// A request of the binary protocol, every frame is `len: u32 | bincode`, little endian.
+///
+/// Requests are answered in order with one `Response` each, except the `Batch` frames of a
+/// bulk insert which are only answered by its `EndInsert`. bincode numbers the variants by
+/// position, new ones go last so that older clients keep working.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Request {
+    /// Bind an API key to the connection, needed first when keys are configured.
+    Authenticate { api_key: String },
+    /// Same fields as the body of `/db/tables`.
+    CreateTable {
+        table_name: String,
+        dimension: usize,
+        options: TableOptions,
+        fields: BTreeMap<String, VectorField>,
+        embedding: Option<EmbeddingBinding>,
+    },
+    DropTable { table_name: String },
+    /// Insert a batch of records, `upsert` replaces the existing ones with the same id.
+    InsertRecords {
+        table_name: String,
+        records: Vec<EmbeddingRecord>,
+        upsert: bool,
+    },
+    DeleteRecord { table_name: String, id: String },
+    Query(QueryRequest),
+    /// Start a bulk insert, followed by any number of `Batch` frames and an `EndInsert`.
+    BeginInsert { table_name: String, upsert: bool },
+    /// Records of the current bulk insert, committed as they arrive.
+    Batch(Vec<EmbeddingRecord>),
+    EndInsert,
+    /// Turn the connection into a stream of `replication::Frame`s of the mutations following
+    /// `from_seq`, after a `Done`. Needs the admin scope on every table.
+    Replicate { from_seq: u64 },
+}
+
+/// Counterpart of the `/db/query_record` body.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueryRequest {
+    pub table_name: String,
+    pub query_embedding: Vec<f32>,
+    pub top_k: usize,
+    /// `None` for the distance of the table.
+    pub distance: Option<Distance>,
+    pub exact: bool,
+    pub ef_search: Option<usize>,
+    pub filter: Option<Filter>,
+    pub min_score: Option<f32>,
+    pub max_distance: Option<f32>,
+    pub rerank: Option<bool>,
+}
+
+impl QueryRequest {
+    /// A plain top k query, the other knobs left at their default.
+    pub fn new(
+        table_name: &str,
+        query_embedding: Vec<f32>,
+        top_k: usize,
+        distance: Distance,
+    ) -> Self {
+        Self {
+            table_name: table_name.to_string(),
+            query_embedding,
+            top_k,
+            distance: Some(distance),
+            exact: false,
+            ef_search: None,
+            filter: None,
+            min_score: None,
+            max_distance: None,
+            rerank: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Response {
+    Done,
+    /// Records written by a bulk insert.
+    Inserted { count: usize },
+    Hits(Vec<ScoredRecord>),
+    /// Same codes as the HTTP API, e.g. `table_not_found`.
+    Error { code: String, message: String },
+}
+
+impl From<DBError> for Response {
+    fn from(e: DBError) -> Self {
+        let e = ApiError::from(e);
+        helper::observe(&e);
+        Response::Error {
+            code: e.code,
+            message: e.message,
+        }
+    }
+}
+
+/// An error answered by the server.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("{code}: {message}")]
+pub struct RemoteError {
+    pub code: String,
+    pub message: String,
+}
+
+/// Read a frame of up to `limit` bytes, `None` when the peer closed the connection in between
+/// frames.
+pub async fn read_frame<T, R>(reader: &mut R, limit: usize) -> Result<Option<T>>
+where
+    T: DeserializeOwned,
+    R: AsyncRead + Unpin,
+{
+    let len = match reader.read_u32_le().await {
+        Ok(len) => len as usize,
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    if len > limit {
+        bail!("Frame of {} bytes exceeds the limit of {}", len, limit);
+    }
+    let mut buf = vec![0; len];
+    reader.read_exact(&mut buf).await?;
+    Ok(Some(bincode::deserialize(&buf)?))
+}
+
+/// Buffer a frame, the caller flushes once the frames it wants sent are written.
+pub async fn write_frame<T, W>(writer: &mut W, value: &T) -> Result<()>
+where
+    T: Serialize,
+    W: AsyncWrite + Unpin,
+{
+    let data = bincode::serialize(value)?;
+    if data.len() > MAX_FRAME_SIZE {
+        bail!("Frame of {} bytes exceeds the limit of {}", data.len(), MAX_FRAME_SIZE);
+    }
+    writer.write_u32_le(data.len() as u32).await?;
+    writer.write_all(&data).await?;
+    Ok(())
+}
+
+/// Accept connections on `listener` until `signal` completes.
+pub async fn serve(
+    listener: TcpListener,
+    db: Arc<Database>,
+    auth: Auth,
+    embedder: Embedder,
+    signal: impl Future<Output = ()>,
+) {
+    tokio::pin!(signal);
+    loop {
+        let (stream, peer) = tokio::select! {
+            _ = &mut signal => break,
+            accepted = listener.accept() => match accepted {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    warn!("Failed to accept binary connection: {:?}", e);
+                    continue;
+                }
+            },
+        };
+        let connection = Connection {
+            db: db.clone(),
+            principal: auth.principal(None),
+            auth: auth.clone(),
+            embedder: embedder.clone(),
+            bulk: None,
+        };
+        tokio::spawn(async move {
+            debug!("Binary connection from {}", peer);
+            if let Err(e) = connection.run(stream).await {
+                warn!("Binary connection from {} failed: {:#}", peer, e);
+            }
+        });
+    }
+    info!("Stopped accepting binary connections");
+}
+
+/// A bulk insert in progress.
+struct BulkInsert {
+    table_name: String,
+    upsert: bool,
+    count: usize,
+    /// The first failed batch, the following ones are skipped.
+    error: Option<DBError>,
+}
+
+struct Connection {
+    db: Arc<Database>,
+    auth: Auth,
+    /// Tells whether tables may be bound to a model.
+    embedder: Embedder,
+    /// `None` until a known key is presented.
+    principal: Option<Principal>,
+    bulk: Option<BulkInsert>,
+}
+
+impl Connection {
+    async fn run(mut self, stream: TcpStream) -> Result<()> {
+        stream.set_nodelay(true)?;
+        let (reader, writer) = stream.into_split();
+        let mut reader = BufReader::new(reader);
+        let mut writer = BufWriter::new(writer);
+        loop {
+            // Nothing large is allocated for a peer that did not present a key.
+            let limit = match self.principal {
+                Some(_) => MAX_FRAME_SIZE,
+                None => UNAUTHENTICATED_FRAME_SIZE,
+            };
+            let Some(request) = read_frame::<Request, _>(&mut reader, limit).await? else {
+                break;
+            };
+            if let Request::Replicate { from_seq } = request {
+                let res = self.principal.as_ref().ok_or(DBError::Unauthenticated);
+                if let Err(e) = res.and_then(|p| p.authorize(Scope::Admin, None)) {
+                    write_frame(&mut writer, &Response::from(e)).await?;
+                    writer.flush().await?;
+                    continue;
+                }
+                write_frame(&mut writer, &Response::Done).await?;
+                return replication::stream(self.db, from_seq, &mut writer).await;
+            }
+            let span = info_span!("request", id = %next_request_id(), transport = "binary");
+            let Some(response) = self.handle(request).instrument(span).await? else {
+                continue;
+            };
+            write_frame(&mut writer, &response).await?;
+            writer.flush().await?;
+        }
+        Ok(())
+    }
+
+    /// Answer a request, `None` for the batches of a bulk insert.
+    async fn handle(&mut self, request: Request) -> Result<Option<Response>> {
+        match request {
+            Request::Authenticate { api_key } => {
+                self.principal = self.auth.principal(Some(&api_key));
+                let res = self.principal.as_ref().map(|_| ()).ok_or(DBError::Unauthenticated);
+                Ok(Some(Self::done(res)))
+            }
+            Request::Batch(records) => {
+                let Some(bulk) = self.bulk.as_mut() else {
+                    bail!("Batch outside of a bulk insert");
+                };
+                if bulk.error.is_none() {
+                    let count = records.len();
+                    let (table_name, upsert) = (bulk.table_name.clone(), bulk.upsert);
+                    let res = run(self.db.clone(), move |db| {
+                        if upsert {
+                            db.upsert_records(table_name, records)
+                        } else {
+                            db.insert_records(table_name, records)
+                        }
+                    })
+                    .await;
+                    match res {
+                        Ok(()) => bulk.count += count,
+                        Err(e) => bulk.error = Some(e),
+                    }
+                }
+                Ok(None)
+            }
+            Request::EndInsert => {
+                let Some(bulk) = self.bulk.take() else {
+                    bail!("EndInsert outside of a bulk insert");
+                };
+                let response = match bulk.error {
+                    Some(e) => e.into(),
+                    None => Response::Inserted { count: bulk.count },
+                };
+                Ok(Some(response))
+            }
+            _ if self.bulk.is_some() => bail!("Request within a bulk insert"),
+            request => Ok(Some(self.execute(request).await)),
+        }
+    }
+
+    /// Run a request with the same permissions as its HTTP counterpart.
+    async fn execute(&mut self, request: Request) -> Response {
+        let Some(principal) = &self.principal else {
+            return DBError::Unauthenticated.into();
+        };
+        let db = self.db.clone();
+        match request {
+            Request::CreateTable {
+                table_name,
+                dimension,
+                options,
+                fields,
+                embedding,
+            } => {
+                if let Err(e) = principal.authorize(Scope::Admin, Some(&table_name)) {
+                    return e.into();
+                }
+                if embedding.is_some() && !self.embedder.is_enabled() {
+                    let message = "No embedding provider is configured".to_string();
+                    return DBError::InvalidInput(message).into();
+                }
+                let res = run(db, move |db| {
+                    db.create_table_with_binding(table_name, dimension, options, fields, embedding)
+                });
+                Self::done(res.await)
+            }
+            Request::DropTable { table_name } => {
+                if let Err(e) = principal.authorize(Scope::Admin, Some(&table_name)) {
+                    return e.into();
+                }
+                Self::done(run(db, move |db| db.drop_table(table_name)).await)
+            }
+            Request::InsertRecords {
+                table_name,
+                records,
+                upsert,
+            } => {
+                if let Err(e) = principal.authorize(Scope::Write, Some(&table_name)) {
+                    return e.into();
+                }
+                let res = run(db, move |db| {
+                    if upsert {
+                        db.upsert_records(table_name, records)
+                    } else {
+                        db.insert_records(table_name, records)
+                    }
+                });
+                Self::done(res.await)
+            }
+            Request::DeleteRecord { table_name, id } => {
+                if let Err(e) = principal.authorize(Scope::Write, Some(&table_name)) {
+                    return e.into();
+                }
+                Self::done(run(db, move |db| db.delete_record(table_name, id)).await)
+            }
+            Request::Query(query) => {
+                if let Err(e) = principal.authorize(Scope::Read, Some(&query.table_name)) {
+                    return e.into();
+                }
+                let res = run(db, move |db| {
+                    let options = SearchOptions {
+                        exact: query.exact,
+                        ef_search: query.ef_search,
+                        filter: query.filter.as_ref(),
+                        min_score: query.min_score,
+                        max_distance: query.max_distance,
+                        rerank: query.rerank,
+                        explain: None,
+                    };
+                    db.query_record_with(
+                        query.table_name,
+                        &query.query_embedding,
+                        query.top_k,
+                        query.distance,
+                        &options,
+                    )
+                });
+                match res.await {
+                    Ok(hits) => Response::Hits(hits),
+                    Err(e) => e.into(),
+                }
+            }
+            Request::BeginInsert { table_name, upsert } => {
+                if let Err(e) = principal.authorize(Scope::Write, Some(&table_name)) {
+                    return e.into();
+                }
+                // Unknown tables fail right away rather than on the first batch.
+                if !db.has_table(&table_name) {
+                    return DBError::TableDoesNotExist.into();
+                }
+                self.bulk = Some(BulkInsert {
+                    table_name,
+                    upsert,
+                    count: 0,
+                    error: None,
+                });
+                Response::Done
+            }
+            Request::Authenticate { .. } | Request::Batch(_) | Request::EndInsert => {
+                unreachable!("answered by `handle`")
+            }
+            Request::Replicate { .. } => unreachable!("answered by `run`"),
+        }
+    }
+
+    fn done(res: Result<(), DBError>) -> Response {
+        match res {
+            Ok(()) => Response::Done,
+            Err(e) => e.into(),
+        }
+    }
+}
+
+/// Run a database call on the blocking pool, like the HTTP handlers do.
+async fn run<T, F>(db: Arc<Database>, f: F) -> Result<T, DBError>
+where
+    T: Send + 'static,
+    F: FnOnce(&Database) -> Result<T, DBError> + Send + 'static,
+{
+    let span = Span::current();
+    tokio::task::spawn_blocking(move || span.in_scope(|| f(&db)))
+        .await
+        .unwrap_or_else(|e| Err(DBError::Internal(e.to_string())))
+}
+
+/// Client of the binary protocol, one request in flight at a time.
+pub struct Client {
+    reader: BufReader<OwnedReadHalf>,
+    writer: BufWriter<OwnedWriteHalf>,
+}
+
+impl Client {
+    pub async fn connect(addr: impl ToSocketAddrs) -> Result<Self> {
+        let stream = TcpStream::connect(addr).await?;
+        stream.set_nodelay(true)?;
+        let (reader, writer) = stream.into_split();
+        Ok(Self {
+            reader: BufReader::new(reader),
+            writer: BufWriter::new(writer),
+        })
+    }
+
+    /// Send a request and wait for its response, server errors become a `RemoteError`.
+    pub async fn call(&mut self, request: &Request) -> Result<Response> {
+        write_frame(&mut self.writer, request).await?;
+        self.writer.flush().await?;
+        self.response().await
+    }
+
+    async fn response(&mut self) -> Result<Response> {
+        match read_frame(&mut self.reader, MAX_FRAME_SIZE).await? {
+            Some(Response::Error { code, message }) => Err(RemoteError { code, message }.into()),
+            Some(response) => Ok(response),
+            None => bail!("Connection closed by the server"),
+        }
+    }
+
+    async fn call_done(&mut self, request: &Request) -> Result<()> {
+        match self.call(request).await? {
+            Response::Done => Ok(()),
+            response => bail!("Unexpected response {:?}", response),
+        }
+    }
+
+    pub async fn authenticate(&mut self, api_key: &str) -> Result<()> {
+        let api_key = api_key.to_string();
+        self.call_done(&Request::Authenticate { api_key }).await
+    }
+
+    pub async fn create_table(
+        &mut self,
+        table_name: &str,
+        dimension: usize,
+        options: TableOptions,
+    ) -> Result<()> {
+        self.create_table_with_binding(table_name, dimension, options, BTreeMap::new(), None)
+            .await
+    }
+
+    /// Create a table with named vector fields and a model embedding its text.
+    pub async fn create_table_with_binding(
+        &mut self,
+        table_name: &str,
+        dimension: usize,
+        options: TableOptions,
+        fields: BTreeMap<String, VectorField>,
+        embedding: Option<EmbeddingBinding>,
+    ) -> Result<()> {
+        let table_name = table_name.to_string();
+        self.call_done(&Request::CreateTable {
+            table_name,
+            dimension,
+            options,
+            fields,
+            embedding,
+        })
+        .await
+    }
+
+    pub async fn drop_table(&mut self, table_name: &str) -> Result<()> {
+        let table_name = table_name.to_string();
+        self.call_done(&Request::DropTable { table_name }).await
+    }
+
+    pub async fn insert_records(
+        &mut self,
+        table_name: &str,
+        records: Vec<EmbeddingRecord>,
+        upsert: bool,
+    ) -> Result<()> {
+        let table_name = table_name.to_string();
+        self.call_done(&Request::InsertRecords {
+            table_name,
+            records,
+            upsert,
+        })
+        .await
+    }
+
+    pub async fn delete_record(&mut self, table_name: &str, id: &str) -> Result<()> {
+        let (table_name, id) = (table_name.to_string(), id.to_string());
+        self.call_done(&Request::DeleteRecord { table_name, id }).await
+    }
+
+    pub async fn query(&mut self, query: QueryRequest) -> Result<Vec<ScoredRecord>> {
+        match self.call(&Request::Query(query)).await? {
+            Response::Hits(hits) => Ok(hits),
+            response => bail!("Unexpected response {:?}", response),
+        }
+    }
+
+    /// Ask for the mutations following `from_seq`, read them with `replication_frame`.
+    ///
+    /// The connection serves no other request afterwards.
+    pub async fn replicate(&mut self, from_seq: u64) -> Result<()> {
+        self.call_done(&Request::Replicate { from_seq }).await
+    }
+
+    pub async fn replication_frame(&mut self) -> Result<Frame> {
+        match read_frame(&mut self.reader, MAX_FRAME_SIZE).await? {
+            Some(frame) => Ok(frame),
+            None => bail!("Replication stream closed by the leader"),
+        }
+    }
+
+    /// Stream the batches without waiting for each of them, returns the records written.
+    ///
+    /// Batches after a failed one are dropped by the server, the error comes back at the end.
+    pub async fn bulk_insert<I>(
+        &mut self,
+        table_name: &str,
+        upsert: bool,
+        batches: I,
+    ) -> Result<usize>
+    where
+        I: IntoIterator<Item = Vec<EmbeddingRecord>>,
+    {
+        let table_name = table_name.to_string();
+        self.call_done(&Request::BeginInsert { table_name, upsert }).await?;
+        for batch in batches {
+            write_frame(&mut self.writer, &Request::Batch(batch)).await?;
+        }
+        write_frame(&mut self.writer, &Request::EndInsert).await?;
+        self.writer.flush().await?;
+        match self.response().await? {
+            Response::Inserted { count } => Ok(count),
+            response => bail!("Unexpected response {:?}", response),
+        }
+    }
+}
+
+/// Bind the binary listener and serve it in the background until `signal` completes.
+pub async fn spawn(
+    addr: SocketAddr,
+    db: Arc<Database>,
+    auth: Auth,
+    embedder: Embedder,
+    signal: impl Future<Output = ()> + Send + 'static,
+) -> Result<SocketAddr> {
+    let listener = TcpListener::bind(addr).await?;
+    let addr = listener.local_addr()?;
+    info!("binary protocol listening at {}", addr);
+    tokio::spawn(async move {
+        serve(listener, db, auth, embedder, signal).await;
+    });
+    Ok(addr)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::AuthConfig;
+
+    fn record(id: &str, embedding: Vec<f32>) -> EmbeddingRecord {
+        EmbeddingRecord {
+            id: id.to_string(),
+            embedding,
+            ..Default::default()
+        }
+    }
+
+    async fn start(auth: Auth) -> SocketAddr {
+        let addr = SocketAddr::from(([127, 0, 0, 1], 0));
+        let db = Arc::new(Database::zero());
+        spawn(addr, db, auth, Embedder::default(), std::future::pending()).await.unwrap()
+    }
+
+    fn remote_code(e: anyhow::Error) -> String {
+        e.downcast::<RemoteError>().unwrap().code
+    }
+
+    #[tokio::test]
+    async fn test_binary_protocol() {
+        let mut client = Client::connect(start(Auth::default()).await).await.unwrap();
+        client.create_table("t", 2, TableOptions::default()).await.unwrap();
+        let records = vec![record("a", vec![1., 0.]), record("b", vec![0., 1.])];
+        client.insert_records("t", records, false).await.unwrap();
+
+        let hits = client
+            .query(QueryRequest::new("t", vec![1., 0.1], 1, Distance::Cosine))
+            .await
+            .unwrap();
+        assert_eq!("a", hits[0].record.id);
+
+        client.delete_record("t", "a").await.unwrap();
+        let e = client.delete_record("t", "a").await.unwrap_err();
+        assert_eq!("record_not_found", remote_code(e));
+        // The connection stays usable after an error.
+        let e = client.insert_records("t", vec![record("c", vec![1.])], false).await;
+        assert_eq!("dimension_mismatch", remote_code(e.unwrap_err()));
+        client.drop_table("t").await.unwrap();
+        let e = client.query(QueryRequest::new("t", vec![1., 0.], 1, Distance::Cosine)).await;
+        assert_eq!("table_not_found", remote_code(e.unwrap_err()));
+    }
+
+    #[tokio::test]
+    async fn test_bulk_insert() {
+        let mut client = Client::connect(start(Auth::default()).await).await.unwrap();
+        client.create_table("t", 2, TableOptions::default()).await.unwrap();
+        let batches = (0..10).map(|b| {
+            (0..100)
+                .map(|i| record(&format!("{}-{}", b, i), vec![b as f32, i as f32]))
+                .collect()
+        });
+        assert_eq!(1000, client.bulk_insert("t", false, batches).await.unwrap());
+
+        // A failed batch stops the insert, the earlier ones stay.
+        let batches = vec![
+            vec![record("x", vec![1., 1.])],
+            vec![record("0-0", vec![1., 1.])],
+            vec![record("y", vec![1., 1.])],
+        ];
+        let e = client.bulk_insert("t", false, batches).await.unwrap_err();
+        assert_eq!("record_already_exists", remote_code(e));
+        let hits = client
+            .query(QueryRequest::new("t", vec![1., 1.], 2000, Distance::Euclidean))
+            .await
+            .unwrap();
+        assert_eq!(1001, hits.len());
+
+        let e = client.bulk_insert("missing", true, Vec::new()).await.unwrap_err();
+        assert_eq!("table_not_found", remote_code(e));
+    }
+
+    #[tokio::test]
+    async fn test_binary_authentication() {
+        let config: AuthConfig = toml::from_str(
+            r#"
+            [[keys]]
+            key = "admin-key"
+            scope = "admin"
+
+            [[keys]]
+            key = "reader"
+            scope = "read"
+            "#,
+        )
+        .unwrap();
+        let addr = start(Auth::new(config)).await;
+
+        let mut client = Client::connect(addr).await.unwrap();
+        let e = client.create_table("t", 2, TableOptions::default()).await.unwrap_err();
+        assert_eq!("unauthenticated", remote_code(e));
+        let e = client.authenticate("wrong").await.unwrap_err();
+        assert_eq!("unauthenticated", remote_code(e));
+        client.authenticate("admin-key").await.unwrap();
+        client.create_table("t", 2, TableOptions::default()).await.unwrap();
+
+        let mut reader = Client::connect(addr).await.unwrap();
+        reader.authenticate("reader").await.unwrap();
+        let e = reader.insert_records("t", vec![record("a", vec![1., 0.])], false).await;
+        assert_eq!("permission_denied", remote_code(e.unwrap_err()));
+        let query = QueryRequest::new("t", vec![1., 0.], 1, Distance::Cosine);
+        assert!(reader.query(query).await.unwrap().is_empty());
+
+        // Large frames are refused until a key is presented.
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream.write_u32_le(1 << 20).await.unwrap();
+        assert_eq!(0, stream.read(&mut [0; 16]).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_create_table_with_binding() {
+        let addr = SocketAddr::from(([127, 0, 0, 1], 0));
+        let db = Arc::new(Database::zero());
+        let signal = std::future::pending();
+        let addr = spawn(addr, db.clone(), Auth::default(), Embedder::default(), signal).await;
+        let mut client = Client::connect(addr.unwrap()).await.unwrap();
+        let field = VectorField {
+            dimension: 3,
+            distance: Distance::Cosine,
+        };
+        let fields = BTreeMap::from([("title".to_string(), field)]);
+        let options = TableOptions::default();
+
+        // Refused like over HTTP when no embedding provider is configured.
+        let binding = Some(EmbeddingBinding::default());
+        let e = client
+            .create_table_with_binding("t", 2, options, fields.clone(), binding)
+            .await
+            .unwrap_err();
+        assert_eq!("invalid_input", remote_code(e));
+        client.create_table_with_binding("t", 2, options, fields, None).await.unwrap();
+        assert_eq!(3, db.table_stats("t").unwrap().fields["title"].dimension);
+    }
+
+    #[test]
+    fn test_frame_smaller_than_json() {
+        let embedding = (0..1536).map(|i| (i as f32).sin()).collect::<Vec<_>>();
+        let query = QueryRequest::new("docs", embedding, 10, Distance::Cosine);
+        let binary = bincode::serialize(&Request::Query(query.clone())).unwrap();
+        let json = serde_json::to_vec(&query).unwrap();
+        assert!(binary.len() * 2 < json.len(), "{} vs {}", binary.len(), json.len());
+    }
+}
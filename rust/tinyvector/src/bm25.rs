@@ -0,0 +1,196 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+pub use tinyvector_types::database::Fusion;
+
+/// Term frequency saturation.
+const K1: f32 = 1.2;
+/// Document length normalization.
+const B: f32 = 0.75;
+
+/// Lowercased alphanumeric tokens, `-` and `_` stay inside a token so product codes like
+/// `AB-1234` are matched as a whole.
+pub fn tokenize(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !(c.is_alphanumeric() || c == '-' || c == '_'))
+        .map(|token| token.trim_matches(|c| c == '-' || c == '_'))
+        .filter(|token| !token.is_empty())
+        .map(str::to_lowercase)
+}
+
+/// Inverted index over the text of the records, scored with BM25.
+#[derive(Debug, Clone, Default)]
+pub struct Bm25Index {
+    /// Frequency of each term per record id.
+    postings: HashMap<String, HashMap<String, u32>>,
+    /// Token count of every indexed record.
+    lengths: HashMap<String, u32>,
+    total_length: u64,
+}
+
+impl Bm25Index {
+    pub fn insert(&mut self, id: &str, text: &str) {
+        let mut length = 0;
+        for term in tokenize(text) {
+            *self
+                .postings
+                .entry(term)
+                .or_default()
+                .entry(id.to_string())
+                .or_default() += 1;
+            length += 1;
+        }
+        self.lengths.insert(id.to_string(), length);
+        self.total_length += length as u64;
+    }
+
+    /// Remove the record `id`, `text` is the text it was inserted with.
+    pub fn remove(&mut self, id: &str, text: &str) {
+        let Some(length) = self.lengths.remove(id) else {
+            return;
+        };
+        self.total_length -= length as u64;
+        for term in tokenize(text) {
+            if let Some(posting) = self.postings.get_mut(&term) {
+                posting.remove(id);
+                if posting.is_empty() {
+                    self.postings.remove(&term);
+                }
+            }
+        }
+    }
+
+    /// Top k records by BM25 score for `query` accepted by `accept`, best match first.
+    pub fn search<F>(&self, query: &str, top_k: usize, accept: F) -> Vec<(&str, f32)>
+    where
+        F: Fn(&str) -> bool,
+    {
+        if self.lengths.is_empty() {
+            return Vec::new();
+        }
+        let count = self.lengths.len() as f32;
+        let average_length = self.total_length as f32 / count;
+
+        let mut scores = HashMap::<&str, f32>::new();
+        let mut terms = tokenize(query).collect::<Vec<_>>();
+        terms.sort();
+        terms.dedup();
+        for term in terms {
+            let Some(posting) = self.postings.get(&term) else {
+                continue;
+            };
+            let df = posting.len() as f32;
+            let idf = (1. + (count - df + 0.5) / (df + 0.5)).ln();
+            for (id, &tf) in posting {
+                let tf = tf as f32;
+                let length = self.lengths[id] as f32;
+                let norm = K1 * (1. - B + B * length / average_length.max(f32::EPSILON));
+                *scores.entry(id.as_str()).or_default() += idf * tf * (K1 + 1.) / (tf + norm);
+            }
+        }
+
+        let mut hits = scores
+            .into_iter()
+            .filter(|(id, _)| accept(id))
+            .collect::<Vec<_>>();
+        sort_ranking(&mut hits);
+        hits.truncate(top_k);
+        hits
+    }
+
+    /// Approximate bytes held by the postings.
+    pub fn memory_usage(&self) -> usize {
+        let postings = self
+            .postings
+            .iter()
+            .map(|(term, posting)| {
+                term.len()
+                    + posting
+                        .keys()
+                        .map(|id| id.len() + std::mem::size_of::<u32>())
+                        .sum::<usize>()
+            })
+            .sum::<usize>();
+        let lengths = self
+            .lengths
+            .keys()
+            .map(|id| id.len() + std::mem::size_of::<u32>())
+            .sum::<usize>();
+        postings + lengths
+    }
+}
+
+/// Best first, ties broken by id so the order is stable.
+fn sort_ranking(ranking: &mut [(&str, f32)]) {
+    ranking.sort_by(|a, b| {
+        b.1.partial_cmp(&a.1)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| a.0.cmp(b.0))
+    });
+}
+
+/// Merge two rankings, given best first with higher-is-better scores, into one.
+pub fn fuse<'a>(
+    vector: &[(&'a str, f32)],
+    keyword: &[(&'a str, f32)],
+    fusion: Fusion,
+) -> Vec<(&'a str, f32)> {
+    let mut fused = HashMap::<&str, f32>::new();
+    match fusion {
+        Fusion::Rrf { k } => {
+            for ranking in [vector, keyword] {
+                for (rank, (id, _)) in ranking.iter().enumerate() {
+                    *fused.entry(id).or_default() += 1. / (k + rank as f32 + 1.);
+                }
+            }
+        }
+        Fusion::Weighted { alpha } => {
+            for (ranking, weight) in [(vector, alpha), (keyword, 1. - alpha)] {
+                let min = ranking.iter().map(|h| h.1).fold(f32::MAX, f32::min);
+                let max = ranking.iter().map(|h| h.1).fold(f32::MIN, f32::max);
+                for (id, score) in ranking {
+                    let normalized = if max > min { (score - min) / (max - min) } else { 1. };
+                    *fused.entry(id).or_default() += weight * normalized;
+                }
+            }
+        }
+    }
+
+    let mut fused = fused.into_iter().collect::<Vec<_>>();
+    sort_ranking(&mut fused);
+    fused
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bm25_search() {
+        let mut index = Bm25Index::default();
+        index.insert("a", "Replacement filter for pump AB-1234");
+        index.insert("b", "Pump manual, see the pump filter section");
+        index.insert("c", "Unrelated text about cats");
+
+        let ids = |hits: Vec<(&str, f32)>| hits.into_iter().map(|h| h.0.to_string()).collect::<Vec<_>>();
+        assert_eq!(vec!["a"], ids(index.search("ab-1234", 10, |_| true)));
+        // Repeating the term outweighs the longer text.
+        assert_eq!(vec!["b", "a"], ids(index.search("pump", 10, |_| true)));
+        assert_eq!(vec!["a"], ids(index.search("pump", 10, |id| id != "b")));
+
+        index.remove("a", "Replacement filter for pump AB-1234");
+        assert!(index.search("ab-1234", 10, |_| true).is_empty());
+        assert_eq!(vec!["b"], ids(index.search("filter", 10, |_| true)));
+    }
+
+    #[test]
+    fn test_fuse() {
+        let vector = [("a", 0.9), ("b", 0.8), ("c", 0.1)];
+        let keyword = [("b", 12.), ("c", 3.)];
+
+        let ids = |hits: Vec<(&str, f32)>| hits.into_iter().map(|h| h.0.to_string()).collect::<Vec<_>>();
+        // `b` is ranked well by both.
+        assert_eq!(vec!["b", "c", "a"], ids(fuse(&vector, &keyword, Fusion::default())));
+        assert_eq!(vec!["a", "b", "c"], ids(fuse(&vector, &keyword, Fusion::Weighted { alpha: 1. })));
+        assert_eq!("b", fuse(&vector, &keyword, Fusion::Weighted { alpha: 0. })[0].0);
+    }
+}
@@ -0,0 +1,216 @@
+use crate::database::{DBError, Database, EmbeddingRecord};
+use crate::wal::{WalEntry, WalRecord};
+use futures::Stream;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::Arc;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::{info, warn};
+
+pub use tinyvector_types::dto::ChangeFormat;
+
+/// A mutation of one table, numbered by the sequence of the write-ahead log.
+///
+/// Sequence numbers grow with every event but are not contiguous, the mutations of the
+/// other tables are skipped.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct ChangeEvent {
+    pub seq: u64,
+    #[serde(flatten)]
+    pub change: Change,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Change {
+    Insert { records: Vec<EmbeddingRecord> },
+    /// The records replace the ones with the same id, if any.
+    Upsert { records: Vec<EmbeddingRecord> },
+    Delete { id: String },
+    /// The records whose `expires_at` is at most `now` are gone.
+    Expire { now: u64 },
+    /// The last event of the feed.
+    DropTable,
+}
+
+impl Change {
+    /// Name of the operation, the `event` field of the SSE stream.
+    pub fn op(&self) -> &'static str {
+        match self {
+            Change::Insert { .. } => "insert",
+            Change::Upsert { .. } => "upsert",
+            Change::Delete { .. } => "delete",
+            Change::Expire { .. } => "expire",
+            Change::DropTable => "drop_table",
+        }
+    }
+}
+
+/// The changes of a table, first the ones still in the write-ahead log then the live ones.
+///
+/// The feed ends when the table is dropped, when it falls more than the feed capacity behind
+/// or when the database is restored. A client resumes from the last `seq` it saw.
+#[derive(Debug)]
+pub struct ChangeFeed {
+    table_name: String,
+    history: VecDeque<WalRecord>,
+    receiver: broadcast::Receiver<Arc<WalRecord>>,
+    /// Sequence number of the last mutation handed out, of any table.
+    last: u64,
+    done: bool,
+}
+
+impl ChangeFeed {
+    /// Follow `table_name` from the mutation after `from_seq`, from now when `None`.
+    pub fn open(db: &Database, table_name: &str, from_seq: Option<u64>) -> Result<Self, DBError> {
+        if !db.has_table(table_name) {
+            return Err(DBError::TableDoesNotExist);
+        }
+        let (history, receiver, last) = match from_seq {
+            Some(seq) => {
+                let (history, receiver) = db.subscribe_after(seq, Some(table_name))?;
+                (history, receiver, seq)
+            }
+            None => {
+                let (seq, receiver) = db.subscribe();
+                (Vec::new(), receiver, seq)
+            }
+        };
+        Ok(Self {
+            table_name: table_name.to_string(),
+            history: history.into(),
+            receiver,
+            last,
+            done: false,
+        })
+    }
+
+    /// The next change of the table, `None` once the feed ended.
+    pub async fn next(&mut self) -> Option<ChangeEvent> {
+        while !self.done {
+            let record = match self.history.pop_front() {
+                Some(record) => record,
+                None => match self.receiver.recv().await {
+                    // Already part of the history.
+                    Ok(record) if record.seq <= self.last => continue,
+                    // Only the records of the followed table are copied.
+                    Ok(record) if record.entry.table_name() != self.table_name => {
+                        self.last = record.seq;
+                        continue;
+                    }
+                    Ok(record) => (*record).clone(),
+                    Err(RecvError::Lagged(count)) => {
+                        warn!("Ending a change feed {} mutations behind", count);
+                        return None;
+                    }
+                    Err(RecvError::Closed) => {
+                        info!("Ending the change feeds of the restored database");
+                        return None;
+                    }
+                },
+            };
+            self.last = record.seq;
+            if let Some(change) = self.change(record.entry) {
+                self.done = matches!(change, Change::DropTable);
+                return Some(ChangeEvent {
+                    seq: record.seq,
+                    change,
+                });
+            }
+        }
+        None
+    }
+
+    pub fn into_stream(self) -> impl Stream<Item = ChangeEvent> {
+        futures::stream::unfold(self, |mut feed| async move {
+            let event = feed.next().await?;
+            Some((event, feed))
+        })
+    }
+
+    /// The change `entry` makes to the followed table, if any.
+    fn change(&self, entry: WalEntry) -> Option<Change> {
+        let (table_name, change) = match entry {
+            WalEntry::InsertRecords {
+                table_name,
+                records,
+                upsert,
+            } => {
+                let change = match upsert {
+                    true => Change::Upsert { records },
+                    false => Change::Insert { records },
+                };
+                (table_name, change)
+            }
+            WalEntry::DeleteRecord { table_name, id } => (table_name, Change::Delete { id }),
+            WalEntry::ExpireRecords { table_name, now } => (table_name, Change::Expire { now }),
+            WalEntry::DropTable { table_name } => (table_name, Change::DropTable),
+            WalEntry::CreateTable { .. } | WalEntry::BindEmbedding { .. } => return None,
+        };
+        (table_name == self.table_name).then_some(change)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wal::WalOptions;
+    use futures::StreamExt;
+    use tempfile::TempDir;
+
+    fn record(id: &str) -> EmbeddingRecord {
+        EmbeddingRecord {
+            id: id.to_string(),
+            embedding: vec![1., 0.],
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_change_feed() {
+        let dir = TempDir::new().unwrap();
+        let db = Database::load_from_dir(dir.path(), WalOptions::default()).unwrap();
+        let e = ChangeFeed::open(&db, "t", None).err();
+        assert_eq!(Some(DBError::TableDoesNotExist), e);
+        db.create_table("t".to_string(), 2).unwrap();
+        db.create_table("other".to_string(), 2).unwrap();
+        let start = db.seq();
+
+        db.insert_record("t".to_string(), record("a")).unwrap();
+        db.insert_record("other".to_string(), record("x")).unwrap();
+        let live = ChangeFeed::open(&db, "t", None).unwrap();
+        db.upsert_records("t".to_string(), vec![record("a")]).unwrap();
+        db.delete_record("t".to_string(), "a".to_string()).unwrap();
+        // The history of the log, then the live changes, up to the drop.
+        let resumed = ChangeFeed::open(&db, "t", Some(start)).unwrap();
+        db.drop_table("t".to_string()).unwrap();
+
+        let ops = |events: Vec<ChangeEvent>| {
+            events.iter().map(|e| (e.seq, e.change.op())).collect::<Vec<_>>()
+        };
+        let expected = vec![
+            (start + 1, "insert"),
+            (start + 3, "upsert"),
+            (start + 4, "delete"),
+            (start + 5, "drop_table"),
+        ];
+        assert_eq!(expected, ops(resumed.into_stream().collect().await));
+        assert_eq!(expected[1..], ops(live.into_stream().collect().await));
+
+        // Compacted into the snapshot.
+        db.create_table("t".to_string(), 2).unwrap();
+        db.checkpoint().unwrap();
+        let e = ChangeFeed::open(&db, "t", Some(start)).err();
+        assert_eq!(Some(DBError::ChangesUnavailable), e);
+        // A cursor from the future is a bad request rather than a lost history.
+        let e = ChangeFeed::open(&db, "t", Some(db.seq() + 1)).err();
+        assert!(matches!(e, Some(DBError::InvalidInput(_))));
+        let mut feed = ChangeFeed::open(&db, "t", Some(db.seq())).unwrap();
+        db.insert_record("t".to_string(), record("b")).unwrap();
+        let json = serde_json::to_value(feed.next().await.unwrap()).unwrap();
+        assert_eq!("insert", json["op"]);
+        assert_eq!(db.seq(), json["seq"]);
+        assert_eq!("b", json["records"][0]["id"]);
+    }
+}
@@ -0,0 +1,270 @@
+use crate::auth::AuthSettings;
+use crate::embedding::EmbeddingSettings;
+use crate::replication::ReplicationSettings;
+use crate::snapshot::SnapshotOptions;
+use crate::wal::{SyncPolicy, WalOptions};
+use anyhow::{Context, Result};
+use clap::{Parser, Subcommand};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+
+/// Command line of the server, every flag can also be set through its environment variable
+/// and takes precedence over the config file.
+#[derive(Debug, Default, Parser)]
+#[command(version, about = "A tiny vector database")]
+pub struct Cli {
+    /// TOML config file.
+    #[arg(short, long, env = "TINYVECTOR_CONFIG")]
+    pub config: Option<PathBuf>,
+    /// Directory of the snapshot and the write-ahead log.
+    #[arg(long, env = "TINYVECTOR_STORAGE_DIR")]
+    pub storage_dir: Option<PathBuf>,
+    /// Address the HTTP server listens on.
+    #[arg(long, env = "TINYVECTOR_BIND")]
+    pub bind: Option<SocketAddr>,
+    /// Address the binary protocol listens on.
+    #[arg(long, env = "TINYVECTOR_BINARY_BIND")]
+    pub binary_bind: Option<SocketAddr>,
+    /// Threads of the async runtime serving requests.
+    #[arg(long, env = "TINYVECTOR_WORKER_THREADS")]
+    pub worker_threads: Option<usize>,
+    /// Threads of the rayon pool scoring records.
+    #[arg(long, env = "TINYVECTOR_SEARCH_THREADS")]
+    pub search_threads: Option<usize>,
+    /// Seconds between periodic snapshots, 0 disables them.
+    #[arg(long, env = "TINYVECTOR_SNAPSHOT_INTERVAL")]
+    pub snapshot_interval_secs: Option<u64>,
+    /// Log filter, e.g. `info` or `tinyvector=debug,tower_http=info`.
+    #[arg(long, env = "TINYVECTOR_LOG")]
+    pub log_level: Option<String>,
+    /// When the write-ahead log is fsynced: `always`, `deferred` or `deferred:<max_batch>`.
+    #[arg(long, env = "TINYVECTOR_WAL_SYNC")]
+    pub wal_sync: Option<SyncPolicy>,
+    /// Size in bytes of the write-ahead log beyond which it is compacted into a snapshot.
+    #[arg(long, env = "TINYVECTOR_WAL_COMPACT_THRESHOLD")]
+    pub wal_compact_threshold: Option<u64>,
+    /// API key file.
+    #[arg(long, env = "TINYVECTOR_AUTH_FILE")]
+    pub auth_file: Option<PathBuf>,
+    /// Serve every request without an API key.
+    #[arg(long, env = "TINYVECTOR_AUTH_DISABLED")]
+    pub auth_disabled: bool,
+    /// Binary protocol address of a leader to follow, as a read-only replica.
+    #[arg(long, env = "TINYVECTOR_LEADER")]
+    pub leader: Option<String>,
+    /// Serve requests when absent.
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Replace the content of the storage dir with a snapshot file, the server must be stopped.
+    Restore {
+        /// Snapshot file taken through `/system/snapshot`.
+        snapshot: PathBuf,
+    },
+    /// Replay a query set against the exact top k and report recall@k and latency percentiles,
+    /// the server must be stopped.
+    Recall {
+        table: String,
+        /// One query per line, a JSON embedding or `{"query_embedding": [..], "filter": {..}}`.
+        queries: PathBuf,
+        #[arg(long, default_value_t = 10)]
+        top_k: usize,
+        /// Override the `ef_search` of the HNSW index.
+        #[arg(long)]
+        ef_search: Option<usize>,
+    },
+}
+
+/// Server settings, see `tinyvector.example.toml`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "snake_case")]
+pub struct Config {
+    pub storage_dir: PathBuf,
+    pub bind: SocketAddr,
+    /// Address of the binary protocol, only HTTP is served when absent.
+    pub binary_bind: Option<SocketAddr>,
+    /// `None` uses one thread per core.
+    pub worker_threads: Option<usize>,
+    /// `None` uses one thread per core.
+    pub search_threads: Option<usize>,
+    /// Seconds between periodic snapshots, 0 only snapshots on compaction and shutdown.
+    pub snapshot_interval_secs: u64,
+    pub log_level: String,
+    pub wal: WalOptions,
+    pub auth: AuthSettings,
+    pub snapshots: SnapshotOptions,
+    /// Provider of the tables bound to an embedding model, text is not embedded without it.
+    pub embedding: Option<EmbeddingSettings>,
+    /// Leader followed by this server, which then only serves reads.
+    pub replication: Option<ReplicationSettings>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            storage_dir: PathBuf::from("./storage"),
+            bind: SocketAddr::from(([0, 0, 0, 0], 3000)),
+            binary_bind: None,
+            worker_threads: None,
+            search_threads: None,
+            snapshot_interval_secs: 0,
+            log_level: "info".to_string(),
+            wal: WalOptions::default(),
+            auth: AuthSettings::default(),
+            snapshots: SnapshotOptions::default(),
+            embedding: None,
+            replication: None,
+        }
+    }
+}
+
+impl Config {
+    /// Read the config file named on the command line, if any, and apply the overrides.
+    pub fn load(cli: &Cli) -> Result<Self> {
+        let mut config = match &cli.config {
+            Some(path) => {
+                let data = fs::read_to_string(path)
+                    .with_context(|| format!("Failed to read config file {:?}", path))?;
+                toml::from_str(&data).with_context(|| format!("Invalid config file {:?}", path))?
+            }
+            None => Self::default(),
+        };
+        config.apply(cli);
+        Ok(config)
+    }
+
+    fn apply(&mut self, cli: &Cli) {
+        if let Some(storage_dir) = &cli.storage_dir {
+            self.storage_dir = storage_dir.clone();
+        }
+        if let Some(bind) = cli.bind {
+            self.bind = bind;
+        }
+        if cli.binary_bind.is_some() {
+            self.binary_bind = cli.binary_bind;
+        }
+        if cli.worker_threads.is_some() {
+            self.worker_threads = cli.worker_threads;
+        }
+        if cli.search_threads.is_some() {
+            self.search_threads = cli.search_threads;
+        }
+        if let Some(interval) = cli.snapshot_interval_secs {
+            self.snapshot_interval_secs = interval;
+        }
+        if let Some(log_level) = &cli.log_level {
+            self.log_level = log_level.clone();
+        }
+        if let Some(sync) = cli.wal_sync {
+            self.wal.sync = sync;
+        }
+        if let Some(threshold) = cli.wal_compact_threshold {
+            self.wal.compact_threshold = threshold;
+        }
+        if let Some(auth_file) = &cli.auth_file {
+            self.auth.file = auth_file.clone();
+        }
+        if cli.auth_disabled {
+            self.auth.disabled = true;
+        }
+        if let Some(leader) = &cli.leader {
+            match &mut self.replication {
+                Some(replication) => replication.leader = leader.clone(),
+                None => {
+                    self.replication = Some(ReplicationSettings {
+                        leader: leader.clone(),
+                        api_key: None,
+                    })
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::Scope;
+
+    #[test]
+    fn test_config_file_and_overrides() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tinyvector.toml");
+        fs::write(
+            &path,
+            r#"
+            storage_dir = "/var/lib/tinyvector"
+            bind = "127.0.0.1:4000"
+            binary_bind = "127.0.0.1:4100"
+            search_threads = 4
+            snapshot_interval_secs = 300
+
+            [wal]
+            sync = { group_commit = { max_batch = 64 } }
+
+            [[auth.keys]]
+            key = "secret"
+            scope = "admin"
+
+            [embedding]
+            base_url = "http://127.0.0.1:8080/v1"
+
+            [replication]
+            leader = "10.0.0.1:3001"
+            api_key = "replica"
+            "#,
+        )
+        .unwrap();
+
+        let cli = Cli::try_parse_from([
+            "tinyvector",
+            "--config",
+            path.to_str().unwrap(),
+            "--bind",
+            "127.0.0.1:4001",
+            "--leader",
+            "10.0.0.2:3001",
+            "--auth-disabled",
+            "--wal-compact-threshold",
+            "1024",
+        ])
+        .unwrap();
+        let config = Config::load(&cli).unwrap();
+        assert_eq!(PathBuf::from("/var/lib/tinyvector"), config.storage_dir);
+        assert_eq!("127.0.0.1:4001".parse::<SocketAddr>().unwrap(), config.bind);
+        assert_eq!(Some("127.0.0.1:4100".parse().unwrap()), config.binary_bind);
+        assert_eq!(Some(4), config.search_threads);
+        assert_eq!(None, config.worker_threads);
+        assert_eq!(300, config.snapshot_interval_secs);
+        assert_eq!("info", config.log_level);
+        assert_eq!(SyncPolicy::Deferred { max_batch: 64 }, config.wal.sync);
+        assert_eq!(1024, config.wal.compact_threshold);
+        assert_eq!(Scope::Admin, config.auth.keys[0].scope);
+        assert!(config.auth.disabled);
+        assert_eq!(SnapshotOptions::default().retain, config.snapshots.retain);
+        let embedding = config.embedding.unwrap();
+        assert_eq!("http://127.0.0.1:8080/v1", embedding.base_url);
+        assert_eq!(EmbeddingSettings::default().batch_size, embedding.batch_size);
+        let replication = config.replication.unwrap();
+        assert_eq!("10.0.0.2:3001", replication.leader);
+        assert_eq!(Some("replica".to_string()), replication.api_key);
+
+        // An explicitly named config file has to exist.
+        let cli = Cli {
+            config: Some(dir.path().join("missing.toml")),
+            ..Default::default()
+        };
+        assert!(Config::load(&cli).is_err());
+
+        let cli = Cli::try_parse_from(["tinyvector", "--wal-sync", "deferred:8"]).unwrap();
+        let config = Config::load(&cli).unwrap();
+        assert_eq!(SyncPolicy::Deferred { max_batch: 8 }, config.wal.sync);
+        assert!(Cli::try_parse_from(["tinyvector", "--wal-sync", "deferred:0"]).is_err());
+        assert!(Cli::try_parse_from(["tinyvector", "--wal-sync", "never"]).is_err());
+    }
+}
@@ -1,67 +1,685 @@
-use crate::similarity::{get_distance_fn, Distance, ScoreIndex};
-use anyhow::{Context, Result};
+use crate::bm25::{fuse, Bm25Index};
+use crate::embedding::EmbeddingBinding;
+use crate::explain::{self, QueryExplain, QueryPlan};
+use crate::hnsw::HnswIndex;
+use crate::metadata::{Filter, Metadata};
+use crate::quantization::{self, QuantizedVectors, Quantizer};
+use crate::segment::{self, Segments, SEGMENT_DIR};
+use crate::snapshot;
+use crate::telemetry::{timed_lock, METRICS};
+use crate::similarity::{
+    cosine_from_dot, dot_product, get_distance_fn, norm, normalize, Distance, ScoreIndex,
+};
+use crate::wal::{sync_dir, Wal, WalEntry, WalOptions, WalReader, WalRecord};
+use anyhow::{anyhow, bail, Result};
 use axum::Extension;
-use lazy_static::lazy_static;
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
-use std::collections::{BinaryHeap, HashMap};
-use std::fs;
-use std::path::PathBuf;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BinaryHeap, HashMap, HashSet};
+use std::ops::Bound;
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
+use parking_lot::{Mutex, RwLock};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
-use std::time::Instant;
-use tokio::sync::RwLock;
-use tracing::{debug, info};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::broadcast;
+use tracing::{debug, error, info, warn};
+
+pub use tinyvector_types::database::{
+    CombinedRecord, DBError, EmbeddingRecord, Fusion, HnswConfig, HybridRecord, IndexType,
+    MemoryUsage, QuantizationConfig, QuantizationKind, RecordPage, ScoredRecord, SegmentConfig,
+    Storage, TableContent, TableOptions, TableStats, VectorField, VectorQuery, MAIN_FIELD,
+};
+
+/// File name of the snapshot inside the storage dir.
+const SNAPSHOT_FILE: &str = "db";
+/// File name of the write-ahead log inside the storage dir.
+const WAL_FILE: &str = "wal";
+/// File name of the lock taken by the process using the storage dir, see `lock_dir`.
+const LOCK_FILE: &str = "lock";
+/// Largest page of a record listing.
+pub const MAX_PAGE_SIZE: usize = 1000;
+/// Mutations a subscriber of the change feed may fall behind by before it is dropped.
+const FEED_CAPACITY: usize = 4096;
+/// Largest history `subscribe_after` reads back from the log, in bytes of its entries.
+const MAX_HISTORY_SIZE: usize = 64 << 20;
+/// Times `open_read_only` reads the files of a storage dir changing underneath it.
+const READ_ONLY_ATTEMPTS: usize = 5;
+
+pub type DbExtension = Extension<Arc<Database>>;
+
+/// A table shared between the database and the requests using it.
+pub type TableRef = Arc<RwLock<Table>>;
 
-lazy_static! {
-    /// The path to the database
-    pub static ref STORE_PATH: PathBuf = PathBuf::from("./storage/db");
+/// The vector database
+///
+/// Every table has its own lock so requests on different tables do not wait for each other.
+/// Locks are taken in the order `tables`, a single table, `log`: record changes hold the
+/// `tables` read lock so their table can not be dropped under them, creating and dropping
+/// tables takes it exclusively.
+#[derive(Debug, Default)]
+pub struct Database {
+    tables: RwLock<HashMap<String, TableRef>>,
+    log: Mutex<Log>,
+    /// Set on followers, only replicated mutations are applied.
+    read_only: AtomicBool,
+    /// Where segmented tables keep their files, `None` for an in-memory database.
+    segment_dir: Option<PathBuf>,
+    /// Set by `open_read_only`, nothing is written to the storage dir then.
+    read_only_dir: bool,
+    /// Held while segments are merged or a restore opens its tables, so no new file is
+    /// collected before it is used.
+    merging: Mutex<()>,
+    /// Held while a snapshot is written, so that an older one never replaces a newer one.
+    checkpointing: Mutex<()>,
 }
 
-#[derive(Debug, PartialEq, Eq, thiserror::Error, Serialize, Deserialize)]
-pub enum DBError {
-    /// The table already exists
-    #[error("The table already exists")]
-    TableAlreadyExists,
-    /// The table does not exist
-    #[error("The table does not exist")]
-    TableDoesNotExist,
-    /// The record does not exist
-    #[error("The record does not exist")]
-    RecordDoesNotExist,
-    /// The record does exist
-    #[error("The record does exist")]
-    RecordExist,
-    /// Dimension mismatch
-    #[error("Dimension mismatch")]
-    DimensionMismatch,
+/// Sequence number of the last applied mutation along with the files persisting them.
+#[derive(Debug, Default)]
+struct Log {
+    seq: u64,
+    /// `None` for an in-memory database.
+    store: Option<Store>,
+    /// Mutations are published once someone subscribed, see `Database::subscribe`.
+    feed: Option<broadcast::Sender<Arc<WalRecord>>>,
 }
 
-pub type DbExtension = Extension<Arc<RwLock<Database>>>;
+/// Layout of the snapshot file.
+#[derive(Serialize)]
+struct SnapshotRef<'a> {
+    tables: HashMap<&'a str, &'a Table>,
+    seq: u64,
+}
 
-/// The vector database
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Database {
-    pub tables: HashMap<String, Table>,
+/// Owned counterpart of `SnapshotRef`, read on load.
+#[derive(Deserialize)]
+struct Snapshot {
+    tables: HashMap<String, Table>,
+    seq: u64,
+}
+
+impl Snapshot {
+    /// Read a snapshot file, converting the one of the first release.
+    fn decode(data: Vec<u8>) -> Result<Self> {
+        let (version, payload) = snapshot::decode(data)?;
+        Self::deserialize(version, payload.as_slice())
+    }
+
+    /// Read a snapshot file from disk without loading its bytes first.
+    fn read(mut file: File) -> Result<Self> {
+        let version = snapshot::check_file(&mut file)?;
+        Self::deserialize(version, BufReader::new(file))
+    }
+
+    fn deserialize(version: u32, payload: impl Read) -> Result<Self> {
+        match version {
+            0 => Ok(bincode::deserialize_from::<_, SnapshotV0>(payload)?.into()),
+            _ => Ok(bincode::deserialize_from(payload)?),
+        }
+    }
+}
+
+/// Layout of the `storage/db` of the first release, written without a header: the tables
+/// with their records and nothing else.
+#[derive(Serialize, Deserialize)]
+struct SnapshotV0 {
+    tables: HashMap<String, TableV0>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct TableV0 {
+    dimension: usize,
+    records: Vec<EmbeddingRecordV0>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct EmbeddingRecordV0 {
+    id: String,
+    embedding: Vec<f32>,
+}
+
+impl From<SnapshotV0> for Snapshot {
+    fn from(snapshot: SnapshotV0) -> Self {
+        let tables = snapshot
+            .tables
+            .into_iter()
+            .map(|(name, table)| {
+                let mut converted = Table::new(table.dimension, TableOptions::default());
+                converted.records = table
+                    .records
+                    .into_iter()
+                    .map(|record| EmbeddingRecord {
+                        id: record.id,
+                        embedding: record.embedding,
+                        ..Default::default()
+                    })
+                    .collect();
+                (name, converted)
+            })
+            .collect();
+        // Mutations were not numbered, the log starts over.
+        Self { tables, seq: 0 }
+    }
+}
+
+/// Snapshot and write-ahead log of a persistent database.
+#[derive(Debug)]
+struct Store {
+    snapshot_path: PathBuf,
+    /// Size of the last snapshot, the log may grow as large before it is compacted.
+    snapshot_size: u64,
+    /// Sequence number covered by the last snapshot.
+    snapshot_seq: u64,
+    wal: Wal,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct Table {
     /// Dimension of the vectors in the collection
     pub dimension: usize,
-    /// Embeddings in the collection
+    /// Embeddings in the collection, only the memtable of a segmented table. The embeddings
+    /// are left empty once a quantizer holds them
     pub records: Vec<EmbeddingRecord>,
+    /// Approximate nearest-neighbour index, `None` for brute-force only tables
+    pub index: Option<HnswIndex>,
+    /// Quantized copy of the vectors for the scan, `None` for full precision only tables
+    pub quantized: Option<QuantizedVectors>,
+    /// Model embedding the text of records and queries sent without a vector
+    pub embedding: Option<EmbeddingBinding>,
+    /// Named vectors the records may carry next to `embedding`
+    pub fields: BTreeMap<String, VectorField>,
+    /// Files the memtable is flushed into, `None` for tables held in memory
+    segments: Option<Segments>,
+    /// Lifetime of the records inserted without an expiry
+    pub ttl_secs: Option<u64>,
+    /// Metric of the queries that do not name one, the index is built for it
+    pub distance: Distance,
+    /// Position of each record in `records` by id, ordered for the cursor of `list`, rebuilt
+    /// on load
+    #[serde(skip)]
+    id_index: BTreeMap<String, usize>,
+    /// Norm of each record in `records` for cosine, rebuilt on load
+    #[serde(skip)]
+    norms: Vec<f32>,
+    /// Keyword index over the record text, rebuilt on load
+    #[serde(skip)]
+    text_index: Bm25Index,
+}
+
+/// Borrowing counterpart of `Table`, with the embeddings held by the quantizer put back into
+/// the records so that it encodes the same way.
+#[derive(Serialize)]
+#[serde(rename = "Table")]
+struct TableView<'a> {
+    dimension: usize,
+    records: RecordsRef<'a>,
+    index: &'a Option<HnswIndex>,
+    quantized: &'a Option<QuantizedVectors>,
+    embedding: &'a Option<EmbeddingBinding>,
+    fields: &'a BTreeMap<String, VectorField>,
+    segments: &'a Option<Segments>,
+    ttl_secs: Option<u64>,
+    distance: Distance,
+}
+
+struct RecordsRef<'a>(&'a Table);
+
+impl Serialize for RecordsRef<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let table = self.0;
+        serializer.collect_seq(
+            (0..table.records.len())
+                .map(|i| EmbeddingRecordRef::new(&table.records[i], table.vector(i))),
+        )
+    }
+}
+
+impl Serialize for Table {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        TableView {
+            dimension: self.dimension,
+            records: RecordsRef(self),
+            index: &self.index,
+            quantized: &self.quantized,
+            embedding: &self.embedding,
+            fields: &self.fields,
+            segments: &self.segments,
+            ttl_secs: self.ttl_secs,
+            distance: self.distance,
+        }
+        .serialize(serializer)
+    }
+}
+
+/// A `VectorQuery` checked against the table, along with its distance.
+#[derive(Debug, Clone, Copy)]
+pub struct FieldQuery<'a> {
+    pub field: &'a str,
+    pub embedding: &'a [f32],
+    pub weight: f32,
+    pub distance: Distance,
+}
+
+/// Per query knobs of the search.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SearchOptions<'a> {
+    /// Skip the approximate index and score every record.
+    pub exact: bool,
+    /// Override the `ef_search` of the HNSW index.
+    pub ef_search: Option<usize>,
+    /// Only records whose metadata matches are candidates.
+    pub filter: Option<&'a Filter>,
+    /// Drop hits scoring below this, meant for the similarity metrics.
+    pub min_score: Option<f32>,
+    /// Drop hits farther away than this, meant for the distance metrics.
+    pub max_distance: Option<f32>,
+    /// Re-rank the candidates of a quantized scan with the full-precision vectors, on by default.
+    pub rerank: Option<bool>,
+    /// Filled in with how the query ran.
+    pub explain: Option<&'a Mutex<QueryExplain>>,
+}
+
+impl SearchOptions<'_> {
+    /// Reject a cutoff that does not fit `distance`, `min_score` is for the metrics where higher
+    /// is better and `max_distance` for the others.
+    pub fn check_cutoffs(&self, distance: Distance) -> Result<(), DBError> {
+        let (cutoff, name) = match distance.higher_is_better() {
+            true => (self.max_distance, "max_distance"),
+            false => (self.min_score, "min_score"),
+        };
+        match cutoff {
+            Some(_) => Err(DBError::InvalidInput(format!(
+                "{} does not apply to the {:?} distance",
+                name, distance
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    /// Whether a hit with `score` survives the cutoffs.
+    pub fn passes(&self, score: f32) -> bool {
+        self.min_score.is_none_or(|min| score >= min)
+            && self.max_distance.is_none_or(|max| score <= max)
+    }
+
+    /// Add to the explanation of the query, if it asked for one.
+    pub fn note(&self, note: impl FnOnce(&mut QueryExplain)) {
+        if let Some(explain) = self.explain {
+            note(&mut explain.lock());
+        }
+    }
+
+    /// Count a scored candidate when explaining, scans share one counter across threads.
+    pub(crate) fn count(&self, counter: &AtomicUsize) {
+        if self.explain.is_some() {
+            counter.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Keyword side of a hybrid query.
+#[derive(Debug, Clone, Copy)]
+pub struct HybridOptions<'a> {
+    pub query_text: &'a str,
+    pub fusion: Fusion,
+    /// Hits taken from each ranking before they are fused.
+    pub candidates: usize,
+}
+
+/// Borrowing counterpart of `EmbeddingRecord` with the embedding kept elsewhere, encodes the
+/// same way.
+#[derive(Serialize)]
+pub(crate) struct EmbeddingRecordRef<'a> {
+    id: &'a str,
+    embedding: &'a [f32],
+    metadata: &'a Metadata,
+    text: &'a Option<String>,
+    vectors: &'a HashMap<String, Vec<f32>>,
+    expires_at: &'a Option<u64>,
+}
+
+impl<'a> EmbeddingRecordRef<'a> {
+    pub fn new(record: &'a EmbeddingRecord, embedding: &'a [f32]) -> Self {
+        Self {
+            id: &record.id,
+            embedding,
+            metadata: &record.metadata,
+            text: &record.text,
+            vectors: &record.vectors,
+            expires_at: &record.expires_at,
+        }
+    }
+}
+
+/// Current unix time in seconds, the clock of record expiry.
+pub fn unix_now() -> u64 {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    now.as_secs()
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "snake_case")]
-pub struct EmbeddingRecord {
-    /// Value of the embedding.
-    pub id: String,
-    /// The embedding.
-    pub embedding: Vec<f32>,
+/// Take the exclusive lock of the storage dir `dir`, held as long as the returned file is open.
+///
+/// Fails while another process, a server or a command line tool, uses the dir.
+pub fn lock_dir(dir: &Path) -> Result<File> {
+    fs::create_dir_all(dir)?;
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(dir.join(LOCK_FILE))?;
+    match file.try_lock() {
+        Ok(()) => Ok(file),
+        Err(TryLockError::WouldBlock) => bail!("{:?} is in use by another process", dir),
+        Err(TryLockError::Error(e)) => Err(e.into()),
+    }
 }
 
 impl Table {
+    fn new(dimension: usize, options: TableOptions) -> Self {
+        let distance = options.default_distance();
+        let index = match options.index {
+            IndexType::Flat => None,
+            IndexType::Hnsw(config) => Some(HnswIndex::new(HnswConfig { distance, ..config })),
+        };
+        Self {
+            dimension,
+            records: Vec::new(),
+            index,
+            quantized: options
+                .quantization
+                .map(|config| QuantizedVectors::new(config, dimension)),
+            embedding: None,
+            fields: BTreeMap::new(),
+            segments: match options.storage {
+                Storage::Memory => None,
+                Storage::Segments(config) => Some(Segments::new(config)),
+            },
+            ttl_secs: options.ttl_secs,
+            distance,
+            id_index: BTreeMap::new(),
+            norms: Vec::new(),
+            text_index: Bm25Index::default(),
+        }
+    }
+
+    /// Look a record up by id, records of a segment are decoded. Expired records are gone.
+    pub fn get(&self, id: &str) -> Result<Option<Cow<'_, EmbeddingRecord>>> {
+        match (self.get_in_memory(id), &self.segments) {
+            (None, Some(segments)) => Ok(segments.get(id, unix_now())?.map(Cow::Owned)),
+            (record, _) => Ok(record),
+        }
+    }
+
+    /// Look a record of the memtable up, enough for the tables with an index or a text query
+    /// which never keep segments.
+    fn get_in_memory(&self, id: &str) -> Option<Cow<'_, EmbeddingRecord>> {
+        let i = *self.id_index.get(id)?;
+        (!self.records[i].is_expired(unix_now())).then(|| self.record(i))
+    }
+
+    /// The embedding of the record at `position`, held by the quantizer once trained.
+    fn vector(&self, position: usize) -> &[f32] {
+        let vector = self.quantized.as_ref().and_then(|q| q.vector(position));
+        vector.unwrap_or(&self.records[position].embedding)
+    }
+
+    /// The record at `position` along with its embedding.
+    fn record(&self, position: usize) -> Cow<'_, EmbeddingRecord> {
+        let record = &self.records[position];
+        match self.quantized.as_ref().and_then(|q| q.vector(position)) {
+            Some(vector) => Cow::Owned(EmbeddingRecord {
+                embedding: vector.to_vec(),
+                ..record.clone()
+            }),
+            None => Cow::Borrowed(record),
+        }
+    }
+
+    pub fn contains(&self, id: &str) -> Result<bool> {
+        let now = unix_now();
+        match (self.id_index.get(id), &self.segments) {
+            (Some(&i), _) => Ok(!self.records[i].is_expired(now)),
+            (None, Some(segments)) => segments.contains(id, now),
+            (None, None) => Ok(false),
+        }
+    }
+
+    /// Whether some records expired by `now` are still stored.
+    pub fn has_expired(&self, now: u64) -> bool {
+        self.records.iter().any(|r| r.is_expired(now))
+            || self.segments.as_ref().is_some_and(|s| s.has_expired(now))
+    }
+
+    /// Remove the records expired by `now`, returns how many.
+    fn expire(&mut self, now: u64) -> usize {
+        let expired = self
+            .records
+            .iter()
+            .filter(|r| r.is_expired(now))
+            .map(|r| r.id.clone())
+            .collect::<Vec<_>>();
+        for id in &expired {
+            self.remove(id);
+        }
+        expired.len() + self.segments.as_mut().map_or(0, |s| s.expire(now))
+    }
+
+    /// Number of records, in memory and in segments. Expired records are not counted, even
+    /// before they are removed.
+    pub fn count(&self) -> usize {
+        let now = unix_now();
+        let held = self.records.iter().filter(|r| !r.is_expired(now)).count();
+        held + self.segments.as_ref().map_or(0, |s| s.count(now))
+    }
+
+    /// Insert a record, replacing the one with the same id if any.
+    fn upsert(&mut self, mut record: EmbeddingRecord) {
+        // The copy in a segment is superseded by the one in the memtable, `validate_records`
+        // already looked it up.
+        if let Some(Err(e)) = self.segments.as_mut().map(|s| s.remove(&record.id)) {
+            error!("Failed to replace {} in the segments: {:?}", record.id, e);
+        }
+        if let Some(index) = self.index.as_mut() {
+            index.insert(record.id.clone(), record.embedding.clone());
+        }
+        // Drop the terms of the replaced text before adding the new ones.
+        let old_text = self.id_index.get(&record.id).and_then(|&i| self.records[i].text.as_deref());
+        if let Some(text) = old_text {
+            self.text_index.remove(&record.id, text);
+        }
+        if let Some(text) = &record.text {
+            self.text_index.insert(&record.id, text);
+        }
+        let position = self.id_index.get(&record.id).copied();
+        if let Some(quantized) = self.quantized.as_mut() {
+            match position {
+                Some(i) => quantized.set(i, &record.embedding),
+                None => quantized.push(&record.embedding),
+            }
+        }
+        let record_norm = norm(&record.embedding);
+        if self.quantized.as_ref().is_some_and(QuantizedVectors::holds_vectors) {
+            record.embedding = Vec::new();
+        }
+        match position {
+            Some(i) => {
+                self.norms[i] = record_norm;
+                self.records[i] = record;
+            }
+            None => {
+                self.id_index.insert(record.id.clone(), self.records.len());
+                self.norms.push(record_norm);
+                self.records.push(record);
+            }
+        }
+        self.maybe_flush();
+    }
+
+    /// Flush the memtable of a segmented table once it is full, a failed flush is retried on
+    /// the next write.
+    fn maybe_flush(&mut self) {
+        let full = self
+            .segments
+            .as_ref()
+            .is_some_and(|s| !s.is_read_only() && self.records.len() >= s.config().memtable_size);
+        if full {
+            if let Err(e) = self.flush() {
+                error!("Failed to flush the memtable: {:?}", e);
+            }
+        }
+    }
+
+    /// Write the memtable into a new segment.
+    fn flush(&mut self) -> Result<()> {
+        let Some(segments) = self.segments.as_mut() else {
+            return Ok(());
+        };
+        let instant = Instant::now();
+        segments.flush(self.dimension, &self.records)?;
+        info!("Flushed {} records into a segment in {:?}", self.records.len(), instant.elapsed());
+        self.records.clear();
+        self.rebuild_caches();
+        Ok(())
+    }
+
+    /// Map the segments of a segmented table from `dir`, flushing an oversized memtable such as
+    /// the one of a restored snapshot unless `read_only`.
+    fn open_segments(&mut self, dir: Option<&Path>, read_only: bool) -> Result<()> {
+        let Some(segments) = self.segments.as_mut() else {
+            return Ok(());
+        };
+        let dir = dir.ok_or_else(|| anyhow!("Segmented tables need a storage dir"))?;
+        if read_only {
+            return segments.open_read_only(dir, self.dimension);
+        }
+        segments.open(dir, self.dimension)?;
+        if self.records.len() >= segments.config().memtable_size {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// A copy holding every record in `records`, so that it does not refer to segment files.
+    ///
+    /// Meant for a clone taken under the table lock: it keeps the segments mapped, so their
+    /// records are decoded without holding the lock.
+    pub fn into_exported(mut self) -> Result<Table> {
+        if let Some(segments) = self.segments.take() {
+            for record in segments.records() {
+                self.records.push(record?);
+            }
+            self.segments = Some(segments.detached());
+            self.rebuild_caches();
+        }
+        Ok(self)
+    }
+
+    /// Let the quantizer take over the embeddings of the records once trained, keeping them
+    /// in a file of `dir` if given.
+    fn open_vectors(&mut self, dir: Option<&Path>) {
+        let Some(quantized) = self.quantized.as_mut() else {
+            return;
+        };
+        let taken = quantized.quantizer().is_some() && !quantized.holds_vectors();
+        let vectors = self.records.iter_mut().filter(|_| taken);
+        quantized.open(dir, vectors.map(|r| std::mem::take(&mut r.embedding)));
+    }
+
+    /// A sample of `train_size` embeddings to train the quantizer with, `None` until the table
+    /// holds that many records or once the quantizer is trained.
+    fn training_sample(&self) -> Option<(QuantizationConfig, Vec<Vec<f32>>)> {
+        let quantized = self.quantized.as_ref()?;
+        let train_size = quantized.config().train_size.max(1);
+        if quantized.quantizer().is_some() || self.records.len() < train_size {
+            return None;
+        }
+        let step = self.records.len() / train_size;
+        let sample = self.records.iter().step_by(step).take(train_size);
+        Some((*quantized.config(), sample.map(|r| r.embedding.clone()).collect()))
+    }
+
+    /// Encode the records with a trained quantizer, which takes over their embeddings.
+    fn install_quantizer(&mut self, quantizer: Quantizer) {
+        let Some(quantized) = self.quantized.as_mut().filter(|q| q.quantizer().is_none()) else {
+            return;
+        };
+        let vectors = self.records.iter().map(|r| r.embedding.as_slice()).collect::<Vec<_>>();
+        quantized.install(quantizer, &vectors);
+        for record in &mut self.records {
+            record.embedding = Vec::new();
+        }
+    }
+
+    /// Remove a record, returns whether it existed.
+    fn remove(&mut self, id: &str) -> bool {
+        let Some(i) = self.id_index.remove(id) else {
+            // Looked up by `validate_records` already.
+            let removed = self.segments.as_mut().map_or(Ok(false), |s| s.remove(id));
+            return removed.unwrap_or_else(|e| {
+                error!("Failed to remove {} from the segments: {:?}", id, e);
+                false
+            });
+        };
+        if let Some(index) = self.index.as_mut() {
+            index.remove(id);
+        }
+        // Move the last record into the hole, so only its position changes.
+        let record = self.records.swap_remove(i);
+        self.norms.swap_remove(i);
+        if let Some(text) = &record.text {
+            self.text_index.remove(id, text);
+        }
+        if let Some(quantized) = self.quantized.as_mut() {
+            quantized.swap_remove(i);
+        }
+        if let Some(moved) = self.records.get(i) {
+            self.id_index.insert(moved.id.clone(), i);
+        }
+        true
+    }
+
+    /// Rebuild the state derived from `records` that is not part of the snapshot.
+    fn rebuild_caches(&mut self) {
+        self.id_index = self
+            .records
+            .iter()
+            .enumerate()
+            .map(|(i, r)| (r.id.clone(), i))
+            .collect();
+        let positions = 0..self.records.len();
+        self.norms = positions.into_par_iter().map(|i| norm(self.vector(i))).collect();
+        self.text_index = Bm25Index::default();
+        for record in &self.records {
+            if let Some(text) = &record.text {
+                self.text_index.insert(&record.id, text);
+            }
+        }
+    }
+
+    /// Score of the query against the record at a position, cosine reuses the cached norms.
+    fn scorer<'a>(
+        &'a self,
+        query_embedding: &'a [f32],
+        distance: Distance,
+    ) -> impl Fn(usize) -> f32 + Sync + 'a {
+        let query_norm = norm(query_embedding);
+        let distance_fn = get_distance_fn(distance);
+        move |position| {
+            let embedding = self.vector(position);
+            match distance {
+                Distance::Cosine => cosine_from_dot(
+                    dot_product(embedding, query_embedding),
+                    self.norms[position],
+                    query_norm,
+                ),
+                _ => distance_fn(embedding, query_embedding),
+            }
+        }
+    }
+
     #[allow(dead_code)]
     pub fn compute_score(distance: Distance, v1: &[f32], v2: &[f32]) -> f32 {
         let distance_fn = get_distance_fn(distance);
@@ -69,182 +687,1795 @@ impl Table {
         distance_fn(v1, v2)
     }
 
-    pub fn top_k_similarity(&self, query_embedding: &[f32], top_k: usize, distance: Distance) -> Vec<EmbeddingRecord> {
-        let distance_fn = get_distance_fn(distance);
+    pub fn top_k_similarity(
+        &self,
+        query_embedding: &[f32],
+        top_k: usize,
+        distance: Distance,
+        options: &SearchOptions,
+    ) -> Vec<ScoredRecord> {
+        let instant = Instant::now();
+        let score_fn = self.scorer(query_embedding, distance);
+        let now = unix_now();
+        let scored = AtomicUsize::new(0);
 
-        // Compute score and corresponding index for each record.
+        // Compute score and corresponding index for each record passing the filter and cutoff.
+        // The heap keeps the rank key, so the best match is on top for every metric.
         let scores = self
             .records
             .par_iter()
             .enumerate()
-            .map(|(index, record)| {
-                let score = distance_fn(&record.embedding, query_embedding);
-                ScoreIndex { score, index }
+            .filter(|(_, record)| !record.is_expired(now))
+            .filter(|(_, record)| options.filter.is_none_or(|f| f.matches(&record.metadata)))
+            .filter_map(|(index, _)| {
+                options.count(&scored);
+                let score = score_fn(index);
+                options.passes(score).then(|| ScoreIndex {
+                    score: distance.rank_key(score),
+                    index,
+                })
             })
             .collect::<Vec<_>>();
 
-        // Sort the scores by top k binary heap.
-        let mut heap = BinaryHeap::new();
-        for score in scores {
-            if heap.len() < top_k {
-                heap.push(score);
-            } else if score.score > heap.peek().unwrap().score {
-                heap.pop();
-                heap.push(score);
-            }
+        let hits = select_top_k(scores, top_k)
+            .into_iter()
+            .map(|score_index| ScoredRecord {
+                record: self.record(score_index.index).into_owned(),
+                score: distance.rank_key(score_index.score),
+            })
+            .collect::<Vec<_>>();
+        options.note(|explain| {
+            explain.candidates += scored.into_inner();
+            explain.phase("scan", instant);
+        });
+        hits
+    }
+
+    /// Top k scan over the quantized vectors with the asymmetric distance, the query stays
+    /// in full precision. `None` while the quantizer is not trained yet.
+    ///
+    /// With re-ranking, `rerank_factor` times more candidates are rescored exactly.
+    pub fn quantized_top_k(
+        &self,
+        query_embedding: &[f32],
+        top_k: usize,
+        distance: Distance,
+        options: &SearchOptions,
+    ) -> Option<Vec<ScoredRecord>> {
+        let quantized = self.quantized.as_ref()?;
+        let query_table = quantized.query_table(query_embedding, distance)?;
+        let rerank_factor = quantized.config().rerank_factor;
+        let rerank = options.rerank.unwrap_or(true) && rerank_factor > 1;
+        let candidates = if rerank { top_k * rerank_factor } else { top_k };
+        let now = unix_now();
+        let instant = Instant::now();
+        let scored = AtomicUsize::new(0);
+
+        let scores = self
+            .records
+            .par_iter()
+            .enumerate()
+            .filter(|(_, record)| !record.is_expired(now))
+            .filter(|(_, record)| options.filter.is_none_or(|f| f.matches(&record.metadata)))
+            .filter_map(|(index, _)| {
+                options.count(&scored);
+                let score = query_table.score(quantized.code(index), quantized.norm(index));
+                // Without re-ranking the approximate score is the final one.
+                (rerank || options.passes(score)).then(|| ScoreIndex {
+                    score: distance.rank_key(score),
+                    index,
+                })
+            })
+            .collect::<Vec<_>>();
+        let candidates = select_top_k(scores, candidates);
+        options.note(|explain| {
+            explain.plan = QueryPlan::Quantized;
+            explain.candidates += scored.into_inner();
+            explain.phase("quantized_scan", instant);
+        });
+
+        if !rerank {
+            let hits = candidates
+                .into_iter()
+                .map(|score_index| ScoredRecord {
+                    record: self.record(score_index.index).into_owned(),
+                    score: distance.rank_key(score_index.score),
+                })
+                .collect();
+            return Some(hits);
         }
 
-        info!("heap size {}", heap.len());
-        heap.into_sorted_vec()
+        let instant = Instant::now();
+        let reranked = candidates.len();
+        let score_fn = self.scorer(query_embedding, distance);
+        let scores = candidates
+            .into_iter()
+            .filter_map(|candidate| {
+                let score = score_fn(candidate.index);
+                options.passes(score).then(|| ScoreIndex {
+                    score: distance.rank_key(score),
+                    index: candidate.index,
+                })
+            })
+            .collect();
+        let hits = select_top_k(scores, top_k)
             .into_iter()
-            .map(|score_index| self.records[score_index.index].clone())
-            .collect::<Vec<_>>()
+            .map(|score_index| ScoredRecord {
+                record: self.record(score_index.index).into_owned(),
+                score: distance.rank_key(score_index.score),
+            })
+            .collect();
+        options.note(|explain| {
+            explain.reranked = reranked;
+            explain.phase("rerank", instant);
+        });
+        Some(hits)
     }
-}
 
-impl Database {
-    pub fn create_table(
-        &mut self,
-        table_name: String,
-        dimension: usize,
-    ) -> Result<(), DBError> {
-        // Check if table already exists.
-        if self.tables.contains_key(&table_name) {
-            return Err(DBError::TableAlreadyExists);
+    /// Top k search through the HNSW index, restricted to the `allowed` ids if given.
+    pub fn approximate_top_k(
+        &self,
+        index: &HnswIndex,
+        query_embedding: &[f32],
+        top_k: usize,
+        options: &SearchOptions,
+        allowed: Option<&HashSet<&str>>,
+    ) -> Vec<ScoredRecord> {
+        let instant = Instant::now();
+        let now = unix_now();
+        let expired = |id: &str| {
+            self.id_index.get(id).is_some_and(|&i| self.records[i].is_expired(now))
+        };
+        let (hits, visited) =
+            index.search_with_visits(query_embedding, top_k, options.ef_search, |id| {
+                allowed.is_none_or(|allowed| allowed.contains(id)) && !expired(id)
+            });
+        options.note(|explain| {
+            explain.plan = match allowed {
+                Some(_) => QueryPlan::HnswFiltered,
+                None => QueryPlan::Hnsw,
+            };
+            explain.candidates += visited;
+            explain.phase("index", instant);
+        });
+        hits.into_iter()
+            // Hits come best first, the cutoff only trims the tail.
+            .take_while(|(_, score)| options.passes(*score))
+            .filter_map(|(id, score)| {
+                let record = self.get_in_memory(id)?.into_owned();
+                Some(ScoredRecord { record, score })
+            })
+            .collect()
+    }
+
+    /// Use the index when it is built for `distance`, fall back to a scan otherwise.
+    pub fn search(
+        &self,
+        query_embedding: &[f32],
+        top_k: usize,
+        distance: Distance,
+        options: &SearchOptions,
+    ) -> Result<Vec<ScoredRecord>> {
+        let normalized = distance.normalizes().then(|| normalize(query_embedding));
+        let query_embedding = normalized.as_deref().unwrap_or(query_embedding);
+        let index = match &self.index {
+            Some(index) if !options.exact && index.config().distance == distance => index,
+            _ => return self.scan(query_embedding, top_k, distance, options),
+        };
+
+        let Some(filter) = options.filter else {
+            return Ok(self.approximate_top_k(index, query_embedding, top_k, options, None));
+        };
+
+        // Pre-filter on the metadata, which is much cheaper than scoring.
+        let instant = Instant::now();
+        let now = unix_now();
+        let allowed = self
+            .records
+            .iter()
+            .filter(|r| !r.is_expired(now) && filter.matches(&r.metadata))
+            .map(|r| r.id.as_str())
+            .collect::<HashSet<_>>();
+        options.note(|explain| {
+            explain.filtered = Some(allowed.len());
+            explain.phase("filter", instant);
+        });
+
+        // A selective filter leaves few enough records to score them all exactly.
+        let ef = options.ef_search.unwrap_or(index.config().ef_search).max(top_k);
+        if allowed.len() <= ef {
+            options.note(|explain| explain.plan = QueryPlan::ExactFiltered);
+            return Ok(self.top_k_similarity(query_embedding, top_k, distance, options));
         }
+        Ok(self.approximate_top_k(index, query_embedding, top_k, options, Some(&allowed)))
+    }
 
-        // Create new table.
-        let table = Table {
-            dimension,
-            records: Vec::new(),
+    /// Scan the quantized vectors once trained, unless the query asks for exact results.
+    ///
+    /// The segments of a segmented table are scanned next to the memtable.
+    fn scan(
+        &self,
+        query_embedding: &[f32],
+        top_k: usize,
+        distance: Distance,
+        options: &SearchOptions,
+    ) -> Result<Vec<ScoredRecord>> {
+        if !options.exact {
+            if let Some(hits) = self.quantized_top_k(query_embedding, top_k, distance, options) {
+                return Ok(hits);
+            }
+        }
+        let mut hits = self.top_k_similarity(query_embedding, top_k, distance, options);
+        if let Some(segments) = &self.segments {
+            let instant = Instant::now();
+            hits.extend(segments.search(query_embedding, top_k, distance, options, unix_now())?);
+            options.note(|explain| {
+                explain.segments = segments.len();
+                explain.phase("segments", instant);
+            });
+            hits.sort_by(|a, b| distance.rank_key(b.score).total_cmp(&distance.rank_key(a.score)));
+            hits.truncate(top_k);
+        }
+        Ok(hits)
+    }
+
+    pub fn memory_usage(&self) -> MemoryUsage {
+        let vectors = self
+            .records
+            .iter()
+            .map(|r| r.embedding.len() + r.vectors.values().map(Vec::len).sum::<usize>())
+            .sum::<usize>()
+            * std::mem::size_of::<f32>();
+        let payload = self
+            .records
+            .iter()
+            .map(|r| {
+                r.id.len()
+                    + r.text.as_ref().map_or(0, String::len)
+                    + bincode::serialized_size(&r.metadata).unwrap_or(0) as usize
+            })
+            .sum();
+        let quantized = self.quantized.as_ref().map_or(0, |q| q.memory_usage());
+        let (held, mapped) = self.quantized.as_ref().map_or((0, 0), |q| q.vectors_usage());
+        let vectors = vectors + held;
+        let index = self.index.as_ref().map_or(0, |i| i.memory_usage());
+        let text_index = self.text_index.memory_usage();
+        MemoryUsage {
+            records: self.count(),
+            vectors,
+            payload,
+            quantized,
+            index,
+            text_index,
+            total: vectors + payload + quantized + index + text_index,
+            mapped: mapped + self.segments.as_ref().map_or(0, Segments::mapped),
+        }
+    }
+
+    /// The options the table was created with.
+    pub fn options(&self) -> TableOptions {
+        TableOptions {
+            index: self
+                .index
+                .as_ref()
+                .map_or(IndexType::Flat, |index| IndexType::Hnsw(*index.config())),
+            quantization: self.quantized.as_ref().map(|q| *q.config()),
+            storage: self
+                .segments
+                .as_ref()
+                .map_or(Storage::Memory, |s| Storage::Segments(s.config())),
+            ttl_secs: self.ttl_secs,
+            distance: Some(self.distance),
+        }
+    }
+
+    pub fn stats(&self) -> TableStats {
+        let options = self.options();
+        TableStats {
+            count: self.count(),
+            dimension: self.dimension,
+            index: options.index,
+            quantization: options.quantization,
+            storage: options.storage,
+            ttl_secs: options.ttl_secs,
+            distance: self.distance,
+            embedding: self.embedding.clone(),
+            fields: self.fields.clone(),
+            memory: self.memory_usage(),
+        }
+    }
+
+    /// Up to `limit` records with an id greater than `after`, ordered by id.
+    ///
+    /// Ids are the cursor so pages stay consistent while records are added or removed,
+    /// embeddings and named vectors are left empty unless `include_embedding`.
+    pub fn list(
+        &self,
+        after: Option<&str>,
+        limit: usize,
+        include_embedding: bool,
+    ) -> Result<RecordPage> {
+        let now = unix_now();
+        let from = after.map_or(Bound::Unbounded, Bound::Excluded);
+        // Every source is ordered by id, `limit + 1` records of each fill the page.
+        let mut records = self
+            .id_index
+            .range::<str, _>((from, Bound::Unbounded))
+            .map(|(_, &i)| i)
+            .filter(|&i| !self.records[i].is_expired(now))
+            .take(limit + 1)
+            .map(|i| match include_embedding {
+                true => self.record(i).into_owned(),
+                false => self.records[i].without_vectors(),
+            })
+            .collect::<Vec<_>>();
+        if let Some(segments) = &self.segments {
+            records.extend(segments.list(after, limit + 1, include_embedding, now)?);
+            records.sort_unstable_by(|a, b| a.id.cmp(&b.id));
+        }
+        let has_more = records.len() > limit;
+        records.truncate(limit);
+
+        let next_cursor = if has_more {
+            records.last().map(|r| r.id.clone())
+        } else {
+            None
         };
-        info!("Create table {:#?}", table);
-        self.tables.insert(table_name, table);
-        Ok(())
+        Ok(RecordPage {
+            records,
+            next_cursor,
+        })
     }
 
-    pub fn insert_record(
-        &mut self,
-        table_name: String,
-        record: EmbeddingRecord,
-    ) -> Result<(), DBError> {
-        // Check if table exists.
-        let table = self
-            .tables
-            .get_mut(&table_name)
-            .ok_or(DBError::TableDoesNotExist)?;
+    /// Top k over several vector fields, scored by the weighted sum of their rank keys.
+    ///
+    /// With several fields, each rank key is min-max normalized to [0, 1] over the records
+    /// taking part first, so that metrics of different scales weigh as asked. A single query
+    /// on `embedding` goes through `search`, otherwise every record carrying all the fields is
+    /// scored exactly. `min_score` applies to the combined score, before the top k.
+    pub fn search_fields(
+        &self,
+        queries: &[FieldQuery],
+        top_k: usize,
+        options: &SearchOptions,
+    ) -> Result<Vec<CombinedRecord>> {
+        let passes = |score: f32| options.min_score.is_none_or(|min| score >= min);
+
+        if let [query] = queries {
+            if query.field == MAIN_FIELD {
+                // weight * rank_key(score) >= min, on the field score so it cuts before the top k.
+                let cutoff =
+                    options.min_score.map(|min| query.distance.rank_key(min / query.weight));
+                let options = SearchOptions {
+                    min_score: cutoff.filter(|_| query.distance.higher_is_better()),
+                    max_distance: cutoff.filter(|_| !query.distance.higher_is_better()),
+                    ..*options
+                };
+                let hits = self.search(query.embedding, top_k, query.distance, &options)?;
+                return Ok(hits
+                    .into_iter()
+                    .map(|hit| CombinedRecord {
+                        score: query.weight * query.distance.rank_key(hit.score),
+                        scores: vec![hit.score],
+                        record: hit.record,
+                    })
+                    .collect());
+            }
+        }
 
-        // Check if record already exists.
-        if table.records.iter().any(|r| r.id == record.id) {
-            return Err(DBError::RecordExist);
+        let embeddings = queries
+            .iter()
+            .map(|q| match q.distance.normalizes() {
+                true => Cow::Owned(normalize(q.embedding)),
+                false => Cow::Borrowed(q.embedding),
+            })
+            .collect::<Vec<_>>();
+        let field_scores = |position: usize| {
+            queries
+                .iter()
+                .zip(&embeddings)
+                .map(|(q, embedding)| {
+                    let vector = match q.field {
+                        MAIN_FIELD => self.vector(position),
+                        field => self.records[position].vectors.get(field)?,
+                    };
+                    Some(get_distance_fn(q.distance)(vector, embedding))
+                })
+                .collect::<Option<Vec<_>>>()
+        };
+        let now = unix_now();
+        let candidates = self
+            .records
+            .par_iter()
+            .enumerate()
+            .filter(|(_, record)| !record.is_expired(now))
+            .filter(|(_, record)| options.filter.is_none_or(|f| f.matches(&record.metadata)))
+            .filter_map(|(index, _)| Some((index, field_scores(index)?)))
+            .collect::<Vec<_>>();
+
+        // Bounds of the rank key of every field, a single field is left as is.
+        let bounds = queries
+            .iter()
+            .enumerate()
+            .map(|(i, q)| {
+                candidates.iter().fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), (_, s)| {
+                    let key = q.distance.rank_key(s[i]);
+                    (min.min(key), max.max(key))
+                })
+            })
+            .collect::<Vec<_>>();
+        let normalized = |key: f32, (min, max): (f32, f32)| match queries.len() {
+            1 => key,
+            _ if max > min => (key - min) / (max - min),
+            _ => 1.,
+        };
+        let weighted = |scores: &[f32]| {
+            queries
+                .iter()
+                .zip(scores)
+                .zip(&bounds)
+                .map(|((q, &s), &bound)| q.weight * normalized(q.distance.rank_key(s), bound))
+                .sum::<f32>()
+        };
+        let scores = candidates
+            .par_iter()
+            .enumerate()
+            .filter_map(|(index, (_, scores))| {
+                let score = weighted(scores);
+                passes(score).then_some(ScoreIndex { score, index })
+            })
+            .collect::<Vec<_>>();
+
+        Ok(select_top_k(scores, top_k)
+            .into_iter()
+            .map(|score_index| {
+                let (position, scores) = &candidates[score_index.index];
+                CombinedRecord {
+                    record: self.record(*position).into_owned(),
+                    score: score_index.score,
+                    scores: scores.clone(),
+                }
+            })
+            .collect())
+    }
+
+    /// Fuse the vector top k with the BM25 ranking of the record text.
+    ///
+    /// Both rankings respect the metadata filter, the score cutoffs only apply to the vector one.
+    pub fn hybrid_search(
+        &self,
+        query_embedding: &[f32],
+        top_k: usize,
+        distance: Distance,
+        options: &SearchOptions,
+        hybrid: &HybridOptions,
+    ) -> Result<Vec<HybridRecord>> {
+        let candidates = hybrid.candidates.max(top_k);
+        let vector_hits = self.search(query_embedding, candidates, distance, options)?;
+        let keyword_hits = self.text_index.search(hybrid.query_text, candidates, |id| {
+            self.get_in_memory(id)
+                .is_some_and(|r| options.filter.is_none_or(|f| f.matches(&r.metadata)))
+        });
+
+        let vector_ranking = vector_hits
+            .iter()
+            .map(|hit| (hit.record.id.as_str(), distance.rank_key(hit.score)))
+            .collect::<Vec<_>>();
+        let vector_scores = vector_hits
+            .iter()
+            .map(|hit| (hit.record.id.as_str(), hit.score))
+            .collect::<HashMap<_, _>>();
+        let keyword_scores = keyword_hits.iter().copied().collect::<HashMap<_, _>>();
+
+        Ok(fuse(&vector_ranking, &keyword_hits, hybrid.fusion)
+            .into_iter()
+            .take(top_k)
+            .filter_map(|(id, score)| {
+                Some(HybridRecord {
+                    record: self.get_in_memory(id)?.into_owned(),
+                    score,
+                    vector_score: vector_scores.get(id).copied(),
+                    keyword_score: keyword_scores.get(id).copied(),
+                })
+            })
+            .collect())
+    }
+}
+
+/// Inner product scores unit vectors, only those of a table or field whose own distance is
+/// inner product are normalized when written.
+fn check_normalized(stored: Distance, distance: Distance) -> Result<(), DBError> {
+    if distance.normalizes() && !stored.normalizes() {
+        return Err(DBError::InvalidInput(format!(
+            "{:?} vectors are not normalized for an inner product query",
+            stored
+        )));
+    }
+    Ok(())
+}
+
+/// A failure to read the segment files of a table.
+fn storage(e: anyhow::Error) -> DBError {
+    DBError::Storage(format!("{e:#}"))
+}
+
+fn observe_query(table_name: &str, kind: &str, instant: Instant) {
+    METRICS
+        .query_seconds
+        .with_label_values(&[METRICS.table_label(table_name), kind])
+        .observe(instant.elapsed().as_secs_f64());
+}
+
+fn observe_snapshot(kind: &str, instant: Instant) {
+    METRICS
+        .snapshot_seconds
+        .with_label_values(&[kind])
+        .observe(instant.elapsed().as_secs_f64());
+}
+
+/// Keep the `top_k` highest rank keys, best first.
+pub(crate) fn select_top_k(scores: Vec<ScoreIndex>, top_k: usize) -> Vec<ScoreIndex> {
+    // Sort the scores by top k binary heap.
+    let mut heap = BinaryHeap::new();
+    for score in scores {
+        if heap.len() < top_k {
+            heap.push(score);
+        } else if heap.peek().is_some_and(|min| score.score > min.score) {
+            heap.pop();
+            heap.push(score);
+        }
+    }
+
+    debug!("heap size {}", heap.len());
+    heap.into_sorted_vec()
+}
+
+impl Database {
+    #[allow(dead_code)]
+    pub fn create_table(
+        &self,
+        table_name: String,
+        dimension: usize,
+    ) -> Result<(), DBError> {
+        self.create_table_with_options(table_name, dimension, TableOptions::default())
+    }
+
+    #[allow(dead_code)]
+    pub fn create_table_with_index(
+        &self,
+        table_name: String,
+        dimension: usize,
+        index: IndexType,
+    ) -> Result<(), DBError> {
+        let options = TableOptions {
+            index,
+            ..Default::default()
+        };
+        self.create_table_with_options(table_name, dimension, options)
+    }
+
+    pub fn create_table_with_options(
+        &self,
+        table_name: String,
+        dimension: usize,
+        options: TableOptions,
+    ) -> Result<(), DBError> {
+        self.create_table_with_fields(table_name, dimension, options, BTreeMap::new())
+    }
+
+    /// Create a table whose records carry named vectors next to `embedding`.
+    pub fn create_table_with_fields(
+        &self,
+        table_name: String,
+        dimension: usize,
+        options: TableOptions,
+        fields: BTreeMap<String, VectorField>,
+    ) -> Result<(), DBError> {
+        self.create_table_with_binding(table_name, dimension, options, fields, None)
+    }
+
+    /// Create a table bound to the model embedding the text sent without a vector, in a single
+    /// mutation.
+    pub fn create_table_with_binding(
+        &self,
+        table_name: String,
+        dimension: usize,
+        options: TableOptions,
+        fields: BTreeMap<String, VectorField>,
+        embedding: Option<EmbeddingBinding>,
+    ) -> Result<(), DBError> {
+        self.commit(WalEntry::CreateTable {
+            table_name,
+            dimension,
+            options,
+            fields,
+            embedding,
+        })
+    }
+
+    pub fn insert_record(
+        &self,
+        table_name: String,
+        record: EmbeddingRecord,
+    ) -> Result<(), DBError> {
+        self.insert_records(table_name, vec![record])
+    }
+
+    /// Insert a batch of new records, either all of them or none.
+    pub fn insert_records(
+        &self,
+        table_name: String,
+        records: Vec<EmbeddingRecord>,
+    ) -> Result<(), DBError> {
+        self.commit(WalEntry::InsertRecords {
+            table_name,
+            records,
+            upsert: false,
+        })
+    }
+
+    /// Insert a batch of records, replacing the existing ones with the same id.
+    pub fn upsert_records(
+        &self,
+        table_name: String,
+        records: Vec<EmbeddingRecord>,
+    ) -> Result<(), DBError> {
+        self.commit(WalEntry::InsertRecords {
+            table_name,
+            records,
+            upsert: true,
+        })
+    }
+
+    pub fn delete_record(&self, table_name: String, id: String) -> Result<(), DBError> {
+        self.commit(WalEntry::DeleteRecord { table_name, id })
+    }
+
+    /// Bind the model embedding text sent without a vector, `None` unbinds it.
+    pub fn bind_embedding(
+        &self,
+        table_name: String,
+        binding: Option<EmbeddingBinding>,
+    ) -> Result<(), DBError> {
+        self.commit(WalEntry::BindEmbedding {
+            table_name,
+            binding,
+        })
+    }
+
+    pub fn drop_table(&self, table_name: impl Into<String>) -> Result<(), DBError> {
+        self.commit(WalEntry::DropTable {
+            table_name: table_name.into(),
+        })
+    }
+
+    /// Validate a mutation, append it to the write-ahead log and apply it.
+    fn commit(&self, entry: WalEntry) -> Result<(), DBError> {
+        if self.is_read_only() {
+            return Err(DBError::ReadOnly);
+        }
+        self.write(entry)
+    }
+
+    fn write(&self, entry: WalEntry) -> Result<(), DBError> {
+        self.execute(entry, true)
+    }
+
+    /// Validate and apply a mutation, logging it first when `log` is set.
+    fn execute(&self, entry: WalEntry, log: bool) -> Result<(), DBError> {
+        let instant = Instant::now();
+        let append = |entry| if log { self.append(entry) } else { Ok(entry) };
+        match entry {
+            WalEntry::CreateTable { .. } | WalEntry::DropTable { .. } => {
+                let mut tables = timed_lock("tables", "write", || self.tables.write());
+                Self::validate_schema(&tables, &entry, self.segment_dir.is_some())?;
+                // A table whose storage fails to open is not created, nor logged.
+                let segment_dir = self.segment_dir.as_deref();
+                let created = Self::build_table(&entry, segment_dir, self.read_only_dir)?;
+                let entry = append(entry)?;
+                Self::apply_schema(&mut tables, entry, created);
+                Ok(())
+            }
+            WalEntry::InsertRecords { ref table_name, .. }
+            | WalEntry::DeleteRecord { ref table_name, .. }
+            | WalEntry::BindEmbedding { ref table_name, .. }
+            | WalEntry::ExpireRecords { ref table_name, .. } => {
+                let tables = timed_lock("tables", "read", || self.tables.read());
+                let table = tables.get(table_name).ok_or(DBError::TableDoesNotExist)?;
+                let mut table = timed_lock("table", "write", || table.write());
+                Self::validate_records(&table, &entry)?;
+                let op = match &entry {
+                    WalEntry::InsertRecords { upsert: true, .. } => Some("upsert"),
+                    WalEntry::InsertRecords { .. } => Some("insert"),
+                    WalEntry::DeleteRecord { .. } => Some("delete"),
+                    _ => None,
+                };
+                let table_name = table_name.clone();
+                let entry = append(Self::prepare_records(&table, entry))?;
+                Self::apply_records(&mut table, entry);
+                if let Some(op) = op.filter(|_| log) {
+                    METRICS
+                        .write_seconds
+                        .with_label_values(&[METRICS.table_label(&table_name), op])
+                        .observe(instant.elapsed().as_secs_f64());
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Append `entry` to the write-ahead log under the next sequence number.
+    fn append(&self, entry: WalEntry) -> Result<WalEntry, DBError> {
+        let mut log = timed_lock("log", "write", || self.log.lock());
+        let record = WalRecord {
+            seq: log.seq + 1,
+            entry,
+        };
+        if let Some(store) = log.store.as_mut() {
+            store.wal.append(&record)?;
+        }
+        log.seq = record.seq;
+        if let Some(feed) = log.feed.as_ref().filter(|feed| feed.receiver_count() > 0) {
+            let _ = feed.send(Arc::new(record.clone()));
+        }
+        Ok(record.entry)
+    }
+
+    /// Check that a table creation or removal can be applied to the current state.
+    fn validate_schema(
+        tables: &HashMap<String, TableRef>,
+        entry: &WalEntry,
+        persistent: bool,
+    ) -> Result<(), DBError> {
+        match entry {
+            WalEntry::CreateTable {
+                table_name,
+                dimension,
+                options,
+                fields,
+                ..
+            } => {
+                // Check if table already exists.
+                if tables.contains_key(table_name) {
+                    return Err(DBError::TableAlreadyExists);
+                }
+
+                for (name, field) in fields {
+                    if name.is_empty() || name == MAIN_FIELD || field.dimension == 0 {
+                        return Err(DBError::InvalidInput(format!(
+                            "Invalid vector field {:?} of dimension {}",
+                            name, field.dimension
+                        )));
+                    }
+                }
+
+                // A TTL of zero would expire the records as they are written.
+                if options.ttl_secs == Some(0) {
+                    return Err(DBError::InvalidInput("ttl_secs must be positive".to_string()));
+                }
+
+                // Product quantization splits the vector into equally sized subspaces.
+                if let Some(QuantizationKind::Product { subspaces }) =
+                    options.quantization.map(|q| q.kind)
+                {
+                    if subspaces == 0 || dimension % subspaces != 0 {
+                        return Err(DBError::InvalidInput(format!(
+                            "{} subspaces do not divide dimension {}",
+                            subspaces, dimension
+                        )));
+                    }
+                }
+
+                // The codes are only scored with the products or the euclidean distance.
+                let distance = options.default_distance();
+                if options.quantization.is_some() && !quantization::supports(distance) {
+                    return Err(DBError::InvalidInput(format!(
+                        "Quantization does not support the {:?} distance",
+                        distance
+                    )));
+                }
+                // The graph would keep a second copy of the full-precision vectors.
+                if options.quantization.is_some() && options.index != IndexType::Flat {
+                    return Err(DBError::InvalidInput(
+                        "Quantized tables do not support an index".to_string(),
+                    ));
+                }
+
+                // Segments are scanned exactly and only hold the main vector.
+                if let Storage::Segments(config) = options.storage {
+                    let reason = if !persistent {
+                        Some("Segmented tables need a storage dir")
+                    } else if config.memtable_size == 0 || config.max_segments == 0 {
+                        Some("memtable_size and max_segments must be positive")
+                    } else if options.index != IndexType::Flat || options.quantization.is_some() {
+                        Some("Segmented tables do not support an index or quantization")
+                    } else if !fields.is_empty() {
+                        Some("Segmented tables do not support vector fields")
+                    } else {
+                        None
+                    };
+                    if let Some(reason) = reason {
+                        return Err(DBError::InvalidInput(reason.to_string()));
+                    }
+                }
+            }
+            WalEntry::DropTable { table_name } => {
+                if !tables.contains_key(table_name) {
+                    return Err(DBError::TableDoesNotExist);
+                }
+            }
+            // Checked by `validate_records`.
+            WalEntry::InsertRecords { .. }
+            | WalEntry::DeleteRecord { .. }
+            | WalEntry::BindEmbedding { .. }
+            | WalEntry::ExpireRecords { .. } => {}
+        }
+        Ok(())
+    }
+
+    /// The table a `CreateTable` entry creates, with its storage opened.
+    fn build_table(
+        entry: &WalEntry,
+        segment_dir: Option<&Path>,
+        read_only: bool,
+    ) -> Result<Option<Table>, DBError> {
+        let WalEntry::CreateTable {
+            dimension,
+            options,
+            fields,
+            embedding,
+            ..
+        } = entry
+        else {
+            return Ok(None);
+        };
+        let mut table = Table::new(*dimension, *options);
+        table.fields = fields.clone();
+        table.embedding = embedding.clone();
+        table.open_vectors(segment_dir.filter(|_| !read_only));
+        table.open_segments(segment_dir, read_only).map_err(storage)?;
+        Ok(Some(table))
+    }
+
+    /// Apply a table creation or removal, `created` is the table `build_table` built.
+    fn apply_schema(
+        tables: &mut HashMap<String, TableRef>,
+        entry: WalEntry,
+        created: Option<Table>,
+    ) {
+        match (entry, created) {
+            (WalEntry::CreateTable { table_name, .. }, Some(table)) => {
+                info!("Create table {:#?}", table);
+                tables.insert(table_name, Arc::new(RwLock::new(table)));
+            }
+            (WalEntry::DropTable { table_name }, _) => {
+                tables.remove(&table_name);
+            }
+            _ => {}
+        }
+    }
+
+    /// Check that a record change can be applied to `table`.
+    fn validate_records(table: &Table, entry: &WalEntry) -> Result<(), DBError> {
+        match entry {
+            WalEntry::InsertRecords {
+                records, upsert, ..
+            } => {
+                let mut ids = HashSet::with_capacity(records.len());
+                for record in records {
+                    // Check if record already exists, in the table or earlier in the batch. An
+                    // upsert looks it up as well, so that a segment failing to read it fails
+                    // before the entry is logged.
+                    let exists = table.contains(&record.id).map_err(storage)?;
+                    if !upsert && (exists || !ids.insert(&record.id)) {
+                        return Err(DBError::RecordExist);
+                    }
+
+                    // Check if record has the correct dimension.
+                    if record.embedding.len() != table.dimension {
+                        return Err(DBError::DimensionMismatch);
+                    }
+                    for (name, vector) in &record.vectors {
+                        let field = table.fields.get(name).ok_or_else(|| {
+                            DBError::InvalidInput(format!("Unknown vector field {:?}", name))
+                        })?;
+                        if vector.len() != field.dimension {
+                            return Err(DBError::DimensionMismatch);
+                        }
+                    }
+                }
+            }
+            WalEntry::DeleteRecord { id, .. } => {
+                if !table.contains(id).map_err(storage)? {
+                    return Err(DBError::RecordDoesNotExist);
+                }
+            }
+            // Any table can be bound, the model only matters once text is embedded.
+            WalEntry::BindEmbedding { .. } => {}
+            // Only what expired by then is removed, which may be nothing.
+            WalEntry::ExpireRecords { .. } => {}
+            // Checked by `validate_schema`.
+            WalEntry::CreateTable { .. } | WalEntry::DropTable { .. } => {}
+        }
+        Ok(())
+    }
+
+    fn apply_records(table: &mut Table, entry: WalEntry) {
+        match entry {
+            WalEntry::InsertRecords { records, .. } => {
+                for record in records {
+                    table.upsert(record);
+                }
+            }
+            WalEntry::DeleteRecord { id, .. } => {
+                table.remove(&id);
+            }
+            WalEntry::BindEmbedding { binding, .. } => {
+                table.embedding = binding;
+            }
+            WalEntry::ExpireRecords { now, .. } => {
+                let expired = table.expire(now);
+                debug!("Removed {expired} expired records");
+            }
+            WalEntry::CreateTable { .. } | WalEntry::DropTable { .. } => {}
+        }
+    }
+
+    /// Give the inserted records without an expiry the one of the table TTL, and normalize the
+    /// vectors scored with inner product. Done before the entry is logged so that replaying it
+    /// gives the same result, the records are then read back with the normalized vectors.
+    fn prepare_records(table: &Table, mut entry: WalEntry) -> WalEntry {
+        let WalEntry::InsertRecords { records, .. } = &mut entry else {
+            return entry;
+        };
+        if let Some(ttl) = table.ttl_secs {
+            let expires_at = unix_now().saturating_add(ttl);
+            for record in records.iter_mut().filter(|r| r.expires_at.is_none()) {
+                record.expires_at = Some(expires_at);
+            }
+        }
+        for record in records.iter_mut() {
+            if table.distance.normalizes() {
+                record.embedding = normalize(&record.embedding);
+            }
+            for (name, vector) in record.vectors.iter_mut() {
+                if table.fields.get(name).is_some_and(|f| f.distance.normalizes()) {
+                    *vector = normalize(vector);
+                }
+            }
+        }
+        entry
+    }
+
+    /// Remove the expired records of every table, returns the number of tables cleaned.
+    ///
+    /// The removal goes through the write-ahead log so that it reaches the snapshot and the
+    /// followers, which leave it to their leader.
+    pub fn expire_records(&self) -> Result<usize, DBError> {
+        if self.is_read_only() {
+            return Ok(0);
+        }
+        let now = unix_now();
+        let expired = {
+            let tables = self.tables.read();
+            tables
+                .iter()
+                .filter(|(_, table)| table.read().has_expired(now))
+                .map(|(name, _)| name.clone())
+                .collect::<Vec<_>>()
+        };
+        let mut cleaned = 0;
+        for table_name in expired {
+            match self.commit(WalEntry::ExpireRecords { table_name, now }) {
+                Ok(()) => cleaned += 1,
+                // Dropped meanwhile.
+                Err(DBError::TableDoesNotExist) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(cleaned)
+    }
+
+    /// The table `table_name`, still usable by the caller if the table is dropped meanwhile.
+    fn table(&self, table_name: &str) -> Result<TableRef, DBError> {
+        timed_lock("tables", "read", || self.tables.read())
+            .get(table_name)
+            .cloned()
+            .ok_or(DBError::TableDoesNotExist)
+    }
+
+    #[allow(dead_code)]
+    pub fn query_record(
+        &self,
+        table_name: String,
+        query_embedding: &[f32],
+        top_k: usize,
+        distance: Distance,
+    ) -> Result<Vec<ScoredRecord>, DBError> {
+        let options = SearchOptions::default();
+        self.query_record_with(table_name, query_embedding, top_k, Some(distance), &options)
+    }
+
+    /// Query with `distance`, or the default distance of the table.
+    pub fn query_record_with(
+        &self,
+        table_name: String,
+        query_embedding: &[f32],
+        top_k: usize,
+        distance: Option<Distance>,
+        options: &SearchOptions,
+    ) -> Result<Vec<ScoredRecord>, DBError> {
+        let start = Instant::now();
+        let table = self.table(&table_name)?;
+        let table = timed_lock("table", "read", || table.read());
+        let lock_wait = start.elapsed();
+
+        // Check if query embedding has the correct dimension.
+        if query_embedding.len() != table.dimension {
+            return Err(DBError::DimensionMismatch);
+        }
+
+        let distance = distance.unwrap_or(table.distance);
+        check_normalized(table.distance, distance)?;
+        options.check_cutoffs(distance)?;
+
+        let instant = Instant::now();
+        let result = table.search(query_embedding, top_k, distance, options).map_err(storage)?;
+        observe_query(&table_name, "vector", instant);
+        info!("Query to {table_name} took {:?}", instant.elapsed());
+        options.note(|explain| {
+            explain.distance = distance;
+            explain.lock_wait_micros = explain::micros(lock_wait);
+            explain.total_micros = explain::micros(start.elapsed());
+        });
+        Ok(result)
+    }
+
+    /// Query one or several vector fields, see `Table::search_fields`.
+    pub fn query_fields(
+        &self,
+        table_name: &str,
+        queries: &[VectorQuery],
+        top_k: usize,
+        options: &SearchOptions,
+    ) -> Result<Vec<CombinedRecord>, DBError> {
+        let table = self.table(table_name)?;
+        let table = timed_lock("table", "read", || table.read());
+        if queries.is_empty() {
+            return Err(DBError::InvalidInput("No vector to query".to_string()));
+        }
+        if table.segments.is_some() && queries.len() > 1 {
+            return Err(DBError::InvalidInput(
+                "Segmented tables only support queries on a single vector".to_string(),
+            ));
+        }
+
+        let mut checked = Vec::with_capacity(queries.len());
+        for query in queries {
+            let (dimension, stored) = match query.field.as_str() {
+                MAIN_FIELD => (table.dimension, table.distance),
+                name => {
+                    let field = table.fields.get(name).ok_or_else(|| {
+                        DBError::InvalidInput(format!("Unknown vector field {:?}", name))
+                    })?;
+                    (field.dimension, field.distance)
+                }
+            };
+            let distance = query.distance.unwrap_or(stored);
+            check_normalized(stored, distance)?;
+            if query.embedding.len() != dimension {
+                return Err(DBError::DimensionMismatch);
+            }
+            if !(query.weight.is_finite() && query.weight > 0.) {
+                return Err(DBError::InvalidInput(format!("Invalid weight {}", query.weight)));
+            }
+            checked.push(FieldQuery {
+                field: &query.field,
+                embedding: &query.embedding,
+                weight: query.weight,
+                distance,
+            });
+        }
+
+        let instant = Instant::now();
+        let result = table.search_fields(&checked, top_k, options).map_err(storage)?;
+        observe_query(table_name, "fields", instant);
+        info!("Field query to {table_name} took {:?}", instant.elapsed());
+        Ok(result)
+    }
+
+    pub fn hybrid_query(
+        &self,
+        table_name: String,
+        query_embedding: &[f32],
+        top_k: usize,
+        distance: Option<Distance>,
+        options: &SearchOptions,
+        hybrid: &HybridOptions,
+    ) -> Result<Vec<HybridRecord>, DBError> {
+        let table = self.table(&table_name)?;
+        let table = timed_lock("table", "read", || table.read());
+
+        // Check if query embedding has the correct dimension.
+        if query_embedding.len() != table.dimension {
+            return Err(DBError::DimensionMismatch);
+        }
+        if table.segments.is_some() {
+            return Err(DBError::InvalidInput(
+                "Segmented tables do not support hybrid queries".to_string(),
+            ));
+        }
+        if let Fusion::Weighted { alpha } = hybrid.fusion {
+            if !(0. ..=1.).contains(&alpha) {
+                return Err(DBError::InvalidInput(format!("alpha {} is not within [0, 1]", alpha)));
+            }
+        }
+
+        let distance = distance.unwrap_or(table.distance);
+        check_normalized(table.distance, distance)?;
+        options.check_cutoffs(distance)?;
+
+        let instant = Instant::now();
+        let result = table
+            .hybrid_search(query_embedding, top_k, distance, options, hybrid)
+            .map_err(storage)?;
+        observe_query(&table_name, "hybrid", instant);
+        info!("Hybrid query to {table_name} took {:?}", instant.elapsed());
+        Ok(result)
+    }
+
+    /// Return the entire data base for debug.
+    pub fn get_entire_db(&self) -> Result<HashMap<String, Table>, DBError> {
+        let tables = self
+            .tables
+            .read()
+            .iter()
+            .map(|(name, table)| (name.clone(), table.read().clone()))
+            .collect::<Vec<_>>();
+        tables
+            .into_iter()
+            .map(|(name, table)| Ok((name, table.into_exported().map_err(storage)?)))
+            .collect()
+    }
+
+    /// Memory usage of a table.
+    pub fn memory_usage(&self, table_name: &str) -> Result<MemoryUsage, DBError> {
+        Ok(self.table(table_name)?.read().memory_usage())
+    }
+
+    pub fn has_table(&self, table_name: &str) -> bool {
+        self.tables.read().contains_key(table_name)
+    }
+
+    /// Number of records of every table, without the expired ones.
+    pub fn record_counts(&self) -> Vec<(String, usize)> {
+        let tables = self.tables.read();
+        tables
+            .iter()
+            .map(|(name, table)| (name.clone(), table.read().count()))
+            .collect()
+    }
+
+    /// Names of the tables, sorted.
+    pub fn list_tables(&self) -> Vec<String> {
+        let mut names = self.tables.read().keys().cloned().collect::<Vec<_>>();
+        names.sort();
+        names
+    }
+
+    /// The embedding model bound to a table, if any.
+    pub fn embedding_binding(&self, table_name: &str) -> Result<Option<EmbeddingBinding>, DBError> {
+        Ok(self.table(table_name)?.read().embedding.clone())
+    }
+
+    pub fn table_stats(&self, table_name: &str) -> Result<TableStats, DBError> {
+        Ok(self.table(table_name)?.read().stats())
+    }
+
+    /// Look a single record up by id.
+    pub fn get_record(&self, table_name: &str, id: &str) -> Result<EmbeddingRecord, DBError> {
+        let table = self.table(table_name)?;
+        let table = table.read();
+        let record = table.get(id).map_err(storage)?;
+        record.map(Cow::into_owned).ok_or(DBError::RecordDoesNotExist)
+    }
+
+    /// A page of the records of a table, see `Table::list`.
+    pub fn list_records(
+        &self,
+        table_name: &str,
+        cursor: Option<&str>,
+        limit: usize,
+        include_embedding: bool,
+    ) -> Result<RecordPage, DBError> {
+        let table = self.table(table_name)?;
+        let page = table
+            .read()
+            .list(cursor, limit.clamp(1, MAX_PAGE_SIZE), include_embedding);
+        page.map_err(storage)
+    }
+
+    /// Return table.
+    pub fn get_table(&self, table_name: String) -> Result<Table, DBError> {
+        let table = self.table(&table_name)?.read().clone();
+        table.into_exported().map_err(storage)
+    }
+
+    /// An in-memory database without any file behind it.
+    pub fn zero() -> Self {
+        Self::default()
+    }
+
+    /// Sequence number of the last applied mutation.
+    pub fn seq(&self) -> u64 {
+        self.log.lock().seq
+    }
+
+    /// Directory the database is stored in, `None` for an in-memory one.
+    pub fn storage_dir(&self) -> Option<PathBuf> {
+        let log = self.log.lock();
+        let store = log.store.as_ref()?;
+        store.snapshot_path.parent().map(Path::to_path_buf)
+    }
+
+    /// Load the snapshot in `dir` and replay the write-ahead log on top of it.
+    pub fn load_from_dir(dir: &Path, options: WalOptions) -> Result<Self> {
+        // Create storage dir if it does not exist.
+        fs::create_dir_all(dir)?;
+        let snapshot_path = dir.join(SNAPSHOT_FILE);
+        let segment_dir = dir.join(SEGMENT_DIR);
+
+        let (tables, seq, snapshot_size) = if snapshot_path.exists() {
+            debug!("Loading database from file: {:?}", snapshot_path);
+            let db_data = fs::read(&snapshot_path)?;
+            let size = db_data.len() as u64;
+            let snapshot = Snapshot::decode(db_data)?;
+            let tables = Self::open_tables(snapshot.tables, Some(&segment_dir), false)?;
+            (tables, snapshot.seq, size)
+        } else {
+            // Create a new database.
+            (HashMap::new(), 0, 0)
+        };
+        let mut db = Self {
+            tables: RwLock::new(tables),
+            log: Mutex::new(Log {
+                seq,
+                ..Default::default()
+            }),
+            read_only: AtomicBool::new(false),
+            segment_dir: Some(segment_dir),
+            read_only_dir: false,
+            merging: Mutex::new(()),
+            checkpointing: Mutex::new(()),
+        };
+
+        let snapshot_seq = db.seq();
+        // Entries of a checkpoint that did not finish come first.
+        let wal_path = dir.join(WAL_FILE);
+        let rotated = Wal::rotated_path(&wal_path);
+        let mut records = match rotated.exists() {
+            true => Wal::read(&rotated)?,
+            false => Vec::new(),
+        };
+        let (wal, current) = Wal::open(&wal_path, options)?;
+        records.extend(current);
+        db.replay(records);
+
+        db.log.get_mut().store = Some(Store {
+            snapshot_path,
+            snapshot_size,
+            snapshot_seq,
+            wal,
+        });
+        if rotated.exists() {
+            db.checkpoint()?;
+        }
+        Ok(db)
+    }
+
+    /// Load the snapshot in `dir` and replay the write-ahead log on top of it without writing
+    /// anything there: mutations are rejected, memtables are not flushed, the log is not opened
+    /// for appends and nothing is saved on drop.
+    ///
+    /// Meant for tools such as the recall command, the dir may belong to a running server:
+    /// when it checkpoints or collects segments while the files are read, they are read again.
+    pub fn open_read_only(dir: &Path) -> Result<Self> {
+        let mut attempt = 1;
+        loop {
+            match Self::try_open_read_only(dir) {
+                Err(e) if attempt < READ_ONLY_ATTEMPTS => {
+                    warn!("Reading {:?} again after attempt {attempt}: {e}", dir);
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    fn try_open_read_only(dir: &Path) -> Result<Self> {
+        let snapshot_path = dir.join(SNAPSHOT_FILE);
+        let segment_dir = dir.join(SEGMENT_DIR);
+        // A checkpoint replaces the snapshot before it resets the log.
+        let snapshot_id =
+            |path: &Path| fs::metadata(path).ok().map(|m| (m.len(), m.modified().ok()));
+        let snapshot_before = snapshot_id(&snapshot_path);
+        let (tables, seq) = if snapshot_path.exists() {
+            let snapshot = Snapshot::decode(fs::read(&snapshot_path)?)?;
+            let tables = Self::open_tables(snapshot.tables, Some(&segment_dir), true)?;
+            (tables, snapshot.seq)
+        } else {
+            (HashMap::new(), 0)
+        };
+        let mut db = Self {
+            tables: RwLock::new(tables),
+            log: Mutex::new(Log {
+                seq,
+                ..Default::default()
+            }),
+            read_only: AtomicBool::new(false),
+            segment_dir: Some(segment_dir),
+            read_only_dir: true,
+            merging: Mutex::new(()),
+            checkpointing: Mutex::new(()),
+        };
+
+        let wal_path = dir.join(WAL_FILE);
+        let mut records = Vec::new();
+        for path in [Wal::rotated_path(&wal_path), wal_path] {
+            if path.exists() {
+                records.extend(Wal::read(&path)?);
+            }
+        }
+        if snapshot_id(&snapshot_path) != snapshot_before {
+            bail!("The snapshot was replaced while the write-ahead log was read");
+        }
+        db.replay(records);
+        db.set_read_only(true);
+        Ok(db)
+    }
+
+    /// Apply the write-ahead log `records` following the loaded snapshot.
+    fn replay(&mut self, records: Vec<WalRecord>) {
+        let count = records.len();
+        for record in records {
+            // Entries below the snapshot sequence are already part of it.
+            let log = self.log.get_mut();
+            if record.seq <= log.seq {
+                continue;
+            }
+            log.seq = record.seq;
+            if let Err(e) = self.execute(record.entry, false) {
+                warn!("Skipping write-ahead log entry {}: {}", record.seq, e);
+            }
+        }
+        info!("Replayed {count} write-ahead log entries, now at sequence {}", self.seq());
+    }
+
+    /// Write a snapshot of the whole database and truncate the write-ahead log.
+    ///
+    /// Writers only wait while the tables are copied and the log is rotated, the snapshot is
+    /// written without any lock. Segmented tables only copy their memtable.
+    pub fn checkpoint(&self) -> Result<()> {
+        let _checkpointing = self.checkpointing.lock();
+        if self.log.lock().store.is_none() {
+            return Ok(());
+        }
+        let instant = Instant::now();
+        let (seq, tables, snapshot_path, rotated) = {
+            let tables = timed_lock("tables", "read", || self.tables.read());
+            let guards = tables
+                .iter()
+                .map(|(name, table)| (name.as_str(), timed_lock("table", "read", || table.read())))
+                .collect::<Vec<_>>();
+            let mut log = timed_lock("log", "write", || self.log.lock());
+            let seq = log.seq;
+            let Some(store) = log.store.as_mut() else {
+                return Ok(());
+            };
+            let rotated = store.wal.rotate()?;
+            let copies = guards
+                .iter()
+                .map(|(name, table)| (name.to_string(), Table::clone(table)))
+                .collect::<HashMap<_, _>>();
+            (seq, copies, store.snapshot_path.clone(), rotated)
+        };
+        let snapshot = SnapshotRef {
+            tables: tables.iter().map(|(name, table)| (name.as_str(), table)).collect(),
+            seq,
+        };
+        let data = bincode::serialize(&snapshot)?;
+        snapshot::write_file(&snapshot_path, &data)?;
+        fs::remove_file(&rotated)?;
+        sync_dir(&rotated)?;
+        if let Some(store) = self.log.lock().store.as_mut() {
+            store.snapshot_size = data.len() as u64;
+            store.snapshot_seq = seq;
+        }
+        self.remove_unused_segments(tables.values());
+        info!("Checkpoint at sequence {} took {:?}", seq, instant.elapsed());
+        observe_snapshot("checkpoint", instant);
+        Ok(())
+    }
+
+    /// Checkpoint once the write-ahead log grew beyond its `compact_threshold`, returns
+    /// whether it did. Called by a background task, so that no write waits for it.
+    pub fn compact(&self) -> Result<bool> {
+        let needed = {
+            let log = self.log.lock();
+            let store = log.store.as_ref();
+            store.is_some_and(|s| s.wal.needs_compaction(s.snapshot_size))
+        };
+        if needed {
+            self.checkpoint()?;
+        }
+        Ok(needed)
+    }
+
+    /// Rebuild the caches of the tables of a snapshot and map their segments, without writing
+    /// to `segment_dir` if `read_only`.
+    fn open_tables(
+        tables: HashMap<String, Table>,
+        segment_dir: Option<&Path>,
+        read_only: bool,
+    ) -> Result<HashMap<String, TableRef>> {
+        tables
+            .into_iter()
+            .map(|(name, mut table)| {
+                table.open_vectors(segment_dir.filter(|_| !read_only));
+                table.rebuild_caches();
+                table.open_segments(segment_dir, read_only)?;
+                Ok((name, Arc::new(RwLock::new(table))))
+            })
+            .collect()
+    }
+
+    /// Remove the segment files neither the tables of the `saved` snapshot nor the current
+    /// ones refer to.
+    ///
+    /// Skipped while a merge or a restore is running, their new files are not part of any
+    /// table yet. The files
+    /// are listed under the table locks so that no flush is in progress, and removed after.
+    fn remove_unused_segments<'a>(&self, saved: impl Iterator<Item = &'a Table>) {
+        let Some(dir) = &self.segment_dir else {
+            return;
+        };
+        let Some(_merging) = self.merging.try_lock() else {
+            return;
+        };
+        let mut keep = saved
+            .filter_map(|table| table.segments.as_ref())
+            .flat_map(Segments::names)
+            .collect::<HashSet<_>>();
+        let unused = {
+            let tables = self.tables.read();
+            let guards = tables.values().map(|table| table.read()).collect::<Vec<_>>();
+            let current = guards.iter().filter_map(|table| table.segments.as_ref());
+            keep.extend(current.flat_map(Segments::names));
+            segment::unused(dir, &keep)
+        };
+        let removed = unused.and_then(|unused| {
+            for path in &unused {
+                fs::remove_file(path)?;
+            }
+            Ok(unused.len())
+        });
+        match removed {
+            Ok(0) => {}
+            Ok(removed) => info!("Removed {removed} unused segment files"),
+            Err(e) => warn!("Failed to remove unused segment files: {:?}", e),
         }
+    }
 
-        // Check if record has the correct dimension.
-        if record.embedding.len() != table.dimension {
-            return Err(DBError::DimensionMismatch);
+    /// Merge the segments of every table holding more than its `max_segments`, returns the
+    /// number of merges.
+    ///
+    /// The merged segment is written without holding the table lock, which is only taken to
+    /// swap it in. Until the next checkpoint the merged files stay on disk.
+    pub fn merge_segments(&self) -> Result<usize> {
+        let _merging = self.merging.lock();
+        let tables = self.tables.read().values().cloned().collect::<Vec<_>>();
+        let mut merges = 0;
+        for table in tables {
+            let merge = {
+                let table = table.read();
+                table.segments.as_ref().and_then(|s| s.plan_merge(table.dimension))
+            };
+            let Some(merge) = merge else {
+                continue;
+            };
+            let instant = Instant::now();
+            let merged = merge.run()?;
+            if let Some(segments) = table.write().segments.as_mut() {
+                segments.finish_merge(merged);
+            }
+            info!("Merged segments in {:?}", instant.elapsed());
+            merges += 1;
         }
-
-        table.records.push(record);
-        Ok(())
+        Ok(merges)
     }
 
-    pub fn delete_record(&mut self, table_name: String, id: String) -> Result<(), DBError> {
-        // Check if table exists.
-        let table = self
-            .tables
-            .get_mut(&table_name)
-            .ok_or(DBError::TableDoesNotExist)?;
+    /// Train the quantizer of every table that reached its `train_size`, returns how many were
+    /// trained.
+    ///
+    /// The codebooks are learned from a copy of a sample of the embeddings without holding the
+    /// table lock, which is only taken to encode the records with them.
+    pub fn train_quantizers(&self) -> usize {
+        let tables = self.tables.read().values().cloned().collect::<Vec<_>>();
+        let mut trained = 0;
+        for table in tables {
+            let (config, dimension, sample) = {
+                let table = table.read();
+                let Some((config, sample)) = table.training_sample() else {
+                    continue;
+                };
+                (config, table.dimension, sample)
+            };
+            let instant = Instant::now();
+            let vectors = sample.iter().map(Vec::as_slice).collect::<Vec<_>>();
+            let quantizer = Quantizer::train(config.kind, dimension, &vectors);
+            table.write().install_quantizer(quantizer);
+            info!(
+                "Trained {:?} quantizer on {} records in {:?}",
+                config.kind,
+                vectors.len(),
+                instant.elapsed()
+            );
+            trained += 1;
+        }
+        trained
+    }
 
-        // Delete record from table.
-        table
-            .records
+    /// Write a snapshot of `tables` at `seq` next to `snapshot_path`, returns the file and the
+    /// size of its payload for `install`.
+    fn write_temp(
+        snapshot_path: &Path,
+        tables: &HashMap<String, TableRef>,
+        seq: u64,
+    ) -> Result<(PathBuf, u64)> {
+        let guards = tables
             .iter()
-            .position(|r| r.id == id)
-            .map(|i| table.records.remove(i))
-            .ok_or(DBError::RecordDoesNotExist)?;
+            .map(|(name, table)| (name.as_str(), table.read()))
+            .collect::<Vec<_>>();
+        let snapshot = SnapshotRef {
+            tables: guards.iter().map(|(name, table)| (*name, &**table)).collect(),
+            seq,
+        };
+        let data = bincode::serialize(&snapshot)?;
+        Ok((snapshot::write_temp(snapshot_path, &data)?, data.len() as u64))
+    }
 
+    /// Replace the snapshot of `store` with the one `write_temp` wrote and truncate its
+    /// write-ahead log.
+    fn install(store: &mut Store, (tmp_path, size): (PathBuf, u64), seq: u64) -> Result<()> {
+        snapshot::install(&tmp_path, &store.snapshot_path)?;
+        store.wal.reset()?;
+        // The entries a failed checkpoint left behind are covered as well.
+        let rotated = Wal::rotated_path(store.wal.path());
+        if rotated.exists() {
+            fs::remove_file(&rotated)?;
+        }
+        store.snapshot_size = size;
+        store.snapshot_seq = seq;
         Ok(())
     }
 
-    pub fn drop_table(&mut self, table_name: impl Into<String>) -> Result<(), DBError> {
-        if self.tables.remove(&table_name.into()).is_some() {
-            Ok(())
-        } else {
-            Err(DBError::TableDoesNotExist)
+    /// A consistent copy of the database encoded as a snapshot payload, along with its sequence
+    /// number.
+    ///
+    /// Writers only wait while the tables are cloned, the encoding happens after. Segmented
+    /// tables are copied with all their records so that the payload stands on its own, these
+    /// are decoded from the segments after the locks are released.
+    pub fn snapshot(&self) -> Result<(u64, Vec<u8>)> {
+        let instant = Instant::now();
+        let (seq, tables) = {
+            let tables = timed_lock("tables", "read", || self.tables.read());
+            let guards = tables
+                .iter()
+                .map(|(name, table)| (name.as_str(), table.read()))
+                .collect::<Vec<_>>();
+            let seq = self.log.lock().seq;
+            let copies = guards
+                .iter()
+                .map(|(name, table)| (name.to_string(), (**table).clone()))
+                .collect::<Vec<_>>();
+            (seq, copies)
+        };
+        let tables = tables
+            .into_iter()
+            .map(|(name, table)| Ok((name, table.into_exported()?)))
+            .collect::<Result<HashMap<_, _>>>()?;
+        let snapshot = SnapshotRef {
+            tables: tables.iter().map(|(name, table)| (name.as_str(), table)).collect(),
+            seq,
+        };
+        let data = bincode::serialize(&snapshot)?;
+        observe_snapshot("snapshot", instant);
+        Ok((seq, data))
+    }
+
+    /// Replace every table with the content of a snapshot file, the change is persisted right
+    /// away. Returns the sequence number of the restore.
+    pub fn restore(&self, data: Vec<u8>) -> Result<u64> {
+        if self.is_read_only() {
+            return Err(DBError::ReadOnly.into());
         }
+        self.replace(Snapshot::decode(data)?, None)
     }
 
-    pub fn query_record(
-        &self,
-        table_name: String,
-        query_embedding: &[f32],
-        top_k: usize,
-        distance: Distance,
-    ) -> Result<Vec<EmbeddingRecord>, DBError> {
-        let table = self
-            .tables
-            .get(&table_name)
-            .ok_or(DBError::TableDoesNotExist)?;
+    /// Replace every table with a snapshot file of the leader, taking over its sequence number.
+    pub fn install_snapshot(&self, file: File) -> Result<u64> {
+        let snapshot = Snapshot::read(file)?;
+        let seq = snapshot.seq;
+        self.replace(snapshot, Some(seq))
+    }
 
-        // Check if query embedding has the correct dimension.
-        if query_embedding.len() != table.dimension {
-            return Err(DBError::DimensionMismatch);
+    /// Apply a mutation of the leader, which has to follow the last one applied.
+    pub fn apply_replicated(&self, record: WalRecord) -> Result<(), DBError> {
+        let seq = self.seq();
+        if record.seq != seq + 1 {
+            return Err(DBError::InvalidInput(format!(
+                "Replicated sequence {} does not follow {}",
+                record.seq, seq
+            )));
         }
+        self.write(record.entry)
+    }
 
+    /// Swap in the tables of `snapshot` at sequence `seq`, the next one when `None`.
+    ///
+    /// The new snapshot file is written without any lock, the locks are only taken to check
+    /// that no mutation came in meanwhile, move the file into place and swap the tables. It is
+    /// written again for the next sequence otherwise. Subscribers of the change feed are
+    /// dropped, they missed the change.
+    fn replace(&self, snapshot: Snapshot, seq: Option<u64>) -> Result<u64> {
+        let _checkpointing = self.checkpointing.lock();
+        // Opening the tables flushes segment files no table refers to until the swap.
+        let merging = self.merging.lock();
         let instant = Instant::now();
-        let result = table.top_k_similarity(query_embedding, top_k, distance);
-        info!("Query to {table_name} took {:?}", instant.elapsed());
-        Ok(result)
-    }
+        let tables =
+            Self::open_tables(snapshot.tables, self.segment_dir.as_deref(), self.read_only_dir)?;
+        let snapshot_path = {
+            let log = self.log.lock();
+            log.store.as_ref().map(|store| store.snapshot_path.clone())
+        };
 
-    /// Return the entire data base for debug.
-    pub fn get_entire_db(&self) -> Result<HashMap<String, Table>, DBError> {
-        Ok(self.tables.clone())
+        let seq = loop {
+            let previous = self.seq();
+            // A restore is a mutation of its own, the sequence keeps growing.
+            let seq = seq.unwrap_or(previous + 1);
+            let written = snapshot_path
+                .as_deref()
+                .map(|path| Self::write_temp(path, &tables, seq))
+                .transpose()?;
+
+            let mut current = timed_lock("tables", "write", || self.tables.write());
+            let mut log = timed_lock("log", "write", || self.log.lock());
+            if log.seq != previous {
+                continue;
+            }
+            if let (Some(store), Some(written)) = (log.store.as_mut(), written) {
+                Self::install(store, written, seq)?;
+            }
+            log.seq = seq;
+            log.feed = None;
+            *current = tables;
+            break seq;
+        };
+        drop(merging);
+        self.remove_unused_segments(std::iter::empty());
+        observe_snapshot("restore", instant);
+        Ok(seq)
     }
 
-    /// Return table.
-    pub fn get_table(&self, table_name: String) -> Result<Table, DBError> {
-        self.tables.get(&table_name).ok_or(DBError::TableDoesNotExist).map(|t| t.clone())
+    /// Sequence number of the last mutation along with a receiver of the following ones.
+    ///
+    /// The receiver is closed when the database is restored and lags once it falls more than
+    /// `FEED_CAPACITY` mutations behind.
+    pub fn subscribe(&self) -> (u64, broadcast::Receiver<Arc<WalRecord>>) {
+        let mut log = self.log.lock();
+        let feed = log.feed.get_or_insert_with(|| broadcast::channel(FEED_CAPACITY).0);
+        let receiver = feed.subscribe();
+        (log.seq, receiver)
     }
 
-    pub fn zero() -> Self {
-        Self {
-            tables: HashMap::new(),
+    /// The mutations following `after`, of `table_name` only if given, read back from the
+    /// write-ahead log along with a receiver of the later ones like `subscribe` gives it.
+    ///
+    /// The log is scanned one frame at a time and only the entries kept are decoded. Fails with
+    /// `ChangesUnavailable` once the log no longer holds all of them, after they were compacted
+    /// into a snapshot or the database was restored, or when the kept ones take more than
+    /// `MAX_HISTORY_SIZE`. An `after` past the current sequence number is `InvalidInput`.
+    pub fn subscribe_after(
+        &self,
+        after: u64,
+        table_name: Option<&str>,
+    ) -> Result<(Vec<WalRecord>, broadcast::Receiver<Arc<WalRecord>>), DBError> {
+        let (seq, receiver) = self.subscribe();
+        if after == seq {
+            return Ok((Vec::new(), receiver));
+        }
+        if after > seq {
+            return Err(DBError::InvalidInput(format!(
+                "Sequence number {} is past the last one {}",
+                after, seq
+            )));
         }
+        let path = self.log.lock().store.as_ref().map(|store| store.wal.path().to_path_buf());
+        let Some(path) = path else {
+            return Err(DBError::ChangesUnavailable);
+        };
+        // Read without the lock, a compaction meanwhile shows up as a gap. The log rotated by
+        // a running checkpoint holds the older entries.
+        let rotated = Wal::rotated_path(&path);
+        let logs = [rotated.exists().then_some(rotated), Some(path)];
+        let mut history = Vec::new();
+        let (mut next, mut size) = (after + 1, 0);
+        for path in logs.into_iter().flatten() {
+            let mut reader = WalReader::open(&path).map_err(storage)?;
+            while next <= seq && reader.advance().map_err(storage)? {
+                if reader.seq() < next {
+                    continue;
+                }
+                // The log holds every mutation since the last snapshot, without gaps.
+                if reader.seq() != next {
+                    return Err(DBError::ChangesUnavailable);
+                }
+                next += 1;
+                let other = |name: &str| reader.table_name().is_some_and(|t| t != name);
+                if table_name.is_some_and(other) {
+                    continue;
+                }
+                size += reader.size();
+                if size > MAX_HISTORY_SIZE {
+                    return Err(DBError::ChangesUnavailable);
+                }
+                history.push(reader.record().map_err(storage)?);
+            }
+        }
+        if next != seq + 1 {
+            return Err(DBError::ChangesUnavailable);
+        }
+        Ok((history, receiver))
     }
 
-    pub fn load_from_file() -> Result<Self> {
-        // Create storage file if file no exist.
-        if !STORE_PATH.exists() {
-            fs::create_dir_all(STORE_PATH.parent().context("Invalid storage path")?)?;
-            // Create a new database.
-            return Ok(Self::zero());
-        }
+    /// Number of subscribers of the change feed.
+    pub fn subscribers(&self) -> usize {
+        let log = self.log.lock();
+        log.feed.as_ref().map_or(0, |feed| feed.receiver_count())
+    }
 
-        debug!(
-            "Loading database from file: {:?}",
-            STORE_PATH.as_path().to_str()
-        );
-        let db_data = fs::read(STORE_PATH.as_path())?;
-        let db = bincode::deserialize(&db_data)?;
-        Ok(db)
+    pub fn is_read_only(&self) -> bool {
+        self.read_only.load(Ordering::SeqCst)
     }
 
-    pub fn save_to_file(&self) -> Result<()> {
-        let db_data = bincode::serialize(&self)?;
-        fs::write(STORE_PATH.as_path(), db_data)?;
-        Ok(())
+    /// Reject every mutation but the replicated ones, or accept them again.
+    pub fn set_read_only(&self, read_only: bool) {
+        self.read_only.store(read_only, Ordering::SeqCst);
+    }
+
+    /// Whether mutations happened since the last snapshot.
+    pub fn is_dirty(&self) -> bool {
+        let log = self.log.lock();
+        log.store.as_ref().is_some_and(|s| s.snapshot_seq != log.seq)
+    }
+
+    /// Handle to fsync the write-ahead log from the deferred sync task.
+    pub fn deferred_sync_handle(&self) -> Option<File> {
+        let log = self.log.lock();
+        log.store.as_ref().and_then(|s| s.wal.deferred_sync_handle())
     }
 
     pub fn extension(self) -> DbExtension {
-        Extension(Arc::new(RwLock::new(self)))
+        Extension(Arc::new(self))
     }
 }
 
 impl Drop for Database {
     fn drop(&mut self) {
         tracing::debug!("Saving database to store");
-        self.save_to_file().ok();
+        if let Err(e) = self.checkpoint() {
+            error!("Failed to save database: {:?}", e);
+        }
     }
 }
 
@@ -252,21 +2483,30 @@ impl Drop for Database {
 mod tests {
     use super::*;
     use crate::test_data::*;
+    use crate::similarity::cosine;
+    use tempfile::TempDir;
+
+    /// Every test gets its own storage dir so they can run in parallel.
+    fn open_db() -> (TempDir, Database) {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::load_from_dir(dir.path(), WalOptions::default()).unwrap();
+        (dir, db)
+    }
 
     #[test]
     fn simple_test_all() {
-        let mut db = Database::load_from_file().unwrap();
+        let (_dir, db) = open_db();
         let table_name = "table".to_string();
         let dimension = 4;
         let top_k = 10;
         let distance = Distance::DotProduct;
-        if let Err(e) = db.query_record(table_name.clone(), &vec![1., 2., 3., 4.], top_k, distance) {
+        if let Err(e) = db.query_record(table_name.clone(), &[1., 2., 3., 4.], top_k, distance) {
             assert_eq!(e, DBError::TableDoesNotExist);
 
             db.create_table(table_name.clone(), dimension)
                 .unwrap();
             let records = db
-                .query_record(table_name.clone(), &vec![1., 2., 3., 4.], top_k, distance)
+                .query_record(table_name.clone(), &[1., 2., 3., 4.], top_k, distance)
                 .unwrap();
             assert_eq!(0, records.len());
         }
@@ -277,14 +2517,15 @@ mod tests {
         let record = EmbeddingRecord {
             id: "test record".to_string(),
             embedding: vec![1., 2., 3., 4.],
+            ..Default::default()
         };
         db.insert_record(table_name.clone(), record).unwrap();
 
         let records = db
-            .query_record(table_name.clone(), &vec![1., 2., 3., 4.], top_k, distance)
+            .query_record(table_name.clone(), &[1., 2., 3., 4.], top_k, distance)
             .unwrap();
         assert_eq!(1, records.len());
-        assert_eq!(vec![1., 2., 3., 4.], records[0].embedding);
+        assert_eq!(vec![1., 2., 3., 4.], records[0].record.embedding);
 
         // Delete record
         let table_name = "table".to_string();
@@ -293,7 +2534,7 @@ mod tests {
         let _ = db.delete_record(table_name.clone(), id);
 
         let records = db
-            .query_record(table_name.clone(), &vec![1., 2., 3., 4.], top_k, distance)
+            .query_record(table_name.clone(), &[1., 2., 3., 4.], top_k, distance)
             .unwrap();
         assert_eq!(0, records.len());
 
@@ -301,14 +2542,14 @@ mod tests {
         let top_k = 10;
         let _ = db.drop_table(table_name.clone());
 
-        let err = db.query_record(table_name.clone(), &vec![1., 2., 3., 4.], top_k, distance);
+        let err = db.query_record(table_name.clone(), &[1., 2., 3., 4.], top_k, distance);
         assert!(err.is_err());
     }
 
     #[test]
     fn test_query_openai() {
         // Create database if not exist.
-        let mut db = Database::load_from_file().unwrap();
+        let (_dir, db) = open_db();
         let table_name = "table2".to_string();
         let dimension = 1536;
         let top_k = 2;
@@ -325,16 +2566,16 @@ mod tests {
             .query_record(table_name.clone(), &query_embedding, top_k, distance)
             .unwrap();
         assert_eq!(top_k, records.len());
-        // assert_eq!(vec![1., 2., 3., 4.], records[0].embedding);
-        assert_eq!("OpenAI change the world", records[0].id);
-        assert_eq!("Cat", records[1].id);
+        // assert_eq!(vec![1., 2., 3., 4.], records[0].record.embedding);
+        assert_eq!("OpenAI change the world", records[0].record.id);
+        assert_eq!("Cat", records[1].record.id);
         // OpenAi, Cat, Dog
     }
 
     #[test]
     fn test_query_cat() {
         // Create database if not exist.
-        let mut db = Database::load_from_file().unwrap();
+        let (_dir, db) = open_db();
         let table_name = "table2".to_string();
         let dimension = 1536;
         let top_k = 3;
@@ -351,15 +2592,15 @@ mod tests {
             .query_record(table_name.clone(), &query_embedding, top_k, distance)
             .unwrap();
         assert_eq!(top_k, records.len());
-        assert_eq!("Cat", records[0].id);
-        assert_eq!("Dog", records[1].id);
-        assert_eq!("OpenAI change the world", records[2].id);
+        assert_eq!("Cat", records[0].record.id);
+        assert_eq!("Dog", records[1].record.id);
+        assert_eq!("OpenAI change the world", records[2].record.id);
     }
 
     #[test]
     fn test_query_dog() {
         // Create database if not exist.
-        let mut db = Database::load_from_file().unwrap();
+        let (_dir, db) = open_db();
         let table_name = "table2".to_string();
         let dimension = 1536;
         let top_k = 3;
@@ -376,15 +2617,15 @@ mod tests {
             .query_record(table_name.clone(), &query_embedding, top_k, distance)
             .unwrap();
         assert_eq!(top_k, records.len());
-        assert_eq!("Dog", records[0].id);
-        assert_eq!("Cat", records[1].id);
-        assert_eq!("OpenAI change the world", records[2].id);
+        assert_eq!("Dog", records[0].record.id);
+        assert_eq!("Cat", records[1].record.id);
+        assert_eq!("OpenAI change the world", records[2].record.id);
     }
 
     #[test]
     fn test_query_ml() {
         // Create database if not exist.
-        let mut db = Database::load_from_file().unwrap();
+        let (_dir, db) = open_db();
         let table_name = "table2".to_string();
         let dimension = 1536;
         let top_k = 3;
@@ -401,12 +2642,1174 @@ mod tests {
             .query_record(table_name.clone(), &query_embedding, top_k, distance)
             .unwrap();
         assert_eq!(top_k, records.len());
-        assert_eq!("OpenAI change the world", records[0].id);
-        assert_eq!("Cat", records[1].id);
-        assert_eq!("Dog", records[2].id);
+        assert_eq!("OpenAI change the world", records[0].record.id);
+        assert_eq!("Cat", records[1].record.id);
+        assert_eq!("Dog", records[2].record.id);
+    }
+
+    #[test]
+    fn test_query_hnsw_table() {
+        let db = Database::zero();
+        let table_name = "hnsw_table".to_string();
+        let index = IndexType::Hnsw(HnswConfig::default());
+        db.create_table_with_index(table_name.clone(), 1536, index).unwrap();
+
+        db.insert_record(table_name.clone(), get_dog_record()).unwrap();
+        db.insert_record(table_name.clone(), get_cat_record()).unwrap();
+        db.insert_record(table_name.clone(), get_openai_record()).unwrap();
+
+        let query_embedding = get_cat_embedding();
+        let approximate = db
+            .query_record(table_name.clone(), &query_embedding, 3, Distance::Cosine)
+            .unwrap();
+        let exact = db
+            .query_record_with(
+                table_name.clone(),
+                &query_embedding,
+                3,
+                Some(Distance::Cosine),
+                &SearchOptions { exact: true, ..Default::default() },
+            )
+            .unwrap();
+        let ids = |records: &[ScoredRecord]| records.iter().map(|r| r.record.id.clone()).collect::<Vec<_>>();
+        assert_eq!(ids(&exact), ids(&approximate));
+        assert_eq!("Cat", approximate[0].record.id);
+
+        db.delete_record(table_name.clone(), "Cat".to_string()).unwrap();
+        let records = db
+            .query_record(table_name.clone(), &query_embedding, 3, Distance::Cosine)
+            .unwrap();
+        assert_eq!(vec!["Dog", "OpenAI change the world"], ids(&records));
+    }
+
+    #[test]
+    fn test_wal_replay_after_crash() {
+        let (dir, db) = open_db();
+        let table_name = "table".to_string();
+        db.create_table(table_name.clone(), 1536).unwrap();
+        db.insert_record(table_name.clone(), get_dog_record()).unwrap();
+        db.insert_record(table_name.clone(), get_cat_record()).unwrap();
+        db.delete_record(table_name.clone(), "Dog".to_string()).unwrap();
+        // Skip the checkpoint on drop, as if the process got killed.
+        std::mem::forget(db);
+
+        let db = Database::load_from_dir(dir.path(), WalOptions::default()).unwrap();
+        assert_eq!(4, db.seq());
+        let table = db.get_table(table_name).unwrap();
+        assert_eq!(vec!["Cat"], table.records.iter().map(|r| r.id.as_str()).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_interrupted_checkpoint() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::load_from_dir(dir.path(), WalOptions::default()).unwrap();
+        db.create_table("table".to_string(), 2).unwrap();
+        let record = |id: &str| EmbeddingRecord {
+            id: id.to_string(),
+            embedding: vec![1., 2.],
+            ..Default::default()
+        };
+        db.insert_record("table".to_string(), record("a")).unwrap();
+        // A crash after the rotation, before the snapshot is written.
+        db.log.lock().store.as_mut().unwrap().wal.rotate().unwrap();
+        db.insert_record("table".to_string(), record("b")).unwrap();
+        std::mem::forget(db);
+
+        let rotated = Wal::rotated_path(&dir.path().join(WAL_FILE));
+        assert!(rotated.exists());
+        let db = Database::load_from_dir(dir.path(), WalOptions::default()).unwrap();
+        assert_eq!(3, db.seq());
+        assert_eq!(2, db.get_table("table".to_string()).unwrap().records.len());
+        assert!(!rotated.exists());
+    }
+
+    #[test]
+    fn test_wal_compaction() {
+        let dir = tempfile::tempdir().unwrap();
+        let options = WalOptions {
+            compact_threshold: 4096,
+            ..Default::default()
+        };
+        let db = Database::load_from_dir(dir.path(), options).unwrap();
+        let table_name = "table".to_string();
+        db.create_table(table_name.clone(), 4).unwrap();
+        for i in 0..100 {
+            let record = EmbeddingRecord {
+                id: i.to_string(),
+                embedding: vec![1., 2., 3., 4.],
+                ..Default::default()
+            };
+            db.insert_record(table_name.clone(), record).unwrap();
+        }
+        // Writes never compact, the background task does.
+        assert!(fs::metadata(dir.path().join(WAL_FILE)).unwrap().len() >= 4096);
+        assert!(db.compact().unwrap());
+        assert!(!db.compact().unwrap());
+        std::mem::forget(db);
+
+        // The log got folded into the snapshot.
+        assert!(fs::metadata(dir.path().join(WAL_FILE)).unwrap().len() < 4096);
+        assert!(dir.path().join(SNAPSHOT_FILE).exists());
+
+        let db = Database::load_from_dir(dir.path(), options).unwrap();
+        assert_eq!(101, db.seq());
+        assert_eq!(100, db.get_table(table_name).unwrap().records.len());
+    }
+
+    #[test]
+    fn test_load_first_release_snapshot() {
+        let dir = tempfile::tempdir().unwrap();
+        // The bare bincode of the first release database, a table of 2 dimensions.
+        let mut data = 1u64.to_le_bytes().to_vec();
+        data.extend_from_slice(&5u64.to_le_bytes());
+        data.extend_from_slice(b"table");
+        data.extend_from_slice(&2u64.to_le_bytes());
+        data.extend_from_slice(&1u64.to_le_bytes());
+        data.extend_from_slice(&1u64.to_le_bytes());
+        data.extend_from_slice(b"a");
+        data.extend_from_slice(&2u64.to_le_bytes());
+        data.extend_from_slice(&1f32.to_le_bytes());
+        data.extend_from_slice(&0f32.to_le_bytes());
+        fs::write(dir.path().join(SNAPSHOT_FILE), data).unwrap();
+
+        let db = Database::load_from_dir(dir.path(), WalOptions::default()).unwrap();
+        assert_eq!(0, db.seq());
+        assert_eq!(vec![1., 0.], db.get_record("table", "a").unwrap().embedding);
+        let hits = db.query_record("table".to_string(), &[1., 0.], 1, Distance::Cosine).unwrap();
+        assert_eq!("a", hits[0].record.id);
+
+        // The next checkpoint writes the current layout.
+        let record = EmbeddingRecord {
+            id: "b".to_string(),
+            embedding: vec![0., 1.],
+            ..Default::default()
+        };
+        db.insert_record("table".to_string(), record).unwrap();
+        db.checkpoint().unwrap();
+        drop(db);
+        let db = Database::load_from_dir(dir.path(), WalOptions::default()).unwrap();
+        assert_eq!(2, db.table_stats("table").unwrap().count);
+    }
+
+    #[test]
+    fn test_create_table_with_binding() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::load_from_dir(dir.path(), WalOptions::default()).unwrap();
+        let binding = Some(EmbeddingBinding::default());
+        let (options, fields) = Default::default();
+        db.create_table_with_binding("t".to_string(), 2, options, fields, binding.clone())
+            .unwrap();
+        // A single mutation, a crash can not leave the table unbound.
+        assert_eq!(1, db.seq());
+        std::mem::forget(db);
+
+        let db = Database::load_from_dir(dir.path(), WalOptions::default()).unwrap();
+        assert_eq!(binding, db.embedding_binding("t").unwrap());
+    }
+
+    #[test]
+    fn test_tables_do_not_block_each_other() {
+        let db = Arc::new(Database::zero());
+        let record = |id: &str| EmbeddingRecord {
+            id: id.to_string(),
+            embedding: vec![1., 0.],
+            ..Default::default()
+        };
+        db.create_table("a".to_string(), 2).unwrap();
+        db.create_table("b".to_string(), 2).unwrap();
+
+        // Hold table `a` as a long running write would.
+        let table = db.table("a").unwrap();
+        let guard = table.write();
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        let other = db.clone();
+        std::thread::spawn(move || {
+            other.insert_record("b".to_string(), record("x")).unwrap();
+            let hits = other.query_record("b".to_string(), &[1., 0.], 1, Distance::Cosine);
+            tx.send(hits.unwrap().len()).unwrap();
+        });
+        assert_eq!(Ok(1), rx.recv_timeout(std::time::Duration::from_secs(5)));
+
+        let other = db.clone();
+        let writer = std::thread::spawn(move || other.insert_record("a".to_string(), record("y")));
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        assert!(!writer.is_finished());
+        drop(guard);
+        writer.join().unwrap().unwrap();
+        assert!(db.get_record("a", "y").is_ok());
+    }
+
+    #[test]
+    fn test_load_on_other_tables() {
+        let db = Arc::new(Database::zero());
+        db.create_table("a".to_string(), 2).unwrap();
+        db.create_table("b".to_string(), 2).unwrap();
+        let record = |id: String| EmbeddingRecord {
+            id,
+            embedding: vec![1., 0.],
+            ..Default::default()
+        };
+
+        // Writers of `a` pile up behind a long running write while `b` takes a steady load of
+        // writes and queries from several threads.
+        let table = db.table("a").unwrap();
+        let guard = table.write();
+        let blocked = (0..4)
+            .map(|i| {
+                let (db, record) = (db.clone(), record(format!("{i}")));
+                std::thread::spawn(move || db.insert_record("a".to_string(), record))
+            })
+            .collect::<Vec<_>>();
+        let (tx, rx) = std::sync::mpsc::channel();
+        for worker in 0..8 {
+            let (db, tx) = (db.clone(), tx.clone());
+            std::thread::spawn(move || {
+                for i in 0..200 {
+                    let id = format!("{worker}-{i}");
+                    db.insert_record("b".to_string(), record(id)).unwrap();
+                    let hits = db.query_record("b".to_string(), &[1., 0.], 5, Distance::Cosine);
+                    assert!(!hits.unwrap().is_empty());
+                }
+                tx.send(()).unwrap();
+            });
+        }
+        drop(tx);
+        for _ in 0..8 {
+            assert_eq!(Ok(()), rx.recv_timeout(std::time::Duration::from_secs(30)));
+        }
+        assert!(blocked.iter().all(|writer| !writer.is_finished()));
+        assert_eq!(1600, db.table_stats("b").unwrap().count);
+
+        drop(guard);
+        for writer in blocked {
+            writer.join().unwrap().unwrap();
+        }
+        assert_eq!(4, db.table_stats("a").unwrap().count);
+    }
+
+    #[test]
+    fn test_checkpoint_during_concurrent_writes() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Arc::new(Database::load_from_dir(dir.path(), WalOptions::default()).unwrap());
+        let tables = (0..4).map(|i| format!("table{}", i)).collect::<Vec<_>>();
+        for table_name in &tables {
+            db.create_table(table_name.clone(), 2).unwrap();
+        }
+
+        let writers = tables
+            .iter()
+            .map(|table_name| {
+                let db = db.clone();
+                let table_name = table_name.clone();
+                std::thread::spawn(move || {
+                    for i in 0..200 {
+                        let record = EmbeddingRecord {
+                            id: i.to_string(),
+                            embedding: vec![i as f32, 1.],
+                            ..Default::default()
+                        };
+                        db.insert_record(table_name.clone(), record).unwrap();
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        for _ in 0..5 {
+            db.checkpoint().unwrap();
+        }
+        for writer in writers {
+            writer.join().unwrap();
+        }
+        // Leave the tail of the log behind, the snapshots above cover the rest.
+        std::mem::forget(Arc::into_inner(db).unwrap());
+
+        let db = Database::load_from_dir(dir.path(), WalOptions::default()).unwrap();
+        assert_eq!(4 + 4 * 200, db.seq());
+        for table_name in tables {
+            assert_eq!(200, db.get_table(table_name).unwrap().records.len());
+        }
+    }
+
+    #[test]
+    fn test_restore_during_writes() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Arc::new(Database::load_from_dir(dir.path(), WalOptions::default()).unwrap());
+        db.create_table("w".to_string(), 2).unwrap();
+        let (_, data) = db.snapshot().unwrap();
+        let data = [snapshot::header(&data), data].concat();
+
+        let writer = {
+            let db = db.clone();
+            std::thread::spawn(move || {
+                for i in 0..300 {
+                    let record = EmbeddingRecord {
+                        id: i.to_string(),
+                        embedding: vec![i as f32, 1.],
+                        ..Default::default()
+                    };
+                    db.upsert_records("w".to_string(), vec![record]).unwrap();
+                }
+            })
+        };
+        // Every restore is written for the sequence it is swapped in at, whatever the writer
+        // logged while the file was written.
+        for _ in 0..5 {
+            db.restore(data.clone()).unwrap();
+        }
+        writer.join().unwrap();
+        assert_eq!(1 + 300 + 5, db.seq());
+        let count = db.table_stats("w").unwrap().count;
+        std::mem::forget(Arc::into_inner(db).unwrap());
+
+        let db = Database::load_from_dir(dir.path(), WalOptions::default()).unwrap();
+        assert_eq!(1 + 300 + 5, db.seq());
+        assert_eq!(count, db.table_stats("w").unwrap().count);
+    }
+
+    #[test]
+    fn test_query_with_filter() {
+        let db = Database::zero();
+        let index = IndexType::Hnsw(HnswConfig {
+            distance: Distance::DotProduct,
+            ..Default::default()
+        });
+        db.create_table_with_index("flat".to_string(), 4, IndexType::Flat).unwrap();
+        db.create_table_with_index("hnsw".to_string(), 4, index).unwrap();
+        for i in 0..300 {
+            let metadata: Metadata = serde_json::from_value(serde_json::json!({
+                "parity": i % 2,
+                "tags": if i % 100 == 0 { vec!["rare"] } else { vec!["common"] },
+            }))
+            .unwrap();
+            let record = EmbeddingRecord {
+                id: i.to_string(),
+                embedding: vec![i as f32, 1., 0., 0.],
+                metadata,
+                ..Default::default()
+            };
+            db.insert_record("flat".to_string(), record.clone()).unwrap();
+            db.insert_record("hnsw".to_string(), record).unwrap();
+        }
+
+        let odd: Filter = serde_json::from_str(r#"{"eq": {"field": "parity", "value": 1}}"#).unwrap();
+        let rare: Filter = serde_json::from_str(r#"{"in": {"field": "tags", "values": ["rare"]}}"#).unwrap();
+        for table_name in ["flat", "hnsw"] {
+            let query = |filter: &Filter| {
+                let options = SearchOptions {
+                    filter: Some(filter),
+                    ..Default::default()
+                };
+                db.query_record_with(table_name.to_string(), &[1., 0., 0., 0.], 10, Some(Distance::DotProduct), &options)
+                    .unwrap()
+                    .into_iter()
+                    .map(|r| r.record.id)
+                    .collect::<Vec<_>>()
+            };
+
+            let ids = query(&odd);
+            assert_eq!(10, ids.len());
+            assert_eq!("299", ids[0]);
+            assert!(ids.iter().all(|id| id.parse::<usize>().unwrap() % 2 == 1));
+
+            assert_eq!(vec!["200", "100", "0"], query(&rare));
+        }
+    }
+
+    #[test]
+    fn test_query_explain() {
+        let db = Database::zero();
+        let index = IndexType::Hnsw(HnswConfig::default());
+        db.create_table_with_index("hnsw".to_string(), 2, index).unwrap();
+        for i in 0..300 {
+            let metadata: Metadata =
+                serde_json::from_value(serde_json::json!({"rare": i % 100 == 0})).unwrap();
+            let record = EmbeddingRecord {
+                id: i.to_string(),
+                embedding: vec![i as f32, 1.],
+                metadata,
+                ..Default::default()
+            };
+            db.insert_record("hnsw".to_string(), record).unwrap();
+        }
+
+        let rare: Filter =
+            serde_json::from_str(r#"{"eq": {"field": "rare", "value": true}}"#).unwrap();
+        let common: Filter =
+            serde_json::from_str(r#"{"eq": {"field": "rare", "value": false}}"#).unwrap();
+        let explain = |exact: bool, filter: Option<&Filter>| {
+            let explain = Mutex::new(QueryExplain::default());
+            let options = SearchOptions {
+                exact,
+                filter,
+                explain: Some(&explain),
+                ..Default::default()
+            };
+            let hits = db
+                .query_record_with("hnsw".to_string(), &[1., 0.], 3, None, &options)
+                .unwrap();
+            assert_eq!(3, hits.len());
+            let explain = explain.into_inner();
+            assert_eq!(Distance::Cosine, explain.distance);
+            assert!(explain.lock_wait_micros <= explain.total_micros);
+            let phases = explain.phases.iter().map(|p| p.name.clone()).collect::<Vec<_>>();
+            (explain, phases)
+        };
+
+        let (hnsw, phases) = explain(false, None);
+        assert_eq!(QueryPlan::Hnsw, hnsw.plan);
+        assert!(hnsw.candidates >= 3 && hnsw.candidates < 300, "{hnsw:?}");
+        assert_eq!(vec!["index"], phases);
+
+        let (flat, phases) = explain(true, None);
+        assert_eq!(QueryPlan::Flat, flat.plan);
+        assert_eq!(300, flat.candidates);
+        assert_eq!(vec!["scan"], phases);
+
+        // Three records pass the filter, fewer than `ef_search`.
+        let (selective, phases) = explain(false, Some(&rare));
+        assert_eq!(QueryPlan::ExactFiltered, selective.plan);
+        assert_eq!((Some(3), 3), (selective.filtered, selective.candidates));
+        assert_eq!(vec!["filter", "scan"], phases);
+
+        let (filtered, phases) = explain(false, Some(&common));
+        assert_eq!(QueryPlan::HnswFiltered, filtered.plan);
+        assert_eq!(Some(297), filtered.filtered);
+        assert_eq!(vec!["filter", "index"], phases);
+    }
+
+    #[test]
+    fn test_query_scores_and_cutoff() {
+        let db = Database::zero();
+        let euclidean = IndexType::Hnsw(HnswConfig {
+            distance: Distance::Euclidean,
+            ..Default::default()
+        });
+        db.create_table_with_index("flat".to_string(), 2, IndexType::Flat).unwrap();
+        db.create_table_with_index("hnsw".to_string(), 2, euclidean).unwrap();
+        for (id, embedding) in [("near", [1., 0.]), ("mid", [3., 0.]), ("far", [10., 0.])] {
+            let record = EmbeddingRecord {
+                id: id.to_string(),
+                embedding: embedding.to_vec(),
+                ..Default::default()
+            };
+            db.insert_record("flat".to_string(), record.clone()).unwrap();
+            db.insert_record("hnsw".to_string(), record).unwrap();
+        }
+
+        for table_name in ["flat", "hnsw"] {
+            let query = |distance: Distance, options: &SearchOptions| {
+                db.query_record_with(table_name.to_string(), &[0., 0.], 3, Some(distance), options)
+                    .unwrap()
+                    .into_iter()
+                    .map(|r| (r.record.id, r.score))
+                    .collect::<Vec<_>>()
+            };
+
+            // Closest first for the euclidean distance.
+            let hits = query(Distance::Euclidean, &SearchOptions::default());
+            assert_eq!(vec![("near".to_string(), 1.), ("mid".to_string(), 3.), ("far".to_string(), 10.)], hits);
+
+            let options = SearchOptions {
+                max_distance: Some(5.),
+                ..Default::default()
+            };
+            let hits = query(Distance::Euclidean, &options);
+            assert_eq!(vec!["near", "mid"], hits.iter().map(|h| h.0.as_str()).collect::<Vec<_>>());
+        }
+
+        let options = SearchOptions {
+            min_score: Some(2.),
+            ..Default::default()
+        };
+        let hits = db
+            .query_record_with("flat".to_string(), &[1., 0.], 3, Some(Distance::DotProduct), &options)
+            .unwrap();
+        assert_eq!(vec![("far", 10.), ("mid", 3.)], hits.iter().map(|h| (h.record.id.as_str(), h.score)).collect::<Vec<_>>());
+
+        // The cutoff of the other kind of metric is rejected rather than applied backwards.
+        let e = db.query_record_with("flat".to_string(), &[1., 0.], 3, Some(Distance::Euclidean), &options);
+        assert!(matches!(e, Err(DBError::InvalidInput(_))), "{:?}", e);
+        let options = SearchOptions {
+            max_distance: Some(2.),
+            ..Default::default()
+        };
+        let e = db.query_record_with("flat".to_string(), &[1., 0.], 3, Some(Distance::Cosine), &options);
+        assert!(matches!(e, Err(DBError::InvalidInput(_))), "{:?}", e);
+    }
+
+    #[test]
+    fn test_batch_insert_and_upsert() {
+        let (dir, db) = open_db();
+        let table_name = "table".to_string();
+        let index = IndexType::Hnsw(HnswConfig::default());
+        db.create_table_with_index(table_name.clone(), 2, index).unwrap();
+        let record = |id: &str, embedding: [f32; 2]| EmbeddingRecord {
+            id: id.to_string(),
+            embedding: embedding.to_vec(),
+            ..Default::default()
+        };
+
+        db.insert_records(table_name.clone(), (0..10).map(|i| record(&i.to_string(), [i as f32, 1.])).collect())
+            .unwrap();
+
+        // A batch is rejected as a whole.
+        let err = db.insert_records(table_name.clone(), vec![record("a", [1., 1.]), record("a", [2., 2.])]);
+        assert_eq!(Err(DBError::RecordExist), err);
+        let err = db.insert_records(table_name.clone(), vec![record("b", [1., 1.]), record("3", [2., 2.])]);
+        assert_eq!(Err(DBError::RecordExist), err);
+        assert_eq!(10, db.get_table(table_name.clone()).unwrap().records.len());
+
+        // Upsert replaces in place and adds the new ones.
+        db.upsert_records(table_name.clone(), vec![record("3", [-1., 0.]), record("b", [0., -1.])])
+            .unwrap();
+        let table = db.get_table(table_name.clone()).unwrap();
+        assert_eq!(11, table.records.len());
+        assert_eq!(vec![-1., 0.], table.get("3").unwrap().unwrap().embedding);
+
+        let hits = db.query_record(table_name.clone(), &[-1., 0.], 1, Distance::Cosine).unwrap();
+        assert_eq!("3", hits[0].record.id);
+
+        // Swap remove keeps the positions of the moved records right.
+        db.delete_record(table_name.clone(), "0".to_string()).unwrap();
+        drop(db);
+
+        let db = Database::load_from_dir(dir.path(), WalOptions::default()).unwrap();
+        let table = db.get_table(table_name).unwrap();
+        assert_eq!(10, table.records.len());
+        assert!(table.get("0").unwrap().is_none());
+        for record in &table.records {
+            assert_eq!(record.id, table.get(&record.id).unwrap().unwrap().id);
+        }
+    }
+
+    #[test]
+    fn test_list_records_and_stats() {
+        let db = Database::zero();
+        let table_name = "table".to_string();
+        db.create_table(table_name.clone(), 2).unwrap();
+        let records = (0..25)
+            .map(|i| EmbeddingRecord {
+                id: format!("id{:02}", (i * 7) % 25),
+                embedding: vec![i as f32, 1.],
+                ..Default::default()
+            })
+            .collect();
+        db.insert_records(table_name.clone(), records).unwrap();
+
+        let mut ids = Vec::new();
+        let mut cursor = None;
+        loop {
+            let page = db.list_records(&table_name, cursor.as_deref(), 10, false).unwrap();
+            assert!(page.records.iter().all(|r| r.embedding.is_empty()));
+            ids.extend(page.records.into_iter().map(|r| r.id));
+            cursor = page.next_cursor;
+            if cursor.is_none() {
+                break;
+            }
+        }
+        let expected = (0..25).map(|i| format!("id{:02}", i)).collect::<Vec<_>>();
+        assert_eq!(expected, ids);
+
+        // Removing records does not shift the following pages.
+        let page = db.list_records(&table_name, None, 5, true).unwrap();
+        assert_eq!(2, page.records[0].embedding.len());
+        db.delete_record(table_name.clone(), "id02".to_string()).unwrap();
+        let page = db.list_records(&table_name, page.next_cursor.as_deref(), 1, true).unwrap();
+        assert_eq!("id05", page.records[0].id);
+
+        assert_eq!(vec![3., 1.], db.get_record(&table_name, "id21").unwrap().embedding);
+        assert_eq!(Some(DBError::RecordDoesNotExist), db.get_record(&table_name, "nope").err());
+        assert_eq!(Some(DBError::TableDoesNotExist), db.get_record("nope", "id21").err());
+
+        let stats = db.table_stats(&table_name).unwrap();
+        assert_eq!(24, stats.count);
+        assert_eq!(2, stats.dimension);
+        assert_eq!(IndexType::Flat, stats.index);
+        assert_eq!(24, stats.memory.records);
+        assert_eq!(vec![table_name], db.list_tables());
+    }
+
+    #[test]
+    fn test_quantized_tables() {
+        let (dir, db) = open_db();
+        let kinds = [
+            ("scalar", QuantizationKind::Scalar),
+            ("product", QuantizationKind::Product { subspaces: 4 }),
+        ];
+        for (table_name, kind) in kinds {
+            let options = TableOptions {
+                quantization: Some(QuantizationConfig {
+                    kind,
+                    train_size: 100,
+                    rerank_factor: 4,
+                }),
+                ..Default::default()
+            };
+            db.create_table_with_options(table_name.to_string(), 8, options).unwrap();
+        }
+        let err = db.create_table_with_options(
+            "bad".to_string(),
+            8,
+            TableOptions {
+                quantization: Some(QuantizationConfig {
+                    kind: QuantizationKind::Product { subspaces: 3 },
+                    train_size: 100,
+                    rerank_factor: 4,
+                }),
+                ..Default::default()
+            },
+        );
+        assert!(matches!(err, Err(DBError::InvalidInput(_))));
+        let err = db.create_table_with_options(
+            "indexed".to_string(),
+            8,
+            TableOptions {
+                index: IndexType::Hnsw(HnswConfig::default()),
+                quantization: Some(QuantizationConfig {
+                    kind: QuantizationKind::Scalar,
+                    train_size: 100,
+                    rerank_factor: 4,
+                }),
+                ..Default::default()
+            },
+        );
+        assert!(matches!(err, Err(DBError::InvalidInput(_))));
+
+        let mut state = 1u32;
+        let records = (0..300)
+            .map(|i| EmbeddingRecord {
+                id: i.to_string(),
+                embedding: (0..8)
+                    .map(|_| {
+                        state = state.wrapping_mul(1664525).wrapping_add(1013904223);
+                        (state >> 16) as f32 / 65536. - 0.5
+                    })
+                    .collect(),
+                ..Default::default()
+            })
+            .collect::<Vec<_>>();
+        // Training happens apart from the writes, which keep the vectors until then.
+        for (table_name, _) in kinds {
+            db.insert_records(table_name.to_string(), records[..150].to_vec()).unwrap();
+            assert_eq!(0, db.memory_usage(table_name).unwrap().quantized);
+        }
+        assert_eq!(2, db.train_quantizers());
+        assert_eq!(0, db.train_quantizers());
+        for (table_name, _) in kinds {
+            db.insert_records(table_name.to_string(), records[150..].to_vec()).unwrap();
+            db.delete_record(table_name.to_string(), "0".to_string()).unwrap();
+        }
+        drop(db);
+
+        let db = Database::load_from_dir(dir.path(), WalOptions::default()).unwrap();
+        let query = &records[42].embedding;
+        for (table_name, _) in kinds {
+            let usage = db.memory_usage(table_name).unwrap();
+            assert_eq!(299, usage.records);
+            assert!(usage.quantized > 0);
+            // The full-precision vectors are mapped from a file rather than held.
+            assert_eq!(0, usage.vectors);
+            assert!(usage.mapped >= 299 * 8 * 4);
+            assert_eq!(records[7].embedding, db.get_record(table_name, "7").unwrap().embedding);
+
+            // Re-ranking turns the approximate scores back into exact ones.
+            for distance in [Distance::Euclidean, Distance::Cosine, Distance::DotProduct] {
+                let exact = db
+                    .query_record_with(
+                        table_name.to_string(),
+                        query,
+                        5,
+                        Some(distance),
+                        &SearchOptions { exact: true, ..Default::default() },
+                    )
+                    .unwrap();
+                let reranked = db.query_record(table_name.to_string(), query, 5, distance).unwrap();
+                assert_eq!(exact[0].record.id, reranked[0].record.id, "{table_name} {distance:?}");
+                assert_eq!(exact[0].score, reranked[0].score);
+            }
+
+            let approximate = db
+                .query_record_with(
+                    table_name.to_string(),
+                    query,
+                    5,
+                    Some(Distance::Euclidean),
+                    &SearchOptions { rerank: Some(false), ..Default::default() },
+                )
+                .unwrap();
+            assert_eq!(5, approximate.len());
+        }
+    }
+
+    #[test]
+    fn test_hybrid_query() {
+        let (dir, db) = open_db();
+        let table_name = "products".to_string();
+        db.create_table(table_name.clone(), 2).unwrap();
+        let record = |id: &str, embedding: [f32; 2], text: &str| EmbeddingRecord {
+            id: id.to_string(),
+            embedding: embedding.to_vec(),
+            text: Some(text.to_string()),
+            ..Default::default()
+        };
+        db.insert_records(
+            table_name.clone(),
+            vec![
+                record("p1", [0., 1.], "Pump filter AB-1234"),
+                record("p2", [1., 0.], "Pump filter XZ-9"),
+                record("p3", [0.9, 0.1], "Garden hose"),
+            ],
+        )
+        .unwrap();
+        drop(db);
+
+        // The text index is rebuilt on load.
+        let db = Database::load_from_dir(dir.path(), WalOptions::default()).unwrap();
+        let query = |db: &Database, fusion: Fusion| {
+            let hybrid = HybridOptions {
+                query_text: "ab-1234",
+                fusion,
+                candidates: 10,
+            };
+            db.hybrid_query(table_name.clone(), &[1., 0.], 2, Some(Distance::Cosine), &SearchOptions::default(), &hybrid)
+                .unwrap()
+        };
+
+        // The exact code match is last by vector similarity alone.
+        let hits = query(&db, Fusion::default());
+        assert_eq!(vec!["p1", "p2"], hits.iter().map(|h| h.record.id.as_str()).collect::<Vec<_>>());
+        assert!(hits[0].keyword_score.is_some() && hits[0].vector_score.is_some());
+        assert_eq!(None, hits[1].keyword_score);
+
+        let hits = query(&db, Fusion::Weighted { alpha: 1. });
+        assert_eq!("p2", hits[0].record.id);
+        let hybrid = HybridOptions {
+            query_text: "",
+            fusion: Fusion::Weighted { alpha: 2. },
+            candidates: 10,
+        };
+        let err = db.hybrid_query(table_name.clone(), &[1., 0.], 2, Some(Distance::Cosine), &SearchOptions::default(), &hybrid);
+        assert!(matches!(err, Err(DBError::InvalidInput(_))));
+
+        // Replacing the text drops the old terms.
+        db.upsert_records(table_name.clone(), vec![record("p1", [0., 1.], "Pump filter")]).unwrap();
+        let hits = query(&db, Fusion::default());
+        assert_eq!("p2", hits[0].record.id);
+        assert!(hits.iter().all(|h| h.keyword_score.is_none()));
+    }
+
+    #[test]
+    fn test_named_vector_fields() {
+        let (dir, db) = open_db();
+        let table_name = "docs".to_string();
+        let fields = BTreeMap::from([
+            ("title".to_string(), VectorField { dimension: 2, distance: Distance::Cosine }),
+            ("body".to_string(), VectorField { dimension: 3, distance: Distance::Euclidean }),
+        ]);
+        let record = |id: &str, title: [f32; 2], body: Option<[f32; 3]>| {
+            let mut vectors = HashMap::from([("title".to_string(), title.to_vec())]);
+            if let Some(body) = body {
+                vectors.insert("body".to_string(), body.to_vec());
+            }
+            EmbeddingRecord {
+                id: id.to_string(),
+                embedding: vec![1., 0.],
+                vectors,
+                ..Default::default()
+            }
+        };
+        let bad = BTreeMap::from([(MAIN_FIELD.to_string(), fields["title"])]);
+        let res = db.create_table_with_fields(table_name.clone(), 2, TableOptions::default(), bad);
+        assert!(matches!(res, Err(DBError::InvalidInput(_))));
+        db.create_table_with_fields(table_name.clone(), 2, TableOptions::default(), fields)
+            .unwrap();
+
+        db.insert_records(
+            table_name.clone(),
+            vec![
+                record("a", [1., 0.], Some([0., 0., 0.])),
+                record("b", [0., 1.], Some([1., 1., 1.])),
+                record("c", [1., 0.1], None),
+            ],
+        )
+        .unwrap();
+        let mut unknown = record("d", [1., 0.], None);
+        unknown.vectors.insert("summary".to_string(), vec![1.]);
+        let res = db.insert_record(table_name.clone(), unknown);
+        assert!(matches!(res, Err(DBError::InvalidInput(_))));
+        let mut short = record("d", [1., 0.], None);
+        short.vectors.insert("body".to_string(), vec![1., 1.]);
+        let res = db.insert_record(table_name.clone(), short);
+        assert!(matches!(res, Err(DBError::DimensionMismatch)));
+
+        let vector = |field: &str, embedding: &[f32], weight: f32| VectorQuery {
+            field: field.to_string(),
+            embedding: embedding.to_vec(),
+            weight,
+            distance: None,
+        };
+        let options = SearchOptions::default();
+        let ids = |hits: Vec<CombinedRecord>| {
+            hits.into_iter().map(|h| h.record.id).collect::<Vec<_>>()
+        };
+
+        // A single field uses its own distance.
+        let hits = db
+            .query_fields(&table_name, &[vector("title", &[1., 0.], 1.)], 3, &options)
+            .unwrap();
+        assert_eq!(vec!["a", "c", "b"], ids(hits));
+        let hits = db
+            .query_fields(&table_name, &[vector("body", &[1., 1., 1.], 1.)], 3, &options)
+            .unwrap();
+        assert_eq!(vec![0.], hits[0].scores);
+        assert_eq!(vec!["b", "a"], ids(hits));
+
+        // Only records carrying every queried field take part, weighted.
+        let queries = [vector("title", &[1., 0.], 0.1), vector("body", &[1., 1., 1.], 1.)];
+        let hits = db.query_fields(&table_name, &queries, 3, &options).unwrap();
+        assert_eq!(vec!["b", "a"], ids(hits));
+        let queries = [vector("title", &[1., 0.], 10.), vector("body", &[1., 1., 1.], 1.)];
+        let hits = db.query_fields(&table_name, &queries, 3, &options).unwrap();
+        // Each field is normalized over the records taking part before it is weighted.
+        assert_eq!(vec![10., 1.], hits.iter().map(|h| h.score).collect::<Vec<_>>());
+        assert_eq!(vec!["a", "b"], ids(hits));
+        let cut = SearchOptions {
+            min_score: Some(5.),
+            ..Default::default()
+        };
+        assert_eq!(vec!["a"], ids(db.query_fields(&table_name, &queries, 3, &cut).unwrap()));
+        let res = db.query_fields(&table_name, &[vector("title", &[1., 0.], 0.)], 1, &options);
+        assert!(matches!(res, Err(DBError::InvalidInput(_))));
+
+        // `embedding` defaults to the distance of the table, cosine.
+        let main = vector(MAIN_FIELD, &[1., 0.], 1.);
+        let hits = db.query_fields(&table_name, std::slice::from_ref(&main), 3, &options).unwrap();
+        assert_eq!(3, hits.len());
+        // `min_score` is on the weighted score, applied before the top k.
+        let heavy = VectorQuery {
+            weight: 2.,
+            ..main.clone()
+        };
+        let cut = SearchOptions {
+            min_score: Some(1.9),
+            ..Default::default()
+        };
+        let hits = db.query_fields(&table_name, std::slice::from_ref(&heavy), 3, &cut).unwrap();
+        assert_eq!(3, hits.len());
+        let cut = SearchOptions {
+            min_score: Some(2.1),
+            ..Default::default()
+        };
+        assert!(db.query_fields(&table_name, &[heavy], 3, &cut).unwrap().is_empty());
+        let euclidean = VectorQuery {
+            distance: Some(Distance::Euclidean),
+            ..main
+        };
+        let hits = db.query_fields(&table_name, &[euclidean], 3, &options).unwrap();
+        assert_eq!(3, hits.len());
+        let res = db.query_fields(&table_name, &[vector("title", &[1.], 1.)], 1, &options);
+        assert!(matches!(res, Err(DBError::DimensionMismatch)));
+
+        // Fields and vectors survive the log and the snapshot.
+        drop(db);
+        let db = Database::load_from_dir(dir.path(), WalOptions::default()).unwrap();
+        assert_eq!(2, db.table_stats(&table_name).unwrap().fields.len());
+        assert_eq!(vec![1., 1., 1.], db.get_record(&table_name, "b").unwrap().vectors["body"]);
     }
 
     // similarity compute test
+    #[test]
+    fn test_segmented_tables() {
+        let (dir, db) = open_db();
+        let table_name = "table".to_string();
+        let config = SegmentConfig {
+            memtable_size: 3,
+            max_segments: 2,
+        };
+        let options = TableOptions {
+            storage: Storage::Segments(config),
+            ..Default::default()
+        };
+        let e = Database::zero().create_table_with_options(table_name.clone(), 2, options);
+        assert!(matches!(e, Err(DBError::InvalidInput(_))));
+        let hnsw = TableOptions {
+            index: IndexType::Hnsw(HnswConfig::default()),
+            ..options
+        };
+        let e = db.create_table_with_options(table_name.clone(), 2, hnsw);
+        assert!(matches!(e, Err(DBError::InvalidInput(_))));
+        db.create_table_with_options(table_name.clone(), 2, options).unwrap();
+
+        let record = |i: usize| EmbeddingRecord {
+            id: format!("id{:02}", i),
+            embedding: vec![i as f32, 1.],
+            ..Default::default()
+        };
+        db.insert_records(table_name.clone(), (0..10).map(record).collect()).unwrap();
+        // 3 segments of 3 records, one in the memtable.
+        let table = db.table(&table_name).unwrap();
+        assert_eq!(3, table.read().segments.as_ref().unwrap().len());
+        assert_eq!(1, table.read().records.len());
+
+        let e = db.insert_record(table_name.clone(), record(4));
+        assert_eq!(Some(DBError::RecordExist), e.err());
+        let moved = EmbeddingRecord {
+            embedding: vec![-1., 0.],
+            ..record(4)
+        };
+        db.upsert_records(table_name.clone(), vec![moved]).unwrap();
+        db.delete_record(table_name.clone(), "id09".to_string()).unwrap();
+        db.delete_record(table_name.clone(), "id01".to_string()).unwrap();
+        assert_eq!(vec![-1., 0.], db.get_record(&table_name, "id04").unwrap().embedding);
+        assert_eq!(Some(DBError::RecordDoesNotExist), db.get_record(&table_name, "id01").err());
+
+        let check = |db: &Database| {
+            assert_eq!(8, db.table_stats(&table_name).unwrap().count);
+            let hits = db
+                .query_record(table_name.clone(), &[-1., 0.], 2, Distance::Euclidean)
+                .unwrap();
+            let ids = hits.iter().map(|h| h.record.id.as_str()).collect::<Vec<_>>();
+            assert_eq!(vec!["id04", "id00"], ids);
+            let hits = db.query_record(table_name.clone(), &[1., 0.], 1, Distance::Cosine).unwrap();
+            assert_eq!("id08", hits[0].record.id);
+
+            let page = db.list_records(&table_name, Some("id02"), 3, false).unwrap();
+            let ids = page.records.iter().map(|r| r.id.as_str()).collect::<Vec<_>>();
+            assert_eq!(vec!["id03", "id04", "id05"], ids);
+            assert_eq!(Some("id05".to_string()), page.next_cursor);
+        };
+        check(&db);
+
+        assert_eq!(1, db.merge_segments().unwrap());
+        assert_eq!(2, table.read().segments.as_ref().unwrap().len());
+        check(&db);
+        let segment_files = || fs::read_dir(dir.path().join(SEGMENT_DIR)).unwrap().count();
+        assert_eq!(4, segment_files());
+        db.checkpoint().unwrap();
+        assert_eq!(2, segment_files());
+
+        // The snapshot keeps the memtable and the segment names, not the segment records.
+        let (_, exported) = db.snapshot().unwrap();
+        db.insert_record(table_name.clone(), record(20)).unwrap();
+        std::mem::forget(db);
+        let db = Database::load_from_dir(dir.path(), WalOptions::default()).unwrap();
+        assert!(db.get_record(&table_name, "id20").is_ok());
+        db.delete_record(table_name.clone(), "id20".to_string()).unwrap();
+        check(&db);
+
+        let (_other_dir, other) = open_db();
+        other.restore([snapshot::header(&exported), exported].concat()).unwrap();
+        check(&other);
+        let table = other.table(&table_name).unwrap();
+        assert!(table.read().records.is_empty());
+        assert_eq!(Storage::Segments(config), table.read().options().storage);
+        assert!(other.query_fields(&table_name, &[], 1, &SearchOptions::default()).is_err());
+    }
+
+    #[test]
+    fn test_open_read_only() {
+        let (dir, db) = open_db();
+        let table_name = "table".to_string();
+        let options = TableOptions {
+            storage: Storage::Segments(SegmentConfig {
+                memtable_size: 3,
+                max_segments: 2,
+            }),
+            ..Default::default()
+        };
+        db.create_table_with_options(table_name.clone(), 2, options).unwrap();
+        let record = |i: usize| EmbeddingRecord {
+            id: format!("id{:02}", i),
+            embedding: vec![i as f32, 1.],
+            ..Default::default()
+        };
+        db.insert_records(table_name.clone(), (0..4).map(record).collect()).unwrap();
+        db.checkpoint().unwrap();
+        // Replaying these fills the memtable again.
+        db.insert_records(table_name.clone(), (4..9).map(record).collect()).unwrap();
+        std::mem::forget(db);
+
+        let files = || {
+            let segments = fs::read_dir(dir.path().join(SEGMENT_DIR)).unwrap();
+            let mut files = fs::read_dir(dir.path())
+                .unwrap()
+                .chain(segments)
+                .map(|entry| entry.unwrap().path())
+                .filter(|path| path.is_file())
+                .map(|path| (fs::read(&path).unwrap(), path))
+                .collect::<Vec<_>>();
+            files.sort();
+            files
+        };
+        let before = files();
+        let db = Database::open_read_only(dir.path()).unwrap();
+        assert_eq!(9, db.table_stats(&table_name).unwrap().count);
+        assert!(db.get_record(&table_name, "id08").is_ok());
+        let e = db.insert_record(table_name.clone(), record(20));
+        assert!(matches!(e, Err(DBError::ReadOnly)));
+        drop(db);
+        assert!(before == files());
+
+        let lock = lock_dir(dir.path()).unwrap();
+        assert!(lock_dir(dir.path()).is_err());
+        drop(lock);
+        lock_dir(dir.path()).unwrap();
+    }
+
+    #[test]
+    fn test_open_read_only_live() {
+        let (dir, db) = open_db();
+        let _lock = lock_dir(dir.path()).unwrap();
+        db.create_table("t".to_string(), 2).unwrap();
+        let record = |i: usize| EmbeddingRecord {
+            id: format!("id{:02}", i),
+            embedding: vec![i as f32, 1.],
+            ..Default::default()
+        };
+        db.insert_records("t".to_string(), (0..4).map(record).collect()).unwrap();
+        let count = || {
+            let read_only = Database::open_read_only(dir.path()).unwrap();
+            read_only.table_stats("t").unwrap().count
+        };
+        assert_eq!(4, count());
+
+        db.checkpoint().unwrap();
+        db.insert_records("t".to_string(), (4..6).map(record).collect()).unwrap();
+        assert_eq!(6, count());
+        db.insert_record("t".to_string(), record(6)).unwrap();
+        assert_eq!(7, count());
+    }
+
+    #[test]
+    fn test_table_default_distance() {
+        let (dir, db) = open_db();
+        let options = |distance, index| TableOptions {
+            distance: Some(distance),
+            index,
+            ..Default::default()
+        };
+        let record = |id: &str, embedding: Vec<f32>| EmbeddingRecord {
+            id: id.to_string(),
+            embedding,
+            ..Default::default()
+        };
+        let hnsw = IndexType::Hnsw(HnswConfig::default());
+        db.create_table_with_options("l2".to_string(), 2, options(Distance::Euclidean, hnsw))
+            .unwrap();
+        db.create_table_with_options("ip".to_string(), 2, options(Distance::InnerProduct, hnsw))
+            .unwrap();
+        db.create_table_with_options("bits".to_string(), 4, options(Distance::Hamming, hnsw))
+            .unwrap();
+        let quantized = TableOptions {
+            quantization: Some(QuantizationConfig {
+                kind: QuantizationKind::Scalar,
+                train_size: 10,
+                rerank_factor: 1,
+            }),
+            ..options(Distance::Jaccard, IndexType::Flat)
+        };
+        let e = db.create_table_with_options("q".to_string(), 2, quantized);
+        assert!(matches!(e, Err(DBError::InvalidInput(_))));
+
+        let points = vec![record("near", vec![1., 1.]), record("far", vec![4., 5.])];
+        db.insert_records("l2".to_string(), points.clone()).unwrap();
+        db.insert_records("ip".to_string(), points).unwrap();
+        db.insert_records("bits".to_string(), vec![
+            record("a", vec![1., 0., 1., 0.]),
+            record("b", vec![1., 1., 1., 1.]),
+        ]).unwrap();
+
+        let check = |db: &Database| {
+            let l2 = db.table("l2").unwrap();
+            assert_eq!(Distance::Euclidean, l2.read().index.as_ref().unwrap().config().distance);
+            assert_eq!(Distance::Euclidean, db.table_stats("l2").unwrap().distance);
+            let options = SearchOptions::default();
+            let hits = db.query_record_with("l2".to_string(), &[0., 0.], 2, None, &options);
+            let hits = hits.unwrap();
+            assert_eq!("near", hits[0].record.id);
+            assert!((hits[0].score - 2f32.sqrt()).abs() < 1e-6);
+
+            // Records and queries are unit vectors, the scores are the cosine similarities.
+            let near = db.get_record("ip", "near").unwrap();
+            assert!((norm(&near.embedding) - 1.).abs() < 1e-6);
+            let hits = db.query_record_with("ip".to_string(), &[0., 3.], 1, None, &options);
+            let hits = hits.unwrap();
+            assert_eq!("far", hits[0].record.id);
+            assert!((hits[0].score - cosine(&[4., 5.], &[0., 1.])).abs() < 1e-6);
+            // The records of the other tables are not unit vectors.
+            let ip = Some(Distance::InnerProduct);
+            let e = db.query_record_with("l2".to_string(), &[0., 3.], 1, ip, &options).err();
+            assert!(matches!(e, Some(DBError::InvalidInput(_))));
+
+            let query = [1., 0., 0., 0.];
+            let hits = db.query_record_with("bits".to_string(), &query, 2, None, &options);
+            let hits = hits.unwrap();
+            assert_eq!(("a", 1.), (hits[0].record.id.as_str(), hits[0].score));
+            assert_eq!(("b", 3.), (hits[1].record.id.as_str(), hits[1].score));
+        };
+        check(&db);
+        drop(db);
+        let db = Database::load_from_dir(dir.path(), WalOptions::default()).unwrap();
+        check(&db);
+    }
+
+    #[test]
+    fn test_record_expiry() {
+        let (dir, db) = open_db();
+        let table_name = "table".to_string();
+        let options = TableOptions {
+            ttl_secs: Some(3600),
+            ..Default::default()
+        };
+        db.create_table_with_options(table_name.clone(), 2, options).unwrap();
+        let record = |id: &str, expires_at| EmbeddingRecord {
+            id: id.to_string(),
+            embedding: vec![1., 0.],
+            expires_at,
+            ..Default::default()
+        };
+        let now = unix_now();
+        db.insert_records(table_name.clone(), vec![
+            record("kept", None),
+            record("expired", Some(now - 1)),
+        ]).unwrap();
+        // The table TTL applies to records without their own expiry.
+        let kept = db.get_record(&table_name, "kept").unwrap();
+        assert!(kept.expires_at.is_some_and(|t| t >= now + 3600));
+        assert_eq!(Some(3600), db.table_stats(&table_name).unwrap().ttl_secs);
+        // The expired record is not counted, though it is still stored.
+        assert_eq!(1, db.table_stats(&table_name).unwrap().count);
+        assert_eq!(vec![(table_name.clone(), 1)], db.record_counts());
+        let options = TableOptions {
+            ttl_secs: Some(0),
+            ..Default::default()
+        };
+        let e = db.create_table_with_options("zero".to_string(), 2, options).err();
+        assert!(matches!(e, Some(DBError::InvalidInput(_))));
+
+        let check = |db: &Database| {
+            let e = db.get_record(&table_name, "expired").err();
+            assert_eq!(Some(DBError::RecordDoesNotExist), e);
+            let hits = db.query_record(table_name.clone(), &[1., 0.], 2, Distance::Cosine).unwrap();
+            assert_eq!(1, hits.len());
+            assert_eq!("kept", hits[0].record.id);
+            let page = db.list_records(&table_name, None, 10, false).unwrap();
+            assert_eq!(1, page.records.len());
+        };
+        check(&db);
+        // Expired records can be inserted again before they are cleaned up.
+        let e = db.insert_record(table_name.clone(), record("expired", Some(now - 1)));
+        assert_eq!(None, e.err());
+
+        assert_eq!(1, db.expire_records().unwrap());
+        assert_eq!(0, db.expire_records().unwrap());
+        assert_eq!(1, db.table(&table_name).unwrap().read().records.len());
+        std::mem::forget(db);
+        let db = Database::load_from_dir(dir.path(), WalOptions::default()).unwrap();
+        assert_eq!(1, db.table(&table_name).unwrap().read().records.len());
+        check(&db);
+    }
+
     #[test]
     fn test_consine_similarity_compute() {
         let distance = Distance::Cosine;
@@ -415,7 +3818,7 @@ mod tests {
         let score = Table::compute_score(distance, &v1 , &v2);
 
         // assert two float number very close.
-        assert!((score - 0.9688639316269662).abs() < 1e-6);
+        assert!((score - 0.968_863_9).abs() < 1e-6);
     }
 
     #[test]
@@ -0,0 +1,284 @@
+use crate::database::DBError;
+use axum::Extension;
+use llm_sdk::{CreateEmbeddingRequestBuilder, LlmSdk};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use tracing::{debug, info};
+
+pub use tinyvector_types::embedding::{EmbeddingBinding, EmbeddingModel};
+
+/// The `[embedding]` section of the server config.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "snake_case")]
+pub struct EmbeddingSettings {
+    /// OpenAI compatible API.
+    pub base_url: String,
+    /// Falls back to `OPENAI_API_KEY`, no key is sent when neither is set.
+    pub api_key: Option<String>,
+    /// Texts sent per request.
+    pub batch_size: usize,
+    /// Embeddings kept in memory, keyed by their model and a digest of their text.
+    pub cache_size: usize,
+}
+
+impl Default for EmbeddingSettings {
+    fn default() -> Self {
+        Self {
+            base_url: "https://api.openai.com/v1".to_string(),
+            api_key: None,
+            batch_size: 256,
+            cache_size: 10_000,
+        }
+    }
+}
+
+/// The model of the SDK named like `model`.
+fn sdk_model(model: &EmbeddingModel) -> llm_sdk::EmbeddingModel {
+    match model {
+        EmbeddingModel::TextEmbeddingAda002 => llm_sdk::EmbeddingModel::TextEmbeddingAda002,
+    }
+}
+
+/// The model and the SHA-256 digest of the text, so that the cache does not keep the texts.
+type CacheKey = (EmbeddingModel, [u8; 32]);
+
+/// Cache key of the embedding of `text` by `model`.
+fn cache_key(model: &EmbeddingModel, text: &str) -> CacheKey {
+    (model.clone(), Sha256::digest(text).into())
+}
+
+/// Bounded map evicting the oldest entry first.
+#[derive(Debug, Default)]
+struct Cache {
+    capacity: usize,
+    entries: HashMap<CacheKey, Arc<Vec<f32>>>,
+    order: VecDeque<CacheKey>,
+}
+
+impl Cache {
+    fn get(&self, key: &CacheKey) -> Option<Arc<Vec<f32>>> {
+        self.entries.get(key).cloned()
+    }
+
+    fn insert(&mut self, key: CacheKey, embedding: Arc<Vec<f32>>) {
+        if self.capacity == 0 || self.entries.insert(key.clone(), embedding).is_some() {
+            return;
+        }
+        self.order.push_back(key);
+        while self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.entries.remove(&oldest);
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Inner {
+    sdk: LlmSdk,
+    batch_size: usize,
+    cache: Mutex<Cache>,
+}
+
+/// Server-side embedding of text through `llm_sdk`, disabled without an `[embedding]` config.
+#[derive(Debug, Clone, Default)]
+pub struct Embedder {
+    inner: Option<Arc<Inner>>,
+}
+
+impl Embedder {
+    pub fn new(settings: &EmbeddingSettings) -> Self {
+        let token = settings
+            .api_key
+            .clone()
+            .or_else(|| std::env::var("OPENAI_API_KEY").ok())
+            .unwrap_or_default();
+        info!("Embedding text through {}", settings.base_url);
+        let inner = Inner {
+            sdk: LlmSdk::new_with_base_url(settings.base_url.clone(), token),
+            batch_size: settings.batch_size.max(1),
+            cache: Mutex::new(Cache {
+                capacity: settings.cache_size,
+                ..Default::default()
+            }),
+        };
+        Self {
+            inner: Some(Arc::new(inner)),
+        }
+    }
+
+    /// Whether an embedding provider is configured.
+    pub fn is_enabled(&self) -> bool {
+        self.inner.is_some()
+    }
+
+    /// Embed `texts` with `model`, in order. Repeated and cached texts are only paid for once.
+    pub async fn embed(
+        &self,
+        model: &EmbeddingModel,
+        texts: &[&str],
+    ) -> Result<Vec<Vec<f32>>, DBError> {
+        let Some(inner) = &self.inner else {
+            return Err(DBError::InvalidInput(
+                "No embedding provider is configured".to_string(),
+            ));
+        };
+        let keys = texts.iter().map(|text| cache_key(model, text)).collect::<Vec<_>>();
+
+        let mut found = HashMap::new();
+        let mut missing = Vec::new();
+        {
+            let cache = inner.cache.lock();
+            for (key, text) in keys.iter().zip(texts) {
+                if found.contains_key(key) {
+                    continue;
+                }
+                match cache.get(key) {
+                    Some(embedding) => {
+                        found.insert(key.clone(), embedding);
+                    }
+                    None => {
+                        // Placeholder so a text repeated within the batch is only sent once.
+                        found.insert(key.clone(), Arc::new(Vec::new()));
+                        missing.push((key.clone(), *text));
+                    }
+                }
+            }
+        }
+        debug!("Embedding {} texts, {} not cached", texts.len(), missing.len());
+
+        for chunk in missing.chunks(inner.batch_size) {
+            let input = chunk.iter().map(|(_, text)| text.to_string()).collect::<Vec<_>>();
+            let request = CreateEmbeddingRequestBuilder::default()
+                .input(input.into())
+                .model(sdk_model(model))
+                .build()
+                .map_err(|e| DBError::InvalidInput(e.to_string()))?;
+            let response = inner
+                .sdk
+                .create_embedding(request)
+                .await
+                .map_err(|e| DBError::Embedding(e.to_string()))?;
+            if response.data.len() != chunk.len() {
+                return Err(DBError::Embedding(format!(
+                    "{} embeddings returned for {} texts",
+                    response.data.len(),
+                    chunk.len()
+                )));
+            }
+
+            let mut cache = inner.cache.lock();
+            for data in response.data {
+                let (key, _) = chunk.get(data.index).ok_or_else(|| {
+                    DBError::Embedding(format!("Unexpected embedding index {}", data.index))
+                })?;
+                let embedding = Arc::new(data.embedding);
+                cache.insert(key.clone(), embedding.clone());
+                found.insert(key.clone(), embedding);
+            }
+        }
+
+        Ok(keys.iter().map(|key| found[key].to_vec()).collect())
+    }
+
+    pub fn extension(&self) -> Extension<Embedder> {
+        Extension(self.clone())
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use axum::routing::post;
+    use axum::{Json, Router};
+    use std::net::SocketAddr;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Embedding of `text` by the mock endpoint, `[len, first byte]`.
+    pub fn mock_embedding(text: &str) -> Vec<f32> {
+        vec![text.len() as f32, text.bytes().next().unwrap_or(0) as f32]
+    }
+
+    /// Serve an OpenAI style `/embeddings` endpoint, returns its base url and the number of
+    /// texts it embedded so far.
+    pub async fn mock_server() -> (String, Arc<AtomicUsize>) {
+        let embedded = Arc::new(AtomicUsize::new(0));
+        let counter = embedded.clone();
+        let app = Router::new().route(
+            "/embeddings",
+            post(move |Json(body): Json<serde_json::Value>| {
+                let counter = counter.clone();
+                async move {
+                    let input = match &body["input"] {
+                        serde_json::Value::String(text) => vec![text.clone()],
+                        input => serde_json::from_value::<Vec<String>>(input.clone()).unwrap(),
+                    };
+                    counter.fetch_add(input.len(), Ordering::SeqCst);
+                    let data = input
+                        .iter()
+                        .enumerate()
+                        .map(|(index, text)| {
+                            serde_json::json!({
+                                "index": index,
+                                "embedding": mock_embedding(text),
+                                "object": "embedding",
+                            })
+                        })
+                        .collect::<Vec<_>>();
+                    Json(serde_json::json!({
+                        "object": "list",
+                        "data": data,
+                        "model": body["model"],
+                        "usage": {"prompt_tokens": 0, "total_tokens": 0},
+                    }))
+                }
+            }),
+        );
+        let server = axum::Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0)))
+            .serve(app.into_make_service());
+        let base_url = format!("http://{}", server.local_addr());
+        tokio::spawn(server);
+        (base_url, embedded)
+    }
+
+    #[tokio::test]
+    async fn test_embed_batches_and_caches() {
+        let (base_url, embedded) = mock_server().await;
+        let embedder = Embedder::new(&EmbeddingSettings {
+            base_url,
+            batch_size: 2,
+            ..Default::default()
+        });
+        let model = EmbeddingModel::default();
+
+        let texts = ["a cat", "dogs", "a cat", "fish"];
+        let embeddings = embedder.embed(&model, &texts).await.unwrap();
+        assert_eq!(texts.map(mock_embedding).to_vec(), embeddings);
+        // The repeated text is only sent once.
+        assert_eq!(3, embedded.load(Ordering::SeqCst));
+
+        let embeddings = embedder.embed(&model, &["dogs", "birds"]).await.unwrap();
+        assert_eq!(vec![mock_embedding("dogs"), mock_embedding("birds")], embeddings);
+        assert_eq!(4, embedded.load(Ordering::SeqCst));
+
+        assert!(Embedder::default().embed(&model, &["a cat"]).await.is_err());
+    }
+
+    #[test]
+    fn test_cache_evicts_oldest() {
+        let mut cache = Cache {
+            capacity: 2,
+            ..Default::default()
+        };
+        let model = EmbeddingModel::default();
+        for i in 0..3 {
+            cache.insert(cache_key(&model, &i.to_string()), Arc::new(vec![i as f32]));
+        }
+        let get = |i: usize| cache.get(&cache_key(&model, &i.to_string()));
+        assert!(get(0).is_none());
+        assert!(get(1).is_some() && get(2).is_some());
+    }
+}
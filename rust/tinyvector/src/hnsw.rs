@@ -0,0 +1,519 @@
+use crate::similarity::get_distance_fn;
+use serde::{Deserialize, Serialize};
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+pub use tinyvector_types::database::HnswConfig;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Node {
+    id: String,
+    vector: Vec<f32>,
+    /// Neighbour list of every layer the node lives on.
+    neighbours: Vec<Vec<usize>>,
+    /// Deleted nodes stay in the graph for routing until the next rebuild.
+    deleted: bool,
+}
+
+/// Hierarchical navigable small world graph.
+///
+/// Deletes only mark the node as a tombstone, the graph is rebuilt from the live
+/// nodes once more than half of them are gone.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HnswIndex {
+    config: HnswConfig,
+    nodes: Vec<Node>,
+    id_to_node: HashMap<String, usize>,
+    entry_point: Option<usize>,
+    max_level: usize,
+    deleted: usize,
+    /// State of the xorshift generator used to draw node levels.
+    rng_state: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Candidate {
+    distance: f32,
+    node: usize,
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.distance
+            .total_cmp(&other.distance)
+            .then(self.node.cmp(&other.node))
+    }
+}
+
+/// Rebuilding a tiny graph is not worth it.
+const MIN_NODES_TO_REBUILD: usize = 64;
+
+impl HnswIndex {
+    pub fn new(config: HnswConfig) -> Self {
+        Self {
+            config: HnswConfig {
+                m: config.m.max(2),
+                ef_construction: config.ef_construction.max(1),
+                ef_search: config.ef_search.max(1),
+                distance: config.distance,
+            },
+            nodes: Vec::new(),
+            id_to_node: HashMap::new(),
+            entry_point: None,
+            max_level: 0,
+            deleted: 0,
+            rng_state: 0x9E37_79B9_7F4A_7C15,
+        }
+    }
+
+    pub fn config(&self) -> &HnswConfig {
+        &self.config
+    }
+
+    /// Number of live vectors in the index.
+    pub fn len(&self) -> usize {
+        self.nodes.len() - self.deleted
+    }
+
+    #[allow(dead_code)]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Approximate bytes held by the nodes, tombstones included.
+    pub fn memory_usage(&self) -> usize {
+        let nodes = self
+            .nodes
+            .iter()
+            .map(|node| {
+                let links = node.neighbours.iter().map(Vec::len).sum::<usize>();
+                node.id.len() * 2
+                    + node.vector.len() * std::mem::size_of::<f32>()
+                    + links * std::mem::size_of::<usize>()
+            })
+            .sum::<usize>();
+        nodes + self.id_to_node.len() * std::mem::size_of::<usize>()
+    }
+
+    pub fn insert(&mut self, id: String, vector: Vec<f32>) {
+        // Replacing an id leaves the old node behind as a tombstone.
+        self.remove(&id);
+
+        let level = self.random_level();
+        let node = self.nodes.len();
+        self.nodes.push(Node {
+            id: id.clone(),
+            vector,
+            neighbours: vec![Vec::new(); level + 1],
+            deleted: false,
+        });
+        self.id_to_node.insert(id, node);
+
+        let Some(mut entry) = self.entry_point else {
+            self.entry_point = Some(node);
+            self.max_level = level;
+            return;
+        };
+
+        let query = self.nodes[node].vector.clone();
+        for layer in (level + 1..=self.max_level).rev() {
+            entry = self.greedy_closest(&query, entry, layer);
+        }
+
+        for layer in (0..=level.min(self.max_level)).rev() {
+            let ef = self.config.ef_construction;
+            let (candidates, _) = self.search_layer(&query, &[entry], ef, layer, |_| true);
+            let neighbours = self.select_neighbours(&candidates, self.config.m);
+            for &neighbour in &neighbours {
+                self.connect(neighbour, node, layer);
+            }
+            self.nodes[node].neighbours[layer] = neighbours;
+            entry = candidates[0].node;
+        }
+
+        if level > self.max_level {
+            self.max_level = level;
+            self.entry_point = Some(node);
+        }
+    }
+
+    /// Mark the vector with `id` as deleted, return whether it was present.
+    pub fn remove(&mut self, id: &str) -> bool {
+        let Some(node) = self.id_to_node.remove(id) else {
+            return false;
+        };
+        self.nodes[node].deleted = true;
+        self.deleted += 1;
+
+        if self.nodes.len() >= MIN_NODES_TO_REBUILD && self.deleted * 2 > self.nodes.len() {
+            self.rebuild();
+        }
+        true
+    }
+
+    /// Approximate `top_k` nearest neighbours of `query` accepted by `accept`.
+    ///
+    /// Returns ids with the score of the index metric, best match first.
+    pub fn search<F>(
+        &self,
+        query: &[f32],
+        top_k: usize,
+        ef_search: Option<usize>,
+        accept: F,
+    ) -> Vec<(&str, f32)>
+    where
+        F: Fn(&str) -> bool,
+    {
+        self.search_with_visits(query, top_k, ef_search, accept).0
+    }
+
+    /// `search`, along with the number of nodes visited on the bottom layer.
+    pub fn search_with_visits<F>(
+        &self,
+        query: &[f32],
+        top_k: usize,
+        ef_search: Option<usize>,
+        accept: F,
+    ) -> (Vec<(&str, f32)>, usize)
+    where
+        F: Fn(&str) -> bool,
+    {
+        let Some(mut entry) = self.entry_point else {
+            return (Vec::new(), 0);
+        };
+        if top_k == 0 {
+            return (Vec::new(), 0);
+        }
+
+        for layer in (1..=self.max_level).rev() {
+            entry = self.greedy_closest(query, entry, layer);
+        }
+
+        let ef = ef_search.unwrap_or(self.config.ef_search).max(top_k);
+        let accept = |node: &Node| !node.deleted && accept(&node.id);
+        let (candidates, visited) = self.search_layer(query, &[entry], ef, 0, accept);
+        let hits = candidates
+            .into_iter()
+            .take(top_k)
+            .map(|candidate| {
+                let node = &self.nodes[candidate.node];
+                (node.id.as_str(), self.to_score(candidate.distance))
+            })
+            .collect();
+        (hits, visited)
+    }
+
+    /// Distance in the graph, smaller is closer for every metric.
+    fn distance(&self, a: &[f32], b: &[f32]) -> f32 {
+        let score = get_distance_fn(self.config.distance)(a, b);
+        -self.config.distance.rank_key(score)
+    }
+
+    /// Map a graph distance back to the score the metric reports.
+    fn to_score(&self, distance: f32) -> f32 {
+        self.config.distance.rank_key(-distance)
+    }
+
+    fn max_connections(&self, layer: usize) -> usize {
+        if layer == 0 {
+            self.config.m * 2
+        } else {
+            self.config.m
+        }
+    }
+
+    fn random_level(&mut self) -> usize {
+        // xorshift64*
+        let mut x = self.rng_state;
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        self.rng_state = x;
+        let uniform = (x.wrapping_mul(0x2545_F491_4F6C_DD1D) >> 11) as f64 / (1u64 << 53) as f64;
+
+        let level_mult = 1.0 / (self.config.m as f64).ln();
+        (-uniform.max(f64::MIN_POSITIVE).ln() * level_mult).floor() as usize
+    }
+
+    /// Walk greedily towards `query` on a single layer.
+    fn greedy_closest(&self, query: &[f32], entry: usize, layer: usize) -> usize {
+        let mut current = entry;
+        let mut current_distance = self.distance(query, &self.nodes[current].vector);
+        loop {
+            let mut changed = false;
+            for &neighbour in &self.nodes[current].neighbours[layer] {
+                let distance = self.distance(query, &self.nodes[neighbour].vector);
+                if distance < current_distance {
+                    current = neighbour;
+                    current_distance = distance;
+                    changed = true;
+                }
+            }
+            if !changed {
+                return current;
+            }
+        }
+    }
+
+    /// Beam search on one layer, returns up to `ef` accepted nodes sorted by distance and the
+    /// number of nodes visited.
+    ///
+    /// Rejected nodes are still expanded so that a selective `accept` does not
+    /// cut the graph into pieces.
+    fn search_layer<F>(
+        &self,
+        query: &[f32],
+        entries: &[usize],
+        ef: usize,
+        layer: usize,
+        accept: F,
+    ) -> (Vec<Candidate>, usize)
+    where
+        F: Fn(&Node) -> bool,
+    {
+        let mut visited = HashSet::new();
+        let mut candidates = BinaryHeap::new();
+        let mut results: BinaryHeap<Candidate> = BinaryHeap::new();
+
+        for &entry in entries {
+            visited.insert(entry);
+            let candidate = Candidate {
+                distance: self.distance(query, &self.nodes[entry].vector),
+                node: entry,
+            };
+            candidates.push(Reverse(candidate));
+            if accept(&self.nodes[entry]) {
+                results.push(candidate);
+            }
+        }
+
+        while let Some(Reverse(current)) = candidates.pop() {
+            if results.len() >= ef && current.distance > results.peek().unwrap().distance {
+                break;
+            }
+
+            for &neighbour in &self.nodes[current.node].neighbours[layer] {
+                if !visited.insert(neighbour) {
+                    continue;
+                }
+                let distance = self.distance(query, &self.nodes[neighbour].vector);
+                if results.len() < ef || distance < results.peek().unwrap().distance {
+                    let candidate = Candidate {
+                        distance,
+                        node: neighbour,
+                    };
+                    candidates.push(Reverse(candidate));
+                    if accept(&self.nodes[neighbour]) {
+                        results.push(candidate);
+                        if results.len() > ef {
+                            results.pop();
+                        }
+                    }
+                }
+            }
+        }
+
+        (results.into_sorted_vec(), visited.len())
+    }
+
+    /// Neighbour selection heuristic from the HNSW paper, `candidates` sorted by distance.
+    ///
+    /// A candidate is skipped when it is closer to an already selected neighbour than
+    /// to the base node, the free slots are then filled with the closest skipped ones.
+    fn select_neighbours(&self, candidates: &[Candidate], m: usize) -> Vec<usize> {
+        let mut selected: Vec<usize> = Vec::with_capacity(m);
+        let mut skipped = Vec::new();
+        for candidate in candidates {
+            if selected.len() >= m {
+                break;
+            }
+            let vector = &self.nodes[candidate.node].vector;
+            let diverse = selected.iter().all(|&other| {
+                self.distance(vector, &self.nodes[other].vector) > candidate.distance
+            });
+            if diverse {
+                selected.push(candidate.node);
+            } else {
+                skipped.push(candidate.node);
+            }
+        }
+
+        for node in skipped {
+            if selected.len() >= m {
+                break;
+            }
+            selected.push(node);
+        }
+        selected
+    }
+
+    /// Add an edge `from -> to` and shrink the neighbour list of `from` if it overflows.
+    fn connect(&mut self, from: usize, to: usize, layer: usize) {
+        self.nodes[from].neighbours[layer].push(to);
+        let max_connections = self.max_connections(layer);
+        if self.nodes[from].neighbours[layer].len() <= max_connections {
+            return;
+        }
+
+        let base = &self.nodes[from].vector;
+        let mut candidates = self.nodes[from].neighbours[layer]
+            .iter()
+            .map(|&node| Candidate {
+                distance: self.distance(base, &self.nodes[node].vector),
+                node,
+            })
+            .collect::<Vec<_>>();
+        candidates.sort();
+        self.nodes[from].neighbours[layer] = self.select_neighbours(&candidates, max_connections);
+    }
+
+    /// Build a fresh graph from the live nodes.
+    fn rebuild(&mut self) {
+        let live = std::mem::take(&mut self.nodes)
+            .into_iter()
+            .filter(|node| !node.deleted)
+            .collect::<Vec<_>>();
+
+        let mut index = HnswIndex::new(self.config);
+        index.rng_state = self.rng_state;
+        for node in live {
+            index.insert(node.id, node.vector);
+        }
+        *self = index;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::similarity::Distance;
+    use std::collections::HashSet;
+
+    /// Deterministic vectors so the test does not need a rng crate.
+    fn random_vectors(count: usize, dimension: usize) -> Vec<Vec<f32>> {
+        let mut state = 42u64;
+        (0..count)
+            .map(|_| {
+                (0..dimension)
+                    .map(|_| {
+                        state = state
+                            .wrapping_mul(6364136223846793005)
+                            .wrapping_add(1442695040888963407);
+                        ((state >> 33) as f32 / (1u64 << 31) as f32) - 0.5
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn exact_top_k(
+        vectors: &[Vec<f32>],
+        query: &[f32],
+        top_k: usize,
+        distance: Distance,
+    ) -> Vec<String> {
+        let distance_fn = get_distance_fn(distance);
+        let mut scored = vectors
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (i, distance_fn(v, query)))
+            .collect::<Vec<_>>();
+        match distance {
+            Distance::Euclidean => scored.sort_by(|a, b| a.1.total_cmp(&b.1)),
+            _ => scored.sort_by(|a, b| b.1.total_cmp(&a.1)),
+        }
+        scored
+            .into_iter()
+            .take(top_k)
+            .map(|(i, _)| i.to_string())
+            .collect()
+    }
+
+    fn recall(distance: Distance) -> f32 {
+        let vectors = random_vectors(1000, 16);
+        let queries = random_vectors(50, 16);
+        let config = HnswConfig {
+            distance,
+            ..Default::default()
+        };
+        let mut index = HnswIndex::new(config);
+        for (i, vector) in vectors.iter().enumerate() {
+            index.insert(i.to_string(), vector.clone());
+        }
+
+        let top_k = 10;
+        let mut hits = 0;
+        for query in &queries {
+            let expected = exact_top_k(&vectors, query, top_k, distance)
+                .into_iter()
+                .collect::<HashSet<_>>();
+            hits += index
+                .search(query, top_k, None, |_| true)
+                .into_iter()
+                .filter(|(id, _)| expected.contains(*id))
+                .count();
+        }
+        hits as f32 / (queries.len() * top_k) as f32
+    }
+
+    #[test]
+    fn test_hnsw_recall_cosine() {
+        assert!(recall(Distance::Cosine) > 0.9);
+    }
+
+    #[test]
+    fn test_hnsw_recall_euclidean() {
+        assert!(recall(Distance::Euclidean) > 0.9);
+    }
+
+    #[test]
+    fn test_hnsw_remove_and_rebuild() {
+        let vectors = random_vectors(200, 8);
+        let mut index = HnswIndex::new(HnswConfig::default());
+        for (i, vector) in vectors.iter().enumerate() {
+            index.insert(i.to_string(), vector.clone());
+        }
+
+        // The exact match of a removed vector must not come back.
+        assert!(index.remove("7"));
+        assert!(!index.remove("7"));
+        let hits = index.search(&vectors[7], 5, None, |_| true);
+        assert!(hits.iter().all(|(id, _)| *id != "7"));
+
+        // Removing most of the nodes triggers a rebuild.
+        for i in 0..150 {
+            index.remove(&i.to_string());
+        }
+        assert_eq!(50, index.len());
+        assert!(index.nodes.len() < 200);
+        let hits = index.search(&vectors[199], 1, None, |_| true);
+        assert_eq!("199", hits[0].0);
+    }
+
+    #[test]
+    fn test_hnsw_search_accept() {
+        let vectors = random_vectors(300, 8);
+        let mut index = HnswIndex::new(HnswConfig::default());
+        for (i, vector) in vectors.iter().enumerate() {
+            index.insert(i.to_string(), vector.clone());
+        }
+
+        let hits = index.search(&vectors[0], 10, None, |id| id.ends_with('3'));
+        assert_eq!(10, hits.len());
+        assert!(hits.iter().all(|(id, _)| id.ends_with('3')));
+    }
+}
@@ -0,0 +1,285 @@
+use crate::database::{DBError, EmbeddingRecord};
+
+pub use tinyvector_types::dto::ImportFormat;
+
+/// Incremental decoder, fed with the body chunks as they arrive.
+#[derive(Debug)]
+pub enum Decoder {
+    Ndjson(NdjsonDecoder),
+    Npy(NpyDecoder),
+}
+
+impl Decoder {
+    pub fn new(format: ImportFormat, id_prefix: String) -> Self {
+        match format {
+            ImportFormat::Ndjson => Decoder::Ndjson(NdjsonDecoder::default()),
+            ImportFormat::Npy => Decoder::Npy(NpyDecoder::new(id_prefix)),
+        }
+    }
+
+    /// Decode every record completed by `chunk`.
+    pub fn feed(&mut self, chunk: &[u8]) -> Result<Vec<EmbeddingRecord>, DBError> {
+        match self {
+            Decoder::Ndjson(decoder) => decoder.feed(chunk),
+            Decoder::Npy(decoder) => decoder.feed(chunk),
+        }
+    }
+
+    /// Decode what is left once the body ended.
+    pub fn finish(self) -> Result<Vec<EmbeddingRecord>, DBError> {
+        match self {
+            Decoder::Ndjson(decoder) => decoder.finish(),
+            Decoder::Npy(decoder) => decoder.finish(),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct NdjsonDecoder {
+    /// Bytes of the line that is not complete yet.
+    pending: Vec<u8>,
+    line: usize,
+}
+
+impl NdjsonDecoder {
+    fn feed(&mut self, chunk: &[u8]) -> Result<Vec<EmbeddingRecord>, DBError> {
+        let mut records = Vec::new();
+        let mut rest = chunk;
+        while let Some(end) = rest.iter().position(|&b| b == b'\n') {
+            self.pending.extend_from_slice(&rest[..end]);
+            rest = &rest[end + 1..];
+            let line = std::mem::take(&mut self.pending);
+            records.extend(self.decode_line(&line)?);
+        }
+        self.pending.extend_from_slice(rest);
+        Ok(records)
+    }
+
+    fn finish(mut self) -> Result<Vec<EmbeddingRecord>, DBError> {
+        let line = std::mem::take(&mut self.pending);
+        Ok(self.decode_line(&line)?.into_iter().collect())
+    }
+
+    fn decode_line(&mut self, line: &[u8]) -> Result<Option<EmbeddingRecord>, DBError> {
+        self.line += 1;
+        if line.iter().all(u8::is_ascii_whitespace) {
+            return Ok(None);
+        }
+        serde_json::from_slice(line)
+            .map(Some)
+            .map_err(|e| DBError::InvalidInput(format!("line {}: {}", self.line, e)))
+    }
+}
+
+const NPY_MAGIC: &[u8] = b"\x93NUMPY";
+
+#[derive(Debug, Clone, Copy)]
+enum NpyType {
+    F32,
+    F64,
+}
+
+impl NpyType {
+    fn size(self) -> usize {
+        match self {
+            NpyType::F32 => 4,
+            NpyType::F64 => 8,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct NpyDecoder {
+    id_prefix: String,
+    buffer: Vec<u8>,
+    /// Element type, row count and dimension, known once the header is parsed.
+    layout: Option<(NpyType, usize, usize)>,
+    row: usize,
+}
+
+impl NpyDecoder {
+    fn new(id_prefix: String) -> Self {
+        Self {
+            id_prefix,
+            buffer: Vec::new(),
+            layout: None,
+            row: 0,
+        }
+    }
+
+    fn feed(&mut self, chunk: &[u8]) -> Result<Vec<EmbeddingRecord>, DBError> {
+        self.buffer.extend_from_slice(chunk);
+        if self.layout.is_none() && !self.parse_header()? {
+            return Ok(Vec::new());
+        }
+
+        let (dtype, rows, dimension) = self.layout.unwrap();
+        let row_size = dtype.size() * dimension;
+        if row_size == 0 {
+            return Ok(Vec::new());
+        }
+        let complete = (self.buffer.len() / row_size).min(rows - self.row);
+        let records = self.buffer[..complete * row_size]
+            .chunks_exact(row_size)
+            .enumerate()
+            .map(|(i, row)| {
+                let embedding = match dtype {
+                    NpyType::F32 => row
+                        .chunks_exact(4)
+                        .map(|b| f32::from_le_bytes(b.try_into().unwrap()))
+                        .collect(),
+                    NpyType::F64 => row
+                        .chunks_exact(8)
+                        .map(|b| f64::from_le_bytes(b.try_into().unwrap()) as f32)
+                        .collect(),
+                };
+                EmbeddingRecord {
+                    id: format!("{}{}", self.id_prefix, self.row + i),
+                    embedding,
+                    ..Default::default()
+                }
+            })
+            .collect::<Vec<_>>();
+        self.buffer.drain(..complete * row_size);
+        self.row += complete;
+        Ok(records)
+    }
+
+    fn finish(self) -> Result<Vec<EmbeddingRecord>, DBError> {
+        match self.layout {
+            Some((_, rows, _)) if self.row == rows && self.buffer.is_empty() => Ok(Vec::new()),
+            Some((_, rows, _)) => Err(DBError::InvalidInput(format!(
+                "npy body ended after {} of {} rows",
+                self.row, rows
+            ))),
+            None => Err(DBError::InvalidInput("incomplete npy header".to_string())),
+        }
+    }
+
+    /// Parse the header once it is fully buffered, return whether it was.
+    fn parse_header(&mut self) -> Result<bool, DBError> {
+        let invalid = |msg: &str| DBError::InvalidInput(format!("invalid npy header: {}", msg));
+
+        if self.buffer.len() < 10 {
+            return Ok(false);
+        }
+        if &self.buffer[..6] != NPY_MAGIC {
+            return Err(invalid("bad magic"));
+        }
+        // Version 1 stores the header length as u16, version 2 and 3 as u32.
+        let (len_size, header_len) = match self.buffer[6] {
+            1 => (2, u16::from_le_bytes([self.buffer[8], self.buffer[9]]) as usize),
+            2 | 3 if self.buffer.len() >= 12 => {
+                (4, u32::from_le_bytes(self.buffer[8..12].try_into().unwrap()) as usize)
+            }
+            2 | 3 => return Ok(false),
+            version => return Err(invalid(&format!("unsupported version {}", version))),
+        };
+        let start = 8 + len_size;
+        if self.buffer.len() < start + header_len {
+            return Ok(false);
+        }
+
+        let header = std::str::from_utf8(&self.buffer[start..start + header_len])
+            .map_err(|_| invalid("not utf8"))?;
+        let field = |key: &str| {
+            let at = header.find(&format!("'{}':", key))? + key.len() + 3;
+            Some(header[at..].trim_start())
+        };
+
+        let descr = field("descr").ok_or_else(|| invalid("missing descr"))?;
+        let dtype = if descr.starts_with("'<f4'") {
+            NpyType::F32
+        } else if descr.starts_with("'<f8'") {
+            NpyType::F64
+        } else {
+            return Err(invalid("only '<f4' and '<f8' are supported"));
+        };
+
+        if field("fortran_order").is_some_and(|v| v.starts_with("True")) {
+            return Err(invalid("fortran order is not supported"));
+        }
+
+        let shape = field("shape").ok_or_else(|| invalid("missing shape"))?;
+        let shape = shape
+            .strip_prefix('(')
+            .and_then(|s| s.split(')').next())
+            .ok_or_else(|| invalid("bad shape"))?
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(|s| s.parse::<usize>().map_err(|_| invalid("bad shape")))
+            .collect::<Result<Vec<_>, _>>()?;
+        let (rows, dimension) = match shape[..] {
+            [rows, dimension] => (rows, dimension),
+            _ => return Err(invalid("expect a 2-D array")),
+        };
+
+        self.buffer.drain(..start + header_len);
+        self.layout = Some((dtype, rows, dimension));
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn npy(rows: &[[f32; 3]]) -> Vec<u8> {
+        let mut header = format!(
+            "{{'descr': '<f4', 'fortran_order': False, 'shape': ({}, 3), }}",
+            rows.len()
+        );
+        // Pad so that the data starts 64 byte aligned, like numpy does.
+        while (10 + header.len() + 1) % 64 != 0 {
+            header.push(' ');
+        }
+        header.push('\n');
+
+        let mut data = NPY_MAGIC.to_vec();
+        data.extend_from_slice(&[1, 0]);
+        data.extend_from_slice(&(header.len() as u16).to_le_bytes());
+        data.extend_from_slice(header.as_bytes());
+        for row in rows {
+            for v in row {
+                data.extend_from_slice(&v.to_le_bytes());
+            }
+        }
+        data
+    }
+
+    /// Feed `data` in small chunks to exercise the buffering.
+    fn decode(mut decoder: Decoder, data: &[u8]) -> Result<Vec<EmbeddingRecord>, DBError> {
+        let mut records = Vec::new();
+        for chunk in data.chunks(7) {
+            records.extend(decoder.feed(chunk)?);
+        }
+        records.extend(decoder.finish()?);
+        Ok(records)
+    }
+
+    #[test]
+    fn test_decode_ndjson() {
+        let data = br#"{"id": "a", "embedding": [1.0, 2.0]}
+
+{"id": "b", "embedding": [3.0, 4.0], "metadata": {"tag": "x"}}"#;
+        let records = decode(Decoder::new(ImportFormat::Ndjson, String::new()), data).unwrap();
+        assert_eq!(vec!["a", "b"], records.iter().map(|r| r.id.as_str()).collect::<Vec<_>>());
+        assert_eq!(vec![3., 4.], records[1].embedding);
+
+        let err = decode(Decoder::new(ImportFormat::Ndjson, String::new()), b"{\"id\": 1}\n");
+        assert!(matches!(err, Err(DBError::InvalidInput(msg)) if msg.starts_with("line 1")));
+    }
+
+    #[test]
+    fn test_decode_npy() {
+        let data = npy(&[[1., 2., 3.], [4., 5., 6.]]);
+        let records = decode(Decoder::new(ImportFormat::Npy, "row-".to_string()), &data).unwrap();
+        assert_eq!(vec!["row-0", "row-1"], records.iter().map(|r| r.id.as_str()).collect::<Vec<_>>());
+        assert_eq!(vec![4., 5., 6.], records[1].embedding);
+
+        // A truncated body is reported instead of silently dropping rows.
+        let err = decode(Decoder::new(ImportFormat::Npy, String::new()), &data[..data.len() - 4]);
+        assert!(err.is_err());
+    }
+}
@@ -0,0 +1,23 @@
+pub mod server;
+pub mod config;
+pub mod routes;
+pub mod database;
+pub mod similarity;
+pub mod hnsw;
+pub mod quantization;
+pub mod bm25;
+pub mod wal;
+pub mod snapshot;
+pub mod import;
+pub mod shutdown;
+pub mod auth;
+pub mod embedding;
+pub mod binary;
+pub mod telemetry;
+pub mod replication;
+pub mod segment;
+pub mod changes;
+pub mod recall;
+pub use tinyvector_types::{dto, explain, metadata};
+#[cfg(test)]
+mod test_data;
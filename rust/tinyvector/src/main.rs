@@ -1,17 +1,58 @@
-mod server;
-mod routes;
-mod dto;
-mod database;
-mod similarity;
-mod shutdown;
-mod test_data;
+use anyhow::{Context, Result};
+use clap::Parser;
+use std::fs;
+use tinyvector::config::{Cli, Command, Config};
+use tinyvector::database::{self, Database};
+use tinyvector::recall::{self, RecallOptions};
+use tinyvector::server;
+use tracing_subscriber::EnvFilter;
 
-use anyhow::Result;
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let config = Config::load(&cli)?;
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    tracing_subscriber::fmt::init();
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::try_new(&config.log_level)?)
+        .init();
 
-    server::start().await?;
-    Ok(())
+    if let Some(Command::Restore { snapshot }) = &cli.command {
+        let data = fs::read(snapshot).with_context(|| format!("Failed to read {:?}", snapshot))?;
+        let _lock = database::lock_dir(&config.storage_dir)?;
+        let db = Database::load_from_dir(&config.storage_dir, config.wal)?;
+        let seq = db.restore(data)?;
+        println!("Restored {:?} into {:?} at sequence {}", snapshot, config.storage_dir, seq);
+        return Ok(());
+    }
+
+    if let Some(threads) = config.search_threads {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build_global()?;
+    }
+
+    if let Some(Command::Recall {
+        table,
+        queries,
+        top_k,
+        ef_search,
+    }) = &cli.command
+    {
+        let data = fs::read_to_string(queries)
+            .with_context(|| format!("Failed to read {:?}", queries))?;
+        let queries = recall::parse_queries(&data)?;
+        let db = Database::open_read_only(&config.storage_dir)?;
+        let options = RecallOptions {
+            top_k: *top_k,
+            ef_search: *ef_search,
+            distance: None,
+        };
+        println!("{}", recall::evaluate(&db, table, &queries, &options)?);
+        return Ok(());
+    }
+
+    let mut runtime = tokio::runtime::Builder::new_multi_thread();
+    if let Some(threads) = config.worker_threads {
+        runtime.worker_threads(threads);
+    }
+    runtime.enable_all().build()?.block_on(server::start(config))
 }
@@ -0,0 +1,723 @@
+use crate::similarity::{dot_product, squared_euclidean, Distance};
+use memmap2::MmapMut;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+pub use tinyvector_types::database::{QuantizationConfig, QuantizationKind};
+
+/// Number of centroids per product quantization subspace, so a code fits a byte.
+const CENTROIDS: usize = 256;
+/// Lloyd iterations while training the codebooks.
+const KMEANS_ITERATIONS: usize = 10;
+/// Vectors a file of full-precision vectors is created for, it doubles when full.
+const INITIAL_CAPACITY: usize = 1024;
+
+/// Whether quantized codes can be scored with `distance`, the others scan the full vectors.
+pub fn supports(distance: Distance) -> bool {
+    matches!(
+        distance,
+        Distance::Euclidean | Distance::Cosine | Distance::DotProduct | Distance::InnerProduct
+    )
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScalarQuantizer {
+    min: Vec<f32>,
+    /// Width of one code step per dimension.
+    scale: Vec<f32>,
+}
+
+impl ScalarQuantizer {
+    fn train(vectors: &[&[f32]], dimension: usize) -> Self {
+        let mut min = vec![f32::MAX; dimension];
+        let mut max = vec![f32::MIN; dimension];
+        for vector in vectors {
+            for (i, &v) in vector.iter().enumerate() {
+                min[i] = min[i].min(v);
+                max[i] = max[i].max(v);
+            }
+        }
+        let scale = min
+            .iter()
+            .zip(&max)
+            .map(|(min, max)| ((max - min) / 255.).max(f32::EPSILON))
+            .collect();
+        Self { min, scale }
+    }
+
+    /// Values outside of the trained range are clamped.
+    fn encode(&self, vector: &[f32]) -> Vec<u8> {
+        vector
+            .iter()
+            .enumerate()
+            .map(|(i, v)| ((v - self.min[i]) / self.scale[i]).round().clamp(0., 255.) as u8)
+            .collect()
+    }
+
+    fn decode(&self, code: &[u8]) -> Vec<f32> {
+        code.iter()
+            .enumerate()
+            .map(|(i, &c)| self.min[i] + c as f32 * self.scale[i])
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProductQuantizer {
+    subspaces: usize,
+    /// Dimension of a subspace.
+    sub_dimension: usize,
+    /// `subspaces * CENTROIDS` centroids of `sub_dimension` floats each.
+    centroids: Vec<f32>,
+}
+
+impl ProductQuantizer {
+    fn train(vectors: &[&[f32]], dimension: usize, subspaces: usize) -> Self {
+        let sub_dimension = dimension / subspaces;
+        let centroids = (0..subspaces)
+            .into_par_iter()
+            .flat_map_iter(|s| {
+                let range = s * sub_dimension..(s + 1) * sub_dimension;
+                let points = vectors.iter().map(|v| &v[range.clone()]).collect::<Vec<_>>();
+                kmeans(&points, sub_dimension, CENTROIDS)
+            })
+            .collect();
+        Self {
+            subspaces,
+            sub_dimension,
+            centroids,
+        }
+    }
+
+    fn centroid(&self, subspace: usize, code: u8) -> &[f32] {
+        let start = (subspace * CENTROIDS + code as usize) * self.sub_dimension;
+        &self.centroids[start..start + self.sub_dimension]
+    }
+
+    fn encode(&self, vector: &[f32]) -> Vec<u8> {
+        (0..self.subspaces)
+            .map(|s| {
+                let sub = &vector[s * self.sub_dimension..(s + 1) * self.sub_dimension];
+                (0..CENTROIDS)
+                    .min_by(|&a, &b| {
+                        squared_euclidean(sub, self.centroid(s, a as u8))
+                            .total_cmp(&squared_euclidean(sub, self.centroid(s, b as u8)))
+                    })
+                    .unwrap() as u8
+            })
+            .collect()
+    }
+
+    fn decode(&self, code: &[u8]) -> Vec<f32> {
+        code.iter()
+            .enumerate()
+            .flat_map(|(s, &c)| self.centroid(s, c).iter().copied())
+            .collect()
+    }
+}
+
+/// Lloyd's k-means, seeded with evenly spaced points so training is deterministic.
+///
+/// Returns `k` centroids flattened, with fewer points than `k` the leftovers repeat points.
+fn kmeans(points: &[&[f32]], dimension: usize, k: usize) -> Vec<f32> {
+    if points.is_empty() {
+        return vec![0.; k * dimension];
+    }
+    let mut centroids = (0..k)
+        .flat_map(|i| points[i * points.len() / k].iter().copied())
+        .collect::<Vec<_>>();
+
+    let mut assignment = vec![0; points.len()];
+    for _ in 0..KMEANS_ITERATIONS {
+        for (point, assigned) in points.iter().zip(assignment.iter_mut()) {
+            *assigned = (0..k)
+                .min_by(|&a, &b| {
+                    let ca = &centroids[a * dimension..(a + 1) * dimension];
+                    let cb = &centroids[b * dimension..(b + 1) * dimension];
+                    squared_euclidean(point, ca).total_cmp(&squared_euclidean(point, cb))
+                })
+                .unwrap();
+        }
+
+        let mut sums = vec![0.; k * dimension];
+        let mut counts = vec![0usize; k];
+        for (point, &assigned) in points.iter().zip(&assignment) {
+            counts[assigned] += 1;
+            for (sum, v) in sums[assigned * dimension..].iter_mut().zip(point.iter()) {
+                *sum += v;
+            }
+        }
+        for c in 0..k {
+            // An empty cluster keeps its old centroid.
+            if counts[c] > 0 {
+                for d in 0..dimension {
+                    centroids[c * dimension + d] = sums[c * dimension + d] / counts[c] as f32;
+                }
+            }
+        }
+    }
+    centroids
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Quantizer {
+    Scalar(ScalarQuantizer),
+    Product(ProductQuantizer),
+}
+
+impl Quantizer {
+    /// Learn the codebooks of `kind` from a sample of the vectors.
+    pub fn train(kind: QuantizationKind, dimension: usize, vectors: &[&[f32]]) -> Self {
+        match kind {
+            QuantizationKind::Scalar => {
+                Quantizer::Scalar(ScalarQuantizer::train(vectors, dimension))
+            }
+            QuantizationKind::Product { subspaces } => {
+                Quantizer::Product(ProductQuantizer::train(vectors, dimension, subspaces))
+            }
+        }
+    }
+
+    fn encode(&self, vector: &[f32]) -> Vec<u8> {
+        match self {
+            Quantizer::Scalar(q) => q.encode(vector),
+            Quantizer::Product(q) => q.encode(vector),
+        }
+    }
+
+    pub fn decode(&self, code: &[u8]) -> Vec<f32> {
+        match self {
+            Quantizer::Scalar(q) => q.decode(code),
+            Quantizer::Product(q) => q.decode(code),
+        }
+    }
+}
+
+/// Per query state for the asymmetric distance, the query stays in full precision.
+pub struct QueryTable<'a> {
+    quantizer: &'a Quantizer,
+    distance: Distance,
+    query: &'a [f32],
+    query_norm: f32,
+    /// Scalar: query scaled per dimension. Product: per subspace and code the partial
+    /// dot product, or the partial squared distance for euclidean.
+    lookup: Vec<f32>,
+    /// Scalar only: dot product of the query with the per dimension minimum.
+    offset: f32,
+}
+
+impl QueryTable<'_> {
+    /// Score of the query against a code, in the convention of `distance`.
+    ///
+    /// `norm` is the norm of the decoded vector, only needed for cosine.
+    pub fn score(&self, code: &[u8], norm: f32) -> f32 {
+        let partial = match self.quantizer {
+            Quantizer::Scalar(q) => match self.distance {
+                Distance::Euclidean => self
+                    .query
+                    .iter()
+                    .zip(code)
+                    .enumerate()
+                    .map(|(i, (x, &c))| {
+                        let diff = x - (q.min[i] + c as f32 * q.scale[i]);
+                        diff * diff
+                    })
+                    .sum(),
+                _ => {
+                    self.offset
+                        + self
+                            .lookup
+                            .iter()
+                            .zip(code)
+                            .map(|(w, &c)| w * c as f32)
+                            .sum::<f32>()
+                }
+            },
+            Quantizer::Product(_) => code
+                .iter()
+                .enumerate()
+                .map(|(s, &c)| self.lookup[s * CENTROIDS + c as usize])
+                .sum(),
+        };
+
+        match self.distance {
+            Distance::Euclidean => partial.sqrt(),
+            Distance::Cosine => {
+                let norms = self.query_norm * norm;
+                if norms > f32::EPSILON {
+                    partial / norms
+                } else {
+                    partial
+                }
+            }
+            _ => partial,
+        }
+    }
+}
+
+/// Full-precision vectors back to back, in memory or in an unnamed file mapped into memory,
+/// which the OS pages in on demand and out under pressure.
+#[derive(Debug, Default)]
+struct FullVectors {
+    dimension: usize,
+    len: usize,
+    /// Where the file is created on the first push, `None` to stay in memory.
+    dir: Option<PathBuf>,
+    memory: Vec<f32>,
+    mapped: Option<MappedVectors>,
+}
+
+#[derive(Debug)]
+struct MappedVectors {
+    file: File,
+    map: MmapMut,
+    /// Vectors the file has room for.
+    capacity: usize,
+}
+
+impl Clone for FullVectors {
+    /// Copies are kept in memory.
+    fn clone(&self) -> Self {
+        Self {
+            dimension: self.dimension,
+            len: self.len,
+            dir: None,
+            memory: self.as_slice().to_vec(),
+            mapped: None,
+        }
+    }
+}
+
+impl FullVectors {
+    fn new(dimension: usize) -> Self {
+        Self {
+            dimension,
+            ..Default::default()
+        }
+    }
+
+    fn as_slice(&self) -> &[f32] {
+        match &self.mapped {
+            // SAFETY: the map is page aligned and holds at least `len` vectors written by this
+            // process, the file has no name so nothing else can change it.
+            Some(mapped) => unsafe {
+                std::slice::from_raw_parts(mapped.map.as_ptr().cast(), self.len * self.dimension)
+            },
+            None => &self.memory,
+        }
+    }
+
+    fn as_mut_slice(&mut self) -> &mut [f32] {
+        let len = self.len * self.dimension;
+        match &mut self.mapped {
+            // SAFETY: see `as_slice`.
+            Some(mapped) => unsafe {
+                std::slice::from_raw_parts_mut(mapped.map.as_mut_ptr().cast(), len)
+            },
+            None => &mut self.memory,
+        }
+    }
+
+    fn get(&self, position: usize) -> &[f32] {
+        &self.as_slice()[position * self.dimension..(position + 1) * self.dimension]
+    }
+
+    /// Map a new file in `dir` and move the vectors there.
+    fn map_file(&mut self, dir: &Path) -> std::io::Result<()> {
+        fs::create_dir_all(dir)?;
+        let file = tempfile::tempfile_in(dir)?;
+        let capacity = (self.len * 2).max(INITIAL_CAPACITY);
+        file.set_len((capacity * self.dimension * 4) as u64)?;
+        // SAFETY: the file has no name, only this process can change it.
+        let map = unsafe { MmapMut::map_mut(&file)? };
+        let memory = std::mem::take(&mut self.memory);
+        self.mapped = Some(MappedVectors {
+            file,
+            map,
+            capacity,
+        });
+        self.as_mut_slice().copy_from_slice(&memory);
+        Ok(())
+    }
+
+    /// Double the room of the file.
+    fn grow(&mut self) -> std::io::Result<()> {
+        let Some(mapped) = self.mapped.as_mut() else {
+            return Ok(());
+        };
+        let capacity = mapped.capacity * 2;
+        mapped.file.set_len((capacity * self.dimension * 4) as u64)?;
+        // SAFETY: see `map_file`.
+        mapped.map = unsafe { MmapMut::map_mut(&mapped.file)? };
+        mapped.capacity = capacity;
+        Ok(())
+    }
+
+    /// Go back to memory after the file failed, for good.
+    fn unmap(&mut self, e: std::io::Error) {
+        warn!("Keeping the full-precision vectors in memory: {:?}", e);
+        self.memory = self.as_slice().to_vec();
+        self.mapped = None;
+        self.dir = None;
+    }
+
+    fn push(&mut self, vector: &[f32]) {
+        if self.mapped.is_none() && self.dimension > 0 {
+            if let Some(dir) = self.dir.clone() {
+                if let Err(e) = self.map_file(&dir) {
+                    self.unmap(e);
+                }
+            }
+        }
+        if self.mapped.as_ref().is_some_and(|m| m.capacity == self.len) {
+            if let Err(e) = self.grow() {
+                self.unmap(e);
+            }
+        }
+        match self.mapped {
+            Some(_) => {
+                self.len += 1;
+                let start = (self.len - 1) * self.dimension;
+                self.as_mut_slice()[start..].copy_from_slice(vector);
+            }
+            None => {
+                self.memory.extend_from_slice(vector);
+                self.len += 1;
+            }
+        }
+    }
+
+    fn set(&mut self, position: usize, vector: &[f32]) {
+        let dimension = self.dimension;
+        self.as_mut_slice()[position * dimension..(position + 1) * dimension]
+            .copy_from_slice(vector);
+    }
+
+    fn swap_remove(&mut self, position: usize) {
+        let dimension = self.dimension;
+        let last = self.len - 1;
+        self.as_mut_slice()
+            .copy_within(last * dimension..(last + 1) * dimension, position * dimension);
+        self.len = last;
+        self.memory.truncate(last * dimension);
+    }
+
+    fn clear(&mut self) {
+        self.len = 0;
+        self.memory.clear();
+    }
+}
+
+/// Quantized copy of the vectors of a table, kept in the same order as its records.
+///
+/// Once trained it also takes over the full-precision vectors of the records, which are only
+/// read to re-rank and to hand the records out.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuantizedVectors {
+    config: QuantizationConfig,
+    dimension: usize,
+    /// `None` until enough records arrived to train it.
+    quantizer: Option<Quantizer>,
+    /// Codes of all records back to back.
+    codes: Vec<u8>,
+    /// Norm of every decoded vector for cosine.
+    norms: Vec<f32>,
+    /// Filled by `open` after a load, the snapshot keeps the vectors in the records.
+    #[serde(skip)]
+    vectors: FullVectors,
+}
+
+impl QuantizedVectors {
+    pub fn new(config: QuantizationConfig, dimension: usize) -> Self {
+        Self {
+            config,
+            dimension,
+            quantizer: None,
+            codes: Vec::new(),
+            norms: Vec::new(),
+            vectors: FullVectors::new(dimension),
+        }
+    }
+
+    pub fn config(&self) -> &QuantizationConfig {
+        &self.config
+    }
+
+    pub fn quantizer(&self) -> Option<&Quantizer> {
+        self.quantizer.as_ref()
+    }
+
+    fn code_size(&self) -> usize {
+        match self.config.kind {
+            QuantizationKind::Scalar => self.dimension,
+            QuantizationKind::Product { subspaces } => subspaces,
+        }
+    }
+
+    pub fn code(&self, position: usize) -> &[u8] {
+        let size = self.code_size();
+        &self.codes[position * size..(position + 1) * size]
+    }
+
+    pub fn norm(&self, position: usize) -> f32 {
+        self.norms[position]
+    }
+
+    /// Whether the full-precision vectors are kept here rather than in the records.
+    pub fn holds_vectors(&self) -> bool {
+        self.quantizer.is_some() && self.vectors.len == self.norms.len()
+    }
+
+    /// The full-precision vector at `position`, `None` while the records hold them.
+    pub fn vector(&self, position: usize) -> Option<&[f32]> {
+        self.holds_vectors().then(|| self.vectors.get(position))
+    }
+
+    /// Keep the full-precision vectors in a file of `dir` from now on, and take over
+    /// `vectors`, those of the records in order, once trained.
+    pub fn open(&mut self, dir: Option<&Path>, vectors: impl Iterator<Item = Vec<f32>>) {
+        self.vectors.dir = dir.map(Path::to_path_buf);
+        if self.quantizer.is_some() && !self.holds_vectors() {
+            self.vectors = FullVectors {
+                dir: self.vectors.dir.take(),
+                ..FullVectors::new(self.dimension)
+            };
+            for vector in vectors {
+                self.vectors.push(&vector);
+            }
+        }
+    }
+
+    /// Train on `vectors` and encode all of them, they are the records of the table in order.
+    pub fn train(&mut self, vectors: &[&[f32]]) {
+        let quantizer = Quantizer::train(self.config.kind, self.dimension, vectors);
+        self.install(quantizer, vectors);
+    }
+
+    /// Encode `vectors`, the records of the table in order, with a trained `quantizer` and
+    /// take them over.
+    pub fn install(&mut self, quantizer: Quantizer, vectors: &[&[f32]]) {
+        self.quantizer = Some(quantizer);
+        let encoded = vectors
+            .par_iter()
+            .map(|vector| self.encode(vector).unwrap())
+            .collect::<Vec<_>>();
+        self.codes.clear();
+        self.norms.clear();
+        for (code, norm) in encoded {
+            self.codes.extend_from_slice(&code);
+            self.norms.push(norm);
+        }
+        self.vectors.clear();
+        for vector in vectors {
+            self.vectors.push(vector);
+        }
+    }
+
+    fn encode(&self, vector: &[f32]) -> Option<(Vec<u8>, f32)> {
+        let quantizer = self.quantizer.as_ref()?;
+        let code = quantizer.encode(vector);
+        let decoded = quantizer.decode(&code);
+        Some((code, dot_product(&decoded, &decoded).sqrt()))
+    }
+
+    /// Append the code of a new last record, no-op while untrained.
+    pub fn push(&mut self, vector: &[f32]) {
+        let holds_vectors = self.holds_vectors();
+        if let Some((code, norm)) = self.encode(vector) {
+            self.codes.extend_from_slice(&code);
+            self.norms.push(norm);
+            if holds_vectors {
+                self.vectors.push(vector);
+            }
+        }
+    }
+
+    /// Replace the code at `position`, no-op while untrained.
+    pub fn set(&mut self, position: usize, vector: &[f32]) {
+        let holds_vectors = self.holds_vectors();
+        if let Some((code, norm)) = self.encode(vector) {
+            let size = self.code_size();
+            self.codes[position * size..(position + 1) * size].copy_from_slice(&code);
+            self.norms[position] = norm;
+            if holds_vectors {
+                self.vectors.set(position, vector);
+            }
+        }
+    }
+
+    /// Mirror `Vec::swap_remove` on the records.
+    pub fn swap_remove(&mut self, position: usize) {
+        if self.quantizer.is_none() {
+            return;
+        }
+        if self.holds_vectors() {
+            self.vectors.swap_remove(position);
+        }
+        let size = self.code_size();
+        let last = self.norms.len() - 1;
+        self.codes.copy_within(last * size..(last + 1) * size, position * size);
+        self.codes.truncate(last * size);
+        self.norms.swap_remove(position);
+    }
+
+    /// `None` before training, or for a metric the codes cannot be scored with.
+    pub fn query_table<'a>(&'a self, query: &'a [f32], distance: Distance) -> Option<QueryTable<'a>> {
+        if !supports(distance) {
+            return None;
+        }
+        let quantizer = self.quantizer.as_ref()?;
+        let (lookup, offset) = match quantizer {
+            Quantizer::Scalar(q) => (
+                query.iter().zip(&q.scale).map(|(x, s)| x * s).collect(),
+                dot_product(query, &q.min),
+            ),
+            Quantizer::Product(q) => {
+                let mut lookup = Vec::with_capacity(q.subspaces * CENTROIDS);
+                for s in 0..q.subspaces {
+                    let sub = &query[s * q.sub_dimension..(s + 1) * q.sub_dimension];
+                    for c in 0..CENTROIDS {
+                        let centroid = q.centroid(s, c as u8);
+                        lookup.push(match distance {
+                            Distance::Euclidean => squared_euclidean(sub, centroid),
+                            _ => dot_product(sub, centroid),
+                        });
+                    }
+                }
+                (lookup, 0.)
+            }
+        };
+        Some(QueryTable {
+            quantizer,
+            distance,
+            query,
+            query_norm: dot_product(query, query).sqrt(),
+            lookup,
+            offset,
+        })
+    }
+
+    /// Bytes held by the codes, norms and codebooks.
+    pub fn memory_usage(&self) -> usize {
+        let codebook = match &self.quantizer {
+            Some(Quantizer::Scalar(q)) => (q.min.len() + q.scale.len()) * 4,
+            Some(Quantizer::Product(q)) => q.centroids.len() * 4,
+            None => 0,
+        };
+        self.codes.len() + self.norms.len() * 4 + codebook
+    }
+
+    /// Bytes of the full-precision vectors held in memory and mapped from a file.
+    pub fn vectors_usage(&self) -> (usize, usize) {
+        let mapped = self.vectors.mapped.as_ref();
+        let mapped = mapped.map_or(0, |m| m.capacity * self.dimension * 4);
+        (self.vectors.memory.len() * 4, mapped)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::similarity::get_distance_fn;
+
+    fn vectors(count: usize, dimension: usize) -> Vec<Vec<f32>> {
+        let mut state = 7u64;
+        (0..count)
+            .map(|_| {
+                (0..dimension)
+                    .map(|_| {
+                        state = state
+                            .wrapping_mul(6364136223846793005)
+                            .wrapping_add(1442695040888963407);
+                        ((state >> 33) as f32 / (1u64 << 31) as f32) - 0.5
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn max_error(kind: QuantizationKind, distance: Distance) -> f32 {
+        let vectors = vectors(500, 16);
+        let refs = vectors.iter().map(|v| v.as_slice()).collect::<Vec<_>>();
+        let config = QuantizationConfig {
+            kind,
+            train_size: 0,
+            rerank_factor: 0,
+        };
+        let mut quantized = QuantizedVectors::new(config, 16);
+        quantized.train(&refs);
+
+        let query = &vectors[0];
+        let table = quantized.query_table(query, distance).unwrap();
+        let distance_fn = get_distance_fn(distance);
+        vectors
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (distance_fn(v, query) - table.score(quantized.code(i), quantized.norm(i))).abs())
+            .fold(0., f32::max)
+    }
+
+    #[test]
+    fn test_scalar_quantization_error() {
+        for distance in [Distance::Euclidean, Distance::Cosine, Distance::DotProduct] {
+            assert!(max_error(QuantizationKind::Scalar, distance) < 0.02, "{:?}", distance);
+        }
+    }
+
+    #[test]
+    fn test_product_quantization_error() {
+        let kind = QuantizationKind::Product { subspaces: 8 };
+        for distance in [Distance::Euclidean, Distance::Cosine, Distance::DotProduct] {
+            assert!(max_error(kind, distance) < 0.35, "{:?}", distance);
+        }
+    }
+
+    #[test]
+    fn test_swap_remove_keeps_codes_aligned() {
+        let vectors = vectors(4, 4);
+        let refs = vectors.iter().map(|v| v.as_slice()).collect::<Vec<_>>();
+        let config = QuantizationConfig {
+            kind: QuantizationKind::Scalar,
+            train_size: 0,
+            rerank_factor: 0,
+        };
+        let mut quantized = QuantizedVectors::new(config, 4);
+        quantized.train(&refs);
+        let last = quantized.code(3).to_vec();
+
+        quantized.swap_remove(1);
+        assert_eq!(last, quantized.code(1));
+        assert_eq!(3 * 4, quantized.codes.len());
+        assert_eq!(vectors[3], quantized.vector(1).unwrap());
+    }
+
+    #[test]
+    fn test_vectors_in_a_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let vectors = vectors(INITIAL_CAPACITY + 10, 4);
+        let mut full = FullVectors::new(4);
+        full.dir = Some(dir.path().to_path_buf());
+        for vector in &vectors {
+            full.push(vector);
+        }
+        assert_eq!(2 * INITIAL_CAPACITY, full.mapped.as_ref().unwrap().capacity);
+        assert!(full.memory.is_empty());
+        assert_eq!(vectors[INITIAL_CAPACITY + 9], full.get(INITIAL_CAPACITY + 9));
+
+        full.set(0, &[1., 2., 3., 4.]);
+        full.swap_remove(1);
+        assert_eq!([1., 2., 3., 4.], full.get(0));
+        assert_eq!(vectors[INITIAL_CAPACITY + 9], full.get(1));
+        assert_eq!(INITIAL_CAPACITY + 9, full.len);
+
+        // Copies live in memory.
+        let copy = full.clone();
+        assert!(copy.mapped.is_none());
+        assert_eq!(full.as_slice(), copy.as_slice());
+        // The file has no name.
+        assert_eq!(0, std::fs::read_dir(dir.path()).unwrap().count());
+    }
+}
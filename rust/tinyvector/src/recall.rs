@@ -0,0 +1,243 @@
+use crate::database::{DBError, Database, SearchOptions};
+use crate::explain::micros;
+use crate::metadata::Filter;
+use crate::similarity::Distance;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+use std::time::Instant;
+
+/// One line of a query set, a bare embedding or an object with a metadata filter.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum RecallQuery {
+    Embedding(Vec<f32>),
+    Filtered {
+        query_embedding: Vec<f32>,
+        #[serde(default)]
+        filter: Option<Filter>,
+    },
+}
+
+impl RecallQuery {
+    fn parts(&self) -> (&[f32], Option<&Filter>) {
+        match self {
+            RecallQuery::Embedding(embedding) => (embedding, None),
+            RecallQuery::Filtered {
+                query_embedding,
+                filter,
+            } => (query_embedding, filter.as_ref()),
+        }
+    }
+}
+
+/// Parse a query set with one JSON query per line, blank lines are skipped.
+pub fn parse_queries(data: &str) -> Result<Vec<RecallQuery>> {
+    data.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(number, line)| {
+            let context = || format!("Invalid query on line {}", number + 1);
+            serde_json::from_str(line).with_context(context)
+        })
+        .collect()
+}
+
+/// How the query set is replayed, the approximate side uses the index of the table.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RecallOptions {
+    pub top_k: usize,
+    pub ef_search: Option<usize>,
+    /// Defaults to the distance of the table.
+    pub distance: Option<Distance>,
+}
+
+/// Latency percentiles of a run, nearest rank.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct Latency {
+    pub mean_micros: u64,
+    pub p50_micros: u64,
+    pub p95_micros: u64,
+    pub p99_micros: u64,
+    pub max_micros: u64,
+}
+
+impl Latency {
+    pub fn from_samples(mut samples: Vec<u64>) -> Self {
+        if samples.is_empty() {
+            return Self::default();
+        }
+        samples.sort_unstable();
+        let percentile = |p: usize| {
+            let rank = (samples.len() * p).div_ceil(100).max(1);
+            samples[rank - 1]
+        };
+        Self {
+            mean_micros: samples.iter().sum::<u64>() / samples.len() as u64,
+            p50_micros: percentile(50),
+            p95_micros: percentile(95),
+            p99_micros: percentile(99),
+            max_micros: samples[samples.len() - 1],
+        }
+    }
+}
+
+impl fmt::Display for Latency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "mean {}us, p50 {}us, p95 {}us, p99 {}us, max {}us",
+            self.mean_micros, self.p50_micros, self.p95_micros, self.p99_micros, self.max_micros
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct RecallReport {
+    pub queries: usize,
+    pub top_k: usize,
+    /// Mean share of the exact top k found by the default search.
+    pub recall: f64,
+    /// Worst recall of a single query.
+    pub min_recall: f64,
+    pub latency: Latency,
+    /// Latency of the exact scans the recall is measured against.
+    pub exact_latency: Latency,
+}
+
+impl fmt::Display for RecallReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "queries: {}, recall@{}: {:.4} (min {:.4})",
+            self.queries, self.top_k, self.recall, self.min_recall
+        )?;
+        writeln!(f, "latency: {}", self.latency)?;
+        write!(f, "exact latency: {}", self.exact_latency)
+    }
+}
+
+/// Replay `queries` against `table_name`, comparing the hits of the default search with the
+/// exact `top_k_similarity` ones.
+pub fn evaluate(
+    db: &Database,
+    table_name: &str,
+    queries: &[RecallQuery],
+    options: &RecallOptions,
+) -> Result<RecallReport, DBError> {
+    let mut recalls = Vec::with_capacity(queries.len());
+    let mut latencies = Vec::with_capacity(queries.len());
+    let mut exact_latencies = Vec::with_capacity(queries.len());
+
+    for query in queries {
+        let (embedding, filter) = query.parts();
+        let run = |exact: bool| {
+            let search = SearchOptions {
+                exact,
+                ef_search: options.ef_search,
+                filter,
+                ..Default::default()
+            };
+            let instant = Instant::now();
+            let hits = db.query_record_with(
+                table_name.to_string(),
+                embedding,
+                options.top_k,
+                options.distance,
+                &search,
+            )?;
+            Ok::<_, DBError>((hits, micros(instant.elapsed())))
+        };
+        let (exact, exact_latency) = run(true)?;
+        let (hits, latency) = run(false)?;
+
+        let found = hits.iter().map(|hit| hit.record.id.as_str()).collect::<HashSet<_>>();
+        let recall = match exact.len() {
+            0 => 1.,
+            len => {
+                let matched = exact.iter().filter(|hit| found.contains(hit.record.id.as_str()));
+                matched.count() as f64 / len as f64
+            }
+        };
+        recalls.push(recall);
+        latencies.push(latency);
+        exact_latencies.push(exact_latency);
+    }
+
+    let mean = match recalls.len() {
+        0 => 1.,
+        len => recalls.iter().sum::<f64>() / len as f64,
+    };
+    Ok(RecallReport {
+        queries: queries.len(),
+        top_k: options.top_k,
+        recall: mean,
+        min_recall: recalls.iter().copied().fold(1., f64::min),
+        latency: Latency::from_samples(latencies),
+        exact_latency: Latency::from_samples(exact_latencies),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{EmbeddingRecord, IndexType};
+    use crate::hnsw::HnswConfig;
+
+    #[test]
+    fn test_latency_percentiles() {
+        let latency = Latency::from_samples((1..=100).rev().collect());
+        assert_eq!(50, latency.mean_micros);
+        assert_eq!(50, latency.p50_micros);
+        assert_eq!(95, latency.p95_micros);
+        assert_eq!(99, latency.p99_micros);
+        assert_eq!(100, latency.max_micros);
+        assert_eq!(7, Latency::from_samples(vec![7]).p99_micros);
+        assert_eq!(Latency::default(), Latency::from_samples(Vec::new()));
+    }
+
+    #[test]
+    fn test_evaluate_recall() {
+        let data = concat!(
+            "[1, 0]\n\n",
+            r#"{"query_embedding": [0, 1], "filter": {"eq": {"field": "even", "value": true}}}"#,
+            "\n",
+        );
+        let queries = parse_queries(data).unwrap();
+        assert_eq!(2, queries.len());
+        let e = parse_queries("[1, 0]\n{\"filter\": null}").unwrap_err();
+        assert_eq!("Invalid query on line 2", e.to_string());
+
+        let db = Database::zero();
+        let index = IndexType::Hnsw(HnswConfig::default());
+        db.create_table_with_index("t".to_string(), 2, index).unwrap();
+        for i in 0..200 {
+            let angle = i as f32 / 200. * std::f32::consts::FRAC_PI_2;
+            let record = EmbeddingRecord {
+                id: i.to_string(),
+                embedding: vec![angle.cos(), angle.sin()],
+                metadata: serde_json::from_value(serde_json::json!({"even": i % 2 == 0})).unwrap(),
+                ..Default::default()
+            };
+            db.insert_record("t".to_string(), record).unwrap();
+        }
+
+        let options = RecallOptions {
+            top_k: 10,
+            ..Default::default()
+        };
+        let report = evaluate(&db, "t", &queries, &options).unwrap();
+        assert_eq!(2, report.queries);
+        assert_eq!(10, report.top_k);
+        assert!(report.recall >= 0.9, "{report}");
+        assert!(report.min_recall <= report.recall);
+        assert!(report.latency.p50_micros <= report.latency.max_micros);
+        assert!(report.to_string().starts_with("queries: 2, recall@10: "));
+
+        let e = evaluate(&db, "missing", &queries, &options).unwrap_err();
+        assert_eq!(DBError::TableDoesNotExist, e);
+    }
+}
@@ -0,0 +1,463 @@
+use crate::binary::{write_frame, Client};
+use crate::database::{DBError, Database};
+use crate::snapshot;
+use crate::telemetry::METRICS;
+use crate::wal::WalRecord;
+use anyhow::{bail, Result};
+use axum::Extension;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::broadcast::Receiver;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+pub use tinyvector_types::system::{ReplicationStatus, Role};
+
+/// Bytes of a snapshot sent per frame, well below `binary::MAX_FRAME_SIZE`.
+const SNAPSHOT_CHUNK_SIZE: usize = 16 << 20;
+/// Time between heartbeats of the leader.
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(1);
+/// Wait before a follower reconnects to its leader.
+const RETRY_INTERVAL: Duration = Duration::from_secs(1);
+
+/// What a leader streams to a follower once it sent `Request::Replicate`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Frame {
+    /// Part of a snapshot file replacing the content of the follower, header first.
+    SnapshotChunk(Vec<u8>),
+    /// The snapshot is complete, it covers the mutations up to `seq`.
+    SnapshotEnd { seq: u64 },
+    /// The next mutation.
+    Record(WalRecord),
+    /// Sequence number of the leader, sent every `HEARTBEAT_INTERVAL`.
+    Heartbeat { seq: u64 },
+}
+
+/// Borrowing counterpart of `Frame`, encoded the same.
+#[derive(Serialize)]
+enum FrameRef<'a> {
+    SnapshotChunk(&'a [u8]),
+    SnapshotEnd { seq: u64 },
+    Record(&'a WalRecord),
+    Heartbeat { seq: u64 },
+}
+
+/// Stream the mutations following `from_seq` to a follower until it disconnects.
+///
+/// A follower the write-ahead log still has the missing mutations of catches up from it, any
+/// other gets a snapshot first. A follower falling behind the change feed catches up from the
+/// log the same, the stream ends once the log no longer covers it or the leader is restored,
+/// the follower reconnects and starts over from a snapshot.
+pub async fn stream<W>(db: Arc<Database>, from_seq: u64, writer: &mut W) -> Result<()>
+where
+    W: AsyncWrite + Unpin,
+{
+    let (mut last, mut feed) = match catch_up(&db, from_seq, writer).await? {
+        Some(caught_up) => caught_up,
+        None => {
+            let (_, feed) = db.subscribe();
+            let snapshot_db = db.clone();
+            let (seq, data) = tokio::task::spawn_blocking(move || snapshot_db.snapshot()).await??;
+            info!("Sending a snapshot at sequence {} to a follower at {}", seq, from_seq);
+            write_frame(writer, &FrameRef::SnapshotChunk(&snapshot::header(&data))).await?;
+            for chunk in data.chunks(SNAPSHOT_CHUNK_SIZE) {
+                write_frame(writer, &FrameRef::SnapshotChunk(chunk)).await?;
+            }
+            write_frame(writer, &FrameRef::SnapshotEnd { seq }).await?;
+            (seq, feed)
+        }
+    };
+    writer.flush().await?;
+
+    let mut heartbeat = tokio::time::interval(HEARTBEAT_INTERVAL);
+    loop {
+        tokio::select! {
+            record = feed.recv() => match record {
+                // Already part of the snapshot.
+                Ok(record) if record.seq <= last => continue,
+                Ok(record) => {
+                    write_frame(writer, &FrameRef::Record(&record)).await?;
+                    last = record.seq;
+                }
+                Err(RecvError::Lagged(count)) => match catch_up(&db, last, writer).await? {
+                    Some(caught_up) => (last, feed) = caught_up,
+                    None => {
+                        warn!("Dropping a follower {} mutations behind", count);
+                        return Ok(());
+                    }
+                },
+                Err(RecvError::Closed) => {
+                    info!("Dropping the followers of the restored database");
+                    return Ok(());
+                }
+            },
+            _ = heartbeat.tick() => {
+                write_frame(writer, &FrameRef::Heartbeat { seq: db.seq() }).await?;
+            }
+        }
+        writer.flush().await?;
+    }
+}
+
+/// Send the mutations following `after` from the write-ahead log, returns the last sequence
+/// number sent along with the feed of the later ones. `None` when the log misses some.
+async fn catch_up<W>(
+    db: &Arc<Database>,
+    after: u64,
+    writer: &mut W,
+) -> Result<Option<(u64, Receiver<Arc<WalRecord>>)>>
+where
+    W: AsyncWrite + Unpin,
+{
+    let log_db = db.clone();
+    let caught_up = tokio::task::spawn_blocking(move || log_db.subscribe_after(after, None));
+    let (records, feed) = match caught_up.await? {
+        Ok(caught_up) => caught_up,
+        // A follower ahead of the leader, restored from an older snapshot, starts over too.
+        Err(DBError::ChangesUnavailable | DBError::InvalidInput(_)) => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    if !records.is_empty() {
+        info!("Sending {} mutations after {} from the write-ahead log", records.len(), after);
+    }
+    let mut last = after;
+    for record in &records {
+        write_frame(writer, &FrameRef::Record(record)).await?;
+        last = record.seq;
+    }
+    Ok(Some((last, feed)))
+}
+
+/// The `[replication]` section of the server config, the server follows `leader` when set.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct ReplicationSettings {
+    /// Address of the binary protocol of the leader, e.g. `10.0.0.1:3001`.
+    pub leader: String,
+    /// Key with the admin scope on every table, when the leader requires keys.
+    pub api_key: Option<String>,
+}
+
+#[derive(Debug)]
+struct State {
+    connected: bool,
+    leader_seq: Option<u64>,
+    caught_up: Instant,
+    /// `None` once promoted.
+    task: Option<(JoinHandle<()>, watch::Sender<bool>)>,
+}
+
+#[derive(Debug)]
+struct Follower {
+    settings: ReplicationSettings,
+    state: Mutex<State>,
+}
+
+/// Replication state of the server, shared with the handlers.
+#[derive(Debug, Clone, Default)]
+pub struct Replication {
+    follower: Option<Arc<Follower>>,
+}
+
+impl Replication {
+    /// Make `db` read-only and apply the mutations of the leader to it in the background.
+    pub fn follow(settings: ReplicationSettings, db: Arc<Database>) -> Self {
+        db.set_read_only(true);
+        info!("Following {}", settings.leader);
+        let follower = Arc::new(Follower {
+            settings,
+            state: Mutex::new(State {
+                connected: false,
+                leader_seq: None,
+                caught_up: Instant::now(),
+                task: None,
+            }),
+        });
+        let (stop, stopped) = watch::channel(false);
+        let task = tokio::spawn(follower.clone().run(db, stopped));
+        follower.state.lock().task = Some((task, stop));
+        Self {
+            follower: Some(follower),
+        }
+    }
+
+    pub fn status(&self, db: &Database) -> ReplicationStatus {
+        let mut status = ReplicationStatus {
+            role: Role::Leader,
+            seq: db.seq(),
+            followers: db.subscribers(),
+            leader: None,
+            connected: None,
+            leader_seq: None,
+            lag: None,
+            lag_secs: None,
+        };
+        let Some(follower) = &self.follower else {
+            return status;
+        };
+        let state = follower.state.lock();
+        if state.task.is_none() {
+            return status;
+        }
+        status.role = Role::Follower;
+        status.leader = Some(follower.settings.leader.clone());
+        status.connected = Some(state.connected);
+        status.leader_seq = state.leader_seq;
+        status.lag = state.leader_seq.map(|seq| seq.saturating_sub(status.seq));
+        status.lag_secs = Some(match status.lag {
+            Some(0) => 0.,
+            _ => state.caught_up.elapsed().as_secs_f64(),
+        });
+        status
+    }
+
+    /// Stop following the leader and accept writes.
+    pub async fn promote(&self, db: &Database) -> Result<(), DBError> {
+        let task = self.follower.as_ref().and_then(|f| f.state.lock().task.take());
+        let Some((task, stop)) = task else {
+            return Err(DBError::InvalidInput("The server is not a follower".to_string()));
+        };
+        let _ = stop.send(true);
+        // A mutation being applied is finished first.
+        let _ = task.await;
+        db.set_read_only(false);
+        METRICS.replication_lag.set(0);
+        info!("Promoted to leader at sequence {}", db.seq());
+        Ok(())
+    }
+
+    pub fn extension(&self) -> Extension<Replication> {
+        Extension(self.clone())
+    }
+}
+
+impl Follower {
+    async fn run(self: Arc<Self>, db: Arc<Database>, mut stopped: watch::Receiver<bool>) {
+        loop {
+            if let Err(e) = self.replicate(&db, &mut stopped).await {
+                warn!("Replication from {} failed: {:#}", self.settings.leader, e);
+            }
+            self.state.lock().connected = false;
+            if *stopped.borrow() {
+                return;
+            }
+            tokio::select! {
+                _ = tokio::time::sleep(RETRY_INTERVAL) => {}
+                _ = stopped.changed() => return,
+            }
+        }
+    }
+
+    /// Apply the stream of the leader until it ends or the follower is stopped.
+    async fn replicate(
+        &self,
+        db: &Arc<Database>,
+        stopped: &mut watch::Receiver<bool>,
+    ) -> Result<()> {
+        let mut client = tokio::select! {
+            client = Client::connect(&self.settings.leader) => client?,
+            _ = stopped.changed() => return Ok(()),
+        };
+        if let Some(api_key) = &self.settings.api_key {
+            client.authenticate(api_key).await?;
+        }
+        client.replicate(db.seq()).await?;
+        self.state.lock().connected = true;
+        info!("Replicating from {} after sequence {}", self.settings.leader, db.seq());
+
+        // The snapshot goes to a temporary file next to the database as it comes.
+        let mut snapshot: Option<tokio::fs::File> = None;
+        loop {
+            // Only the wait for the leader is interrupted, a frame is applied in full.
+            let frame = tokio::select! {
+                frame = client.replication_frame() => frame?,
+                _ = stopped.changed() => return Ok(()),
+            };
+            match frame {
+                Frame::SnapshotChunk(data) => {
+                    let file = match &mut snapshot {
+                        Some(file) => file,
+                        None => {
+                            let file = match db.storage_dir() {
+                                Some(dir) => tempfile::tempfile_in(dir)?,
+                                None => tempfile::tempfile()?,
+                            };
+                            snapshot.insert(tokio::fs::File::from_std(file))
+                        }
+                    };
+                    file.write_all(&data).await?;
+                }
+                Frame::SnapshotEnd { seq } => {
+                    let Some(file) = snapshot.take() else {
+                        bail!("Snapshot end without a snapshot");
+                    };
+                    let file = file.into_std().await;
+                    let snapshot_db = db.clone();
+                    tokio::task::spawn_blocking(move || snapshot_db.install_snapshot(file))
+                        .await??;
+                    info!("Installed the snapshot of the leader at sequence {}", seq);
+                    self.observe(db.seq(), Some(seq));
+                }
+                Frame::Record(record) => {
+                    let record_db = db.clone();
+                    tokio::task::spawn_blocking(move || record_db.apply_replicated(record))
+                        .await??;
+                    self.observe(db.seq(), None);
+                }
+                Frame::Heartbeat { seq } => self.observe(db.seq(), Some(seq)),
+            }
+        }
+    }
+
+    fn observe(&self, seq: u64, leader_seq: Option<u64>) {
+        let mut state = self.state.lock();
+        if let Some(leader_seq) = leader_seq {
+            state.leader_seq = Some(leader_seq);
+        }
+        let lag = state.leader_seq.map_or(0, |leader_seq| leader_seq.saturating_sub(seq));
+        if lag == 0 {
+            state.caught_up = Instant::now();
+        }
+        METRICS.replication_lag.set(lag as i64);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::{Auth, AuthConfig};
+    use crate::binary::{self, RemoteError};
+    use crate::database::EmbeddingRecord;
+    use crate::embedding::Embedder;
+    use crate::similarity::Distance;
+    use std::net::SocketAddr;
+
+    fn record(id: &str, embedding: Vec<f32>) -> EmbeddingRecord {
+        EmbeddingRecord {
+            id: id.to_string(),
+            embedding,
+            ..Default::default()
+        }
+    }
+
+    /// Wait until `follower` applied every mutation of `leader`.
+    async fn caught_up(leader: &Database, follower: &Database) {
+        for _ in 0..500 {
+            if follower.seq() == leader.seq() {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("follower at {} behind leader at {}", follower.seq(), leader.seq());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_follow_and_promote() {
+        let leader = Arc::new(Database::zero());
+        leader.create_table("t".to_string(), 2).unwrap();
+        leader.insert_record("t".to_string(), record("a", vec![1., 0.])).unwrap();
+        let addr = SocketAddr::from(([127, 0, 0, 1], 0));
+        let (auth, embedder) = (Auth::default(), Embedder::default());
+        let addr = binary::spawn(addr, leader.clone(), auth, embedder, std::future::pending())
+            .await
+            .unwrap();
+
+        // The follower starts from a snapshot and goes on with the following mutations.
+        let follower = Arc::new(Database::zero());
+        let settings = ReplicationSettings {
+            leader: addr.to_string(),
+            api_key: None,
+        };
+        let replication = Replication::follow(settings, follower.clone());
+        caught_up(&leader, &follower).await;
+        leader.insert_record("t".to_string(), record("b", vec![0., 1.])).unwrap();
+        leader.delete_record("t".to_string(), "a".to_string()).unwrap();
+        caught_up(&leader, &follower).await;
+        let hits = follower.query_record("t".to_string(), &[0., 1.], 10, Distance::Cosine);
+        let ids = hits.unwrap().into_iter().map(|h| h.record.id).collect::<Vec<_>>();
+        assert_eq!(vec!["b"], ids);
+
+        let e = follower.insert_record("t".to_string(), record("c", vec![1., 1.])).unwrap_err();
+        assert!(matches!(e, DBError::ReadOnly));
+        let status = replication.status(&follower);
+        assert_eq!(Role::Follower, status.role);
+        assert_eq!(Some(true), status.connected);
+        assert_eq!(1, replication.status(&leader).followers);
+
+        // A restore of the leader makes the follower start over.
+        let (_, data) = leader.snapshot().unwrap();
+        leader.insert_record("t".to_string(), record("c", vec![1., 1.])).unwrap();
+        leader.restore([snapshot::header(&data), data].concat()).unwrap();
+        leader.insert_record("t".to_string(), record("d", vec![1., 1.])).unwrap();
+        caught_up(&leader, &follower).await;
+        assert!(follower.get_record("t", "c").is_err());
+        assert!(follower.get_record("t", "d").is_ok());
+
+        replication.promote(&follower).await.unwrap();
+        assert_eq!(Role::Leader, replication.status(&follower).role);
+        follower.insert_record("t".to_string(), record("e", vec![1., 1.])).unwrap();
+        leader.insert_record("t".to_string(), record("f", vec![1., 1.])).unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(follower.get_record("t", "f").is_err());
+        assert!(replication.promote(&follower).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_catch_up_from_the_log() {
+        let dir = tempfile::tempdir().unwrap();
+        let leader = Arc::new(Database::load_from_dir(dir.path(), Default::default()).unwrap());
+        leader.create_table("t".to_string(), 2).unwrap();
+        leader.insert_record("t".to_string(), record("a", vec![1., 0.])).unwrap();
+        leader.insert_record("t".to_string(), record("b", vec![0., 1.])).unwrap();
+
+        let next = |from_seq: u64| {
+            let leader = leader.clone();
+            async move {
+                let (mut writer, mut reader) = tokio::io::duplex(1 << 20);
+                tokio::spawn(async move { stream(leader, from_seq, &mut writer).await });
+                binary::read_frame::<Frame, _>(&mut reader, binary::MAX_FRAME_SIZE)
+                    .await
+                    .unwrap()
+                    .unwrap()
+            }
+        };
+        // The log still holds the mutations after 1, no snapshot is needed.
+        assert!(matches!(next(1).await, Frame::Record(record) if record.seq == 2));
+        assert!(matches!(next(3).await, Frame::Heartbeat { seq: 3 }));
+        // A follower ahead of the leader starts over.
+        assert!(matches!(next(4).await, Frame::SnapshotChunk(_)));
+        leader.checkpoint().unwrap();
+        assert!(matches!(next(1).await, Frame::SnapshotChunk(_)));
+    }
+
+    #[tokio::test]
+    async fn test_replication_needs_admin_key() {
+        let config: AuthConfig = toml::from_str(
+            r#"
+            [[keys]]
+            key = "reader"
+            scope = "read"
+            "#,
+        )
+        .unwrap();
+        let leader = Arc::new(Database::zero());
+        let addr = SocketAddr::from(([127, 0, 0, 1], 0));
+        let (auth, embedder) = (Auth::new(config), Embedder::default());
+        let addr = binary::spawn(addr, leader, auth, embedder, std::future::pending())
+            .await
+            .unwrap();
+
+        let mut client = Client::connect(addr).await.unwrap();
+        client.authenticate("reader").await.unwrap();
+        let e = client.replicate(0).await.unwrap_err();
+        assert_eq!("permission_denied", e.downcast::<RemoteError>().unwrap().code);
+        // Still usable as a regular connection.
+        let e = client.drop_table("t").await.unwrap_err();
+        assert_eq!("permission_denied", e.downcast::<RemoteError>().unwrap().code);
+    }
+}
@@ -1,13 +1,31 @@
 use std::collections::HashMap;
+use std::sync::Arc;
 
-use crate::database::{DBError, DbExtension, EmbeddingRecord, Table};
+use crate::auth::{Principal, Scope};
+use crate::changes::{ChangeFeed, ChangeFormat};
+use crate::database::{
+    CombinedRecord, Database, DBError, DbExtension, EmbeddingRecord, HybridOptions, HybridRecord,
+    MemoryUsage, RecordPage, SearchOptions, Table, TableStats,
+};
 use crate::dto::*;
+use crate::embedding::Embedder;
+use crate::explain::QueryExplain;
+use crate::import::Decoder;
+use crate::routes::extract::{Admin, Json, Path, Query, Read, Write};
 use crate::routes::helper::*;
-use axum::extract::{Json, Path, Query};
-use axum::http::StatusCode;
-use axum::routing::{delete, get, post};
+use crate::shutdown::Shutdown;
+use axum::body::StreamBody;
+use axum::extract::BodyStream;
+use axum::http::header::CONTENT_TYPE;
+use axum::http::HeaderMap;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{delete, get, post, put};
 use axum::{Extension, Router};
-use tracing::info;
+use futures::StreamExt;
+use crate::embedding::EmbeddingModel;
+use parking_lot::Mutex;
+use tracing::{info, Span};
 
 pub struct DbHandler {}
 
@@ -17,126 +35,689 @@ impl DbHandler {
             .route("/create_table", post(Self::create_table))
             .route("/drop_table/:table_name", delete(Self::drop_table))
             .route("/insert_record", post(Self::insert_record))
+            .route("/insert_records", post(Self::insert_records))
+            .route("/upsert_record", post(Self::upsert_record))
+            .route("/upsert_records", post(Self::upsert_records))
+            .route("/import/:table_name", post(Self::import))
             .route(
                 "/delete_record/:table_name/:id",
                 delete(Self::delete_record),
             )
             .route("/query_record", post(Self::query_record))
+            .route("/query_fields", post(Self::query_fields))
+            .route("/hybrid_query", post(Self::hybrid_query))
             .route("/get_entire_db", get(Self::get_entire_db))
             .route("/query_table", get(Self::query_table))
+            .route("/memory_usage", get(Self::memory_usage))
+            .route("/tables", get(Self::list_tables))
+            .route("/tables/:table_name/stats", get(Self::table_stats))
+            .route("/tables/:table_name/embedding", put(Self::bind_embedding))
+            .route("/tables/:table_name/records", get(Self::list_records))
+            .route("/tables/:table_name/records/:id", get(Self::get_record))
+            .route("/tables/:table_name/changes", get(Self::changes))
     }
 
     async fn create_table(
         Extension(db): DbExtension,
-        Json(data): Json<CreateTableRequest>,
-    ) -> (StatusCode, Json<BaseHttpResponse<Result<(), DBError>>>) {
+        Extension(embedder): Extension<Embedder>,
+        Admin(Json(data)): Admin<Json<CreateTableRequest>>,
+    ) -> ApiResponse<()> {
+        if data.embedding.is_some() && !embedder.is_enabled() {
+            return respond(Err(DBError::InvalidInput(
+                "No embedding provider is configured".to_string(),
+            )));
+        }
         info!("Create table: {:?}", data);
-        let res = {
-            let mut db = db.write().await;
-            db.create_table(data.table_name, data.dimension)
-        };
+        let res = Self::run(db, move |db| {
+            db.create_table_with_binding(
+                data.table_name,
+                data.dimension,
+                data.options,
+                data.fields,
+                data.embedding,
+            )
+        })
+        .await;
 
-        if res.is_err() {
-            return (StatusCode::BAD_REQUEST, Json(generate_base_response(res, false, 0)));
+        respond(res)
+    }
+
+    /// Expect http request like this: http://localhost:3000/db/tables/table1/embedding
+    async fn bind_embedding(
+        Extension(db): DbExtension,
+        Extension(embedder): Extension<Embedder>,
+        Admin(Path(table)): Admin<Path<TableRequest>>,
+        Json(data): Json<BindEmbeddingRequest>,
+    ) -> ApiResponse<()> {
+        if data.embedding.is_some() && !embedder.is_enabled() {
+            return respond(Err(DBError::InvalidInput(
+                "No embedding provider is configured".to_string(),
+            )));
         }
+        info!("Bind embedding of table {:?}: {:?}", table.table_name, data.embedding);
+        let res =
+            Self::run(db, move |db| db.bind_embedding(table.table_name, data.embedding)).await;
 
-        (StatusCode::OK, Json(generate_base_response(res, true, 0)))
+        respond(res)
     }
 
     async fn drop_table(
         Extension(db): DbExtension,
-        Path(data): Path<DropTableRequest>,
-    ) -> (StatusCode, Json<BaseHttpResponse<Result<(), DBError>>>) {
+        Admin(Path(data)): Admin<Path<DropTableRequest>>,
+    ) -> ApiResponse<()> {
         info!("Drop table: {:?}", data);
 
-        let res = {
-            let mut db = db.write().await;
-            db.drop_table(data.table_name)
-        };
+        let res = Self::run(db, move |db| db.drop_table(data.table_name)).await;
 
-        if res.is_err() {
-            return (StatusCode::OK, Json(generate_base_response(res, false, 0)));
-        }
-
-        (StatusCode::OK, Json(generate_base_response(res, true, 0)))
+        respond(res)
     }
     async fn insert_record(
         Extension(db): DbExtension,
-        Json(data): Json<InsertRecordRequest>,
-    ) -> (StatusCode, Json<BaseHttpResponse<Result<(), DBError>>>) {
+        Extension(embedder): Extension<Embedder>,
+        Write(Json(mut data)): Write<Json<InsertRecordRequest>>,
+    ) -> ApiResponse<()> {
         info!("Inserd record table name: {:?}, record id: {:?}", data.table_name, data.record.id);
-        let res = {
-            let mut db = db.write().await;
-            db.insert_record(data.table_name, data.record)
-        };
+        let res = async {
+            let records = std::slice::from_mut(&mut data.record);
+            Self::embed_records(&db, &embedder, &data.table_name, records).await?;
+            Self::run(db, move |db| db.insert_record(data.table_name, data.record)).await
+        }
+        .await;
 
-        if res.is_err() {
-            return (StatusCode::OK, Json(generate_base_response(res, false, 0)));
+        respond(res)
+    }
+    async fn insert_records(
+        Extension(db): DbExtension,
+        Extension(embedder): Extension<Embedder>,
+        Write(Json(mut data)): Write<Json<InsertRecordsRequest>>,
+    ) -> ApiResponse<()> {
+        info!("Insert {} records to table: {:?}", data.records.len(), data.table_name);
+        let res = async {
+            Self::embed_records(&db, &embedder, &data.table_name, &mut data.records).await?;
+            Self::run(db, move |db| db.insert_records(data.table_name, data.records)).await
         }
+        .await;
 
-        (StatusCode::OK, Json(generate_base_response(res, true, 0)))
+        respond(res)
     }
-    async fn delete_record(
+    async fn upsert_record(
         Extension(db): DbExtension,
-        Path(data): Path<DeleteRecordRequest>,
-    ) -> (StatusCode, Json<BaseHttpResponse<Result<(), DBError>>>) {
-        info!("Delete record: {:?}", data);
-        let res = {
-            let mut db = db.write().await;
-            db.delete_record(data.table_name, data.id)
-        };
+        Extension(embedder): Extension<Embedder>,
+        Write(Json(mut data)): Write<Json<InsertRecordRequest>>,
+    ) -> ApiResponse<()> {
+        info!("Upsert record table name: {:?}, record id: {:?}", data.table_name, data.record.id);
+        let res = async {
+            let records = std::slice::from_mut(&mut data.record);
+            Self::embed_records(&db, &embedder, &data.table_name, records).await?;
+            Self::run(db, move |db| db.upsert_records(data.table_name, vec![data.record])).await
+        }
+        .await;
 
-        if res.is_err() {
-            return (StatusCode::OK, Json(generate_base_response(res, false, 0)));
+        respond(res)
+    }
+    async fn upsert_records(
+        Extension(db): DbExtension,
+        Extension(embedder): Extension<Embedder>,
+        Write(Json(mut data)): Write<Json<InsertRecordsRequest>>,
+    ) -> ApiResponse<()> {
+        info!("Upsert {} records to table: {:?}", data.records.len(), data.table_name);
+        let res = async {
+            Self::embed_records(&db, &embedder, &data.table_name, &mut data.records).await?;
+            Self::run(db, move |db| db.upsert_records(data.table_name, data.records)).await
         }
+        .await;
 
-        (StatusCode::OK, Json(generate_base_response(res, true, 0)))
+        respond(res)
     }
-    async fn query_record(
+
+    /// Stream NDJSON or npy records from the body into the table, one batch per write lock.
+    ///
+    /// Expect http request like this: http://localhost:3000/db/import/table1?format=npy&id_prefix=row-
+    async fn import(
         Extension(db): DbExtension,
-        Json(data): Json<QueryRecordRequest>,
-    ) -> (StatusCode, Json<BaseHttpResponse<Result<Vec<EmbeddingRecord>, DBError>>>) {
-        info!("Query record: table name: {:?}, top k: {:?}", data.table_name, data.top_k);
-        let res = {
-            let db = db.read().await;
-            db.query_record(data.table_name, &data.query_embedding, data.top_k, data.distance)
+        Extension(embedder): Extension<Embedder>,
+        Write(Path(table)): Write<Path<ImportTableRequest>>,
+        Query(data): Query<ImportRequest>,
+        mut body: BodyStream,
+    ) -> ApiResponse<ImportResponse> {
+        info!("Import {:?} into table: {:?}", data, table.table_name);
+        let batch_size = data.batch_size.max(1);
+        let mut decoder = Decoder::new(data.format, data.id_prefix);
+        let mut imported = 0;
+
+        let res = async {
+            let mut batch = Vec::new();
+            while let Some(chunk) = body.next().await {
+                let chunk = chunk.map_err(|e| DBError::InvalidInput(e.to_string()))?;
+                batch.extend(decoder.feed(&chunk)?);
+                while batch.len() >= batch_size {
+                    let records = batch.drain(..batch_size).collect();
+                    imported +=
+                        Self::import_batch(&db, &embedder, &table.table_name, records, data.upsert)
+                            .await?;
+                }
+            }
+            batch.extend(decoder.finish()?);
+            imported +=
+                Self::import_batch(&db, &embedder, &table.table_name, batch, data.upsert).await?;
+            Ok::<_, DBError>(())
+        }
+        .await;
+        info!("Imported {} records into table: {:?}", imported, table.table_name);
+
+        match res {
+            Ok(()) => respond::<_, ApiError>(Ok(ImportResponse { imported, error: None })),
+            Err(e) => {
+                // The committed batches are not rolled back, tell the caller how far it got.
+                let e = ApiError::from(e);
+                let status = e.status;
+                let body = ImportResponse { imported, error: Some(e) };
+                (
+                    status,
+                    axum::Json(generate_base_response(Ok(body), false, status.as_u16() as usize)),
+                )
+            }
+        }
+    }
+
+    async fn import_batch(
+        db: &Arc<Database>,
+        embedder: &Embedder,
+        table_name: &str,
+        mut records: Vec<EmbeddingRecord>,
+        upsert: bool,
+    ) -> Result<usize, DBError> {
+        let count = records.len();
+        if count == 0 {
+            return Ok(0);
+        }
+        Self::embed_records(db, embedder, table_name, &mut records).await?;
+        let table_name = table_name.to_string();
+        Self::run(db.clone(), move |db| {
+            if upsert {
+                db.upsert_records(table_name, records)
+            } else {
+                db.insert_records(table_name, records)
+            }
+        })
+        .await?;
+        Ok(count)
+    }
+
+    /// Embed the text of the records sent without a vector, with the model bound to the table.
+    async fn embed_records(
+        db: &Arc<Database>,
+        embedder: &Embedder,
+        table_name: &str,
+        records: &mut [EmbeddingRecord],
+    ) -> Result<(), DBError> {
+        let pending = records
+            .iter()
+            .enumerate()
+            .filter(|(_, r)| r.embedding.is_empty() && r.text.is_some())
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>();
+        if pending.is_empty() {
+            return Ok(());
+        }
+        let model = Self::embedding_model(db, table_name).await?;
+        let texts = pending
+            .iter()
+            .map(|&i| records[i].text.as_deref().unwrap_or_default())
+            .collect::<Vec<_>>();
+        let embeddings = embedder.embed(&model, &texts).await?;
+        for (i, embedding) in pending.into_iter().zip(embeddings) {
+            records[i].embedding = embedding;
+        }
+        Ok(())
+    }
+
+    /// Embed `query_text` when the query comes without a vector.
+    async fn embed_query(
+        db: &Arc<Database>,
+        embedder: &Embedder,
+        table_name: &str,
+        query_embedding: &mut Vec<f32>,
+        query_text: Option<&str>,
+    ) -> Result<(), DBError> {
+        let Some(text) = query_text.filter(|_| query_embedding.is_empty()) else {
+            return Ok(());
         };
+        let model = Self::embedding_model(db, table_name).await?;
+        *query_embedding = embedder.embed(&model, &[text]).await?.remove(0);
+        Ok(())
+    }
 
-        if res.is_err() {
-            return (StatusCode::OK, Json(generate_base_response(res, false, 0)));
+    async fn embedding_model(
+        db: &Arc<Database>,
+        table_name: &str,
+    ) -> Result<EmbeddingModel, DBError> {
+        let name = table_name.to_string();
+        let binding = Self::run(db.clone(), move |db| db.embedding_binding(&name)).await?;
+        binding.map(|b| b.model).ok_or_else(|| {
+            DBError::InvalidInput(format!("Table {} has no embedding model bound", table_name))
+        })
+    }
+
+    /// Run a database call on the blocking pool, it may wait for a table lock or score
+    /// every record of a table.
+    async fn run<T, F>(db: Arc<Database>, f: F) -> Result<T, DBError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Database) -> Result<T, DBError> + Send + 'static,
+    {
+        let span = Span::current();
+        tokio::task::spawn_blocking(move || span.in_scope(|| f(&db)))
+            .await
+            .unwrap_or_else(|e| Err(DBError::Internal(e.to_string())))
+    }
+    async fn delete_record(
+        Extension(db): DbExtension,
+        Write(Path(data)): Write<Path<DeleteRecordRequest>>,
+    ) -> ApiResponse<()> {
+        info!("Delete record: {:?}", data);
+        let res = Self::run(db, move |db| db.delete_record(data.table_name, data.id)).await;
+
+        respond(res)
+    }
+    async fn query_record(
+        Extension(db): DbExtension,
+        Extension(embedder): Extension<Embedder>,
+        Read(Json(mut data)): Read<Json<QueryRecordRequest>>,
+    ) -> ApiResponse<QueryRecordResponse> {
+        info!("Query record: table name: {:?}, top k: {:?}", data.table_name, data.top_k);
+        let text = data.query_text.as_deref();
+        let embedded =
+            Self::embed_query(&db, &embedder, &data.table_name, &mut data.query_embedding, text)
+                .await;
+        if let Err(e) = embedded {
+            return respond(Err(e));
         }
+        let res = Self::run(db, move |db| {
+            let explain = Mutex::new(QueryExplain::default());
+            let options = SearchOptions {
+                exact: data.exact,
+                ef_search: data.ef_search,
+                filter: data.filter.as_ref(),
+                min_score: data.min_score,
+                max_distance: data.max_distance,
+                rerank: data.rerank,
+                explain: data.explain.then_some(&explain),
+            };
+            let hits = db.query_record_with(
+                data.table_name,
+                &data.query_embedding,
+                data.top_k,
+                data.distance,
+                &options,
+            )?;
+            Ok(match data.explain {
+                true => QueryRecordResponse::Explained {
+                    hits,
+                    explain: explain.into_inner(),
+                },
+                false => QueryRecordResponse::Hits(hits),
+            })
+        })
+        .await;
 
-        (StatusCode::OK, Json(generate_base_response(res, true, 0)))
+        respond(res)
     }
-    async fn get_entire_db(
+    /// Query one or several named vector fields, weighting their scores.
+    async fn query_fields(
         Extension(db): DbExtension,
-    ) -> (StatusCode, Json<BaseHttpResponse<Result<HashMap<String, Table>, DBError>>>) {
-        let res = {
-            let db = db.read().await;
-            db.get_entire_db()
-        };
+        Read(Json(data)): Read<Json<QueryFieldsRequest>>,
+    ) -> ApiResponse<Vec<CombinedRecord>> {
+        info!(
+            "Query fields: table name: {:?}, fields: {:?}, top k: {:?}",
+            data.table_name,
+            data.vectors.iter().map(|v| v.field.as_str()).collect::<Vec<_>>(),
+            data.top_k
+        );
+        let res = Self::run(db, move |db| {
+            let options = SearchOptions {
+                exact: data.exact,
+                ef_search: data.ef_search,
+                filter: data.filter.as_ref(),
+                min_score: data.min_score,
+                max_distance: None,
+                rerank: data.rerank,
+                explain: None,
+            };
+            db.query_fields(&data.table_name, &data.vectors, data.top_k, &options)
+        })
+        .await;
 
-        if res.is_err() {
-            return (StatusCode::OK, Json(generate_base_response(res, false, 0)));
+        respond(res)
+    }
+    async fn hybrid_query(
+        Extension(db): DbExtension,
+        Extension(embedder): Extension<Embedder>,
+        Read(Json(mut data)): Read<Json<HybridQueryRequest>>,
+    ) -> ApiResponse<Vec<HybridRecord>> {
+        info!(
+            "Hybrid query: table name: {:?}, text: {:?}, top k: {:?}",
+            data.table_name, data.query_text, data.top_k
+        );
+        let text = Some(data.query_text.as_str());
+        let embedded =
+            Self::embed_query(&db, &embedder, &data.table_name, &mut data.query_embedding, text)
+                .await;
+        if let Err(e) = embedded {
+            return respond(Err(e));
         }
+        let res = Self::run(db, move |db| {
+            let options = SearchOptions {
+                exact: data.exact,
+                ef_search: data.ef_search,
+                filter: data.filter.as_ref(),
+                min_score: data.min_score,
+                max_distance: data.max_distance,
+                rerank: None,
+                explain: None,
+            };
+            let hybrid = HybridOptions {
+                query_text: &data.query_text,
+                fusion: data.fusion,
+                candidates: data.candidates,
+            };
+            db.hybrid_query(
+                data.table_name.clone(),
+                &data.query_embedding,
+                data.top_k,
+                data.distance,
+                &options,
+                &hybrid,
+            )
+        })
+        .await;
 
-        (StatusCode::OK, Json(generate_base_response(res, true, 0)))
+        respond(res)
+    }
+    async fn get_entire_db(
+        Extension(db): DbExtension,
+        _: Admin,
+    ) -> ApiResponse<HashMap<String, Table>> {
+        let res = Self::run(db, move |db| db.get_entire_db()).await;
+
+        respond(res)
     }
 
     /// Expect http query like this: http://localhost:3000/query_table?table_name=table1
     async fn query_table(
         Extension(db): DbExtension,
-        Query(data): Query<QueryTableRequest>,
-    ) -> (StatusCode, Json<BaseHttpResponse<Result<Table, DBError>>>) {
-        let res = {
-            let db = db.read().await;
-            db.get_table(data.table_name)
+        Read(Query(data)): Read<Query<QueryTableRequest>>,
+    ) -> ApiResponse<Table> {
+        let res = Self::run(db, move |db| db.get_table(data.table_name)).await;
+
+        respond(res)
+    }
+
+    /// Expect http query like this: http://localhost:3000/db/memory_usage?table_name=table1
+    async fn memory_usage(
+        Extension(db): DbExtension,
+        Read(Query(data)): Read<Query<QueryTableRequest>>,
+    ) -> ApiResponse<MemoryUsage> {
+        let res = Self::run(db, move |db| db.memory_usage(&data.table_name)).await;
+
+        respond(res)
+    }
+
+    /// Names of the tables the caller may read.
+    async fn list_tables(
+        Extension(db): DbExtension,
+        Extension(principal): Extension<Principal>,
+    ) -> ApiResponse<Vec<String>> {
+        let res = Self::run(db, move |db| {
+            let mut names = db.list_tables();
+            names.retain(|name| principal.authorize(Scope::Read, Some(name)).is_ok());
+            Ok(names)
+        })
+        .await;
+
+        respond(res)
+    }
+
+    /// Expect http request like this: http://localhost:3000/db/tables/table1/stats
+    async fn table_stats(
+        Extension(db): DbExtension,
+        Read(Path(data)): Read<Path<TableRequest>>,
+    ) -> ApiResponse<TableStats> {
+        let res = Self::run(db, move |db| db.table_stats(&data.table_name)).await;
+
+        respond(res)
+    }
+
+    /// Expect http request like this: http://localhost:3000/db/tables/table1/records?cursor=id42&limit=100&include_embedding=false
+    async fn list_records(
+        Extension(db): DbExtension,
+        Read(Path(table)): Read<Path<TableRequest>>,
+        Query(data): Query<ListRecordsRequest>,
+    ) -> ApiResponse<RecordPage> {
+        let res = Self::run(db, move |db| {
+            db.list_records(
+                &table.table_name,
+                data.cursor.as_deref(),
+                data.limit,
+                data.include_embedding,
+            )
+        })
+        .await;
+
+        respond(res)
+    }
+
+    /// Expect http request like this: http://localhost:3000/db/tables/table1/records/id42
+    async fn get_record(
+        Extension(db): DbExtension,
+        Read(Path(data)): Read<Path<GetRecordRequest>>,
+    ) -> ApiResponse<EmbeddingRecord> {
+        let res = Self::run(db, move |db| db.get_record(&data.table_name, &data.id)).await;
+
+        respond(res)
+    }
+
+    /// Expect http request like this: http://localhost:3000/db/tables/table1/changes?from_seq=42&format=ndjson
+    ///
+    /// A stream of the inserts, upserts and deletes rather than the usual JSON body.
+    async fn changes(
+        Extension(db): DbExtension,
+        Extension(shutdown): Extension<Shutdown>,
+        Read(Path(table)): Read<Path<TableRequest>>,
+        Query(data): Query<ChangesRequest>,
+        headers: HeaderMap,
+    ) -> Response {
+        let last_event_id = headers
+            .get("last-event-id")
+            .and_then(|id| id.to_str().ok()?.parse().ok());
+        let from_seq = data.from_seq.or(last_event_id);
+        info!("Changes of table {:?} from {:?}", table.table_name, from_seq);
+        // Reads the write-ahead log to resume.
+        let feed = Self::run(db, move |db| ChangeFeed::open(db, &table.table_name, from_seq)).await;
+        let feed = match feed {
+            Ok(feed) => feed,
+            Err(e) => return respond::<(), _>(Err(e)).into_response(),
         };
 
-        if res.is_err() {
-            return (StatusCode::OK, Json(generate_base_response(res, false, 0)));
+        // Open streams would hold the graceful shutdown back.
+        let events = feed.into_stream().take_until(shutdown.wait());
+        match data.format {
+            ChangeFormat::Sse => {
+                let events = events.map(|event| {
+                    Event::default()
+                        .id(event.seq.to_string())
+                        .event(event.change.op())
+                        .json_data(&event)
+                });
+                Sse::new(events).keep_alive(KeepAlive::default()).into_response()
+            }
+            ChangeFormat::Ndjson => {
+                let lines = events.map(|event| {
+                    let mut line = serde_json::to_vec(&event)?;
+                    line.push(b'\n');
+                    Ok::<_, serde_json::Error>(line)
+                });
+                ([(CONTENT_TYPE, "application/x-ndjson")], StreamBody::new(lines)).into_response()
+            }
         }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::embedding::tests::{mock_embedding, mock_server};
+    use crate::embedding::EmbeddingSettings;
+    use crate::wal::WalOptions;
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use serde_json::{json, Value};
+    use std::sync::atomic::Ordering;
+    use tower::ServiceExt;
+
+    async fn post_json(app: &Router, uri: &str, body: Value) -> (StatusCode, Value) {
+        let request = Request::builder()
+            .method("POST")
+            .uri(uri)
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_embed_text_on_ingest_and_query() {
+        let (base_url, embedded) = mock_server().await;
+        let embedder = Embedder::new(&EmbeddingSettings {
+            base_url,
+            ..Default::default()
+        });
+        let principal = Principal {
+            name: "test".to_string(),
+            scope: Scope::Admin,
+            tables: None,
+        };
+        let app = DbHandler::handler()
+            .layer(Database::zero().extension())
+            .layer(Extension(principal))
+            .layer(embedder.extension());
+
+        let table = json!({"table_name": "docs", "dimension": 2});
+        let (status, _) = post_json(&app, "/create_table", table).await;
+        assert_eq!(StatusCode::OK, status);
+        // Text is only embedded for tables bound to a model.
+        let record = json!({"table_name": "docs", "record": {"id": "a", "text": "a cat"}});
+        let (status, body) = post_json(&app, "/insert_record", record.clone()).await;
+        assert_eq!(StatusCode::BAD_REQUEST, status);
+        assert_eq!("invalid_input", body["result"]["Err"]["code"]);
+
+        let table = json!({
+            "table_name": "bound",
+            "dimension": 2,
+            "embedding": {"model": "text-embedding-ada-002"},
+        });
+        let (status, _) = post_json(&app, "/create_table", table).await;
+        assert_eq!(StatusCode::OK, status);
+        let records = json!({"table_name": "bound", "records": [
+            {"id": "a", "text": "a cat"},
+            {"id": "b", "text": "zebras"},
+            {"id": "c", "embedding": [1.0, 1.0]},
+        ]});
+        let (status, _) = post_json(&app, "/insert_records", records).await;
+        assert_eq!(StatusCode::OK, status);
+        assert_eq!(2, embedded.load(Ordering::SeqCst));
+
+        let query = json!({
+            "table_name": "bound",
+            "query_text": "a cat",
+            "top_k": 1,
+            "distance": "euclidean",
+        });
+        let (status, body) = post_json(&app, "/query_record", query).await;
+        assert_eq!(StatusCode::OK, status);
+        let hit = &body["result"]["Ok"][0];
+        assert_eq!("a", hit["record"]["id"]);
+        assert_eq!(json!(mock_embedding("a cat")), hit["record"]["embedding"]);
+        // The query text was embedded on insert already.
+        assert_eq!(2, embedded.load(Ordering::SeqCst));
+
+        let query = json!({
+            "table_name": "bound",
+            "query_embedding": [1.0, 1.0],
+            "top_k": 1,
+            "explain": true,
+        });
+        let (status, body) = post_json(&app, "/query_record", query).await;
+        assert_eq!(StatusCode::OK, status);
+        let result = &body["result"]["Ok"];
+        assert_eq!("c", result["hits"][0]["record"]["id"]);
+        assert_eq!("flat", result["explain"]["plan"]);
+        assert_eq!("cosine", result["explain"]["distance"]);
+        assert_eq!(3, result["explain"]["candidates"]);
+    }
+
+    #[tokio::test]
+    async fn test_stream_changes() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let db = Arc::new(Database::load_from_dir(dir.path(), WalOptions::default()).unwrap());
+        db.create_table("t".to_string(), 2).unwrap();
+        let from_seq = db.seq();
+        let principal = Principal {
+            name: "test".to_string(),
+            scope: Scope::Read,
+            tables: None,
+        };
+        let shutdown = Shutdown {
+            sender: tokio::sync::broadcast::channel(1).0,
+        };
+        let app = DbHandler::handler()
+            .layer(Extension(db.clone()))
+            .layer(Extension(principal))
+            .layer(Extension(shutdown));
+        let get = |uri: String, last_event_id: Option<u64>| {
+            let mut request = Request::builder().uri(uri);
+            if let Some(id) = last_event_id {
+                request = request.header("last-event-id", id.to_string());
+            }
+            app.clone().oneshot(request.body(Body::empty()).unwrap())
+        };
+        let record = EmbeddingRecord {
+            id: "a".to_string(),
+            embedding: vec![1., 0.],
+            ..Default::default()
+        };
+        db.insert_record("t".to_string(), record).unwrap();
+
+        let uri = format!("/tables/t/changes?from_seq={}&format=ndjson", from_seq);
+        let ndjson = get(uri, None).await.unwrap();
+        assert_eq!("application/x-ndjson", ndjson.headers()[CONTENT_TYPE]);
+        let sse = get("/tables/t/changes".to_string(), Some(from_seq + 1)).await.unwrap();
+        assert_eq!(StatusCode::OK, sse.status());
+        // Both streams end with the drop.
+        db.drop_table("t".to_string()).unwrap();
+
+        let body = hyper::body::to_bytes(ndjson.into_body()).await.unwrap();
+        let events = body
+            .split(|&b| b == b'\n')
+            .filter(|line| !line.is_empty())
+            .map(|line| serde_json::from_slice::<Value>(line).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(2, events.len());
+        assert_eq!(json!(from_seq + 1), events[0]["seq"]);
+        assert_eq!("insert", events[0]["op"]);
+        assert_eq!("a", events[0]["records"][0]["id"]);
+        assert_eq!("drop_table", events[1]["op"]);
+
+        let body = hyper::body::to_bytes(sse.into_body()).await.unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        assert!(!body.contains("event:insert"));
+        assert!(body.contains(&format!("id:{}\n", from_seq + 2)));
+        assert!(body.contains("event:drop_table"));
 
-        (StatusCode::OK, Json(generate_base_response(res, true, 0)))
+        let response = get("/tables/t/changes".to_string(), None).await.unwrap();
+        assert_eq!(StatusCode::NOT_FOUND, response.status());
     }
 }
@@ -0,0 +1,293 @@
+//! Wrappers around the axum extractors whose rejections use the `ApiError` body, and the
+//! extractors checking the scope of the caller.
+
+use crate::auth::{Principal, Scope};
+use crate::database::DBError;
+use crate::dto::*;
+use crate::routes::helper::{ApiError, ApiRejection};
+use axum::async_trait;
+use axum::extract::rejection::JsonRejection;
+use axum::extract::{FromRequest, FromRequestParts};
+use axum::http::request::Parts;
+use axum::http::Request;
+use serde::de::DeserializeOwned;
+
+/// `axum::Json` for request bodies.
+pub struct Json<T>(pub T);
+
+/// `axum::extract::Path`.
+pub struct Path<T>(pub T);
+
+/// `axum::extract::Query`.
+pub struct Query<T>(pub T);
+
+#[async_trait]
+impl<T, S, B> FromRequest<S, B> for Json<T>
+where
+    axum::Json<T>: FromRequest<S, B, Rejection = JsonRejection>,
+    S: Send + Sync,
+    B: Send + 'static,
+{
+    type Rejection = ApiRejection;
+
+    async fn from_request(req: Request<B>, state: &S) -> Result<Self, Self::Rejection> {
+        match axum::Json::<T>::from_request(req, state).await {
+            Ok(axum::Json(value)) => Ok(Json(value)),
+            Err(e) => Err(ApiError::new(e.status(), "invalid_body", e.body_text()).into()),
+        }
+    }
+}
+
+#[async_trait]
+impl<T, S> FromRequestParts<S> for Path<T>
+where
+    T: DeserializeOwned + Send,
+    S: Send + Sync,
+{
+    type Rejection = ApiRejection;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        match axum::extract::Path::<T>::from_request_parts(parts, state).await {
+            Ok(axum::extract::Path(value)) => Ok(Path(value)),
+            Err(e) => Err(ApiError::new(e.status(), "invalid_path", e.body_text()).into()),
+        }
+    }
+}
+
+#[async_trait]
+impl<T, S> FromRequestParts<S> for Query<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = ApiRejection;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        match axum::extract::Query::<T>::from_request_parts(parts, state).await {
+            Ok(axum::extract::Query(value)) => Ok(Query(value)),
+            Err(e) => Err(ApiError::new(e.status(), "invalid_query", e.body_text()).into()),
+        }
+    }
+}
+
+/// Names the table a request is about, `None` standing for the whole database.
+pub trait TableScoped {
+    fn table_name(&self) -> Option<&str>;
+}
+
+impl TableScoped for () {
+    fn table_name(&self) -> Option<&str> {
+        None
+    }
+}
+
+macro_rules! forward_table_scoped {
+    ($($wrapper:ident),*) => {
+        $(
+            impl<T: TableScoped> TableScoped for $wrapper<T> {
+                fn table_name(&self) -> Option<&str> {
+                    self.0.table_name()
+                }
+            }
+        )*
+    };
+}
+
+forward_table_scoped!(Json, Path, Query);
+
+macro_rules! table_scoped {
+    ($($request:ty),*) => {
+        $(
+            impl TableScoped for $request {
+                fn table_name(&self) -> Option<&str> {
+                    Some(&self.table_name)
+                }
+            }
+        )*
+    };
+}
+
+table_scoped!(
+    CreateTableRequest,
+    DropTableRequest,
+    InsertRecordRequest,
+    InsertRecordsRequest,
+    ImportTableRequest,
+    DeleteRecordRequest,
+    QueryRecordRequest,
+    QueryFieldsRequest,
+    HybridQueryRequest,
+    QueryTableRequest,
+    TableRequest,
+    GetRecordRequest
+);
+
+/// Check the `Principal` the authentication middleware put in the extensions.
+fn authorize(
+    principal: Option<&Principal>,
+    scope: Scope,
+    table: Option<&str>,
+) -> Result<(), ApiError> {
+    let principal = principal.ok_or(DBError::Unauthenticated)?;
+    Ok(principal.authorize(scope, table)?)
+}
+
+macro_rules! scoped {
+    ($(#[$doc:meta])* $name:ident, $scope:expr) => {
+        $(#[$doc])*
+        ///
+        /// Wraps the extractor naming the table, `()` for the whole database.
+        pub struct $name<T = ()>(pub T);
+
+        #[async_trait]
+        impl<T, S> FromRequestParts<S> for $name<T>
+        where
+            T: FromRequestParts<S> + TableScoped,
+            T::Rejection: Into<ApiRejection>,
+            S: Send + Sync,
+        {
+            type Rejection = ApiRejection;
+
+            async fn from_request_parts(
+                parts: &mut Parts,
+                state: &S,
+            ) -> Result<Self, Self::Rejection> {
+                let inner = T::from_request_parts(parts, state).await.map_err(Into::into)?;
+                authorize(parts.extensions.get(), $scope, inner.table_name())?;
+                Ok($name(inner))
+            }
+        }
+
+        #[async_trait]
+        impl<T, S, B> FromRequest<S, B> for $name<Json<T>>
+        where
+            Json<T>: FromRequest<S, B, Rejection = ApiRejection>,
+            T: TableScoped,
+            S: Send + Sync,
+            B: Send + 'static,
+        {
+            type Rejection = ApiRejection;
+
+            async fn from_request(req: Request<B>, state: &S) -> Result<Self, Self::Rejection> {
+                let principal = req.extensions().get::<Principal>().cloned();
+                let inner = Json::<T>::from_request(req, state).await?;
+                authorize(principal.as_ref(), $scope, inner.table_name())?;
+                Ok($name(inner))
+            }
+        }
+    };
+}
+
+scoped!(
+    /// Requires the read scope.
+    Read,
+    Scope::Read
+);
+scoped!(
+    /// Requires the write scope.
+    Write,
+    Scope::Write
+);
+scoped!(
+    /// Requires the admin scope.
+    Admin,
+    Scope::Admin
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dto::CreateTableRequest;
+    use axum::body::Body;
+    use axum::http::StatusCode;
+    use axum::routing::{get, post};
+    use axum::{Extension, Router};
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_json_rejection() {
+        let app = Router::new().route(
+            "/create_table",
+            post(|Json(data): Json<CreateTableRequest>| async move { data.table_name }),
+        );
+        let request = |body: &'static str| {
+            Request::builder()
+                .method("POST")
+                .uri("/create_table")
+                .header("content-type", "application/json")
+                .body(Body::from(body))
+                .unwrap()
+        };
+
+        let response = app.clone().oneshot(request("{\"table_name\": ")).await.unwrap();
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(400, body["result_code"]);
+        assert_eq!("invalid_body", body["result"]["Err"]["code"]);
+
+        let response = app.clone().oneshot(request("{\"table_name\": \"t\"}")).await.unwrap();
+        assert_eq!(StatusCode::UNPROCESSABLE_ENTITY, response.status());
+
+        let response = app
+            .oneshot(request("{\"table_name\": \"t\", \"dimension\": 2}"))
+            .await
+            .unwrap();
+        assert_eq!(StatusCode::OK, response.status());
+    }
+
+    #[tokio::test]
+    async fn test_scoped_extractors() {
+        let app = Router::new()
+            .route(
+                "/tables/:table_name",
+                get(|Read(Path(data)): Read<Path<TableRequest>>| async move { data.table_name }),
+            )
+            .route(
+                "/create_table",
+                post(|Admin(Json(data)): Admin<Json<CreateTableRequest>>| async move {
+                    data.table_name
+                }),
+            )
+            .route("/shutdown", get(|_: Admin| async { "bye" }));
+        let status = |principal: Option<Principal>, request: Request<Body>| {
+            let app = match principal {
+                Some(principal) => app.clone().layer(Extension(principal)),
+                None => app.clone(),
+            };
+            async move { app.oneshot(request).await.unwrap().status() }
+        };
+        let get = |uri: &str| Request::builder().uri(uri).body(Body::empty()).unwrap();
+        let create = || {
+            Request::builder()
+                .method("POST")
+                .uri("/create_table")
+                .header("content-type", "application/json")
+                .body(Body::from("{\"table_name\": \"docs\", \"dimension\": 2}"))
+                .unwrap()
+        };
+        let docs_writer = Principal {
+            name: "docs-writer".to_string(),
+            scope: Scope::Write,
+            tables: Some(["docs".to_string()].into()),
+        };
+
+        assert_eq!(StatusCode::UNAUTHORIZED, status(None, get("/tables/docs")).await);
+        let writer = Some(docs_writer.clone());
+        assert_eq!(StatusCode::OK, status(writer, get("/tables/docs")).await);
+        let writer = Some(docs_writer.clone());
+        assert_eq!(StatusCode::FORBIDDEN, status(writer, get("/tables/other")).await);
+        let writer = Some(docs_writer.clone());
+        assert_eq!(StatusCode::FORBIDDEN, status(writer, create()).await);
+        let writer = Some(docs_writer);
+        assert_eq!(StatusCode::FORBIDDEN, status(writer, get("/shutdown")).await);
+
+        let admin = Principal {
+            name: "ops".to_string(),
+            scope: Scope::Admin,
+            tables: None,
+        };
+        assert_eq!(StatusCode::OK, status(Some(admin.clone()), create()).await);
+        assert_eq!(StatusCode::OK, status(Some(admin), get("/shutdown")).await);
+    }
+}
@@ -1,21 +1,75 @@
-use serde::{Deserialize, Serialize};
-
-#[derive(Serialize, Deserialize, Debug)]
-#[serde(rename_all = "snake_case")]
-pub struct BaseHttpResponse<T> {
-    result: T,
-    success: bool,
-    result_code: usize,
+use crate::telemetry::METRICS;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+
+pub use tinyvector_types::api::{generate_base_response, ApiError, BaseHttpResponse};
+
+/// What every handler returns, `result_code` is 0 on success and the HTTP status otherwise.
+pub type ApiResponse<T> = (StatusCode, Json<BaseHttpResponse<Result<T, ApiError>>>);
+
+/// Count the error by its code, done once where it is answered.
+pub fn observe(e: &ApiError) {
+    METRICS.errors.with_label_values(&[&e.code]).inc();
+}
+
+/// Rejection of the extractors, answered like the errors of the handlers.
+#[derive(Debug)]
+pub struct ApiRejection(pub ApiError);
+
+impl<E: Into<ApiError>> From<E> for ApiRejection {
+    fn from(e: E) -> Self {
+        ApiRejection(e.into())
+    }
+}
+
+impl IntoResponse for ApiRejection {
+    fn into_response(self) -> Response {
+        respond::<(), _>(Err(self.0)).into_response()
+    }
+}
+
+/// Turn the result of a handler into its response, errors get their status code.
+pub fn respond<T, E: Into<ApiError>>(res: Result<T, E>) -> ApiResponse<T> {
+    match res {
+        Ok(value) => (StatusCode::OK, Json(generate_base_response(Ok(value), true, 0))),
+        Err(e) => {
+            let e = e.into();
+            observe(&e);
+            let status = e.status;
+            (
+                status,
+                Json(generate_base_response(Err(e), false, status.as_u16() as usize)),
+            )
+        }
+    }
 }
 
-pub fn generate_base_response<T>(
-    result: T,
-    success: bool,
-    result_code: usize,
-) -> BaseHttpResponse<T> {
-    BaseHttpResponse {
-        result,
-        success,
-        result_code,
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::DBError;
+
+    #[test]
+    fn test_error_response() {
+        let (status, Json(body)) = respond::<(), _>(Err(DBError::TableDoesNotExist));
+        assert_eq!(StatusCode::NOT_FOUND, status);
+        let body = serde_json::to_value(body).unwrap();
+        assert_eq!(false, body["success"]);
+        assert_eq!(404, body["result_code"]);
+        assert_eq!("table_not_found", body["result"]["Err"]["code"]);
+        assert_eq!("The table does not exist", body["result"]["Err"]["message"]);
+        assert!(body["result"]["Err"].get("detail").is_none());
+        let (_, Json(body)) = respond::<(), _>(Err(DBError::InvalidInput("top_k".to_string())));
+        assert_eq!("top_k", serde_json::to_value(body).unwrap()["result"]["Err"]["detail"]);
+
+        let (status, _) = respond::<(), _>(Err(DBError::DimensionMismatch));
+        assert_eq!(StatusCode::UNPROCESSABLE_ENTITY, status);
+        let (status, _) = respond::<(), _>(Err(DBError::RecordExist));
+        assert_eq!(StatusCode::CONFLICT, status);
+
+        let (status, Json(body)) = respond::<_, DBError>(Ok(1));
+        assert_eq!(StatusCode::OK, status);
+        assert_eq!(0, serde_json::to_value(body).unwrap()["result_code"]);
     }
 }
@@ -0,0 +1,26 @@
+use crate::database::DbExtension;
+use crate::routes::extract::Read;
+use crate::telemetry::METRICS;
+use axum::http::header::CONTENT_TYPE;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Extension, Router};
+
+pub struct MetricsHandler {}
+
+impl MetricsHandler {
+    pub fn handler() -> Router {
+        Router::new().route("/metrics", get(Self::metrics))
+    }
+
+    /// Expect http request like this: http://localhost:3000/metrics
+    ///
+    /// Prometheus text format rather than the usual JSON body.
+    async fn metrics(
+        Extension(db): DbExtension,
+        _: Read,
+    ) -> Response {
+        let text = METRICS.render(&db);
+        ([(CONTENT_TYPE, "text/plain; version=0.0.4")], text).into_response()
+    }
+}
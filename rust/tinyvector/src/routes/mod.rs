@@ -1,9 +1,11 @@
 mod test_handler;
 mod db_handler;
-mod helper;
+pub mod extract;
+pub mod helper;
 mod system_handler;
+mod metrics_handler;
 
 pub use test_handler::*;
 pub use db_handler::*;
-pub use helper::*;
 pub use system_handler::*;
+pub use metrics_handler::*;
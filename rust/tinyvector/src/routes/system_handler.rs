@@ -1,7 +1,14 @@
-use axum::{Router, Json};
-use axum::routing::get;
+use axum::Router;
+use axum::routing::{get, post};
 use axum::Extension;
+use crate::database::{DBError, DbExtension};
+use crate::dto::{RestoreRequest, RestoreResponse};
+use crate::routes::extract::{Admin, Json, Read};
+use crate::routes::helper::*;
+use crate::replication::{Replication, ReplicationStatus};
 use crate::shutdown::Shutdown;
+use crate::snapshot::{SnapshotInfo, Snapshots};
+use tracing::{info, Span};
 
 pub struct SystemHandler {}
 
@@ -9,10 +16,89 @@ impl SystemHandler {
     pub fn handler() -> Router {
         Router::new()
             .route("/shutdown", get(Self::shutdown))
+            .route("/snapshot", post(Self::snapshot))
+            .route("/snapshots", get(Self::list_snapshots))
+            .route("/restore", post(Self::restore))
+            .route("/replication", get(Self::replication))
+            .route("/promote", post(Self::promote))
     }
 
-    async fn shutdown(Extension(shutdown): Extension<Shutdown>) -> Json<String> {
+    async fn shutdown(
+        Extension(shutdown): Extension<Shutdown>,
+        _: Admin,
+    ) -> ApiResponse<String> {
         shutdown.start_shutdown();
-        Json("Shutting down...".to_string())
+        let res: Result<String, DBError> = Ok("Shutting down...".to_string());
+        respond(res)
+    }
+
+    /// Write a snapshot to the snapshot dir, older ones beyond the retention are removed.
+    async fn snapshot(
+        Extension(db): DbExtension,
+        Extension(snapshots): Extension<Snapshots>,
+        _: Admin,
+    ) -> ApiResponse<SnapshotInfo> {
+        let res = Self::run(move || snapshots.create(&db)).await;
+        respond(res)
+    }
+
+    async fn list_snapshots(
+        Extension(snapshots): Extension<Snapshots>,
+        _: Admin,
+    ) -> ApiResponse<Vec<SnapshotInfo>> {
+        let res = Self::run(move || snapshots.list()).await;
+        respond(res)
+    }
+
+    /// Replace every table with the content of a snapshot.
+    async fn restore(
+        Extension(db): DbExtension,
+        Extension(snapshots): Extension<Snapshots>,
+        _: Admin,
+        Json(data): Json<RestoreRequest>,
+    ) -> ApiResponse<RestoreResponse> {
+        info!("Restore snapshot: {:?}", data.name);
+        let res = Self::run(move || {
+            let seq = snapshots.restore(&db, &data.name)?;
+            Ok(RestoreResponse { seq })
+        })
+        .await;
+        respond(res)
+    }
+
+    /// Role of the server, followers also report how far behind the leader they are.
+    async fn replication(
+        Extension(db): DbExtension,
+        Extension(replication): Extension<Replication>,
+        _: Read,
+    ) -> ApiResponse<ReplicationStatus> {
+        let res: Result<_, DBError> = Ok(replication.status(&db));
+        respond(res)
+    }
+
+    /// Stop following the leader and accept writes.
+    async fn promote(
+        Extension(db): DbExtension,
+        Extension(replication): Extension<Replication>,
+        _: Admin,
+    ) -> ApiResponse<ReplicationStatus> {
+        let res = replication.promote(&db).await.map(|()| replication.status(&db));
+        respond(res)
+    }
+
+    /// Run file work on the blocking pool, errors that are not a `DBError` are storage errors.
+    async fn run<T, F>(f: F) -> Result<T, DBError>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> anyhow::Result<T> + Send + 'static,
+    {
+        let span = Span::current();
+        match tokio::task::spawn_blocking(move || span.in_scope(f)).await {
+            Ok(res) => res.map_err(|e| {
+                e.downcast::<DBError>()
+                    .unwrap_or_else(|e| DBError::Storage(format!("{:#}", e)))
+            }),
+            Err(e) => Err(DBError::Internal(e.to_string())),
+        }
     }
 }
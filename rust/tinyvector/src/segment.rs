@@ -0,0 +1,918 @@
+use crate::database::{select_top_k, EmbeddingRecord, EmbeddingRecordRef};
+use crate::database::ScoredRecord;
+use crate::database::SearchOptions;
+use crate::metadata::Filter;
+use crate::similarity::{cosine_from_dot, dot_product, get_distance_fn, norm, Distance, ScoreIndex};
+use crate::wal;
+use anyhow::{anyhow, bail, Result};
+use memmap2::Mmap;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub use tinyvector_types::database::SegmentConfig;
+
+/// Directory of the segment files inside the storage dir.
+pub const SEGMENT_DIR: &str = "segments";
+/// Start of every segment file.
+const MAGIC: [u8; 8] = *b"TVSEG\0\0\0";
+/// Version written by this build, the only one read.
+const SEGMENT_VERSION: u32 = 1;
+/// `magic | version: u32 | dimension: u32 | len: u64` followed by the u64 offsets of the
+/// norms, expiries, payload offsets, id order and payloads sections, the crc32 of the vectors,
+/// norms, expiries, offsets, order and payloads sections, then the crc32 of everything before
+/// it. Zero padded.
+const HEADER_SIZE: usize = 96;
+/// Where the crc32 of the sections start in the header.
+const CRCS_OFFSET: usize = 64;
+const NEVER: u64 = u64::MAX;
+const SUFFIX: &str = ".seg";
+
+// Vectors and norms are used in place, in the byte order of the file.
+const _: () = assert!(cfg!(target_endian = "little"), "segments are little endian");
+
+/// The record without its embedding, as stored in a segment.
+fn encode_payload(record: &EmbeddingRecord) -> Result<Vec<u8>> {
+    Ok(bincode::serialize(&EmbeddingRecordRef::new(record, &[]))?)
+}
+
+/// A record about to be written into a segment.
+struct Entry<'a> {
+    id: &'a str,
+    vector: &'a [f32],
+    expires_at: Option<u64>,
+    /// The record encoded without its embedding.
+    payload: Cow<'a, [u8]>,
+}
+
+/// Writes the sections of a segment one after the other, keeping the crc32 of each.
+struct SectionWriter {
+    file: BufWriter<File>,
+    hasher: crc32fast::Hasher,
+    crcs: Vec<u32>,
+}
+
+impl SectionWriter {
+    fn write(&mut self, bytes: &[u8]) -> Result<()> {
+        self.hasher.update(bytes);
+        self.file.write_all(bytes)?;
+        Ok(())
+    }
+
+    fn end_section(&mut self) {
+        self.crcs.push(std::mem::take(&mut self.hasher).finalize());
+    }
+}
+
+/// An immutable file of records, mapped into memory.
+///
+/// Layout after the header: the vectors and their norms as f32, the expiries and the offsets
+/// of the payloads as u64, the positions in id order as u32, then the bincode payloads, which
+/// are the records without their embedding. Every section but the vectors is checked on open,
+/// the vectors are paged in on demand and checked before a merge.
+#[derive(Debug)]
+pub struct Segment {
+    map: Mmap,
+    dimension: usize,
+    len: usize,
+    norms: usize,
+    expiries: usize,
+    offsets: usize,
+    order: usize,
+    payloads: usize,
+}
+
+impl Segment {
+    /// Write `entries` into a new segment at `path`, through a temporary file.
+    fn write(path: &Path, dimension: usize, entries: &[Entry]) -> Result<()> {
+        let len = entries.len();
+        let norms = HEADER_SIZE + len * dimension * 4;
+        let expiries = norms + len * 4;
+        let offsets = expiries + len * 8;
+        let order = offsets + (len + 1) * 8;
+        let payloads = order + len * 4;
+
+        let mut sorted = (0..len as u32).collect::<Vec<_>>();
+        sorted.par_sort_unstable_by_key(|&position| entries[position as usize].id);
+
+        let tmp_path = path.with_extension("tmp");
+        let mut file = BufWriter::new(File::create(&tmp_path)?);
+        // The header goes last, once the checksums are known.
+        file.write_all(&[0; HEADER_SIZE])?;
+        let mut sections = SectionWriter {
+            file,
+            hasher: crc32fast::Hasher::new(),
+            crcs: Vec::with_capacity(6),
+        };
+        for entry in entries {
+            for value in entry.vector {
+                sections.write(&value.to_le_bytes())?;
+            }
+        }
+        sections.end_section();
+        for entry in entries {
+            sections.write(&norm(entry.vector).to_le_bytes())?;
+        }
+        sections.end_section();
+        for entry in entries {
+            sections.write(&entry.expires_at.unwrap_or(NEVER).to_le_bytes())?;
+        }
+        sections.end_section();
+        let mut offset = 0u64;
+        sections.write(&offset.to_le_bytes())?;
+        for entry in entries {
+            offset += entry.payload.len() as u64;
+            sections.write(&offset.to_le_bytes())?;
+        }
+        sections.end_section();
+        for position in sorted {
+            sections.write(&position.to_le_bytes())?;
+        }
+        sections.end_section();
+        for entry in entries {
+            sections.write(&entry.payload)?;
+        }
+        sections.end_section();
+
+        let mut header = MAGIC.to_vec();
+        header.extend_from_slice(&SEGMENT_VERSION.to_le_bytes());
+        header.extend_from_slice(&(dimension as u32).to_le_bytes());
+        for value in [len, norms, expiries, offsets, order, payloads] {
+            header.extend_from_slice(&(value as u64).to_le_bytes());
+        }
+        for crc in &sections.crcs {
+            header.extend_from_slice(&crc.to_le_bytes());
+        }
+        header.extend_from_slice(&crc32fast::hash(&header).to_le_bytes());
+        header.resize(HEADER_SIZE, 0);
+
+        let mut file = sections.file.into_inner().map_err(|e| e.into_error())?;
+        file.seek(SeekFrom::Start(0))?;
+        file.write_all(&header)?;
+        file.sync_all()?;
+        fs::rename(&tmp_path, path)?;
+        wal::sync_dir(path)?;
+        Ok(())
+    }
+
+    /// Map the segment at `path`, checking every section but the vectors.
+    pub fn open(path: &Path, dimension: usize) -> Result<Self> {
+        let file = File::open(path)?;
+        // SAFETY: segment files are never modified once written, only removed.
+        let map = unsafe { Mmap::map(&file)? };
+        if map.len() < HEADER_SIZE || map[..8] != MAGIC {
+            bail!("{:?} is not a segment", path);
+        }
+        let u32_at = |at: usize| u32::from_le_bytes(map[at..at + 4].try_into().unwrap());
+        let u64_at = |at: usize| u64::from_le_bytes(map[at..at + 8].try_into().unwrap()) as usize;
+        let version = u32_at(8);
+        if version != SEGMENT_VERSION {
+            bail!("Segment version {} of {:?} is not supported", version, path);
+        }
+        let crc_at = CRCS_OFFSET + 6 * 4;
+        if crc32fast::hash(&map[..crc_at]) != u32_at(crc_at) {
+            bail!("Segment {:?} has a corrupted header", path);
+        }
+        if u32_at(12) as usize != dimension {
+            bail!("Segment {:?} has dimension {}, {} expected", path, u32_at(12), dimension);
+        }
+        let len = u64_at(16);
+        // Every record takes more than a byte, this keeps the offsets below from overflowing.
+        if len > map.len() {
+            bail!("Segment {:?} is truncated or corrupted", path);
+        }
+        let norms = HEADER_SIZE + len * dimension * 4;
+        let (expiries, offsets, order, payloads) = (u64_at(32), u64_at(40), u64_at(48), u64_at(56));
+        let consistent = u64_at(24) == norms
+            && expiries == norms + len * 4
+            && offsets == expiries + len * 8
+            && order == offsets + (len + 1) * 8
+            && payloads == order + len * 4
+            && map.len() >= payloads
+            && map.len() - payloads == u64_at(offsets + len * 8);
+        if !consistent {
+            bail!("Segment {:?} is truncated or corrupted", path);
+        }
+        let segment = Self {
+            map,
+            dimension,
+            len,
+            norms,
+            expiries,
+            offsets,
+            order,
+            payloads,
+        };
+        segment.check_sections(path)?;
+        Ok(segment)
+    }
+
+    /// The bytes of each section along with the index of its checksum in the header.
+    fn sections(&self) -> [(usize, &[u8]); 6] {
+        [
+            (0, &self.map[HEADER_SIZE..self.norms]),
+            (1, &self.map[self.norms..self.expiries]),
+            (2, &self.map[self.expiries..self.offsets]),
+            (3, &self.map[self.offsets..self.order]),
+            (4, &self.map[self.order..self.payloads]),
+            (5, &self.map[self.payloads..]),
+        ]
+    }
+
+    fn check_crc(&self, path: &Path, (index, bytes): (usize, &[u8])) -> Result<()> {
+        let start = CRCS_OFFSET + index * 4;
+        let expected = u32::from_le_bytes(self.map[start..start + 4].try_into().unwrap());
+        if crc32fast::hash(bytes) != expected {
+            bail!("Segment {:?} is corrupted", path);
+        }
+        Ok(())
+    }
+
+    /// Check the sections read on lookups against their checksums, so that no lookup reads
+    /// out of the file.
+    fn check_sections(&self, path: &Path) -> Result<()> {
+        let [_, rest @ ..] = self.sections();
+        rest.into_iter().try_for_each(|section| self.check_crc(path, section))
+    }
+
+    /// Check the vectors, which are not read on open.
+    fn check_vectors(&self, path: &Path) -> Result<()> {
+        self.check_crc(path, self.sections()[0])
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn vector(&self, position: usize) -> &[f32] {
+        let start = HEADER_SIZE + position * self.dimension * 4;
+        let bytes = &self.map[start..start + self.dimension * 4];
+        // SAFETY: the map is page aligned and the vectors start at a multiple of 4 bytes, any
+        // bit pattern is a valid f32.
+        unsafe { std::slice::from_raw_parts(bytes.as_ptr().cast::<f32>(), self.dimension) }
+    }
+
+    pub fn norm(&self, position: usize) -> f32 {
+        let start = self.norms + position * 4;
+        f32::from_le_bytes(self.map[start..start + 4].try_into().unwrap())
+    }
+
+    /// Unix time the record at `position` expires at.
+    pub fn expires_at(&self, position: usize) -> Option<u64> {
+        let start = self.expiries + position * 8;
+        let expires_at = u64::from_le_bytes(self.map[start..start + 8].try_into().unwrap());
+        (expires_at != NEVER).then_some(expires_at)
+    }
+
+    pub fn is_expired(&self, position: usize, now: u64) -> bool {
+        self.expires_at(position).is_some_and(|expires_at| expires_at <= now)
+    }
+
+    /// Offset of the payload `i` in the payloads section.
+    fn offset(&self, i: usize) -> usize {
+        let start = self.offsets + i * 8;
+        u64::from_le_bytes(self.map[start..start + 8].try_into().unwrap()) as usize
+    }
+
+    fn payload_bytes(&self, position: usize) -> &[u8] {
+        &self.map[self.payloads + self.offset(position)..self.payloads + self.offset(position + 1)]
+    }
+
+    /// The record at `position` without its embedding.
+    pub fn payload(&self, position: usize) -> Result<EmbeddingRecord> {
+        bincode::deserialize(self.payload_bytes(position))
+            .map_err(|e| anyhow!("Corrupted segment payload: {e}"))
+    }
+
+    pub fn record(&self, position: usize) -> Result<EmbeddingRecord> {
+        Ok(EmbeddingRecord {
+            embedding: self.vector(position).to_vec(),
+            ..self.payload(position)?
+        })
+    }
+
+    /// Id of the record at `position`, read without decoding the rest of the payload.
+    pub fn id(&self, position: usize) -> Result<&str> {
+        // bincode writes a string as its u64 length followed by the bytes.
+        let bytes = self.payload_bytes(position);
+        let id = bytes
+            .get(..8)
+            .map(|len| u64::from_le_bytes(len.try_into().unwrap()) as usize)
+            .and_then(|len| bytes.get(8..8usize.checked_add(len)?))
+            .ok_or_else(|| anyhow!("Corrupted segment id"))?;
+        Ok(std::str::from_utf8(id)?)
+    }
+
+    /// Position of the record at `rank` in id order.
+    fn sorted(&self, rank: usize) -> usize {
+        let start = self.order + rank * 4;
+        u32::from_le_bytes(self.map[start..start + 4].try_into().unwrap()) as usize
+    }
+
+    /// Rank of the first id not below `id`.
+    fn lower_bound(&self, id: &str) -> Result<usize> {
+        let (mut low, mut high) = (0, self.len);
+        while low < high {
+            let mid = (low + high) / 2;
+            if self.id(self.sorted(mid))? < id {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+        Ok(low)
+    }
+
+    pub fn find(&self, id: &str) -> Result<Option<usize>> {
+        let rank = self.lower_bound(id)?;
+        if rank == self.len {
+            return Ok(None);
+        }
+        let position = self.sorted(rank);
+        Ok((self.id(position)? == id).then_some(position))
+    }
+
+    /// Positions of the records with an id greater than `after`, in id order.
+    pub fn ids_after(&self, after: Option<&str>) -> Result<impl Iterator<Item = usize> + '_> {
+        let start = match after {
+            Some(after) => {
+                let rank = self.lower_bound(after)?;
+                match self.find(after)? {
+                    Some(_) => rank + 1,
+                    None => rank,
+                }
+            }
+            None => 0,
+        };
+        Ok((start..self.len).map(|rank| self.sorted(rank)))
+    }
+
+    fn score(&self, position: usize, query: &[f32], query_norm: f32, distance: Distance) -> f32 {
+        let vector = self.vector(position);
+        match distance {
+            Distance::Cosine => {
+                cosine_from_dot(dot_product(vector, query), self.norm(position), query_norm)
+            }
+            distance => get_distance_fn(distance)(vector, query),
+        }
+    }
+
+    /// Bytes of the file.
+    pub fn size(&self) -> usize {
+        self.map.len()
+    }
+}
+
+/// A unique file name for a new segment.
+fn segment_name() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    format!("{:x}-{:x}{}", now.as_micros(), COUNTER.fetch_add(1, Ordering::Relaxed), SUFFIX)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SegmentHandle {
+    /// File name inside the segment dir.
+    name: String,
+    /// Positions of the records deleted or replaced since the segment was written.
+    deleted: BTreeSet<u32>,
+    #[serde(skip)]
+    segment: Option<Arc<Segment>>,
+}
+
+impl SegmentHandle {
+    fn segment(&self) -> &Segment {
+        self.segment.as_deref().expect("segments are opened on load")
+    }
+
+    fn is_live(&self, position: usize) -> bool {
+        !self.deleted.contains(&(position as u32))
+    }
+
+    /// Live and not expired by `now`.
+    fn is_visible(&self, position: usize, now: u64) -> bool {
+        self.is_live(position) && !self.segment().is_expired(position, now)
+    }
+
+    fn live(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.segment().len()).filter(|&position| self.is_live(position))
+    }
+
+    fn count(&self) -> usize {
+        self.segment().len() - self.deleted.len()
+    }
+}
+
+/// The segments of a table, part of the snapshot by name along with their deleted records.
+///
+/// A record is live in at most one segment or in the memtable, replacing it deletes the older
+/// copy, so the order of the segments does not matter.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Segments {
+    config: SegmentConfig,
+    handles: Vec<SegmentHandle>,
+    /// Set by `open`.
+    #[serde(skip)]
+    dir: PathBuf,
+    /// Set by `open_read_only`, no segment is written then.
+    #[serde(skip)]
+    read_only: bool,
+}
+
+impl Segments {
+    pub fn new(config: SegmentConfig) -> Self {
+        Self {
+            config,
+            handles: Vec::new(),
+            dir: PathBuf::new(),
+            read_only: false,
+        }
+    }
+
+    pub fn config(&self) -> SegmentConfig {
+        self.config
+    }
+
+    /// Map the segment files kept in `dir`.
+    pub fn open(&mut self, dir: &Path, dimension: usize) -> Result<()> {
+        fs::create_dir_all(dir)?;
+        self.map(dir, dimension)
+    }
+
+    /// Map the segment files kept in `dir` without ever writing there: the memtable is not
+    /// flushed and the segments are not merged.
+    pub fn open_read_only(&mut self, dir: &Path, dimension: usize) -> Result<()> {
+        self.read_only = true;
+        self.map(dir, dimension)
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    fn map(&mut self, dir: &Path, dimension: usize) -> Result<()> {
+        for handle in &mut self.handles {
+            let segment = Segment::open(&dir.join(&handle.name), dimension)?;
+            handle.segment = Some(Arc::new(segment));
+        }
+        self.dir = dir.to_path_buf();
+        Ok(())
+    }
+
+    /// File names of the segments.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.handles.iter().map(|handle| handle.name.as_str())
+    }
+
+    pub fn len(&self) -> usize {
+        self.handles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.handles.is_empty()
+    }
+
+    /// Live records across the segments, without the ones expired by `now`.
+    pub fn count(&self, now: u64) -> usize {
+        let visible = |handle: &SegmentHandle| {
+            let positions = 0..handle.segment().len();
+            positions.filter(|&position| handle.is_visible(position, now)).count()
+        };
+        self.handles.iter().map(visible).sum()
+    }
+
+    /// Bytes of the segment files.
+    pub fn mapped(&self) -> usize {
+        self.handles.iter().map(|handle| handle.segment().size()).sum()
+    }
+
+    /// Handle and position of the live record `id`.
+    fn find(&self, id: &str) -> Result<Option<(usize, usize)>> {
+        for (i, handle) in self.handles.iter().enumerate() {
+            let position = handle.segment().find(id)?;
+            if let Some(position) = position.filter(|&position| handle.is_live(position)) {
+                return Ok(Some((i, position)));
+            }
+        }
+        Ok(None)
+    }
+
+    pub fn contains(&self, id: &str, now: u64) -> Result<bool> {
+        let found = self.find(id)?;
+        Ok(found.is_some_and(|(i, position)| !self.handles[i].segment().is_expired(position, now)))
+    }
+
+    pub fn get(&self, id: &str, now: u64) -> Result<Option<EmbeddingRecord>> {
+        let Some((i, position)) = self.find(id)? else {
+            return Ok(None);
+        };
+        let segment = self.handles[i].segment();
+        match segment.is_expired(position, now) {
+            true => Ok(None),
+            false => segment.record(position).map(Some),
+        }
+    }
+
+    /// Whether some live records expired by `now`.
+    pub fn has_expired(&self, now: u64) -> bool {
+        self.handles.iter().any(|handle| {
+            let segment = handle.segment();
+            handle.live().any(|position| segment.is_expired(position, now))
+        })
+    }
+
+    /// Mark the records expired by `now` as deleted, returns how many.
+    pub fn expire(&mut self, now: u64) -> usize {
+        let mut expired = 0;
+        for handle in &mut self.handles {
+            let positions = handle
+                .live()
+                .filter(|&position| handle.segment().is_expired(position, now))
+                .collect::<Vec<_>>();
+            expired += positions.len();
+            handle.deleted.extend(positions.into_iter().map(|position| position as u32));
+        }
+        expired
+    }
+
+    /// Mark the record `id` as deleted, returns whether it was live.
+    pub fn remove(&mut self, id: &str) -> Result<bool> {
+        let Some((i, position)) = self.find(id)? else {
+            return Ok(false);
+        };
+        Ok(self.handles[i].deleted.insert(position as u32))
+    }
+
+    /// Write `records` into a new segment.
+    pub fn flush(&mut self, dimension: usize, records: &[EmbeddingRecord]) -> Result<()> {
+        if self.read_only {
+            bail!("Segments opened read-only are never written");
+        }
+        let entries = records
+            .par_iter()
+            .map(|record| {
+                Ok(Entry {
+                    id: &record.id,
+                    vector: &record.embedding,
+                    expires_at: record.expires_at,
+                    payload: Cow::Owned(encode_payload(record)?),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let name = segment_name();
+        let path = self.dir.join(&name);
+        Segment::write(&path, dimension, &entries)?;
+        self.handles.push(SegmentHandle {
+            name,
+            deleted: BTreeSet::new(),
+            segment: Some(Arc::new(Segment::open(&path, dimension)?)),
+        });
+        Ok(())
+    }
+
+    /// Top k of every segment, the caller merges them with the memtable hits.
+    pub fn search(
+        &self,
+        query_embedding: &[f32],
+        top_k: usize,
+        distance: Distance,
+        options: &SearchOptions,
+        now: u64,
+    ) -> Result<Vec<ScoredRecord>> {
+        let query_norm = norm(query_embedding);
+        let scored = AtomicUsize::new(0);
+        let matches = |segment: &Segment, position, filter: &Filter| {
+            segment.payload(position).map(|payload| filter.matches(&payload.metadata))
+        };
+        let hits = self
+            .handles
+            .par_iter()
+            .map(|handle| {
+                let segment = handle.segment();
+                let scores = (0..segment.len())
+                    .into_par_iter()
+                    .filter(|&position| handle.is_visible(position, now))
+                    .filter_map(|position| {
+                        if let Some(filter) = options.filter {
+                            match matches(segment, position, filter) {
+                                Ok(true) => {}
+                                Ok(false) => return None,
+                                Err(e) => return Some(Err(e)),
+                            }
+                        }
+                        options.count(&scored);
+                        let score = segment.score(position, query_embedding, query_norm, distance);
+                        options.passes(score).then(|| {
+                            Ok(ScoreIndex {
+                                score: distance.rank_key(score),
+                                index: position,
+                            })
+                        })
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                select_top_k(scores, top_k)
+                    .into_iter()
+                    .map(|score_index| {
+                        Ok(ScoredRecord {
+                            record: segment.record(score_index.index)?,
+                            score: distance.rank_key(score_index.score),
+                        })
+                    })
+                    .collect::<Result<Vec<_>>>()
+            })
+            .collect::<Result<Vec<_>>>()?;
+        options.note(|explain| explain.candidates += scored.into_inner());
+        Ok(hits.into_iter().flatten().collect())
+    }
+
+    /// Up to `limit` records of every segment with an id greater than `after`.
+    pub fn list(
+        &self,
+        after: Option<&str>,
+        limit: usize,
+        include_embedding: bool,
+        now: u64,
+    ) -> Result<Vec<EmbeddingRecord>> {
+        let mut records = Vec::new();
+        for handle in &self.handles {
+            let segment = handle.segment();
+            let positions = segment
+                .ids_after(after)?
+                .filter(|&position| handle.is_visible(position, now))
+                .take(limit);
+            for position in positions {
+                records.push(match include_embedding {
+                    true => segment.record(position)?,
+                    false => EmbeddingRecord {
+                        vectors: HashMap::new(),
+                        ..segment.payload(position)?
+                    },
+                });
+            }
+        }
+        Ok(records)
+    }
+
+    /// Every live record, decoded.
+    pub fn records(&self) -> impl Iterator<Item = Result<EmbeddingRecord>> + '_ {
+        self.handles.iter().flat_map(|handle| {
+            let segment = handle.segment();
+            handle.live().map(|position| segment.record(position))
+        })
+    }
+
+    /// Same settings without any segment.
+    pub fn detached(&self) -> Self {
+        Self::new(self.config)
+    }
+
+    /// Pick the smallest segments to merge once there are more than `max_segments`.
+    pub fn plan_merge(&self, dimension: usize) -> Option<Merge> {
+        let excess = self.handles.len().saturating_sub(self.config.max_segments.max(1));
+        if excess == 0 || self.read_only {
+            return None;
+        }
+        let mut handles = self.handles.clone();
+        handles.sort_by_key(SegmentHandle::count);
+        handles.truncate(excess + 1);
+        Some(Merge {
+            dir: self.dir.clone(),
+            dimension,
+            sources: handles,
+        })
+    }
+
+    /// Swap the sources of a merge for the merged segment.
+    ///
+    /// Records deleted while the merge ran are deleted from the merged segment as well.
+    pub fn finish_merge(&mut self, merged: Merged) {
+        let mut deleted = BTreeSet::new();
+        for (source, base) in merged.sources.iter().zip(&merged.bases) {
+            let Some(current) = self.handles.iter().find(|h| h.name == source.name) else {
+                // The table was replaced meanwhile, the merged file is collected later.
+                return;
+            };
+            for &position in current.deleted.difference(&source.deleted) {
+                let skipped = source.deleted.range(..position).count() as u32;
+                deleted.insert(base + position - skipped);
+            }
+        }
+        self.handles
+            .retain(|handle| !merged.sources.iter().any(|s| s.name == handle.name));
+        self.handles.push(SegmentHandle {
+            deleted,
+            ..merged.handle
+        });
+    }
+}
+
+/// Segments picked by `Segments::plan_merge`, along with the records deleted when picked.
+pub struct Merge {
+    dir: PathBuf,
+    dimension: usize,
+    sources: Vec<SegmentHandle>,
+}
+
+/// A merged segment waiting for `Segments::finish_merge`.
+pub struct Merged {
+    sources: Vec<SegmentHandle>,
+    /// Position in the merged segment of the first live record of each source.
+    bases: Vec<u32>,
+    handle: SegmentHandle,
+}
+
+impl Merge {
+    /// Write the live records of the sources into a new segment, copying their payloads as is.
+    /// The vectors of the sources are checked first.
+    pub fn run(self) -> Result<Merged> {
+        let name = segment_name();
+        let path = self.dir.join(&name);
+        let mut bases = Vec::with_capacity(self.sources.len());
+        {
+            let mut entries = Vec::new();
+            for source in &self.sources {
+                bases.push(entries.len() as u32);
+                let segment = source.segment();
+                segment.check_vectors(&self.dir.join(&source.name))?;
+                for position in source.live() {
+                    entries.push(Entry {
+                        id: segment.id(position)?,
+                        vector: segment.vector(position),
+                        expires_at: segment.expires_at(position),
+                        payload: Cow::Borrowed(segment.payload_bytes(position)),
+                    });
+                }
+            }
+            Segment::write(&path, self.dimension, &entries)?;
+        }
+        let handle = SegmentHandle {
+            name,
+            deleted: BTreeSet::new(),
+            segment: Some(Arc::new(Segment::open(&path, self.dimension)?)),
+        };
+        Ok(Merged {
+            sources: self.sources,
+            bases,
+            handle,
+        })
+    }
+}
+
+/// The files of `dir` that are not among the segments to `keep`, left over by merges and
+/// interrupted flushes.
+pub fn unused(dir: &Path, keep: &HashSet<&str>) -> Result<Vec<PathBuf>> {
+    let mut unused = Vec::new();
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(unused),
+        Err(e) => return Err(e.into()),
+    };
+    for entry in entries {
+        let entry = entry?;
+        let name = entry.file_name();
+        let Some(name) = name.to_str() else {
+            continue;
+        };
+        if !keep.contains(name) && (name.ends_with(SUFFIX) || name.ends_with(".tmp")) {
+            unused.push(entry.path());
+        }
+    }
+    Ok(unused)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::MetadataValue;
+
+    fn record(id: &str, embedding: Vec<f32>) -> EmbeddingRecord {
+        EmbeddingRecord {
+            id: id.to_string(),
+            embedding,
+            metadata: HashMap::from([("n".to_string(), MetadataValue::Int(id.len() as i64))]),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_segment_lookup_search_and_merge() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut segments = Segments::new(SegmentConfig {
+            memtable_size: 2,
+            max_segments: 1,
+        });
+        segments.open(dir.path(), 2).unwrap();
+        segments
+            .flush(2, &[record("b", vec![0., 1.]), record("a", vec![1., 0.])])
+            .unwrap();
+        let expiring = EmbeddingRecord {
+            expires_at: Some(200),
+            ..record("e", vec![2., 1.])
+        };
+        segments
+            .flush(2, &[record("cc", vec![1., 1.]), record("d", vec![-1., 0.]), expiring])
+            .unwrap();
+        let now = 100;
+        assert_eq!(5, segments.count(now));
+        assert_eq!(4, segments.count(200));
+        assert_eq!(vec![1., 1.], segments.get("cc", now).unwrap().unwrap().embedding);
+        assert_eq!(Some(200), segments.get("e", now).unwrap().unwrap().expires_at);
+        assert!(segments.get("e", 200).unwrap().is_none());
+        assert!(segments.get("f", now).unwrap().is_none());
+        assert_eq!("cc", segments.handles[1].segment().id(0).unwrap());
+
+        let search = |segments: &Segments, top_k, options: &SearchOptions, now| {
+            let mut hits =
+                segments.search(&[1., 0.], top_k, Distance::Cosine, options, now).unwrap();
+            hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+            hits.into_iter().map(|h| h.record.id).collect::<Vec<_>>()
+        };
+        assert_eq!(vec!["a", "e"], search(&segments, 1, &SearchOptions::default(), now));
+        assert_eq!(vec!["a", "cc"], search(&segments, 1, &SearchOptions::default(), 300));
+        assert!(!segments.has_expired(now));
+        assert!(segments.has_expired(300));
+        assert_eq!(1, segments.expire(300));
+        assert!(!segments.has_expired(300));
+        let filter = Filter::Eq {
+            field: "n".to_string(),
+            value: MetadataValue::Int(2),
+        };
+        let options = SearchOptions {
+            filter: Some(&filter),
+            ..Default::default()
+        };
+        assert_eq!(vec!["cc"], search(&segments, 5, &options, now));
+
+        let mut ids = segments.list(Some("a"), 10, false, now).unwrap();
+        ids.sort_by(|a, b| a.id.cmp(&b.id));
+        assert_eq!(vec!["b", "cc", "d"], ids.iter().map(|r| r.id.as_str()).collect::<Vec<_>>());
+        assert!(ids[0].embedding.is_empty());
+
+        // A delete during the merge carries over to the merged segment.
+        assert!(segments.remove("b").unwrap());
+        assert!(!segments.remove("b").unwrap());
+        let merge = segments.plan_merge(2).unwrap();
+        let merged = merge.run().unwrap();
+        assert!(segments.remove("d").unwrap());
+        segments.finish_merge(merged);
+        assert_eq!(1, segments.len());
+        assert_eq!(2, segments.count(now));
+        assert!(segments.contains("a", now).unwrap() && segments.contains("cc", now).unwrap());
+        assert!(!segments.contains("b", now).unwrap() && !segments.contains("d", now).unwrap());
+        assert!(segments.plan_merge(2).is_none());
+
+        let keep = segments.names().collect::<HashSet<_>>();
+        let unused = unused(dir.path(), &keep).unwrap();
+        assert_eq!(2, unused.len());
+        for path in unused {
+            fs::remove_file(path).unwrap();
+        }
+        let mut reopened = segments.clone();
+        reopened.open(dir.path(), 2).unwrap();
+        assert_eq!(vec![1., 0.], reopened.get("a", now).unwrap().unwrap().embedding);
+        assert!(Segment::open(&dir.path().join(&segments.handles[0].name), 3).is_err());
+    }
+
+    #[test]
+    fn test_segment_checksums() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut segments = Segments::new(SegmentConfig {
+            memtable_size: 2,
+            max_segments: 1,
+        });
+        segments.open(dir.path(), 2).unwrap();
+        for records in [[record("a", vec![1., 0.])], [record("b", vec![0., 1.])]] {
+            segments.flush(2, &records).unwrap();
+        }
+        let path = |i: usize| dir.path().join(&segments.handles[i].name);
+        let flip = |path: &Path, at: usize| {
+            let mut bytes = fs::read(path).unwrap();
+            bytes[at] ^= 1;
+            fs::write(path, bytes).unwrap();
+        };
+
+        // A corrupted payload fails the open.
+        let size = fs::metadata(path(0)).unwrap().len() as usize;
+        flip(&path(0), size - 1);
+        assert!(Segment::open(&path(0), 2).is_err());
+        flip(&path(0), size - 1);
+        assert!(Segment::open(&path(0), 2).is_ok());
+
+        // A corrupted vector is only read on demand, the merge checks it.
+        flip(&path(1), HEADER_SIZE);
+        let mut reopened = segments.clone();
+        reopened.open(dir.path(), 2).unwrap();
+        assert!(reopened.plan_merge(2).unwrap().run().is_err());
+    }
+}
@@ -1,32 +1,128 @@
-use crate::database::Database;
-use crate::routes::{DbHandler, TestHandler, SystemHandler};
+use crate::auth::{self, Auth};
+use crate::binary;
+use crate::config::Config;
+use crate::database::{self, Database};
+use crate::embedding::Embedder;
+use crate::routes::helper::{respond, ApiError};
+use crate::routes::{DbHandler, MetricsHandler, TestHandler, SystemHandler};
 use crate::shutdown::Shutdown;
+use crate::replication::Replication;
+use crate::snapshot::Snapshots;
+use crate::telemetry;
 use anyhow::Result;
-use axum::Server;
-use std::net::SocketAddr;
-use tracing::info;
+use axum::http::StatusCode;
+use axum::{middleware, Extension, Server};
+use std::net::TcpListener;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{error, info};
 
-pub async fn start() -> Result<()> {
+/// How often the entries of a deferred sync write-ahead log are fsynced.
+const DEFERRED_SYNC_INTERVAL: Duration = Duration::from_millis(10);
+/// How often the write-ahead log is checked for its `compact_threshold`.
+const COMPACT_INTERVAL: Duration = Duration::from_secs(1);
+/// How often tables with too many segments get them merged.
+const MERGE_INTERVAL: Duration = Duration::from_secs(10);
+/// How often the expired records are removed, they are hidden from reads as soon as they expire.
+const EXPIRE_INTERVAL: Duration = Duration::from_secs(10);
+/// How often quantized tables that reached their `train_size` get their quantizer trained.
+const TRAIN_INTERVAL: Duration = Duration::from_secs(1);
+
+pub async fn start(config: Config) -> Result<()> {
+    let listener = TcpListener::bind(config.bind)?;
+    serve(config, listener).await
+}
+
+/// Serve on a bound listener, `config.bind` is ignored. Tests bind port 0 and read the address.
+pub async fn serve(config: Config, listener: TcpListener) -> Result<()> {
     let test_hander = TestHandler::handler();
     let db_hander = DbHandler::handler();
     let system_handler = SystemHandler::handler();
 
-    let db = Database::load_from_file()?;
+    // Held until the server stops, so that no other process writes the same files.
+    let _lock = database::lock_dir(&config.storage_dir)?;
+    let db = Database::load_from_dir(&config.storage_dir, config.wal)?;
     let shutdown = Shutdown::new()?;
+    let auth = Auth::load(&config.auth)?;
+    let snapshots = Snapshots::new(&config.storage_dir, &config.snapshots);
+    let embedder = config.embedding.as_ref().map(Embedder::new).unwrap_or_default();
 
-    // Build our application with a single route.
-    let app = axum::Router::new()
+    let deferred_sync = db.deferred_sync_handle().is_some();
+    let db = db.extension();
+    let background = |period, name, task: fn(&Database) -> Result<()>| {
+        let db = db.0.clone();
+        spawn_periodic(period, name, &shutdown, move || task(&db));
+    };
+    if deferred_sync {
+        background(DEFERRED_SYNC_INTERVAL, "sync the write-ahead log", |db| {
+            // Taken every time, checkpoints swap the log file.
+            if let Some(file) = db.deferred_sync_handle() {
+                file.sync_data()?;
+            }
+            Ok(())
+        });
+    }
+    background(COMPACT_INTERVAL, "compact the write-ahead log", |db| {
+        db.compact()?;
+        Ok(())
+    });
+    background(MERGE_INTERVAL, "merge segments", |db| {
+        db.merge_segments()?;
+        Ok(())
+    });
+    background(EXPIRE_INTERVAL, "remove expired records", |db| {
+        db.expire_records()?;
+        Ok(())
+    });
+    background(TRAIN_INTERVAL, "train quantizers", |db| {
+        db.train_quantizers();
+        Ok(())
+    });
+    if config.snapshot_interval_secs > 0 {
+        let period = Duration::from_secs(config.snapshot_interval_secs);
+        background(period, "write the periodic snapshot", |db| {
+            if db.is_dirty() {
+                db.checkpoint()?;
+            }
+            Ok(())
+        });
+    }
+    let replication = match config.replication {
+        Some(settings) => Replication::follow(settings, db.0.clone()),
+        None => Replication::default(),
+    };
+    if let Some(addr) = config.binary_bind {
+        let signal = shutdown.clone().wait();
+        binary::spawn(addr, db.0.clone(), auth.clone(), embedder.clone(), signal).await?;
+    }
+
+    // Every route needs an API key, the handlers check its scope.
+    let protected = axum::Router::new()
         .nest("/test", test_hander)
         .nest("/db", db_hander)
-        .nest("/system", system_handler);
+        .nest("/system", system_handler)
+        .merge(MetricsHandler::handler())
+        .route_layer(middleware::from_fn(auth::authenticate));
 
-    let app = app.layer(db.extension()).layer(shutdown.extension());
+    // Build our application with a single route.
+    let app = axum::Router::new()
+        .merge(protected)
+        .fallback(|| async {
+            let e = ApiError::new(StatusCode::NOT_FOUND, "route_not_found", "No such route");
+            respond::<(), _>(Err(e))
+        });
 
-    // Run our application as a hyper server on http://localhost:3000.
-    info!("listening at port 3000");
-    let addr = SocketAddr::from(([0, 0, 0, 0], 3000));
+    let app = app
+        .layer(db)
+        .layer(shutdown.extension())
+        .layer(auth.extension())
+        .layer(Extension(snapshots))
+        .layer(embedder.extension())
+        .layer(replication.extension())
+        .layer(middleware::from_fn(telemetry::trace_request));
 
-    Server::bind(&addr)
+    info!("listening at {}", listener.local_addr()?);
+    Server::from_tcp(listener)?
         .serve(app.into_make_service())
         .with_graceful_shutdown(shutdown.wait())
         .await?;
@@ -34,3 +130,30 @@ pub async fn start() -> Result<()> {
     //     .await?;
     Ok(())
 }
+
+/// Run `task` on the blocking pool every `period` until the server shuts down, the first run
+/// one period after the start. A failed run is logged as a failure to `name` and retried on
+/// the next one.
+fn spawn_periodic<F>(period: Duration, name: &'static str, shutdown: &Shutdown, task: F)
+where
+    F: Fn() -> Result<()> + Send + Sync + 'static,
+{
+    let task = Arc::new(task);
+    let mut stop = shutdown.sender.subscribe();
+    tokio::spawn(async move {
+        let start = tokio::time::Instant::now() + period;
+        let mut interval = tokio::time::interval_at(start, period);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = stop.recv() => break,
+            }
+            let task = task.clone();
+            match tokio::task::spawn_blocking(move || task()).await {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => error!("Failed to {}: {:?}", name, e),
+                Err(e) => error!("Failed to {}: {}", name, e),
+            }
+        }
+    });
+}
@@ -1,49 +1,273 @@
 use std::cmp::Ordering;
-use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
 
-#[derive(Debug, Copy, Clone, Serialize, Deserialize, PartialEq, Eq)]
-#[serde(rename_all = "lowercase")]
-pub enum Distance {
-    Euclidean,
-    Cosine,
-    DotProduct,
-}
+pub use tinyvector_types::similarity::Distance;
 
 pub fn get_distance_fn(distance: Distance) -> fn(&[f32], &[f32]) -> f32 {
     match distance {
         Distance::Euclidean => euclidean,
         Distance::Cosine => cosine,
-        Distance::DotProduct => dot_product,
+        Distance::DotProduct | Distance::InnerProduct => dot_product,
+        Distance::Manhattan => manhattan,
+        Distance::Hamming => hamming,
+        Distance::Jaccard => jaccard,
+    }
+}
+
+pub fn euclidean(a: &[f32], b: &[f32]) -> f32 {
+    squared_euclidean(a, b).sqrt()
+}
+
+pub fn dot_product(a: &[f32], b: &[f32]) -> f32 {
+    (kernels().dot)(a, b)
+}
+
+pub fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    cosine_from_dot(dot_product(a, b), norm(a), norm(b))
+}
+
+pub fn manhattan(a: &[f32], b: &[f32]) -> f32 {
+    portable::manhattan(a, b)
+}
+
+pub fn hamming(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).filter(|(&x, &y)| (x != 0.) != (y != 0.)).count() as f32
+}
+
+/// Two vectors without any set bit are identical.
+pub fn jaccard(a: &[f32], b: &[f32]) -> f32 {
+    let (mut intersection, mut union) = (0, 0);
+    for (&x, &y) in a.iter().zip(b) {
+        let (x, y) = (x != 0., y != 0.);
+        intersection += (x && y) as usize;
+        union += (x || y) as usize;
+    }
+    if union == 0 {
+        0.
+    } else {
+        1. - intersection as f32 / union as f32
     }
 }
 
-fn euclidean(a: &[f32], b: &[f32]) -> f32 {
-    a.iter().zip(b).fold(0.0, |acc, (x, y)| {
-        let diff = x - y;
-        acc + diff * diff
-    }).sqrt()
+pub fn squared_euclidean(a: &[f32], b: &[f32]) -> f32 {
+    (kernels().squared_euclidean)(a, b)
 }
 
-fn dot_product(a: &[f32], b: &[f32]) -> f32 {
-    a.iter().zip(b).fold(0.0, |acc, (x, y)| acc + x * y)
+pub fn norm(vec: &[f32]) -> f32 {
+    dot_product(vec, vec).sqrt()
 }
 
-fn cosine(a: &[f32], b: &[f32]) -> f32 {
-    let a = normalize(a);
-    let b = normalize(b);
-    a.iter().zip(b).fold(0.0, |acc, (x, y)| acc + x * y)
+/// Cosine similarity out of the dot product and the norms, e.g. norms cached at insert time.
+///
+/// A vector with a norm of about zero is taken as is, like `normalize` does.
+pub fn cosine_from_dot(dot: f32, norm_a: f32, norm_b: f32) -> f32 {
+    let scale = |norm: f32| if norm > f32::EPSILON { norm } else { 1. };
+    dot / (scale(norm_a) * scale(norm_b))
 }
 
 pub fn normalize(vec: &[f32]) -> Vec<f32> {
     let magnitude = (vec.iter().fold(0.0, |acc, &val| val.mul_add(val, acc))).sqrt();
 
-    if magnitude > std::f32::EPSILON {
+    if magnitude > f32::EPSILON {
         vec.iter().map(|&val| val / magnitude).collect()
     } else {
         vec.to_vec()
     }
 }
 
+/// Distance kernels picked once for the CPU we run on.
+struct Kernels {
+    dot: fn(&[f32], &[f32]) -> f32,
+    squared_euclidean: fn(&[f32], &[f32]) -> f32,
+}
+
+static KERNELS: OnceLock<Kernels> = OnceLock::new();
+
+fn kernels() -> &'static Kernels {
+    KERNELS.get_or_init(|| {
+        #[cfg(target_arch = "x86_64")]
+        if is_x86_feature_detected!("avx2") && is_x86_feature_detected!("fma") {
+            return Kernels {
+                dot: avx2::dot,
+                squared_euclidean: avx2::squared_euclidean,
+            };
+        }
+        #[cfg(target_arch = "aarch64")]
+        if std::arch::is_aarch64_feature_detected!("neon") {
+            return Kernels {
+                dot: neon::dot,
+                squared_euclidean: neon::squared_euclidean,
+            };
+        }
+        Kernels {
+            dot: portable::dot,
+            squared_euclidean: portable::squared_euclidean,
+        }
+    })
+}
+
+/// Plain Rust with independent lanes, so the compiler can vectorize it for the baseline target.
+mod portable {
+    const LANES: usize = 8;
+
+    pub fn dot(a: &[f32], b: &[f32]) -> f32 {
+        fold(a, b, |x, y| x * y)
+    }
+
+    pub fn squared_euclidean(a: &[f32], b: &[f32]) -> f32 {
+        fold(a, b, |x, y| (x - y) * (x - y))
+    }
+
+    /// No hand-written kernel, the lanes vectorize well enough.
+    pub fn manhattan(a: &[f32], b: &[f32]) -> f32 {
+        fold(a, b, |x, y| (x - y).abs())
+    }
+
+    #[inline(always)]
+    fn fold(a: &[f32], b: &[f32], f: impl Fn(f32, f32) -> f32) -> f32 {
+        let n = a.len().min(b.len());
+        let (a, b) = (&a[..n], &b[..n]);
+        let mut acc = [0.; LANES];
+        let a_chunks = a.chunks_exact(LANES);
+        let b_chunks = b.chunks_exact(LANES);
+        let tail = a_chunks
+            .remainder()
+            .iter()
+            .zip(b_chunks.remainder())
+            .map(|(&x, &y)| f(x, y))
+            .sum::<f32>();
+        for (x, y) in a_chunks.zip(b_chunks) {
+            for i in 0..LANES {
+                acc[i] += f(x[i], y[i]);
+            }
+        }
+        acc.iter().sum::<f32>() + tail
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+mod avx2 {
+    use std::arch::x86_64::*;
+
+    /// Only called once `kernels` detected avx2 and fma.
+    pub fn dot(a: &[f32], b: &[f32]) -> f32 {
+        unsafe { dot_avx2(a, b) }
+    }
+
+    pub fn squared_euclidean(a: &[f32], b: &[f32]) -> f32 {
+        unsafe { squared_euclidean_avx2(a, b) }
+    }
+
+    #[target_feature(enable = "avx2,fma")]
+    unsafe fn dot_avx2(a: &[f32], b: &[f32]) -> f32 {
+        let n = a.len().min(b.len());
+        let (pa, pb) = (a.as_ptr(), b.as_ptr());
+        // Two accumulators hide the latency of the fused multiply-add.
+        let mut acc0 = _mm256_setzero_ps();
+        let mut acc1 = _mm256_setzero_ps();
+        let mut i = 0;
+        while i + 16 <= n {
+            acc0 = _mm256_fmadd_ps(_mm256_loadu_ps(pa.add(i)), _mm256_loadu_ps(pb.add(i)), acc0);
+            acc1 = _mm256_fmadd_ps(
+                _mm256_loadu_ps(pa.add(i + 8)),
+                _mm256_loadu_ps(pb.add(i + 8)),
+                acc1,
+            );
+            i += 16;
+        }
+        let mut sum = horizontal_sum(_mm256_add_ps(acc0, acc1));
+        for j in i..n {
+            sum += a[j] * b[j];
+        }
+        sum
+    }
+
+    #[target_feature(enable = "avx2,fma")]
+    unsafe fn squared_euclidean_avx2(a: &[f32], b: &[f32]) -> f32 {
+        let n = a.len().min(b.len());
+        let (pa, pb) = (a.as_ptr(), b.as_ptr());
+        let mut acc0 = _mm256_setzero_ps();
+        let mut acc1 = _mm256_setzero_ps();
+        let mut i = 0;
+        while i + 16 <= n {
+            let d0 = _mm256_sub_ps(_mm256_loadu_ps(pa.add(i)), _mm256_loadu_ps(pb.add(i)));
+            let d1 = _mm256_sub_ps(_mm256_loadu_ps(pa.add(i + 8)), _mm256_loadu_ps(pb.add(i + 8)));
+            acc0 = _mm256_fmadd_ps(d0, d0, acc0);
+            acc1 = _mm256_fmadd_ps(d1, d1, acc1);
+            i += 16;
+        }
+        let mut sum = horizontal_sum(_mm256_add_ps(acc0, acc1));
+        for j in i..n {
+            let diff = a[j] - b[j];
+            sum += diff * diff;
+        }
+        sum
+    }
+
+    #[target_feature(enable = "avx2,fma")]
+    unsafe fn horizontal_sum(v: __m256) -> f32 {
+        let sum = _mm_add_ps(_mm256_castps256_ps128(v), _mm256_extractf128_ps(v, 1));
+        let sum = _mm_add_ps(sum, _mm_movehl_ps(sum, sum));
+        let sum = _mm_add_ss(sum, _mm_shuffle_ps(sum, sum, 1));
+        _mm_cvtss_f32(sum)
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+mod neon {
+    use std::arch::aarch64::*;
+
+    /// Only called once `kernels` detected neon.
+    pub fn dot(a: &[f32], b: &[f32]) -> f32 {
+        unsafe { dot_neon(a, b) }
+    }
+
+    pub fn squared_euclidean(a: &[f32], b: &[f32]) -> f32 {
+        unsafe { squared_euclidean_neon(a, b) }
+    }
+
+    #[target_feature(enable = "neon")]
+    unsafe fn dot_neon(a: &[f32], b: &[f32]) -> f32 {
+        let n = a.len().min(b.len());
+        let (pa, pb) = (a.as_ptr(), b.as_ptr());
+        let mut acc0 = vdupq_n_f32(0.);
+        let mut acc1 = vdupq_n_f32(0.);
+        let mut i = 0;
+        while i + 8 <= n {
+            acc0 = vfmaq_f32(acc0, vld1q_f32(pa.add(i)), vld1q_f32(pb.add(i)));
+            acc1 = vfmaq_f32(acc1, vld1q_f32(pa.add(i + 4)), vld1q_f32(pb.add(i + 4)));
+            i += 8;
+        }
+        let mut sum = vaddvq_f32(vaddq_f32(acc0, acc1));
+        for j in i..n {
+            sum += a[j] * b[j];
+        }
+        sum
+    }
+
+    #[target_feature(enable = "neon")]
+    unsafe fn squared_euclidean_neon(a: &[f32], b: &[f32]) -> f32 {
+        let n = a.len().min(b.len());
+        let (pa, pb) = (a.as_ptr(), b.as_ptr());
+        let mut acc0 = vdupq_n_f32(0.);
+        let mut acc1 = vdupq_n_f32(0.);
+        let mut i = 0;
+        while i + 8 <= n {
+            let d0 = vsubq_f32(vld1q_f32(pa.add(i)), vld1q_f32(pb.add(i)));
+            let d1 = vsubq_f32(vld1q_f32(pa.add(i + 4)), vld1q_f32(pb.add(i + 4)));
+            acc0 = vfmaq_f32(acc0, d0, d0);
+            acc1 = vfmaq_f32(acc1, d1, d1);
+            i += 8;
+        }
+        let mut sum = vaddvq_f32(vaddq_f32(acc0, acc1));
+        for j in i..n {
+            let diff = a[j] - b[j];
+            sum += diff * diff;
+        }
+        sum
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ScoreIndex {
     pub score: f32,
@@ -71,3 +295,39 @@ impl Ord for ScoreIndex {
         self.partial_cmp(other).unwrap_or(Ordering::Equal)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kernels_match_portable() {
+        for len in [0, 1, 7, 8, 15, 16, 17, 33, 1536] {
+            let a = (0..len).map(|i| (i as f32 * 0.37).sin()).collect::<Vec<_>>();
+            let b = (0..len).map(|i| (i as f32 * 0.11).cos()).collect::<Vec<_>>();
+            let close = |x: f32, y: f32| (x - y).abs() <= 1e-4 * (1. + x.abs());
+            assert!(close(portable::dot(&a, &b), dot_product(&a, &b)), "{len}");
+            assert!(close(portable::squared_euclidean(&a, &b), squared_euclidean(&a, &b)), "{len}");
+
+            let expected = normalize(&a).iter().zip(normalize(&b)).map(|(x, y)| x * y).sum::<f32>();
+            assert!(close(expected, cosine(&a, &b)), "{len}");
+        }
+    }
+
+    #[test]
+    fn test_binary_and_manhattan_distances() {
+        let a = [1., 0., 1., 1., 0.];
+        let b = [1., 1., 0., 1., 0.];
+        assert_eq!(6., manhattan(&[1., -2., 3.], &[0., 1., 1.]));
+        assert_eq!(2., hamming(&a, &b));
+        assert_eq!(0.5, jaccard(&a, &b));
+        assert_eq!(0., jaccard(&[0., 0.], &[0., 0.]));
+
+        assert!(Distance::InnerProduct.higher_is_better());
+        for distance in [Distance::Manhattan, Distance::Hamming, Distance::Jaccard] {
+            assert!(!distance.higher_is_better());
+        }
+        let json = serde_json::to_string(&Distance::InnerProduct).unwrap();
+        assert_eq!(r#""innerproduct""#, json);
+    }
+}
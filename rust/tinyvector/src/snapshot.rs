@@ -0,0 +1,306 @@
+use crate::database::{DBError, Database};
+use crate::wal;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{ErrorKind, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::{info, warn};
+
+pub use tinyvector_types::system::SnapshotInfo;
+
+/// Start of every snapshot file.
+const MAGIC: [u8; 8] = *b"TVSNAP\0\0";
+/// `magic | version: u32 | crc32: u32 | len: u64`, all little endian.
+const HEADER_SIZE: usize = 24;
+/// Version written by this build, version 0 stands for the files written before the header
+/// existed.
+pub const SNAPSHOT_VERSION: u32 = 1;
+/// Suffix of the files kept by `Snapshots`.
+const SUFFIX: &str = ".tvs";
+
+/// Write `payload` with the header in front, through a temporary file so a crash never
+/// leaves a half written snapshot behind.
+pub fn write_file(path: &Path, payload: &[u8]) -> Result<()> {
+    let tmp_path = write_temp(path, payload)?;
+    install(&tmp_path, path)
+}
+
+/// Write `payload` with the header in front into a temporary file next to `path`, moved there
+/// by `install`.
+pub fn write_temp(path: &Path, payload: &[u8]) -> Result<PathBuf> {
+    let tmp_path = path.with_extension("tmp");
+    let mut file = File::create(&tmp_path)?;
+    file.write_all(&header(payload))?;
+    file.write_all(payload)?;
+    file.sync_all()?;
+    Ok(tmp_path)
+}
+
+/// Move the file written by `write_temp` to `path`.
+pub fn install(tmp_path: &Path, path: &Path) -> Result<()> {
+    fs::rename(tmp_path, path)?;
+    wal::sync_dir(path)
+}
+
+/// Header of a snapshot of the current version holding `payload`.
+pub fn header(payload: &[u8]) -> Vec<u8> {
+    let mut header = Vec::with_capacity(HEADER_SIZE);
+    header.extend_from_slice(&MAGIC);
+    header.extend_from_slice(&SNAPSHOT_VERSION.to_le_bytes());
+    header.extend_from_slice(&crc32fast::hash(payload).to_le_bytes());
+    header.extend_from_slice(&(payload.len() as u64).to_le_bytes());
+    header
+}
+
+/// Check the header of a snapshot and return its version along with the payload.
+pub fn decode(mut data: Vec<u8>) -> Result<(u32, Vec<u8>)> {
+    if !data.starts_with(&MAGIC) {
+        return Ok((0, data));
+    }
+    let (version, crc, len) = parse_header(&data)?;
+    if data.len() - HEADER_SIZE != len {
+        bail!("Snapshot holds {} bytes, {} expected", data.len() - HEADER_SIZE, len);
+    }
+    let payload = data.split_off(HEADER_SIZE);
+    if crc32fast::hash(&payload) != crc {
+        bail!("Snapshot checksum mismatch");
+    }
+    Ok((version, payload))
+}
+
+/// Check the header of a snapshot file like `decode` and return its version, leaving `file`
+/// at the start of the payload. The payload is checksummed in chunks, never held in memory.
+pub fn check_file(file: &mut File) -> Result<u32> {
+    let size = file.metadata()?.len() as usize;
+    let mut header = Vec::with_capacity(HEADER_SIZE);
+    file.seek(SeekFrom::Start(0))?;
+    Read::by_ref(file).take(HEADER_SIZE as u64).read_to_end(&mut header)?;
+    if !header.starts_with(&MAGIC) {
+        file.seek(SeekFrom::Start(0))?;
+        return Ok(0);
+    }
+    let (version, crc, len) = parse_header(&header)?;
+    if size - HEADER_SIZE != len {
+        bail!("Snapshot holds {} bytes, {} expected", size - HEADER_SIZE, len);
+    }
+    let mut hasher = crc32fast::Hasher::new();
+    let mut buffer = vec![0; 1 << 20];
+    loop {
+        match file.read(&mut buffer)? {
+            0 => break,
+            n => hasher.update(&buffer[..n]),
+        }
+    }
+    if hasher.finalize() != crc {
+        bail!("Snapshot checksum mismatch");
+    }
+    file.seek(SeekFrom::Start(HEADER_SIZE as u64))?;
+    Ok(version)
+}
+
+/// `(version, crc, len)` of a header starting with `MAGIC`.
+fn parse_header(data: &[u8]) -> Result<(u32, u32, usize)> {
+    if data.len() < HEADER_SIZE {
+        bail!("Truncated snapshot header");
+    }
+    let version = u32::from_le_bytes(data[8..12].try_into().unwrap());
+    let crc = u32::from_le_bytes(data[12..16].try_into().unwrap());
+    let len = u64::from_le_bytes(data[16..24].try_into().unwrap()) as usize;
+    if version > SNAPSHOT_VERSION {
+        bail!("Snapshot version {} is newer than {}", version, SNAPSHOT_VERSION);
+    }
+    Ok((version, crc, len))
+}
+
+/// The `[snapshots]` section of the server config.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "snake_case")]
+pub struct SnapshotOptions {
+    /// Directory of the snapshots, `<storage_dir>/snapshots` when absent.
+    pub dir: Option<PathBuf>,
+    /// Snapshots kept, older ones are removed after a new one is taken. 0 keeps every one.
+    pub retain: usize,
+}
+
+impl Default for SnapshotOptions {
+    fn default() -> Self {
+        Self {
+            dir: None,
+            retain: 7,
+        }
+    }
+}
+
+/// File name of the `SnapshotInfo` taken at `created_at` and `seq`.
+fn file_name(created_at: u64, seq: u64) -> String {
+    format!("snapshot-{:010}-{:012}{}", created_at, seq, SUFFIX)
+}
+
+/// Parse the created time and sequence number back out of a file name.
+fn parse_file_name(name: &str) -> Option<(u64, u64)> {
+    let stem = name.strip_prefix("snapshot-")?.strip_suffix(SUFFIX)?;
+    let (created_at, seq) = stem.split_once('-')?;
+    Some((created_at.parse().ok()?, seq.parse().ok()?))
+}
+
+/// Snapshots taken on demand, kept in a directory of their own next to the storage.
+#[derive(Debug, Clone)]
+pub struct Snapshots {
+    dir: PathBuf,
+    retain: usize,
+}
+
+impl Snapshots {
+    pub fn new(storage_dir: &Path, options: &SnapshotOptions) -> Self {
+        Self {
+            dir: options
+                .dir
+                .clone()
+                .unwrap_or_else(|| storage_dir.join("snapshots")),
+            retain: options.retain,
+        }
+    }
+
+    /// Take a snapshot of `db` and apply the retention policy.
+    pub fn create(&self, db: &Database) -> Result<SnapshotInfo> {
+        fs::create_dir_all(&self.dir)?;
+        let created_at = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let (seq, payload) = db.snapshot()?;
+        let name = file_name(created_at, seq);
+        write_file(&self.dir.join(&name), &payload)?;
+        info!("Wrote snapshot {} at sequence {}", name, seq);
+
+        if self.retain > 0 {
+            for old in self.list()?.into_iter().skip(self.retain) {
+                if let Err(e) = fs::remove_file(self.dir.join(&old.name)) {
+                    warn!("Failed to remove old snapshot {}: {:?}", old.name, e);
+                }
+            }
+        }
+        Ok(SnapshotInfo {
+            name,
+            created_at,
+            seq,
+            size: (HEADER_SIZE + payload.len()) as u64,
+        })
+    }
+
+    /// The snapshots in the directory, newest first.
+    pub fn list(&self) -> Result<Vec<SnapshotInfo>> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let mut snapshots = Vec::new();
+        for entry in entries {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if let Some((created_at, seq)) = parse_file_name(&name) {
+                snapshots.push(SnapshotInfo {
+                    name,
+                    created_at,
+                    seq,
+                    size: entry.metadata()?.len(),
+                });
+            }
+        }
+        snapshots.sort_by(|a, b| b.name.cmp(&a.name));
+        Ok(snapshots)
+    }
+
+    /// Path of the snapshot `name`, only names of the listing are accepted.
+    pub fn path(&self, name: &str) -> Result<PathBuf> {
+        let path = self.dir.join(name);
+        if parse_file_name(name).is_none() || name.contains(['/', '\\']) || !path.is_file() {
+            return Err(DBError::SnapshotDoesNotExist.into());
+        }
+        Ok(path)
+    }
+
+    /// Replace the content of `db` with the snapshot `name`.
+    pub fn restore(&self, db: &Database, name: &str) -> Result<u64> {
+        let path = self.path(name)?;
+        let data = fs::read(&path).with_context(|| format!("Failed to read {:?}", path))?;
+        let seq = db.restore(data)?;
+        info!("Restored snapshot {}", name);
+        Ok(seq)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_file_header() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("snapshot");
+        write_file(&path, b"payload").unwrap();
+        let data = fs::read(&path).unwrap();
+        assert_eq!((SNAPSHOT_VERSION, b"payload".to_vec()), decode(data.clone()).unwrap());
+
+        // Files without the header are read as version 0.
+        assert_eq!((0, b"legacy".to_vec()), decode(b"legacy".to_vec()).unwrap());
+
+        let mut corrupted = data.clone();
+        *corrupted.last_mut().unwrap() ^= 1;
+        assert!(decode(corrupted).is_err());
+        assert!(decode(data[..data.len() - 1].to_vec()).is_err());
+        let mut newer = data.clone();
+        newer[8] = SNAPSHOT_VERSION as u8 + 1;
+        assert!(decode(newer).is_err());
+
+        // Files are checked the same without being read in full.
+        let mut file = File::open(&path).unwrap();
+        assert_eq!(SNAPSHOT_VERSION, check_file(&mut file).unwrap());
+        let mut payload = String::new();
+        file.read_to_string(&mut payload).unwrap();
+        assert_eq!("payload", payload);
+        let mut corrupted = data;
+        *corrupted.last_mut().unwrap() ^= 1;
+        fs::write(&path, corrupted).unwrap();
+        assert!(check_file(&mut File::open(&path).unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_snapshot_retention_and_restore() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::load_from_dir(&dir.path().join("storage"), Default::default()).unwrap();
+        let options = SnapshotOptions {
+            dir: None,
+            retain: 2,
+        };
+        let snapshots = Snapshots::new(&dir.path().join("storage"), &options);
+
+        db.create_table("table".to_string(), 2).unwrap();
+        let first = snapshots.create(&db).unwrap();
+        assert_eq!(1, first.seq);
+        db.create_table("other".to_string(), 2).unwrap();
+        snapshots.create(&db).unwrap();
+        db.drop_table("table").unwrap();
+        let last = snapshots.create(&db).unwrap();
+        assert_eq!(3, last.seq);
+
+        // The oldest one went away.
+        let names = snapshots.list().unwrap();
+        assert_eq!(2, names.len());
+        assert_eq!(last, names[0]);
+        assert!(!names.contains(&first));
+
+        assert!(snapshots.path("../storage/db").is_err());
+        assert!(snapshots.restore(&db, &first.name).is_err());
+
+        let restored = &names[1];
+        assert_eq!(4, snapshots.restore(&db, &restored.name).unwrap());
+        assert_eq!(vec!["other", "table"], db.list_tables());
+
+        // The restored state survives a restart.
+        drop(db);
+        let db = Database::load_from_dir(&dir.path().join("storage"), Default::default()).unwrap();
+        assert_eq!(4, db.seq());
+        assert_eq!(vec!["other", "table"], db.list_tables());
+    }
+}
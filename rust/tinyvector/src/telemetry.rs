@@ -0,0 +1,244 @@
+use crate::database::Database;
+use axum::http::{HeaderValue, Request};
+use axum::middleware::Next;
+use axum::response::Response;
+use prometheus::{
+    exponential_buckets, Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGauge,
+    IntGaugeVec, Opts, Registry, TextEncoder,
+};
+use parking_lot::Mutex;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::LazyLock;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use tracing::{info_span, Instrument};
+
+/// Header carrying the request id, taken from the request when the client sets one.
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Tables getting their own label, the metrics of the others are summed under `OTHER_TABLES`.
+pub const MAX_TABLE_LABELS: usize = 100;
+/// Label of the tables past the first `MAX_TABLE_LABELS`.
+pub const OTHER_TABLES: &str = "_other";
+
+/// Metrics of the process, rendered by `/metrics`.
+pub static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::new);
+
+pub struct Metrics {
+    registry: Registry,
+    /// Records per table, refreshed on every scrape.
+    pub records: IntGaugeVec,
+    /// Tables labeled so far, dropped ones keep their label until the restart.
+    table_labels: Mutex<HashSet<String>>,
+    /// Query latency by table and kind, `vector`, `fields` or `hybrid`.
+    pub query_seconds: HistogramVec,
+    /// Latency of record changes by table and operation, `insert`, `upsert` or `delete`.
+    pub write_seconds: HistogramVec,
+    /// Time spent waiting for a lock, `tables`, `table` or `log`, by mode.
+    pub lock_wait_seconds: HistogramVec,
+    /// Duration of `checkpoint`, `snapshot` and `restore`.
+    pub snapshot_seconds: HistogramVec,
+    /// Failed requests by error code, one code per `DBError` variant.
+    pub errors: IntCounterVec,
+    /// HTTP requests by method and status.
+    pub http_seconds: HistogramVec,
+    /// Mutations of the leader a follower did not apply yet.
+    pub replication_lag: IntGauge,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let registry = Registry::new_custom(Some("tinyvector".to_string()), None).unwrap();
+        // 10us up to about 40s.
+        let buckets = exponential_buckets(1e-5, 4., 12).unwrap();
+        let histogram = |name: &str, help: &str, labels: &[&str]| {
+            let opts = HistogramOpts::new(name, help).buckets(buckets.clone());
+            let histogram = HistogramVec::new(opts, labels).unwrap();
+            registry.register(Box::new(histogram.clone())).unwrap();
+            histogram
+        };
+        let query_seconds = histogram(
+            "query_duration_seconds",
+            "Query latency",
+            &["table", "kind"],
+        );
+        let write_seconds = histogram(
+            "write_duration_seconds",
+            "Latency of record changes",
+            &["table", "op"],
+        );
+        let lock_wait_seconds = histogram(
+            "lock_wait_seconds",
+            "Time spent waiting for a lock",
+            &["lock", "mode"],
+        );
+        let snapshot_seconds = histogram(
+            "snapshot_duration_seconds",
+            "Duration of checkpoints, snapshots and restores",
+            &["kind"],
+        );
+        let http_seconds = histogram(
+            "http_request_duration_seconds",
+            "HTTP request latency",
+            &["method", "status"],
+        );
+
+        let records =
+            IntGaugeVec::new(Opts::new("records", "Records per table"), &["table"]).unwrap();
+        registry.register(Box::new(records.clone())).unwrap();
+        let errors =
+            IntCounterVec::new(Opts::new("errors_total", "Failed requests"), &["code"]).unwrap();
+        registry.register(Box::new(errors.clone())).unwrap();
+        let replication_lag =
+            IntGauge::new("replication_lag", "Mutations a follower is behind").unwrap();
+        registry.register(Box::new(replication_lag.clone())).unwrap();
+
+        Self {
+            registry,
+            records,
+            table_labels: Mutex::default(),
+            query_seconds,
+            write_seconds,
+            lock_wait_seconds,
+            snapshot_seconds,
+            errors,
+            http_seconds,
+            replication_lag,
+        }
+    }
+
+    /// The label of `table`, `OTHER_TABLES` once `MAX_TABLE_LABELS` tables got one.
+    pub fn table_label<'a>(&self, table: &'a str) -> &'a str {
+        let mut labels = self.table_labels.lock();
+        if labels.contains(table) {
+            return table;
+        }
+        if labels.len() < MAX_TABLE_LABELS {
+            labels.insert(table.to_string());
+            return table;
+        }
+        OTHER_TABLES
+    }
+
+    /// The metrics in the Prometheus text format, with the record counts of `db`.
+    pub fn render(&self, db: &Database) -> String {
+        self.records.reset();
+        for (table, count) in db.record_counts() {
+            let label = self.table_label(&table);
+            self.records.with_label_values(&[label]).add(count as i64);
+        }
+        let mut buffer = Vec::new();
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buffer)
+            .expect("metrics are valid");
+        String::from_utf8(buffer).expect("metrics are utf-8")
+    }
+}
+
+/// Take a lock through `lock`, recording the wait.
+pub fn timed_lock<G>(lock: &str, mode: &str, lock_fn: impl FnOnce() -> G) -> G {
+    let instant = Instant::now();
+    let guard = lock_fn();
+    METRICS
+        .lock_wait_seconds
+        .with_label_values(&[lock, mode])
+        .observe(instant.elapsed().as_secs_f64());
+    guard
+}
+
+/// A new request id, unique within the process and unlikely to repeat across restarts.
+pub fn next_request_id() -> String {
+    static START: LazyLock<u64> = LazyLock::new(|| {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        now.as_millis() as u64
+    });
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    format!("{:x}-{:x}", *START, COUNTER.fetch_add(1, Ordering::Relaxed))
+}
+
+/// Middleware running every request in a span with its request id, which is echoed in the
+/// response along with the latency metric.
+pub async fn trace_request<B>(mut request: Request<B>, next: Next<B>) -> Response {
+    let id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .filter(|v| !v.is_empty() && v.len() <= 64)
+        .map(str::to_string)
+        .unwrap_or_else(next_request_id);
+    let method = request.method().clone();
+    let span = info_span!("request", id = %id, method = %method, path = %request.uri().path());
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        request.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+
+    let instant = Instant::now();
+    let mut response = next.run(request).instrument(span).await;
+    METRICS
+        .http_seconds
+        .with_label_values(&[method.as_str(), response.status().as_str()])
+        .observe(instant.elapsed().as_secs_f64());
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::DBError;
+    use crate::routes::helper::respond;
+    use crate::similarity::Distance;
+    use axum::body::Body;
+    use axum::routing::get;
+    use axum::{middleware, Router};
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_request_id() {
+        let app = Router::new()
+            .route("/", get(|| async { "ok" }))
+            .layer(middleware::from_fn(trace_request));
+
+        let response = app.clone().oneshot(Request::new(Body::empty())).await.unwrap();
+        let generated = response.headers()[REQUEST_ID_HEADER].to_str().unwrap().to_string();
+        assert!(!generated.is_empty());
+        let response = app.clone().oneshot(Request::new(Body::empty())).await.unwrap();
+        assert_ne!(generated, response.headers()[REQUEST_ID_HEADER].to_str().unwrap());
+
+        // The id of the client is kept.
+        let request = Request::builder()
+            .header(REQUEST_ID_HEADER, "client-1")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!("client-1", response.headers()[REQUEST_ID_HEADER]);
+    }
+
+    #[test]
+    fn test_render_metrics() {
+        let db = Database::zero();
+        db.create_table("metrics_table".to_string(), 2).unwrap();
+        db.query_record("metrics_table".to_string(), &[1., 0.], 1, Distance::Cosine)
+            .unwrap();
+        let _ = respond::<(), _>(Err(DBError::TableDoesNotExist));
+
+        let text = METRICS.render(&db);
+        assert!(text.contains("tinyvector_records{table=\"metrics_table\"} 0"));
+        let queries = "query_duration_seconds_count{kind=\"vector\",table=\"metrics_table\"} 1";
+        assert!(text.contains(queries));
+        assert!(text.contains("tinyvector_errors_total{code=\"table_not_found\"}"));
+        assert!(text.contains("tinyvector_lock_wait_seconds_bucket"));
+    }
+
+    #[test]
+    fn test_table_labels() {
+        let metrics = Metrics::new();
+        for i in 0..MAX_TABLE_LABELS {
+            assert_eq!(format!("t{i}"), metrics.table_label(&format!("t{i}")));
+        }
+        assert_eq!(OTHER_TABLES, metrics.table_label("one_too_many"));
+        assert_eq!("t0", metrics.table_label("t0"));
+    }
+}
@@ -6,18 +6,21 @@ pub fn get_dog_record() -> EmbeddingRecord {
     EmbeddingRecord {
         id: "Dog".to_string(),
         embedding: vec![-0.0009933243, -0.015114395, -0.01846033, -0.029796125, -0.017595, 0.012907809, -0.0051595178, -0.028757732, -0.0178546, -0.021561088, 0.009453707, 0.035074625, 0.0005097322, -0.0072146715, -0.004524944, 0.017494047, 0.047131527, 0.0034703256, 0.014169745, -0.010787755, -0.017883444, 0.011393485, 0.019873697, -0.011645872, -0.014761052, 0.0067026857, -0.003991325, -0.019700633, -0.0041175187, -0.0049395803, 0.01115552, -0.0060320566, -0.017825756, -0.031007584, -0.001975832, -0.012893386, -0.006100562, -0.018763194, -0.0028375546, -0.012136225, 0.010405568, 0.0060789287, 0.0145951975, 0.0017324586, -0.005667898, 0.015835501, -0.00021013497, -0.012547255, -0.024358982, -0.0053722444, 0.008393681, 0.02009003, -0.007128139, -0.015460526, 0.0038651314, -0.0046403212, 0.0048782863, 0.0032828378, 0.001305203, -0.0031584469, 0.021200534, -0.0050225076, -0.0056354483, 0.022743702, 0.0001168418, -0.002796091, 0.0001259683, 0.0060212403, -0.011083408, 0.011379062, 0.025455063, -0.004207657, 0.0023760463, -0.0013232307, 0.00016946005, -0.012979919, -0.030546077, 0.022484103, 0.00039187638, -0.0003535676, 0.029940346, -0.03449774, -0.0045682103, 0.0025887727, 0.032449797, -0.0044672554, -0.016628718, -0.0000613504, -0.007946595, 0.0033946093, 0.011371851, 0.024935866, -0.011004087, 0.021719731, -0.035276536, 0.0073985537, 0.011797304, 0.026132904, -0.013780347, -0.036487993, 0.004038197, 0.0080331275, -0.040756945, -0.012467933, -0.011191575, 0.0025148592, 0.016542185, 0.006994734, 0.014126479, -0.014674519, -0.011912681, 0.039949305, 0.026060794, -0.050217863, -0.01569128, 0.010996875, -0.0011754038, -0.009648407, -0.00090859435, -0.0028772154, 0.01886415, 0.029334618, 0.046900775, 0.00027965417, 0.0066161533, 0.010636322, -0.021647621, 0.00042365014, 0.0031386164, 0.014840374, 0.03227673, 0.026551146, 0.0025274786, 0.008710968, -0.015460526, 0.027589539, -0.0089922, 0.010924765, 0.0049756356, -0.036170706, -0.0024121017, 0.01460962, -0.0377283, -0.009980115, 0.0013863274, 0.035363067, 0.00557776, 0.006511593, 0.020984203, 0.0070740557, 0.0015846317, 0.0040670414, 0.013845247, -0.025209887, 0.0004696207, 0.02496471, -0.0025256758, 0.027387628, -0.007650941, 0.0066882637, 0.011133886, 0.021243801, 0.008610013, -0.0017243462, -0.0043915394, 0.040756945, 0.015705703, 0.0019181436, -0.00460066, -0.010254136, -0.011068987, 0.02632039, -0.007319232, 0.015821079, -0.021344755, 0.020811137, -0.009922427, 0.0012439089, -0.037526388, -0.010751699, -0.0038434982, 0.0037822041, 0.03002688, 0.021633576, 0.0061582504, 0.0047593038, 0.0074706646, -0.029680748, 0.013030397, -0.005011691, -0.00037069386, 0.0103190355, 0.019267969, -0.020479428, -0.6645719, -0.0029763675, -0.016599875, 0.0068685403, 0.0178546, 0.02096978, 0.008934511, 0.014025523, -0.010708434, -0.0015377598, -0.001810879, 0.0074634533, -0.010088282, -0.0014476215, -0.0128140645, 0.00096177595, -0.011494439, -0.025628129, 0.010059437, 0.017811334, -0.0012862739, 0.00048809903, -0.008134083, -0.004524944, -0.004214868, -0.013744292, 0.008105238, -0.009215742, 0.0016342079, 0.012965497, -0.007232699, 0.008920088, -0.010254136, 0.0108238105, 0.04084348, 0.0010041409, 0.0060681123, 0.03767061, 0.015503792, 0.040353127, -0.023118678, -0.012561677, 0.021157268, 0.0061185895, -0.02293119, 0.0024697902, 0.013953413, 0.015417259, 0.013333261, -0.015359571, -0.008631646, -0.0039660865, 0.012439089, -0.0018126818, 0.010881498, 0.015474948, 0.026724212, -0.016513342, 0.016051833, -0.002763641, 0.027849138, 0.015402838, -0.018085353, -0.022094706, -0.018258419, 0.0018460329, -0.0284116, 0.010578634, 0.0072002495, -0.008025916, 0.011076198, 0.03204598, -0.0080619715, -0.015359571, 0.006749558, 0.024099384, 0.0021903613, -0.0041319407, -0.0073336544, -0.006147434, -0.01237419, -0.020450583, -0.037497543, 0.011934315, -0.003560464, 0.001907327, -0.012237179, -0.0015089156, 0.01352796, 0.0025977865, 0.016152788, 0.04782379, -0.007874484, 0.006201517, 0.013167406, 0.0141409, 0.008819134, 0.0062267557, 0.013470272, -0.034065075, -0.011321374, -0.0115665505, 0.021633198, 0.0032143327, 0.021936063, 0.012943864, -0.0064214543, 0.016758518, 0.016196055, 0.0038182596, 0.00497203, 0.006904596, 0.0016756714, -0.020176563, -0.0060789287, -0.026118482, -0.00463311, 0.0005579562, 0.027488584, -0.017277714, -0.008487425, -0.0088047115, 0.016758518, -0.0059022577, 0.0063060774, 0.0040201694, -0.028873108, -0.02034963, 0.019527568, -0.030459544, -0.0012529227, -0.019109325, 0.027517429, -0.02962306, 0.009850317, 0.01413369, 0.030142257, 0.020190986, 0.0026590806, -0.0066305753, -0.014450977, 0.007730263, 0.0037641765, -0.0074850866, -0.008444158, -0.003976903, -0.00018286813, -0.022556216, 0.0053217667, 0.013506327, -0.0019650154, 0.0067747966, 0.002154306, 0.00024382418, 0.032219045, -0.028844265, -0.0028159213, -0.016412387, -0.0017576973, 0.0080042835, 0.021878375, 0.021676464, -0.01623932, -0.008624435, -0.012554466, -0.024070539, 0.0060789287, 0.03103643, -0.016051833, -0.028959641, 0.013838036, -0.010102703, -0.0286712, 0.013917358, 0.02238315, 0.012316501, 0.006114984, -0.026536724, -0.010362302, 0.014364444, 0.014840374, -0.0039011869, -0.0032594018, -0.009093154, 0.012165069, 0.016268166, 0.051054347, 0.034266986, -0.035334226, 0.007831218, -0.029522104, 0.0038651314, 0.017566158, 0.0045682103, -0.006363766, -0.022628326, 0.008307148, 0.013607282, 0.018806461, 0.02197933, 0.014797107, 0.0049071303, 0.0012898794, -0.020609226, 0.02421476, -0.03037301, -0.0010365908, -0.025238732, 0.02205144, 0.009179687, -0.012727532, -0.011422329, 0.0074778753, 0.0050008744, 0.017796911, 0.018373797, 0.003235966, 0.0041896296, 0.010513734, 0.002981776, 0.00071389554, -0.0022101917, 0.0041968403, 0.009208531, -0.005812119, 0.01921028, 0.0049287635, 0.024459936, -0.016397964, -0.020407317, -0.019916965, 0.0044780723, -0.009424863, 0.008408103, -0.0014719588, 0.0060645067, 0.013152985, -0.025930993, 0.028988486, -0.013484694, 0.009165265, 0.024863755, 0.014191378, -0.02225335, 0.01799882, -0.0037641765, 0.020378472, -0.014667309, -0.0027798659, -0.014393288, -0.0076797856, 0.0008468496, -0.017796911, 0.011963159, 0.011105042, -0.014523087, 0.025628129, -0.004560999, -0.001124025, 0.01616721, 0.013066452, 0.01028298, 0.002540098, 0.0064502987, 0.029565372, -0.008891244, 0.015215349, -0.034209296, 0.013441428, 0.011436751, -0.011811727, 0.001655841, 0.00426174, -0.0024806068, 0.017234448, 0.020681338, -0.015864346, 0.010326247, 0.009287853, -0.004910736, -0.033690102, -0.040410813, 0.018705506, 0.015359571, 0.018344952, 0.004593449, -0.031728692, -0.016268166, -0.008162927, 0.019729476, -0.012821276, 0.018719928, -0.0002216276, 0.014616831, 0.004820598, -0.0080042835, 0.02745974, -0.016946005, -0.0022895136, 0.004113913, 0.014479821, -0.0046980097, -0.011898259, -0.023652297, -0.0043590893, 0.008970566, -0.018763194, -0.034930404, -0.034209296, 0.009908005, -0.009446496, 0.0007485988, -0.016297009, -0.010138759, -0.010362302, 0.016470075, -0.011891048, -0.011415117, 0.023421543, -0.011653082, -0.018892992, -0.013902935, -0.013095296, -0.010174815, 0.1106466, 0.030459544, 0.0024842122, 0.030286478, 0.020032343, -0.017782489, -0.019787166, -0.002062365, 0.020032343, -0.010535368, 0.0077086296, 0.009475341, 0.00497203, -0.014472609, 0.010708434, 0.006857724, -0.0064070323, -0.0076942076, 0.016946005, -0.001498099, -0.004658349, -0.018878572, -0.00008118083, -0.0014926906, 0.0047268537, -0.0041283355, 0.0012421061, 0.020724604, 0.001528746, -0.018359374, -0.007888907, 0.038189806, -0.0007017269, 0.0037966263, 0.0033892011, 0.0088624, -0.0043122177, 0.0071858275, 0.04747766, 0.0076581524, 0.006987523, 0.023493653, -0.02190722, -0.013917358, 0.016268166, -0.019758321, 0.002116448, 0.027906826, 0.0005182954, -0.006338527, 0.04118961, 0.02394074, -0.009028255, -0.019844854, 0.0025815617, 0.0006589112, 0.02144571, 0.008811923, 0.0046439264, -0.018662238, -0.03444005, -0.023825362, -0.005520071, -0.0060789287, -0.003513592, -0.04234338, -0.023161944, -0.011955948, -0.021964908, -0.0047160373, -0.023594609, -0.024546469, -0.011213208, 0.024315715, 0.02130149, 0.0032107271, 0.032911304, -0.0054371436, 0.0010870682, 0.0085955905, -0.004896314, -0.012431878, 0.013902935, -0.0011258277, -0.0056318427, 0.020811137, 0.0037100934, 0.009561874, -0.003428862, 0.0066305753, 0.023796517, 0.0019578044, 0.019844854, 0.0019109325, -0.0053614276, 0.0009888174, 0.019527568, 0.011249263, -0.016224898, 0.01014597, 0.02617617, -0.01737867, -0.032017134, -0.0026356447, -0.015316305, 0.009158054, 0.00048269072, -0.00005101266, -0.0011456581, -0.011761249, 0.011480018, -0.0141409, 0.018518018, -0.01352796, 0.011797304, 0.012330923, -0.021878375, 0.006756769, 0.008473002, -0.037093725, -0.025628129, -0.02610406, 0.03686297, 0.03233442, -0.013607282, -0.007196644, -0.0047989646, 0.009388808, 0.0018027666, 0.011537706, 0.005768853, 0.024661846, 0.0028177241, -0.024935866, -0.018258419, -0.009569084, 0.003621758, 0.008458581, -0.0095186075, -0.012266023, -0.02637808, 0.0033693707, 0.0039805085, -0.022916768, -0.008393681, -0.04984289, -0.02685401, -0.0045682103, 0.011790093, 0.012965497, -0.00019661423, -0.010831022, -0.01150165, 0.014652886, -0.01176846, -0.015547059, -0.004694404, 0.0048133866, 0.0062051225, 0.04254529, 0.025238732, -0.0050693797, 0.008696546, 0.01657103, -0.006284444, 0.009432075, -0.0078240065, 0.01501344, -0.021734154, 0.0015350557, 0.0018496384, -0.01528746, 0.030055724, 0.007182222, -0.041218452, -0.0038326816, 0.013636126, -0.018849727, -0.012540044, -0.008198982, 0.003560464, -0.0020046763, 0.018445907, 0.024099384, -0.0024589736, -0.0027600355, 0.006810852, 0.0145951975, -0.009980115, -0.0060825343, 0.015748968, -0.004719643, 0.0049900576, -0.010636322, -0.014494243, -0.0068973848, -0.012640999, 0.005700348, 0.004160785, -0.0016495314, 0.021258222, 0.02929135, 0.006655814, 0.02082556, -0.008177349, -0.008054761, -0.014285122, -0.020681338, 0.023118678, -0.02868562, -0.023378277, -0.03334397, -0.031440247, -0.033459347, 0.004146363, 0.01352796, 0.006277233, 0.007513931, 0.007910539, 0.0017712181, -0.0006084337, 0.0011826148, 0.009857527, -0.013239518, 0.020162141, 0.036949504, -0.01224439, -0.014703364, 0.03037301, 0.012821276, -0.01102572, 0.003713699, 0.0025527175, 0.009994538, -0.0045826323, 0.02089767, 0.0014286925, -0.007380526, -0.04118961, 0.03138256, 0.011040142, -0.013217884, -0.00081665325, -0.014256278, -0.035593823, 0.022426415, -0.021460133, -0.0140904235, -0.045198962, -0.002603195, -0.02373883, -0.009713306, -0.019094903, 0.04064157, -0.00033418785, -0.002471593, 0.009951271, 0.007182222, -0.0069839177, -0.019455457, -0.009749361, 0.034122765, -0.028988486, 0.0038002317, 0.010593056, -0.007816795, -0.011205996, 0.01440771, -0.021618776, 0.027820293, -0.022008173, -0.008898456, -0.0020659703, -0.009143632, 0.019094903, -0.0034775366, -0.007831218, -0.012424666, -0.007614886, -0.031728692, 0.011076198, -0.001112307, 0.0012150647, 0.0015467737, -0.026277125, -0.002601392, -0.029565372, -0.015907612, 0.0044672554, 0.0070992946, -0.0033224986, -0.009677251, 0.008148504, -0.00066431944, 0.0025437037, -0.00398772, 0.0028483712, 0.014176956, -0.03645915, 0.013304417, -0.0017270503, 0.013737081, -0.033718944, 0.0034991698, 0.012864542, -0.0022823024, 0.0014088621, 0.0088624, -0.005768853, 0.013499116, -0.0098142605, 0.015186505, 0.008855189, -0.007888907, 0.008025916, -0.00054533686, 0.014912485, -0.028498134, -0.03444005, 0.03184407, -0.003915609, 0.013960624, -0.010852654, -0.0232629, -0.018849727, -0.0061654616, 0.0232629, 0.018114198, -0.007636519, -0.017421937, -0.0044816774, 0.010578634, -0.017623845, -0.035016935, -0.026882855, -0.016628718, -0.010376724, 0.0284116, 0.01745078, 0.0007639223, 0.023594609, 0.023854207, 0.0051090405, -0.0015296474, -0.034670807, -0.036314927, -0.0150999725, -0.012381401, -0.010073859, 0.0006940651, -0.015330726, 0.025729084, 0.011213208, -0.018835304, -0.0015792234, -0.010903132, -0.012958285, 0.009533029, 0.00372091, 0.020594805, 0.012770798, -0.004045408, -0.010138759, 0.017969977, -0.009244586, 0.028642355, -0.020104453, -0.014753841, 0.003243177, -0.02373883, -0.0028285407, -0.017407514, 0.006587309, -0.02874331, 0.00187668, 0.006994734, 0.008314359, 0.027358785, 0.010182025, -0.018950682, -0.016744096, 0.010593056, 0.00014106648, 0.0045285495, 0.01981601, -0.005354217, -0.015748968, 0.0048602587, -0.022873502, -0.0179844, -0.0015305488, -0.00048314143, -0.0038326816, 0.017421937, -0.009850317, -0.017320981, -0.023609031, -0.029651904, -0.012626577, 0.007257938, -0.014306755, 0.025815617, -0.0062952605, -0.015157661, -0.020118874, 0.0001120532, -0.008970566, -0.007614886, 0.0034504952, -0.0047124317, 0.0026626862, -0.0075788307, 0.0023742435, -0.03097874, 0.011992003, 0.0027996965, -0.002594181, 0.020912092, 0.011213208, 0.017479625, -0.037468698, -0.011407906, -0.012532833, -0.01853244, 0.00025013386, -0.02104189, -0.0118333595, -0.023839785, -0.013960624, 0.012878964, -0.011617027, 0.0009112985, -0.012410245, -0.0022264167, -0.013672181, -0.004546577, 0.2016791, 0.008573958, 0.01460962, 0.042920265, -0.0022732886, -0.012886175, 0.026796322, 0.02421476, 0.0072543323, 0.02914713, 0.0019487906, -0.008898456, -0.010867077, 0.0069767064, -0.005750825, 0.0000034382842, -0.03253633, 0.00405983, -0.0213736, -0.00008084281, 0.00852348, 0.0041824183, -0.025729084, -0.0064899595, 0.024258027, 0.006879357, -0.039487798, 0.008552324, 0.015172083, -0.0076076747, -0.017032538, -0.0073985537, -0.008429736, -0.014580776, -0.00049621146, -0.0019019187, -0.0073985537, 0.00046195893, 0.019109325, 0.011847782, -0.007953806, -0.005538099, -0.0033008654, -0.018359374, 0.0017081213, 0.025181042, -0.002354413, -0.0077735293, 0.01413369, 0.015229772, -0.034555428, 0.011580972, 0.010333458, 0.024705112, 0.00460066, 0.009417652, 0.029493261, 0.00520639, 0.0068613295, 0.018878572, -0.0053758495, 0.00241751, -0.02421476, 0.036372617, -0.015734546, -0.004085069, -0.014797107, 0.0042437124, 0.007852851, -0.005228023, -0.009756573, -0.008025916, -0.006825274, -0.014523087, -0.009158054, -0.027921248, 0.02434456, 0.029493261, 0.022267772, 0.043670215, -0.011638661, -0.012756376, -0.015359571, -0.00031323067, -0.011386273, -0.026724212, 0.024993556, 0.009129209, 0.0003240473, 0.008285515, -0.015619169, -0.010246925, -0.009215742, -0.013225095, 0.011523284, 0.011371851, -0.006958679, 0.024113806, 0.00588423, 0.009482552, -0.002392271, 0.008674912, 0.017494047, -0.0035496473, -0.008653279, 0.0032413742, -0.0063168937, 0.01805651, 0.0028862292, 0.011617027, -0.013924569, -0.0051631234, -0.011047353, 0.0003758768, 0.023998428, 0.015229772, 0.006864935, -0.010773333, -0.0032233465, 0.006619759, 0.010419991, -0.027647227, -0.0121001685, -0.00872539, -0.022022596, -0.00605369, -0.007997072, 0.019585256, -0.010239714, -0.042862576, 0.0015323516, -0.007513931, 0.013571226, -0.013073663, -0.0075355642, 0.0057580364, 0.005202784, -0.0179844, -0.011213208, -0.0049612136, -0.0073841317, 0.0067892186, 0.0032341632, -0.0040670414, 0.013874091, -0.05411184, 0.013398161, 0.0044672554, -0.015172083, -0.020133296, -0.013174618, 0.0011726996, -0.019022793, -0.0004849442, 0.023709984, -0.011148308, -0.018186308, -0.010441624, -0.0029060596, -0.006075323, -0.018287264, 0.0018676661, 0.0046403212, -0.019224701, -0.010369513, -0.004286979, -0.18933375, 0.027906826, 0.025080089, -0.03389201, 0.023161944, 0.016801784, 0.021474555, -0.008840767, -0.0052640783, -0.0053109503, 0.010045015, -0.012215546, -0.027445318, -0.029998034, -0.012323712, 0.0031386164, 0.006720714, -0.015561481, 0.0037749931, 0.0060356623, 0.040439658, -0.02353692, 0.015056706, -0.014782686, -0.009778205, 0.004409567, -0.0087758675, -0.014984596, -0.021085158, -0.017090227, -0.01968621, -0.030344166, 0.023623453, 0.010571423, -0.0056859255, 0.006511593, 0.028526977, -0.014515876, -0.016585452, 0.029377883, -0.018301686, 0.050362084, 0.030661453, 0.0020479427, -0.0080331275, 0.011912681, -0.012698688, 0.011004087, 0.018503595, -0.012129013, 0.005419116, -0.00095636764, 0.015402838, 0.029204817, 0.036689904, 0.023594609, 0.011516073, 0.013686604, -0.007420187, -0.023060989, -0.017753644, -0.029161552, -0.0062592053, -0.023911895, -0.0074634533, -0.030113412, -0.04355484, 0.014645675, -0.028945219, -0.012972708, -0.011999214, -0.0033315124, 0.02962306, -0.015157661, -0.017018115, 0.010629111, -0.011242052, 0.012035269, -0.007528353, 0.0012240785, -0.014148111, 0.045545094, -0.019772744, -0.027430896, 0.0036632216, 0.011285318, 0.006709897, 0.02036405, 0.00091896026, 0.0035730833, 0.015345149, -0.01996023, 0.002942115, -0.01908048, 0.0048061754, 0.017090227, 0.019325657, 0.015402838, -0.0003988621, -0.000793668, 0.010419991, 0.0034991698, -0.02868562, 0.024229182, 0.027719337, -0.0045682103, 0.022945613, 0.009114788, 0.0070776613, -0.010268558, -0.01908048, 0.021128424, 0.021243801, 0.0015819276, 0.0035153946, 0.008444158, -0.009035466, 0.0020984202, -0.0046439264, -0.008011495, 0.06005376, 0.01548937, -0.00055840693, 0.011278108, -0.026608834, -0.01994581, -0.14237529, -0.01440771, -0.008660491, 0.03077683, -0.0025310842, 0.015460526, 0.012165069, 0.014162534, -0.0012952877, 0.014523087, -0.024387825, -0.008898456, -0.017061383, 0.0027996965, -0.0027924853, -0.0047881478, -0.014097634, -0.0070524225, -0.02597426, 0.008876822, 0.014450977, 0.0041030967, 0.005768853, -0.013441428, -0.008898456, -0.03299784, -0.03233442, 0.02570024, 0.028945219, 0.007946595, 0.029709592, -0.017609423, 0.03640146, -0.008386469, -0.013773136, 0.013982257, -0.009165265, -0.024445513, 0.031122962, -0.010030593, 0.02645019, 0.015575903, 0.009432075, -0.023176366, 0.014263488, -0.013542382, 0.017306559, 0.03585342, 0.022599481, -0.02089767, -0.03449774, -0.013722659, -0.021791842, -0.020839982, 0.046929616, -0.007412976, 0.008545114, 0.040901165, -0.004160785, 0.013484694, 0.0006958679, -0.007874484, 0.005119857, 0.030863363, 0.006378188, 0.010535368, -0.029853813, -0.023551341, 0.00052640785, -0.009482552, -0.0016747701, 0.0065909144, -0.0016278982, 0.022210084, -0.016556608, -0.007932173, -0.01968621, -0.023291744, 0.021388022, -0.02482049, -0.011133886, -0.01812862, 0.0075716195, -0.0032143327, 0.040555038, 0.01481153, -0.0023796519, 0.028483711, -0.0026735028, -0.040064685, -0.00090859435, 0.0142058, 0.0080619715, -0.031296026, -0.00426174, 0.003412637, 0.0031674607, -0.010917554, -0.005429933, 0.009403231, -0.008177349, -0.01860455, -0.08820576, 0.008386469, -0.014999017, -0.009648407, 0.019152591, 0.003360357, -0.007961017, -0.014061579, -0.014097634, 0.006370977, -0.016210476, 0.0075932527, -0.011184364, -0.0016576438, -0.0043807225, -0.016628718, 0.027243407, -0.000010640548, 0.0008459482, 0.009590718, -0.009720517, -0.00010422244, 0.0010861668, 0.0055092545, -0.0028682016, -0.005058563, -0.022152396, 0.015518215, -0.0019523961, 0.0059815794, -0.008220616, -0.025945416, 0.0077086296, 0.048746806, -0.015748968, -0.014508665, -0.007896117, -0.00041936856, -0.008018705, 0.0059058634, -0.0038434982, -0.04012237, 0.013196251, -0.022772547, 0.0014187773, -0.014097634, -0.023652297, -0.006973101, 0.007881695, -0.008372048, 0.026291547, 0.007744685, -0.01000896, -0.0032684158, -0.017234448, -0.020854402, 0.0045682103, -0.009496974, 0.000010006763, -0.013571226, -0.0031584469, 0.018503595, 0.02434456, 0.0041211243, 0.020537116, 0.0065404368, 0.0022678804, -0.012467933, 0.0018117804, -0.011667505, -0.024935866, 0.002857385, 0.028498134, 0.0017739222, -0.000045688863, 0.012345345, -0.0108238105, -0.00994406, -0.014400499, 0.03207482, 0.0015296474, 0.0035171974, -0.024330137, 0.0025238732, 0.015460526, -0.020652493, -0.010196447, 0.00980705, 0.010751699, 0.00608614, -0.02732994, -0.01664314, 0.0014881837, -0.0075644082, 0.00062736275, 0.00919411, -0.0053866664, -0.017955555, 0.022801392, 0.030459544, 0.014350021, -0.010838232, -0.00031706155, 0.0047881478, -0.00008991298, 0.00960514, -0.024950288, -0.0059094685, -0.008393681, 0.0072723604, 0.025483908, -0.007629308, 0.006169067, 0.021791842, -0.019570833, 0.020609226, -0.009597929, -0.022022596, -0.01021087, 0.017551735, 0.0284116, 0.025382953, 0.016080678, 0.016282586, 0.014631253, 0.0039408477, 0.018316109, -0.019167013, 0.0051018293, 0.0077086296, -0.005574154, -0.015532636, -0.045083586, 0.0018640606, 0.0019037215, 0.0062555997, 0.014602409, 0.024806067, -0.033459347, 0.03423814, 0.008357625, 0.0045069163, -0.013261151, -0.009972905, 0.012453511, 0.016484497, 0.01737867, -0.029002909, -0.00038601737, 0.011386273, -0.020234251, -0.00588423, -0.015575903, -0.026536724, -0.009179687, -0.032622863, 0.005721981, -0.004456439, 0.0030520838, 0.023176366, 0.017436357, 0.012078536, -0.002725783, -0.013787558, -0.024056116, 0.029882658, -0.015431682, -0.008710968, -0.0015548861, 0.0075788307, 0.007405765, -0.040612724, 0.0029204818, 0.0072218827, -0.026118482, -0.0017631056, -0.026594412, -0.0060681123, -0.00022816264, -0.0046547432, 0.014941329, -0.027531851, 0.001860455, 0.0036037304, 0.024806067, 0.00019931837, -0.005491227, -0.013455849],
+        ..Default::default()
     }
 }
 pub fn get_cat_record() -> EmbeddingRecord {
     EmbeddingRecord {
         id: "Cat".to_string(),
         embedding: vec![-0.008115427, -0.007452803, -0.009768569, -0.02487911, -0.012705971, 0.006544258, -0.0014618697, -0.03776269, -0.014304464, -0.026313655, 0.01709158, 0.046151362, 0.0036171027, 0.004255817, -0.032161135, -0.0045700506, 0.039538793, 0.0051131286, 0.0077943616, -0.015602385, -0.02359485, 0.005362466, 0.014891944, -0.0119067235, -0.0067901798, 0.004470999, 0.012350749, -0.013484723, 0.005540076, 0.00096404843, 0.009871036, -0.016586073, -0.017829346, -0.039210897, -0.029401341, -0.0003518051, 0.011387555, -0.007384491, 0.020575475, -0.013921918, 0.009508984, 0.0097754, -0.01340958, 0.011872568, -0.0025565643, 0.01047901, -0.02160015, -0.007452803, -0.00623344, 0.012514697, 0.006660388, 0.006964375, -0.005837232, 0.013956074, -0.004880869, 0.005574232, 0.008470647, -0.012022854, 0.013518879, -0.0007441702, 0.012289269, 0.0042899726, -0.006417881, 0.025166018, 0.007835348, -0.0049696737, -0.004860375, 0.00007669052, -0.004149934, 0.0039894013, 0.041533496, 0.016654385, 0.021395214, -0.009208413, -0.007008777, -0.00037934323, -0.018307528, 0.011462698, 0.00082785205, -0.010895711, 0.035631366, -0.032926224, -0.01323197, 0.009304049, 0.021832408, 0.007582595, -0.0050653103, 0.009761738, -0.012186802, 0.009208413, 0.01521984, 0.023977395, -0.013812619, 0.04221661, -0.011653971, 0.020356877, -0.013102178, 0.022201292, -0.0014661392, -0.057600398, 0.004597375, 0.005803076, -0.04817339, -0.010110127, -0.0031679536, -0.001681321, 0.016818333, 0.0036990768, 0.020684773, -0.00582357, -0.0027990707, 0.053720295, 0.020807734, -0.030303055, 0.0070429333, -0.0035522068, 0.00019009857, -0.028499627, 0.0043821936, -0.019591786, 0.01908628, 0.013949242, 0.038445808, 0.0044061025, 0.02277511, 0.017774696, -0.0100759715, 0.008047114, -0.013655502, 0.015902957, 0.027816512, 0.003641012, 0.009208413, 0.009420179, -0.0031081808, 0.026518589, -0.040877704, -0.002221837, -0.003272129, -0.06667219, -0.0025292395, 0.01897698, -0.020206591, 0.017132567, -0.0024370188, 0.023786124, 0.017296515, 0.002549733, 0.009297218, 0.0053761285, 0.009092283, -0.0010571231, 0.029947836, -0.016189866, -0.004730583, 0.03008446, -0.0007535631, 0.013477893, -0.00004632385, -0.013880931, 0.0013209769, -0.004091869, 0.017310176, -0.011824749, 0.0021279084, 0.018539786, 0.023103006, 0.0029630186, -0.017310176, 0.0035317133, -0.021326903, 0.018963318, -0.008634595, 0.0133412685, -0.0102604125, 0.0067355307, -0.0122961, -0.007698725, -0.01603958, -0.023430903, -0.015670696, 0.008702907, 0.015438437, 0.027201707, -0.02333530, 0.007384491, 0.019987995, -0.018826695, -0.004430012, -0.0073025175, 0.009652439, 0.025097707, 0.02101267, -0.024032045, -0.67677736, -0.027351992, -0.015151528, -0.0076918937, 0.014659684, 0.020971682, 0.0024899603, 0.0043992717, 0.0014251522, -0.007534777, -0.011107477, 0.006127557, 0.0068755695, -0.0098368805, -0.009399686, -0.008996647, -0.008771218, -0.015766334, -0.020534487, 0.02101267, 0.004942349, 0.004576882, -0.0040474664, -0.012958723, 0.006397388, -0.009078621, 0.011326075, -0.027884822, 0.023184981, 0.021203943, -0.014331788, 0.024728823, -0.004044051, -0.01826654, 0.049020454, 0.01726919, 0.0025394864, 0.022337915, 0.0081974, 0.038609754, -0.018580774, -0.018744722, 0.018539786, -0.0015327431, -0.027297342, -0.015902957, 0.0009367238, 0.0015327431, 0.006462284, -0.0053146477, 0.007568933, -0.008299868, 0.004846713, -0.008395504, 0.0060558296, 0.008819036, 0.02498841, -0.016982282, 0.0032909147, 0.008368179, -0.0059977644, 0.005393206, -0.004327544, -0.019591786, -0.013512048, 0.007459634, -0.017405814, 0.00019063224, 0.025439266, 0.010233088, 0.00652718, 0.0025429018, -0.03038503, -0.0013884347, 0.0045734663, 0.027447628, 0.0050379857, -0.010861555, -0.000053501913, -0.020097293, -0.006574998, -0.0025360708, -0.025261655, -0.010786412, 0.018457813, -0.013156828, -0.023567526, -0.000778753, 0.015902957, 0.00877805, 0.004853544, 0.031423368, -0.011209945, 0.015356463, -0.014878281, 0.0046930118, -0.005864557, 0.002582181, 0.0140312165, -0.034210484, -0.0012279022, -0.006025089, 0.008730231, -0.0028417655, 0.016545085, 0.015083216, -0.014468411, 0.017952306, 0.03546742, -0.015493087, 0.0096251145, -0.007582595, -0.010239919, 0.00017312738, 0.012712802, -0.030248407, 0.009686595, 0.0045461417, 0.017132567, -0.017255528, -0.010868386, 0.022925396, 0.0125966715, -0.0046451935, 0.007445972, 0.00045896904, -0.024537552, 0.0011365354, -0.004836466, -0.008450153, 0.0067526083, -0.013798957, 0.008832699, -0.011797425, 0.004197752, 0.0063119982, 0.025603214, 0.0128221, 0.017064255, -0.002879337, -0.019892357, 0.009406516, 0.0030432849, 0.0069097253, 0.019455163, 0.00056784076, -0.006100232, -0.024687838, 0.007705556, 0.00019853079, -0.013532542, -0.009317712, 0.02423698, 0.018239215, 0.034811627, -0.02423698, -0.0028912914, -0.016422125, -0.016818333, -0.0059670242, 0.0073161796, 0.030412355, -0.0044402583, -0.011735945, -0.012610334, -0.022406228, 0.008716569, 0.028581602, -0.02961994, -0.033800613, 0.0003001444, -0.02190072, -0.0052326736, 0.010567815, 0.0093313735, 0.004706674, 0.013047528, -0.030467004, 0.0048091416, 0.009666101, 0.024223318, -0.005434193, -0.010813737, -0.0028417655, -0.0010699315, 0.0140312165, 0.041779418, 0.020356877, -0.015820982, 0.016463112, -0.013238802, 0.0038664404, 0.00069208257, 0.00854579, -0.0023669994, -0.019865034, -0.0020151942, 0.01703693, 0.01598493, 0.027065083, -0.0030859797, 0.008661919, 0.007582595, -0.0076782317, 0.018840358, -0.03735282, -0.014523061, -0.00600118, 0.027174382, 0.028417654, -0.0015643372, -0.003979155, -0.016777346, -0.010096464, 0.013382256, 0.02054815, -0.007363998, 0.00354196, -0.0024558045, -0.0021244928, -0.011264594, -0.0051609464, -0.0030689016, 0.02042519, 0.0037332326, 0.018006956, 0.014864619, 0.036970276, 0.0021330318, -0.025630537, -0.026381966, -0.0015028567, 0.005601557, 0.0074664652, 0.0027614993, -0.00011730394, 0.02523433, -0.01562971, 0.033636667, 0.01457771, -0.0043172976, 0.030958848, 0.009440673, -0.021285916, 0.0091469325, -0.0009273309, 0.022788772, 0.016982282, -0.0032004018, 0.013539373, -0.0140585415, 0.0034412004, -0.018826695, 0.014700671, 0.0046042064, -0.01691397, 0.021531837, 0.0102604125, 0.019619111, 0.02587646, 0.017050592, 0.0072683617, 0.0009956426, -0.0022320838, 0.012419062, 0.011927217, 0.010752257, -0.017665397, 0.023307942, -0.00026278646, -0.014782645, -0.009973504, 0.013313944, -0.01521984, 0.017460462, 0.0195508, -0.0012671815, -0.0054888427, 0.015957605, -0.0008897595, -0.018881345, -0.044976402, 0.021053657, -0.001656558, -0.010895711, -0.004033804, -0.028253706, -0.015356463, 0.0010460224, 0.012282438, 0.0015933696, 0.016736358, 0.015452099, 0.01616254, 0.010280906, 0.0022594084, 0.031150121, -0.018949658, 0.0098095555, 0.0078011923, -0.0036375963, -0.024851784, -0.0065818294, -0.022515526, 0.0137921255, -0.0096251145, -0.013942411, -0.022816097, -0.0020800903, -0.0031508757, -0.017132567, 0.014290801, -0.02190072, -0.0068755695, -0.020561812, -0.010151114, -0.027884822, -0.006011427, 0.048801854, -0.0007659446, -0.012323425, -0.00848431, -0.034565706, -0.014441087, 0.092685275, 0.0052190116, -0.018922333, 0.013853607, 0.0017137689, -0.018471476, -0.028991472, -0.006984868, 0.027092408, 0.011168958, -0.00972075, 0.0022406226, 0.016148878, -0.005686946, 0.0005704024, 0.015028567, -0.021750435, -0.0024045708, 0.009215244, -0.014905606, 0.009406516, -0.00079326925, 0.015315476, 0.0029049537, -0.006574998, -0.0069472967, 0.0030244992, 0.034046534, 0.012241451, -0.028144408, 0.010991347, 0.016599735, -0.014126853, -0.0035385445, 0.0073025175, 0.0028588434, -0.0031423368, 0.0140585415, 0.023813447, -0.022324253, 0.018471476, 0.02505672, -0.016326489, -0.013887762, 0.007971971, -0.0039313366, -0.0036615054, 0.018334853, 0.016189866, 0.003205525, 0.03642378, 0.026081394, -0.00848431, 0.006421297, -0.0107659185, -0.0069677904, 0.020165605, -0.0072342055, -0.0017214541, -0.01826654, -0.016791008, -0.013901425, 0.00614805, 0.0047920635, -0.0022867331, -0.024346279, 0.0048262193, -0.02213298, -0.027652564, -0.009160595, -0.01311584, -0.023225967, -0.024100356, 0.010813737, 0.0073503354, 0.011681295, 0.01890867, -0.022228617, -0.002467759, 0.023512876, -0.014413762, -0.024605863, 0.0015489671, -0.034101184, -0.022201292, 0.0018683241, -0.0019332202, -0.009871036, 0.00020909775, 0.0019895774, 0.017843008, -0.0030654862, 0.036013912, -0.006516933, -0.006868738, 0.007869504, 0.0041123624, -0.007118076, -0.0013986814, -0.029756563, 0.017405814, -0.02423698, -0.018990643, -0.0011126263, 0.0057689203, 0.0020886292, 0.01903163, 0.028308356, -0.0021808501, -0.0138467755, 0.002288441, -0.020766748, -0.004744245, -0.011278257, 0.0140312165, 0.017446801, -0.024291629, 0.013163659, 0.019209241, -0.011667633, -0.024756148, -0.01616254, 0.020343214, 0.031095471, -0.007323011, -0.0048877, 0.00065237645, 0.006322245, -0.007575764, 0.007404985, 0.0051609464, 0.009392855, -0.015247164, -0.041834068, -0.02224228, -0.0117222825, -0.011804257, 0.010724932, -0.0015156651, -0.015506749, -0.038363833, 0.0041738427, 0.0047100894, -0.024305291, -0.021559162, -0.043965388, -0.031286743, -0.0015284736, 0.003979155, 0.0216548, -0.0112167755, 0.009140101, -0.009461166, -0.012343919, -0.016189866, -0.027119732, -0.015096879, -0.0004944057, 0.030822225, 0.0206028, -0.002073259, 0.00049269793, 0.015479424, -0.003681999, -0.0074391407, 0.009918855, 0.01000766, 0.009092283, -0.032598328, 0.010253581, 0.013095347, -0.00078259554, -0.0019981163, -0.001590808, -0.019181916, 0.017378489, 0.0072546992, -0.015247164, -0.00026129212, -0.003927921, 0.013559866, -0.00096490234, 0.01480997, 0.014154178, 0.0016582657, -0.006066076, 0.010779581, 0.0013816035, -0.012009191, 0.0043343753, 0.0183895, -0.010485841, 0.007124907, -0.028663576, 0.01024675, -0.035959262, -0.011742776, -0.00082785205, -0.002098876, -0.012227789, 0.0022969798, 0.037325494, 0.0025480252, 0.0087234005, -0.0014089282, -0.0042455704, 0.00013416838, -0.014659684, 0.0051472844, -0.016517762, -0.011018672, -0.01657241, -0.05667136, -0.032243107, -0.005625466, 0.0011510516, -0.006919972, 0.010820569, -0.015684359, -0.008299868, -0.014768982, 0.000622917, 0.028909499, -0.023977395, 0.030685602, 0.030221082, -0.015684359, -0.010519997, 0.01129875, -0.014687009, -0.046451934, 0.006339323, 0.0014866327, -0.008846361, 0.015875632, -0.00013747724, 0.006598907, -0.014359113, -0.029756563, 0.02400472, 0.023089344, -0.0061548813, -0.02587646, 0.011086984, -0.013218308, 0.017761033, -0.012186802, -0.0070156083, -0.036150534, -0.0062915045, -0.019072618, 0.012439555, -0.021176618, 0.014700671, 0.0032943303, -0.0033848432, 0.016463112, 0.012029685, -0.005184856, -0.016859319, -0.027147057, 0.015288152, -0.011653971, 0.030685602, 0.017132567, 0.0017479248, -0.014768982, -0.0013414704, -0.019195579, 0.031997185, -0.0008675582, -0.00936553, 0.009420179, -0.01586197, 0.010642958, -0.020917034, -0.0165041, 0.004880869, -0.019414175, -0.0368883, 0.01616254, 0.010554153, -0.013109009, 0.0018392917, -0.014823632, -0.015670696, -0.005089219, -0.018061604, -0.017474124, -0.009761738, -0.012200464, -0.0024967915, 0.016791008, 0.0022320838, 0.010130621, -0.005990933, -0.001180938, 0.034292456, -0.011606152, 0.027584251, -0.024578538, 0.010642958, -0.021463526, -0.007883167, 0.00559131, 0.006954128, 0.011633477, -0.018362176, -0.00795831, 0.0003255478, -0.0050448165, 0.0034019211, -0.011592491, 0.008272543, 0.001484071, 0.005017492, 0.011831581, -0.011811088, -0.015274489, 0.017528774, -0.008825867, 0.006814089, 0.0029339863, -0.005393206, 0.0071044136, -0.01903163, 0.0206028, 0.015575061, -0.008422828, -0.014372775, -0.014837295, 0.014140516, -0.00019191309, -0.03642378, -0.02282976, -0.035522066, -0.017009605, 0.021149293, -0.01539745, -0.0075552706, 0.0056630373, 0.0086687505, -0.0053488035, 0.007411816, -0.016736358, -0.02400472, -0.007971971, 0.0076782317, -0.027953135, -0.007869504, -0.022857085, 0.023253292, 0.009556802, -0.037216198, -0.01170862, 0.008538959, -0.026764512, 0.0013192691, -0.002156941, 0.009918855, 0.0026214602, -0.016777346, 0.015875632, 0.006271011, -0.006574998, 0.014618697, -0.02108098, -0.009816387, 0.012227789, 0.0050140764, 0.024155006, -0.012521529, -0.01223462, -0.024551213, 0.007541608, 0.016736358, 0.004952596, 0.015370126, -0.0037468951, -0.0061958684, 0.0029954666, 0.007883167, 0.018116254, -0.00007524957, 0.013901425, 0.003181616, -0.0125966715, 0.012419062, -0.010670283, 0.0021552332, -0.00503457, -0.0081974, -0.007814855, 0.00354196, -0.0121458145, 0.0020681357, -0.0029203238, -0.04770887, -0.019482488, 0.013853607, 0.0016147171, 0.0216548, -0.01750145, -0.015711684, -0.011667633, 0.016189866, -0.017761033, -0.019687423, 0.026614226, 0.01416784, 0.025958434, -0.003535129, -0.0057006087, -0.0350029, -0.010424361, -0.004617869, -0.00801979, -0.0021125383, 0.006253933, 0.007896828, -0.025507577, 0.0019622527, -0.0059875175, -0.022488201, 0.005437609, -0.007500621, -0.0057211025, -0.0014832171, -0.0071385694, 0.015752671, 0.0022167137, -0.0011049412, -0.0047852322, 0.0061924527, 0.012371243, -0.000844503, 0.21936242, -0.008511634, 0.00877805, 0.030986173, 0.011346568, -0.026641551, 0.030111784, 0.0079514785, 0.010444854, 0.017364826, -0.0016872982, -0.00477157, -0.002901538, 0.010567815, 0.0036512588, 0.009112776, -0.020069968, -0.007685063, -0.024100356, -0.022105657, 0.02418233, 0.012917737, -0.020001657, 0.008340855, 0.03869173, 0.021805085, -0.029346693, -0.0051711933, 0.0089078415, 0.0029869277, -0.013375425, -0.011278257, -0.006387141, -0.007985634, 0.00037614114, -0.010601971, -0.015493087, 0.021463526, 0.004942349, 0.013566697, -0.032161135, 0.0064486214, -0.010342387, -0.0073981537, -0.013040697, 0.031997185, -0.0165041, -0.000057851445, 0.0145367235, 0.010041815, -0.008661919, 0.020261241, 0.02336259, 0.016818333, -0.01206384, 0.0073571666, 0.031587314, 0.0034394925, -0.0030979342, 0.015875632, -0.0007561248, 0.023758799, -0.020056305, 0.021395214, -0.015028567, 0.009700257, -0.007698725, 0.006954128, 0.009406516, -0.0068550757, 0.002353337, -0.015083216, -0.0009905192, 0.008354517, -0.0133412685, -0.030057134, 0.022378903, 0.019714747, 0.020261241, 0.014919268, -0.0036990768, 0.0048842845, -0.01276062, -0.006892647, -0.010192101, -0.03437443, 0.015096879, 0.017351164, 0.005058479, 0.013915087, -0.023922747, -0.0115105165, 0.01762441, -0.01867641, 0.031997185, 0.02418233, -0.016886644, 0.027174382, -0.008709738, -0.0019127267, -0.0021432785, -0.0012885289, 0.036860976, 0.0053180633, -0.007828517, 0.01164714, -0.009515815, -0.0006054122, 0.00064639916, 0.007534777, -0.018485138, -0.009980335, -0.006253933, -0.009522647, 0.01644945, 0.01311584, -0.010062309, -0.001680467, 0.003869856, 0.0021279084, 0.0035317133, -0.025125032, 0.010608803, 0.008559452, -0.008340855, -0.018949658, -0.018922333, 0.011435374, -0.023444565, -0.040249236, -0.0069097253, -0.005174609, -0.007131738, -0.0010263828, -0.013020204, 0.0128221, 0.004764739, -0.036314484, -0.014017554, 0.01603958, 0.007534777, -0.014126853, 0.015971268, 0.017132567, -0.008573114, -0.016189866, 0.01457771, 0.017446801, -0.018553449, -0.033745963, -0.036259834, 0.010636127, -0.0054137, -0.016025918, 0.019509813, -0.015752671, -0.0215455, -0.014140516, 0.013081685, -0.008866855, -0.040823054, -0.0057108556, 0.019701086, -0.008982984, -0.008340855, -0.0027000187, -0.17837542, 0.0013969736, 0.01399023, -0.02002898, -0.0019605448, 0.015711684, 0.027024096, -0.009666101, -0.007077089, 0.00082785205, 0.034675002, -0.005526414, -0.04664321, -0.0081974, 0.012555685, -0.026259005, 0.004723752, -0.015014905, 0.0070156083, 0.010847893, 0.044593856, -0.0307949, 0.016271839, -0.025917446, 0.005468349, 0.017665397, -0.0032448042, -0.010813737, -0.0051609464, -0.020835059, -0.02569885, 0.00012616311, 0.020288566, 0.008135919, 0.011824749, 0.012924568, 0.007965141, -0.006691128, -0.00532831, 0.012781113, 0.0145093985, 0.040932354, 0.022447214, 0.0011911847, -0.0145367235, 0.02236524, 0.017993294, 0.010970854, 0.02739298, -0.025999421, 0.004600791, -0.008293036, 0.015698021, 0.010574646, 0.034347106, 0.02452389, 0.0036683367, -0.009092283, -0.0027205122, -0.007459634, -0.021791423, -0.027297342, -0.005697193, -0.014618697, -0.002156941, -0.016189866, -0.012050178, 0.010519997, -0.037434794, 0.00035244552, -0.01926389, 0.0140858665, 0.03207916, -0.023799784, 0.010321893, 0.008976153, -0.021272253, 0.013272957, -0.01586197, -0.01141488, -0.012330256, 0.0471897, -0.015520412, -0.029018797, 0.0016420417, 0.03232508, 0.009987166, -0.00096404843, 0.0050311545, 0.0011408048, 0.028417654, -0.019509813, -0.013942411, -0.0307949, 0.023977395, 0.0183895, 0.013184152, 0.0165041, -0.0009700257, -0.014468411, -0.0017043761, -0.01036288, -0.0031218433, 0.0032328498, 0.025357291, 0.0035214666, 0.025726175, 0.016736358, 0.02733833, -0.006715037, -0.006339323, 0.0047100894, 0.020589137, 0.013006542, 0.0077943616, -0.008538959, -0.010451686, -0.00029651533, 0.0009666101, -0.020302229, 0.04238056, 0.015028567, -0.0025616875, 0.011175789, -0.023499213, -0.010991347, -0.10716735, -0.037134223, -0.009857373, 0.03882835, 0.008737062, -0.002377246, -0.0030381614, 0.018348515, -0.01903163, 0.029893186, -0.0062880893, -0.0215455, 0.0019502981, -0.0031833237, 0.010895711, 0.00848431, 0.006626232, -0.018348515, -0.01434545, -0.0033728885, 0.0011203113, 0.0011749608, 0.001303899, -0.0044812453, -0.0060797385, 0.004385609, -0.047982115, 0.030685602, 0.034675002, -0.003559038, 0.014618697, -0.030876875, 0.0086960755, -0.025125032, -0.009030802, 0.020534487, -0.0044744145, 0.00177098, 0.03213381, -0.015233502, 0.012746957, 0.0074937902, -0.007685063, -0.014564048, 0.008812205, -0.005782583, 0.0015216424, 0.026709862, 0.0041635963, -0.022556514, -0.032571003, -0.006889232, -0.016367476, 0.0059396997, 0.043172974, -0.022583839, 0.0047920635, 0.032516353, 0.0054478557, 0.025917446, -0.0048740376, -0.021887058, 0.020097293, 0.035986587, 0.01890867, 0.013225139, -0.017364826, -0.017897658, -0.0018751553, -0.020534487, 0.004368531, 0.019824047, -0.029920511, 0.038883, -0.018075267, 0.020589137, -0.01996067, 0.007780699, 0.011927217, -0.030412355, -0.010110127, -0.01616254, 0.018881345, -0.014154178, 0.022460876, 0.0013918503, -0.0016599735, 0.016886644, 0.0049594273, -0.016668048, 0.010895711, -0.006797011, 0.02001532, -0.034456406, 0.011817919, 0.009269893, 0.007889998, -0.0016830288, 0.0046451935, 0.01821189, 0.008873686, -0.0062368554, -0.09131904, 0.014550385, -0.016408462, 0.00708392, 0.014837295, -0.0033967977, 0.00020472154, -0.026682537, -0.0163948, -0.0028605512, -0.023663161, 0.011203114, -0.006021674, 0.008327193, -0.003010837, -0.015820982, 0.0055708163, 0.0031047654, 0.0015506749, 0.012514697, -0.0012688893, -0.0023584603, 0.02295272, -0.013716983, -0.017460462, 0.0028110251, -0.012364412, 0.005509336, -0.017815683, -0.008012959, 0.019113604, -0.021477189, 0.02423698, 0.03530347, -0.004450505, -0.021982694, -0.017938644, 0.0025309473, -0.005625466, 0.015670696, -0.0028844601, -0.0204935, 0.012603503, -0.019332202, 0.009973504, -0.0007612482, -0.009918855, 0.0071795564, 0.027912147, 0.016121553, 0.020698436, 0.012692308, -0.012999711, 0.0023909083, -0.02910077, -0.034046534, 0.011988698, -0.0036990768, -0.004402687, -0.027556928, -0.0020852138, 0.039156247, 0.009324543, -0.0033489796, 0.004081622, 0.011291919, -0.0006498147, -0.031559993, 0.0034019211, -0.037489444, -0.015424775, -0.012494205, 0.014044879, 0.0018409995, 0.009399686, 0.0102604125, -0.015561398, -0.014181502, -0.012787945, 0.03202451, -0.016954957, -0.009126439, -0.008156413, 0.009925686, 0.02224228, 0.00061565894, -0.010554153, 0.0062026996, 0.0072000497, 0.006779933, -0.00058406475, 0.018157242, 0.0045188167, -0.023731474, 0.0051199594, 0.004368531, -0.014154178, 0.0039005962, 0.00020568217, 0.037270848, 0.006960959, -0.0064588683, -0.02839033, 0.01252836, -0.009645607, 0.012487373, -0.02739298, -0.016927632, -0.02131324, 0.023895422, 0.019222904, -0.007445972, 0.0054239463, 0.01375797, -0.0043309596, 0.020315891, -0.006137803, -0.018567111, -0.017146228, 0.014864619, 0.0128630875, 0.010342387, 0.017173553, -0.008825867, 0.012166308, 0.0056083878, 0.03858243, -0.026368303, -0.009208413, 0.008217894, -0.016777346, -0.025931109, -0.018239215, -0.013033866, -0.017665397, -0.008989816, 0.013020204, 0.014550385, -0.024441915, 0.03524882, 0.004730583, 0.018539786, 0.0053829593, -0.02833568, -0.0060455827, 0.035686016, 0.017870333, -0.017173553, 0.014304464, 0.018703735, -0.012774282, -0.011373892, -0.012480542, -0.021873396, -0.006513518, -0.013245633, 0.0038835183, -0.0050550634, 0.004279726, 0.024906434, 0.008450153100, 0.01890867, -0.0017308469, -0.02640929, -0.012446386, 0.0351122, -0.001508834, -0.014523061, -0.008675582, 0.009092283, 0.012453217, -0.029128095, -0.0030022978, 0.024114018, -0.015670696, 0.004293388, -0.01657241, -0.0033079926, 0.00089829846, -0.01715989, 0.007090751, -0.0023806617, -0.018471476, 0.00825205, 0.019878695, -0.017296515, -0.014564048, -0.011346568 ],
+        ..Default::default()
     }
 }
 pub fn get_openai_record() -> EmbeddingRecord {
     EmbeddingRecord {
         id: "OpenAI change the world".to_string(),
         embedding: vec![0.00994385, -0.010416101, -0.012384917, -0.020273482, 0.010795231, -0.000855537, -0.014752816, 0.011520234, -0.0100236675, -0.038418517, 0.020712476, 0.024051482, 0.014433549, -0.012983543, 0.01718723, 0.0153115345, 0.022880834, 0.0008788169, -0.0050351135, 0.00068343186, -0.012431476, 0.012478036, 0.0010376192, -0.01102803, -0.010203255, -0.00241446, 0.015032176, -0.03791301, 0.015178506, -0.03517263, 0.025967086, 0.013768408, -0.0010334621, 0.004742452, -0.026898282, -0.0003780908, -0.001957176, -0.015590894, 0.022761108, -0.022521658, 0.009065866, 0.015058781, -0.0071369577, -0.0062889038, -0.04227633, 0.002547488, -0.015072084, 0.008813112, -0.0007158575, 0.0098041715, -0.006944067, 0.036955208, 0.004845549, -0.009431693, -0.023931757, 0.0035185933, -0.030410225, 0.021750094, 0.016588604, 0.0015414632, 0.027230853, -0.01543126, -0.017453287, -0.00077655155, -0.016256034, 0.019568434, 0.0012396557, 0.024304235, 0.008460588, 0.011094544, 0.04597451, 0.0054541524, 0.004226968, -0.010582386, 0.0045927954, 0.011447068, -0.023319827, -0.0023828659, -0.010622294, 0.0074030138, 0.011307389, -0.0026040252, 0.00842733, -0.0058066766, 0.031926744, -0.010103485, 0.010482615, 0.004439813, -0.009305316, -0.015843647, 0.01803861, 0.012571156, 0.018863386, 0.02201615, -0.047890116, 0.018357879, -0.0017792509, 0.0054408493, 0.018025309, -0.032112986, -0.011806244, -0.01552438, -0.014539972, -0.016921176, -0.014234007, 0.00017782116, 0.004306785, 0.0008339199, 0.018943202, 0.004200362, 0.005800025, 0.008866323, -0.0064053033, -0.035811167, -0.010595689, -0.0093119675, -0.016083097, -0.008919534, -0.023878545, -0.009664492, 0.023027165, 0.035092812, 0.022747805, -0.005307821, 0.002283095, 0.0031993259, -0.030223984, -0.015644105, 0.0068575987, -0.019994125, 0.022854228, -0.001755971, 0.027749663, -0.0033523082, -0.033017576, 0.010841791, -0.0019372217, 0.012065649, -0.021829912, -0.011427114, 0.010030319, 0.016309245, -0.005570552, 0.0071635633, -0.03607722, 0.016229428, -0.0011772987, 0.00847389, 0.03384235, 0.003408845, 0.0066015194, 0.0014267265, -0.017599618, 0.011234223, 0.028973522, 0.0025873967, 0.013741803, 0.03413501, -0.0090259565, -0.0034188223, 0.0071968203, 0.0046493323, -0.03445428, -0.012105557, 0.030516647, 0.017386774, 0.021098258, 0.001867382, -0.006541657, -0.015950069, -0.016881267, 0.03195335, -0.038099248, 0.0105424775, -0.0125512015, 0.026100114, 0.014566577, 0.02044642, -0.016402366, 0.0019372217, -0.013741803, 0.0039110263, 0.028334986, 0.022135876, 0.012192026, -0.005992916, 0.03639649, -0.014473458, 0.021856517, 0.011626656, 0.026964797, 0.010848442, -0.00691081, -0.0067944103, -0.65683955, -0.026592318, 0.0023346432, -0.0036416443, 0.020379905, 0.016814752, 0.009092471, 0.018477604, 0.014087676, 0.032219406, -0.005923076, 0.0112940855, -0.02551479, -0.013129874, 0.0073498026, -0.025794148, 0.018091822, -0.037061628, 0.015910162, 0.017666131, -0.0057967, 0.025674423, -0.00320764, -0.015723921, 0.00074121595, 0.005287867, -0.014553274, 0.0032508743, -0.010336284, -0.0010750333, 0.004848874, 0.01434043, 0.003967563, 0.012837212, 0.05411583, 0.00871334, -0.0032791428, 0.008686735, 0.010076879, 0.034720335, -0.023359735, -0.026206536, -0.0017642853, 0.011686519, -0.000112242466, -0.0030912405, 0.027989114, -0.024676712, -0.020246876, -0.010263118, 0.009524812, -0.010848442, -0.0036948556, 0.0120456945, 0.005427547, 0.007097049, 0.024144601, 0.014593183, 0.011161058, -0.0058432594, 0.007921823, 0.025408369, -0.039216686, -0.005660346, -0.01665512, 0.00771563, 0.0010725391, 0.020233575, -0.01017665, -0.019262468, 0.017306956, 0.016256034, -0.012850515, -0.006195784, 0.007356454, 0.029479027, 0.01694778, -0.020858807, -0.010781928, 0.011606702, 0.018956505, -0.019475315, 0.0048056403, -0.010429404, 0.019488618, 0.018637238, -0.012710835, -0.01339593, -0.009737657, -0.0008501327, 0.025381763, 0.02480974, -0.015417957, -0.026485896, 0.022694595, 0.0022099293, -0.016881267, 0.015471169, 0.03086252, -0.024490474, -0.008573662, 0.0001430052, 0.018145034, 0.019275771, 0.025927177, 0.0009203882, -0.007183518, 0.01145372, 0.025115706, -0.040866233, -0.00395426, -0.0016586941, -0.03676897, 0.007981686, 0.0037048326, -0.022455145, -0.0046659606, -0.00085387414, 0.01874366, -0.02163037, 0.004695892, -0.0066480795, 0.0039974945, -0.0024793113, 0.014420247, 0.0135356095, -0.0058199796, -0.0040673343, -0.01789228, -0.0028102186, 0.013888134, -0.01543126, 0.0020785641, -0.00033028383, 0.027696451, -0.0061592013, 0.018916596, -0.0026588992, 0.015085387, 0.008221136, -0.008034897, 0.0065616113, 0.0015788773, 0.016575301, -0.011274132, -0.014978965, -0.020619355, 0.00024402342, -0.0074761794, -0.011886061, 0.0090525625, -0.0063920002, -0.016921176, 0.021138165, -0.004077311, -0.009484904, -0.02163037, -0.021005137, -0.009258756, -0.024916165, 0.0042768535, 0.0070504895, 0.0068908557, -0.013954648, -0.014659697, -0.0070172325, -0.01485924, 0.03317721, -0.023000559, -0.011380554, 0.008819764, -0.036582727, -0.009651189, 0.008939489, -0.014633091, 0.03160748, -0.035199236, -0.002761996, 0.0015996629, 0.0019555131, -0.00053003384, -0.014047768, -0.017825766, -0.014739513, 0.032831337, -0.0025308596, 0.019262468, 0.032831337, -0.020300088, 0.025381763, -0.00852045, 0.041903853, 0.013495701, 0.009624584, 0.0100369705, -0.0077222814, 0.019515222, -0.012956937, 0.013149828, 0.019568434, 0.008553707, 0.006830993, 0.01746659, -0.0025308596, 0.010821836, -0.023067074, 0.00082435855, -0.023745516, 0.0037846495, 0.011520234, 0.008147972, 0.027802873, -0.0022531636, -0.008420679, 0.006844296, 0.0496993, -0.014460155, -0.0033074112, -0.0100369705, 0.007522739, -0.003388891, -0.004037403, 0.003857815, -0.010017016, 0.015870253, 0.020087244, -0.0024261, 0.015537682, 0.016362457, -0.037327684, -0.0026888305, -0.008034897, -0.00028725757, -0.0056470428, 0.013661986, 0.0056270887, 0.017586315, 0.0016204485, 0.01907623, -0.009252105, -0.005567226, -0.0030779378, 0.03110197, -0.027004706, 0.016428972, -0.007023884, 0.044750653, -0.0068243416, -0.029931324, 0.0014616463, -0.030623069, -0.018397788, -0.005879842, -0.007416317, 0.0011639959, -0.024104692, 0.0028800585, -0.0063454406, -0.0031161832, 0.026033599, 0.012112209, 0.028494619, 0.0135489125, 0.00894614, -0.026499199, -0.017094111, 0.0130567085, -0.00089045684, -0.0026189908, 0.00020307572, -0.0063886745, -0.009591326, 0.016934477, -0.026618924, 0.029452423, 0.009491555, 0.007935126, -0.016748238, -0.0068908557, 0.012265191, 0.01689457, -0.01983449, 0.009584675, 0.012611064, -0.0051249075, -0.023891848, -0.021683581, 0.008354165, -0.028734071, 0.00442651, -0.0022614778, -0.009431693, -0.010948213, 0.0112940855, -0.0051614903, 0.011440417, 0.0031328117, -0.019821187, 0.011713125, -0.02939921, -0.0009744309, 0.015950069, 0.00029640325, -0.01050922, 0.027457, 0.011932621, 0.0008779855, 0.0074096653, -0.030516647, -0.044617627, 0.015910162, -0.021098258, 0.00937183, -0.019821187, 0.025687726, 0.0014416921, 0.0042901565, -0.026166627, 0.01140716, 0.03086252, -0.010688808, -0.009378482, -0.024211114, -0.0027121105, 0.09008663, 0.008746598, -0.01742668, 0.013768408, -0.015590894, 0.0071170037, -0.012331706, -0.008872975, -0.02958545, -0.0011398846, -0.023984967, 0.009664492, 0.009817474, 0.00064975914, 0.010861744, 0.015457866, -0.011300737, -0.027204247, -0.010994772, -0.01297024, -0.00035938373, -0.0035817816, 0.0026672136, 0.011101196, 0.012764047, 0.004323413, 0.00072375603, 0.029904718, -0.022428539, -0.025927177, 0.018717054, -0.0036749013, 0.013701894, 0.009105774, -0.011034681, 0.00021679424, -0.0052479585, 0.03086252, 0.0067744562, -0.0054541524, 0.004406556, 0.009963805, 0.005267913, -0.02527534, -0.008407376, 0.012943635, 0.002560791, 0.025647819, -0.020752383, -0.05954338, -0.0058565624, -0.011779639, -0.040839627, -0.0024344143, 0.0059663104, 0.03863136, -0.0069041587, -0.015657408, -0.011666564, -0.015205112, -0.03341666, -0.029798295, -0.010615642, -0.027935902, -0.010376192, -0.015457866, -0.0058731907, -0.010569083, -0.024224417, 0.013608775, 0.014234007, -0.033363447, -0.045096528, -0.015923465, 0.0036283415, 0.017173927, 0.022801017, -0.015151901, -0.010475963, 0.024397355, 0.014673, -0.027669845, -0.031687293, -0.007269986, 0.018437695, -0.0033739253, -0.0021567182, -0.014978965, -0.0095447665, 0.013688592, 0.0038511637, -0.0029681895, 0.027430395, 0.023519369, -0.009897291, -0.000513821, 0.01665512, 0.01098147, -0.0037447412, -0.0016803113, 0.0038511637, -0.013296159, -0.020433117, 0.0064053033, -0.011054635, 0.0059862644, 0.024370749, 0.017812463, -0.002056947, -0.01779916, 0.017439984, -0.0075094365, 0.01216542, -0.038924024, -0.00093202817, -0.015165204, 0.027137732, -0.001515689, -0.008041549, -0.0061592013, -0.0036948556, -0.018357879, -0.007848658, 0.01874366, 0.0063820234, -0.02669874, 0.019954216, -0.006395326, -0.022242298, 0.011673216, -0.018184941, 0.009238802, -0.0025774194, -0.0058765165, -0.0006709605, -0.015870253, 0.0021317753, -0.019821187, -0.00984408, -0.0030230635, -0.04185064, -0.02566112, -0.006362069, -0.0018457649, -0.011333995, -0.03530566, -0.0014400292, 0.020526236, 0.01297024, 0.030064352, -0.019701462, -0.00020172464, -0.021696884, -0.012145466, -0.018584026, -0.012105557, -0.0092720585, -0.021976242, 0.034241434, 0.03535887, 0.04469744, -0.008906231, 0.03299097, -0.00036458013, 0.015989978, -0.018584026, 0.018969808, -0.00742962, -0.023027165, 0.0007067118, 0.011546839, 0.0062988806, 0.019821187, -0.016322548, 0.002560791, 0.004429836, -0.022308813, -0.012631019, -0.016788147, -0.040626783, -0.018171638, 0.0046460065, 0.01306336, 0.014034465, -0.0203533, -0.0033789137, 0.047464427, 0.009165636, 0.020020729, -0.01046266, 0.019634947, -0.0052712387, -0.0128239095, 0.026153324, -0.00975096, -0.0046792636, -0.004060683, -0.021683581, 0.010010364, 0.01789228, 0.0033007597, 0.027137732, -0.005108279, -0.0015090376, 0.00395426, 0.02324001, 0.012145466, 0.0030929034, -0.007449574, -0.039802007, 0.013928043, -0.034347855, -0.0073498026, -0.033629503, 0.00042132495, 0.005400941, -0.013967951, 0.020246876, -0.012843863, -0.029292788, 0.028707465, 0.012138815, 0.036795575, -0.006811039, 0.02026018, 0.003222606, 0.0071502607, -0.00871334, 0.010675506, -0.003777998, -0.0061592013, 0.02181661, 0.006375372, -0.017453287, -0.0025408368, -0.0074030138, 0.014140887, -0.016974386, -0.037753377, -0.000111722824, -0.008340862, 0.019209258, -0.006797736, -0.018118428, -0.011067938, 0.012717486, -0.010409449, -0.0015148575, -0.006229041, -0.020127151, -0.0025009282, -0.011067938, -0.0014583205, 0.013901437, 0.025488185, 0.013602124, 0.012172071, 0.008633524, -0.0027320646, -0.0077422354, -0.02257487, 0.015684014, 0.0005420895, -0.0065616113, -0.008693387, -0.0037480667, 0.0006767805, -0.01173973, -0.0008389085, 0.023745516, -0.028946916, -0.01406107, -0.01382162, -0.01334937, 0.02693819, -0.0014134236, -0.02026018, 0.0077089784, -0.025980389, 0.0072167744, 0.0127840005, 0.0040872884, -0.018238153, -0.0007191832, -0.0053311014, -0.023545975, -0.005923076, -0.006046127, 0.026020298, -0.024836347, -0.0027586704, 0.00563374, 0.0058565624, 0.005936379, 0.021803306, -0.0008513799, -0.0025341853, 0.041983668, -0.022255601, -0.0071170037, 0.009478252, 0.024410658, -0.022495052, 0.008547056, -0.018198244, 0.0049619484, 0.008054852, -0.0032026516, -0.006172504, 0.0050716964, -0.005500712, 0.020938624, 0.0016736598, 0.0113606, 0.0010858419, 0.02163037, -0.00080980855, -0.028946916, -0.0060660816, 0.0063155093, 0.0016462228, -0.001173973, 0.008154622, -0.013928043, -0.006172504, 0.00197713, 0.0035751304, 0.0042768535, -0.031580873, -0.005367684, 0.01964825, -0.008101411, 0.020366604, -0.010276421, -0.024690015, -0.012231934, -0.021550553, -0.007748887, 0.024304235, -0.017772555, 0.00010133, 0.03200656, 0.027297366, -0.027856085, -0.00005991461, 0.0026871676, 0.0034287993, 0.003187686, -0.044165332, -0.0128106065, 0.0048522, 0.028654253, 0.01652209, -0.020765686, -0.021084955, -0.004932017, -0.029000126, 0.0044996757, 0.0033007597, 0.007828704, 0.017120717, 0.021563856, 0.018863386, 0.026033599, 0.0027121105, -0.0009860708, 0.0016636827, -0.011047984, 0.0029731782, -0.014752816, -0.012231934, 0.0016761541, -0.010575734, 0.017386774, 0.024862953, 0.01216542, -0.0060660816, 0.0082876505, -0.006784433, -0.0053643584, -0.011034681, 0.011460371, 0.007549345, 0.016468879, 0.0026539105, -0.037407503, -0.027483607, -0.0027819502, -0.015072084, -0.0024294257, -0.00047391263, -0.012491339, 0.005234656, 0.011327343, -0.0122917965, 0.010815185, 0.0042236424, -0.0051714676, 0.035518505, -0.01263767, 0.010389495, 0.013781711, 0.021430828, -0.0028916984, 0.014127584, 0.011234223, 0.0054741064, -0.010216558, -0.0046692863, -0.011413812, 0.031261604, -0.008527101, -0.002853453, -0.025807451, 0.0097775655, -0.0035485246, 0.005637066, -0.010708762, 0.0005890651, 0.00032695814, -0.01187941, 0.007988337, -0.033097394, 0.0056403917, -0.002717099, -0.03871118, 0.0002675112, -0.008300954, -0.028015718, 0.0069706724, -0.0013643695, -0.029292788, 0.027669845, 0.027669845, -0.018610632, 0.014992267, 0.2409405, -0.019062927, -0.034587305, 0.026951494, -0.019302377, 0.010502568, 0.011886061, -0.0018241479, -0.00371481, 0.0039210035, -0.00024714126, 0.018384485, 0.0014167493, -0.0087798545, -0.008560359, -0.004200362, -0.041265316, -0.006428583, -0.015391352, -0.0007075432, 0.01746659, -0.016761541, -0.033017576, -0.018544117, -0.008879626, 0.010123438, -0.024091389, -0.003162743, 0.003937632, 0.0046992176, -0.010243164, -0.00027312333, 0.0047125206, 0.0038977235, 0.003688204, -0.022481749, 0.0148060275, 0.0063487664, 0.031501055, 0.0076890243, 0.014739513, 0.017785857, 0.011101196, -0.0056403917, -0.00013697738, 0.032591887, -0.0015090376, -0.007090398, -0.00010548713, 0.013715197, -0.0076158587, -0.0039210035, 0.0053311014, 0.02072578, -0.0064684916, 0.003967563, 0.00056911085, 0.02092532, 0.013116571, 0.018051913, -0.029000126, 0.017000992, 0.008646826, 0.010662203, -0.044511203, 0.011420462, -0.039216686, 0.014500063, 0.016588604, -0.0017044225, -0.004014123, -0.03251207, -0.01457988, -0.025581304, -0.03243225, -0.011826199, 0.036343277, 0.030383619, 0.008773203, 0.01774595, 0.015111992, -0.0010293049, 0.011892713, 0.00523133, -0.034720335, -0.03748732, 0.011187663, -0.022175785, -0.026100114, -0.002470997, -0.0042635505, -0.030090956, -0.0042801793, 0.0031211718, 0.009817474, 0.0020303414, 0.03474694, -0.00527789, -0.019235864, -0.0011365588, -0.01552438, -0.009119077, -0.019382196, 0.0012878784, 0.0018973133, 0.00655496, 0.005780071, -0.009265407, 0.018251456, -0.013402581, -0.014486761, -0.024610199, -0.005041765, -0.00025711837, -0.0064585144, 0.004532933, -0.017147323, -0.0018141707, 0.035092812, -0.0012163757, -0.01406107, -0.0025109055, -0.011034681, 0.0049054115, -0.006664708, -0.011906016, -0.0057301857, 0.012983543, -0.0137285, -0.0049586226, 0.024596896, -0.009744309, -0.009238802, 0.015151901, -0.007582602, -0.0038977235, 0.016681723, -0.021723488, 0.00286343, -0.008500496, -0.026672134, 0.010655551, 0.021071652, -0.006937416, -0.010116788, -0.005065045, 0.031740505, 0.0022980606, 0.0011598388, 0.012172071, -0.043446977, 0.009012654, -0.017772555, 0.01339593, 0.016455576, 0.011227572, -0.015630802, -0.0060527786, 0.007236729, 0.012817258, -0.056989238, 0.013575518, -0.002695482, -0.0020985184, -0.026845071, -0.024862953, -0.1709145, 0.008280999, 0.02727076, -0.025129009, 0.03019738, -0.012345008, 0.018996414, 0.0006668034, -0.0051947474, -0.009152333, 0.009504858, 0.01320969, -0.023825333, -0.005693603, 0.0198877, 0.004014123, -0.0063687204, 0.022707896, 0.01334937, 0.0010309678, 0.019488618, -0.011639959, 0.005058394, 0.0059297276, 0.00070588035, 0.007549345, 0.000009899161, 0.029931324, -0.008979397, -0.033629503, -0.014978965, -0.038072642, 0.044324964, 0.011373903, 0.01628264, 0.013901437, 0.004951971, -0.016601907, -0.02281432, 0.017559709, 0.016961083, 0.032059774, 0.00042444278, -0.0041438253, -0.016907873, 0.0022099293, 0.020858807, -0.018889992, 0.008839717, 0.00790187, 0.01524502, -0.013848226, -0.0042568995, 0.0057967, 0.042781837, 0.020912018, 0.008433982, -0.0076690703, -0.0026206537, -0.010183302, -0.018424392, -0.0011939273, 0.017506499, -0.002670539, -0.016176218, 0.00080315716, -0.0009428367, 0.02617993, -0.036715757, 0.03700842, -0.01524502, 0.007682373, 0.0028767327, 0.0015846973, 0.004606098, 0.012012438, -0.027616635, 0.000984408, 0.010549129, -0.019235864, -0.03227262, 0.004935343, -0.017759252, 0.014659697, -0.010349587, -0.0044198586, 0.01363538, 0.012364962, -0.03019738, -0.014114282, 0.021457434, -0.01931568, -0.015111992, -0.0047823605, -0.006079384, 0.016535394, 0.026858374, 0.006498423, 0.0070504895, -0.015830344, -0.012225282, 0.011254177, 0.014965662, -0.0009461624, 0.034108404, 0.00038183221, -0.0032708284, -0.0061226184, 0.0354919, -0.02958545, -0.013462444, 0.011553491, -0.005367684, 0.014513366, -0.030303802, 0.0060660816, -0.0070172325, -0.00871334, 0.01916935, 0.0015622488, 0.046932314, 0.0077023273, 0.0060228473, 0.022761108, -0.0058997963, -0.011506931, -0.08322238, -0.047624063, 0.0045794924, 0.017346865, -0.003947609, 0.027430395, -0.009351876, 0.027723057, -0.02068587, 0.027297366, 0.0008596941, -0.030463435, -0.015045478, -0.0058898195, 0.025780847, -0.02995793, -0.0027719731, 0.004795663, -0.028840493, 0.021417525, -0.000939511, 0.003565153, 0.024969375, -0.02527534, -0.013356022, 0.009591326, -0.03291115, 0.024197811, -0.0008580313, -0.00771563, -0.00527789, -0.024729924, 0.0066680335, -0.03895063, -0.0033589597, 0.010595689, -0.004316762, -0.006325486, 0.0058931448, -0.032884546, 0.018184941, 0.0010758648, 0.0025208825, -0.020818898, -0.022761108, -0.0057168826, 0.013382628, 0.02125789, -0.0024743227, -0.02971848, -0.03602401, -0.02673865, 0.0011573446, 0.0015364746, 0.011234223, 0.011706473, 0.033868954, 0.0028501272, 0.00035730517, -0.0059097735, 0.005783397, 0.0030247264, -0.024862953, 0.00776219, -0.0074562253, 0.0053144726, -0.023306524, 0.0076092076, 0.013648683, 0.00057908794, 0.0051382105, 0.022521658, 0.0013876494, 0.03086252, -0.03365611, -0.02044642, -0.0092986645, -0.02205606, 0.027457, 0.0074961334, -0.021989545, -0.017519802, -0.012571156, -0.011819547, 0.0153115345, -0.0039077005, -0.0045362585, 0.0026140022, -0.005849911, -0.015750527, -0.018051913, -0.005337753, -0.023918454, -0.026073508, -0.008374119, 0.0077089784, 0.011054635, -0.0023961687, 0.0090259565, 0.014406944, -0.021750094, -0.0074894824, -0.0591709, 0.02253496, 0.0014599834, 0.004350019, 0.0038212324, -0.028813887, 0.010243164, -0.017918885, 0.0023878545, -0.004110568, -0.021590462, 0.000025553347, 0.005510689, -0.0035684789, -0.00048097974, -0.0077887955, 0.036609333, 0.009897291, 0.019954216, -0.0024643457, 0.015444563, -0.0006414449, 0.015457866, -0.010855094, 0.016269337, 0.010988121, -0.025381763, 0.01765283, 0.02072578, -0.01765283, 0.0087798545, -0.027802873, -0.007043838, 0.030383619, 0.00022988919, 0.0008305942, -0.013901437, 0.006538331, 0.00800164, 0.02617993, -0.037646953, -0.03370932, 0.016841358, -0.019754672, -0.00086301984, 0.0052812155, 0.0035418733, -0.01249799, 0.0095314635, 0.01406107, -0.006588217, 0.0340818, -0.047544245, -0.026712043, -0.012052346, -0.014832634, 0.017479893, 0.014034465, -0.0024726598, 0.017932188, 0.041983668, 0.005883168, 0.009225499, -0.009917245, 0.020699173, -0.00097193656, -0.007895218, 0.0043333904, 0.014899148, -0.0029016754, 0.0057202084, -0.00842733, 0.0041404995, 0.021949638, -0.0040207743, -0.00035688945, -0.0012354985, -0.0062257154, -0.02727076, 0.0321928, 0.0023396318, -0.011440417, -0.02072578, 0.019182652, 0.012930332, 0.022561567, 0.0004510484, -0.0020586099, -0.0016628513, 0.0071968203, -0.023359735, -0.005949682, 0.015417957, 0.029638661, 0.0045262813, 0.012697533, 0.0070704436, -0.002615665, 0.032538675, 0.01098147, 0.0033572968, -0.015564288, -0.008367468, -0.009345224, -0.02958545, -0.0025890595, -0.027483607, -0.016362457, 0.008054852, 0.02457029, 0.034401067, 0.009438344, 0.0012163757, 0.0050816736, -0.015670711, 0.02480974, 0.018637238, -0.00641528, -0.04743782, 0.022282207, 0.017213836, -0.016109703, 0.019089533, -0.02338634, 0.022322116, 0.010821836, 0.016149612, -0.016495485, 0.010582386, 0.002918304, -0.0028817214, 0.0056104604, -0.016575301, -0.012451431, -0.002672202, -0.019621646, -0.01718723, 0.013941345, -0.031447843, 0.06608836, 0.015989978, -0.002504254, 0.010582386, 0.011486976, 0.0044896984, 0.011220921, 0.027776267, -0.02214918, -0.01500557, 0.0044697444, -0.0014583205, 0.0050484166, -0.015976675, 0.00070962176, 0.0009877337, 0.0153115345, 0.03871118, 0.0054042665, 0.015896859, 0.01004299468, 0.024862953, 0.0069507184, -0.0017975422, -0.017932188, -0.026273051, -0.0014624777, -0.012850515, -0.002941584, -0.014194098, 0.0005799194, 0.009484904, -0.028388197, -0.023745516, -0.000735396, 0.012418173, 0.013222993, -0.008719992, -0.01600328, -0.0037414155, 0.0018041936, 0.025967086, -0.01093491, -0.025315247, -0.003187686, 0.008926186, -0.013848226, -0.010249815, -0.012631019 ],
+        ..Default::default()
     }
 }
 
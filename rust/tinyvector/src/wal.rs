@@ -0,0 +1,625 @@
+use crate::database::{DBError, EmbeddingRecord, TableOptions, VectorField};
+use crate::embedding::EmbeddingBinding;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufReader, ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use tracing::warn;
+
+/// A mutation of the database, the unit the write-ahead log is made of.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WalEntry {
+    /// Create a table, bound to `embedding` if given.
+    CreateTable {
+        table_name: String,
+        dimension: usize,
+        options: TableOptions,
+        fields: BTreeMap<String, VectorField>,
+        embedding: Option<EmbeddingBinding>,
+    },
+    DropTable {
+        table_name: String,
+    },
+    /// Insert a batch of records, `upsert` replaces the existing ones with the same id.
+    InsertRecords {
+        table_name: String,
+        records: Vec<EmbeddingRecord>,
+        upsert: bool,
+    },
+    DeleteRecord {
+        table_name: String,
+        id: String,
+    },
+    /// Bind or unbind the embedding model of a table.
+    BindEmbedding {
+        table_name: String,
+        binding: Option<EmbeddingBinding>,
+    },
+    /// Remove the records of a table expired by `now`, in unix seconds.
+    ExpireRecords {
+        table_name: String,
+        now: u64,
+    },
+}
+
+impl WalEntry {
+    /// The table the entry changes, every kind of entry names one.
+    pub fn table_name(&self) -> &str {
+        match self {
+            WalEntry::CreateTable { table_name, .. }
+            | WalEntry::DropTable { table_name }
+            | WalEntry::InsertRecords { table_name, .. }
+            | WalEntry::DeleteRecord { table_name, .. }
+            | WalEntry::BindEmbedding { table_name, .. }
+            | WalEntry::ExpireRecords { table_name, .. } => table_name,
+        }
+    }
+}
+
+/// An entry tagged with its sequence number.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WalRecord {
+    pub seq: u64,
+    pub entry: WalEntry,
+}
+
+/// When appended entries are fsynced.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncPolicy {
+    /// fsync after every entry before the request returns.
+    Always,
+    /// Acknowledge entries before they are fsynced, once `max_batch` are pending or by the
+    /// periodic sync task every 10ms.
+    ///
+    /// The acknowledged entries not fsynced yet survive a crash of the process but are lost on
+    /// a crash of the OS or a power failure.
+    #[serde(alias = "group_commit")]
+    Deferred { max_batch: usize },
+}
+
+/// Entries pending before a deferred sync log is fsynced, when not given.
+const DEFAULT_MAX_BATCH: usize = 64;
+
+/// `always`, `deferred` or `deferred:<max_batch>`, for the command line.
+impl FromStr for SyncPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            None if s == "always" => Ok(Self::Always),
+            None if s == "deferred" => Ok(Self::Deferred {
+                max_batch: DEFAULT_MAX_BATCH,
+            }),
+            Some(("deferred", max_batch)) => match max_batch.parse() {
+                Ok(max_batch) if max_batch > 0 => Ok(Self::Deferred { max_batch }),
+                _ => Err(format!("Invalid max batch {:?}", max_batch)),
+            },
+            _ => Err(format!("Expected always, deferred or deferred:<max_batch>, got {:?}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default, rename_all = "snake_case")]
+pub struct WalOptions {
+    pub sync: SyncPolicy,
+    /// Compact the log into a snapshot once it grows beyond this many bytes
+    /// and beyond the size of the last snapshot.
+    pub compact_threshold: u64,
+}
+
+impl Default for WalOptions {
+    fn default() -> Self {
+        Self {
+            sync: SyncPolicy::Always,
+            compact_threshold: 64 * 1024 * 1024,
+        }
+    }
+}
+
+/// Every frame starts with the payload length and its crc32.
+const FRAME_HEADER_SIZE: usize = 8;
+/// Start of every log file.
+const MAGIC: [u8; 8] = *b"TVWAL\0\0\0";
+/// `magic | version: u32`, little endian.
+const FILE_HEADER_SIZE: usize = 12;
+/// Version written by this build, the only one read.
+pub const WAL_VERSION: u32 = 1;
+
+/// Append-only log of `WalRecord`s.
+///
+/// File layout: `magic | version: u32` followed by frames of
+/// `len: u32 | crc32: u32 | bincode(WalRecord)`, all little endian.
+#[derive(Debug)]
+pub struct Wal {
+    path: PathBuf,
+    file: File,
+    options: WalOptions,
+    /// Entries written since the last fsync.
+    pending: usize,
+    size: u64,
+}
+
+impl Wal {
+    /// Open the log at `path` and return the records that survived on disk.
+    ///
+    /// A torn tail, left behind by a crash in the middle of an append, is cut off so that new
+    /// entries are appended after the last valid frame. A frame with a valid checksum that does
+    /// not decode is an error, not a torn write. A log without a header is created anew.
+    pub fn open(path: &Path, options: WalOptions) -> Result<(Self, Vec<WalRecord>)> {
+        let data = match fs::read(path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e.into()),
+        };
+
+        let has_header = Self::check_header(&data)?;
+        let (records, valid_len) = match has_header {
+            true => {
+                let (records, len) = Self::decode(&data[FILE_HEADER_SIZE..])?;
+                (records, FILE_HEADER_SIZE + len)
+            }
+            false => (Vec::new(), 0),
+        };
+        if valid_len < data.len() {
+            warn!(
+                "Truncating {} bytes of torn write-ahead log tail at {:?}",
+                data.len() - valid_len,
+                path
+            );
+        }
+
+        if !has_header {
+            Self::create(path)?;
+        }
+        let file = OpenOptions::new().append(true).open(path)?;
+        if has_header {
+            file.set_len(valid_len as u64)?;
+            file.sync_all()?;
+        }
+
+        let wal = Self {
+            path: path.to_path_buf(),
+            size: file.metadata()?.len(),
+            file,
+            options,
+            pending: 0,
+        };
+        Ok((wal, records))
+    }
+
+    /// Read the records of the log at `path` without opening it for appends, for the readers
+    /// of the change history. A torn tail is left alone.
+    pub fn read(path: &Path) -> Result<Vec<WalRecord>> {
+        let data = fs::read(path)?;
+        if !Self::check_header(&data)? {
+            return Ok(Vec::new());
+        }
+        Ok(Self::decode(&data[FILE_HEADER_SIZE..])?.0)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Check the header of the log in `data`, `false` when it is too short to hold one, as
+    /// left by a crash while the log was created or reset.
+    fn check_header(data: &[u8]) -> Result<bool> {
+        if data.len() < FILE_HEADER_SIZE {
+            return Ok(false);
+        }
+        if !data.starts_with(&MAGIC) {
+            bail!("Not a write-ahead log");
+        }
+        let version = u32::from_le_bytes(data[8..12].try_into().unwrap());
+        if version != WAL_VERSION {
+            bail!("Write-ahead log version {} is not supported", version);
+        }
+        Ok(true)
+    }
+
+    fn file_header() -> Vec<u8> {
+        let mut header = MAGIC.to_vec();
+        header.extend_from_slice(&WAL_VERSION.to_le_bytes());
+        header
+    }
+
+    /// Replace the log with an empty one, through a temporary file.
+    fn create(path: &Path) -> Result<()> {
+        let tmp_path = path.with_extension("tmp");
+        let mut file = File::create(&tmp_path)?;
+        file.write_all(&Self::file_header())?;
+        file.sync_all()?;
+        fs::rename(&tmp_path, path)?;
+        sync_dir(path)
+    }
+
+    /// Decode frames until the first incomplete one or the first whose checksum does not match,
+    /// returns the records and the length of the valid frames.
+    fn decode(data: &[u8]) -> Result<(Vec<WalRecord>, usize)> {
+        let mut records = Vec::new();
+        let mut offset = 0;
+        while data.len() - offset >= FRAME_HEADER_SIZE {
+            let len = u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap()) as usize;
+            let crc = u32::from_le_bytes(data[offset + 4..offset + 8].try_into().unwrap());
+            let start = offset + FRAME_HEADER_SIZE;
+            let Some(payload) = data.get(start..start + len) else {
+                break;
+            };
+            if crc32fast::hash(payload) != crc {
+                break;
+            }
+            let record = bincode::deserialize(payload).with_context(|| {
+                format!("Corrupted write-ahead log entry at offset {}", offset)
+            })?;
+            records.push(record);
+            offset = start + len;
+        }
+        Ok((records, offset))
+    }
+
+    pub fn append(&mut self, record: &WalRecord) -> Result<(), DBError> {
+        self.try_append(record)
+            .map_err(|e| DBError::Storage(e.to_string()))
+    }
+
+    fn frame(record: &WalRecord) -> Result<Vec<u8>> {
+        let payload = bincode::serialize(record)?;
+        let mut frame = Vec::with_capacity(FRAME_HEADER_SIZE + payload.len());
+        frame.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        frame.extend_from_slice(&crc32fast::hash(&payload).to_le_bytes());
+        frame.extend_from_slice(&payload);
+        Ok(frame)
+    }
+
+    fn try_append(&mut self, record: &WalRecord) -> Result<()> {
+        let frame = Self::frame(record)?;
+
+        // One write per frame, so a crash can only tear the last frame.
+        self.file.write_all(&frame)?;
+        self.size += frame.len() as u64;
+        self.pending += 1;
+
+        let sync = match self.options.sync {
+            SyncPolicy::Always => true,
+            SyncPolicy::Deferred { max_batch } => self.pending >= max_batch,
+        };
+        if sync {
+            self.sync()?;
+        }
+        Ok(())
+    }
+
+    pub fn sync(&mut self) -> Result<()> {
+        if self.pending > 0 {
+            self.file.sync_data()?;
+            self.pending = 0;
+        }
+        Ok(())
+    }
+
+    /// A second handle on the log file for deferred syncs, used to fsync without the database
+    /// lock.
+    pub fn deferred_sync_handle(&self) -> Option<File> {
+        match self.options.sync {
+            SyncPolicy::Always => None,
+            SyncPolicy::Deferred { .. } => self.file.try_clone().ok(),
+        }
+    }
+
+    /// Growing the threshold with the snapshot keeps the compaction cost linear in the
+    /// amount of data written, even during a bulk import.
+    pub fn needs_compaction(&self, snapshot_size: u64) -> bool {
+        self.size >= self.options.compact_threshold.max(snapshot_size)
+    }
+
+    /// Where `rotate` moves the entries of the log at `path`.
+    pub fn rotated_path(path: &Path) -> PathBuf {
+        path.with_extension("old")
+    }
+
+    /// Move the entries to the rotated log and start over with an empty one, so that a
+    /// snapshot covering them can be written while new entries are appended. The rotated log
+    /// is removed once the snapshot is saved.
+    ///
+    /// The entries are appended to a rotated log left behind by a failed snapshot.
+    pub fn rotate(&mut self) -> Result<PathBuf> {
+        self.file.sync_data()?;
+        let rotated = Self::rotated_path(&self.path);
+        if rotated.exists() {
+            let data = fs::read(&self.path)?;
+            let mut file = OpenOptions::new().append(true).open(&rotated)?;
+            file.write_all(&data[FILE_HEADER_SIZE.min(data.len())..])?;
+            file.sync_data()?;
+        } else {
+            fs::rename(&self.path, &rotated)?;
+            self.file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+            sync_dir(&self.path)?;
+        }
+        self.reset()?;
+        Ok(rotated)
+    }
+
+    /// Drop every entry, called once they are all covered by a snapshot.
+    pub fn reset(&mut self) -> Result<()> {
+        // An empty file left by a crash in between is read as an empty log.
+        self.file.set_len(0)?;
+        let header = Self::file_header();
+        self.file.write_all(&header)?;
+        self.file.sync_all()?;
+        self.size = header.len() as u64;
+        self.pending = 0;
+        Ok(())
+    }
+}
+
+/// Reads a log one frame at a time into a reused buffer, for the readers of the change
+/// history. Like `Wal::read` it stops at a torn tail.
+pub struct WalReader {
+    reader: BufReader<File>,
+    /// Bytes of the file not read yet.
+    remaining: u64,
+    /// Payload of the current frame.
+    payload: Vec<u8>,
+}
+
+impl WalReader {
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = File::open(path)?;
+        let size = file.metadata()?.len();
+        let mut reader = BufReader::new(file);
+        let mut header = Vec::with_capacity(FILE_HEADER_SIZE);
+        Read::by_ref(&mut reader)
+            .take(FILE_HEADER_SIZE as u64)
+            .read_to_end(&mut header)?;
+        Wal::check_header(&header)?;
+        Ok(Self {
+            reader,
+            remaining: size - header.len() as u64,
+            payload: Vec::new(),
+        })
+    }
+
+    /// Move to the next frame, `false` at the end of the log or at a torn tail.
+    ///
+    /// A log truncated meanwhile by a checkpoint ends early, the reader sees a gap.
+    pub fn advance(&mut self) -> Result<bool> {
+        let mut header = [0; FRAME_HEADER_SIZE];
+        if self.remaining < FRAME_HEADER_SIZE as u64 || !self.read(&mut header)? {
+            return Ok(false);
+        }
+        let len = u32::from_le_bytes(header[..4].try_into().unwrap()) as u64;
+        let crc = u32::from_le_bytes(header[4..].try_into().unwrap());
+        self.remaining -= FRAME_HEADER_SIZE as u64;
+        if len > self.remaining {
+            return Ok(false);
+        }
+        let mut payload = std::mem::take(&mut self.payload);
+        payload.resize(len as usize, 0);
+        let read = self.read(&mut payload)?;
+        self.payload = payload;
+        if !read {
+            return Ok(false);
+        }
+        self.remaining -= len;
+        if crc32fast::hash(&self.payload) != crc {
+            return Ok(false);
+        }
+        if self.payload.len() < 8 {
+            bail!("Corrupted write-ahead log entry of {} bytes", self.payload.len());
+        }
+        Ok(true)
+    }
+
+    /// Fill `buf`, `false` when the file ends first.
+    fn read(&mut self, buf: &mut [u8]) -> Result<bool> {
+        match self.reader.read_exact(buf) {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Sequence number of the current frame, read without decoding it, the record starts with
+    /// it.
+    pub fn seq(&self) -> u64 {
+        u64::from_le_bytes(self.payload[..8].try_into().unwrap())
+    }
+
+    /// Table of the current frame read without decoding the rest, `None` when it does not
+    /// hold one.
+    pub fn table_name(&self) -> Option<&str> {
+        // bincode writes the variant as a u32 and a string as its u64 length followed by the
+        // bytes, `table_name` is the first field of every entry.
+        let len = self.payload.get(12..20)?;
+        let len = u64::from_le_bytes(len.try_into().unwrap()) as usize;
+        let name = self.payload.get(20..20usize.checked_add(len)?)?;
+        std::str::from_utf8(name).ok()
+    }
+
+    /// Bytes of the current frame.
+    pub fn size(&self) -> usize {
+        self.payload.len()
+    }
+
+    /// Decode the current frame.
+    pub fn record(&self) -> Result<WalRecord> {
+        bincode::deserialize(&self.payload).context("Corrupted write-ahead log entry")
+    }
+}
+
+/// fsync the directory of `path`, so that a rename into it survives a crash.
+pub fn sync_dir(path: &Path) -> Result<()> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    File::open(dir)?.sync_all()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_records(path: &Path) -> Vec<WalRecord> {
+        let data = fs::read(path).unwrap();
+        assert!(Wal::check_header(&data).unwrap());
+        Wal::decode(&data[FILE_HEADER_SIZE..]).unwrap().0
+    }
+
+    fn insert(seq: u64, id: &str) -> WalRecord {
+        WalRecord {
+            seq,
+            entry: WalEntry::InsertRecords {
+                table_name: "table".to_string(),
+                records: vec![EmbeddingRecord {
+                    id: id.to_string(),
+                    embedding: vec![1., 2., 3., 4.],
+                    ..Default::default()
+                }],
+                upsert: false,
+            },
+        }
+    }
+
+    #[test]
+    fn test_wal_append_and_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("wal");
+
+        let (mut wal, records) = Wal::open(&path, WalOptions::default()).unwrap();
+        assert!(records.is_empty());
+        wal.append(&insert(1, "a")).unwrap();
+        wal.append(&insert(2, "b")).unwrap();
+        drop(wal);
+
+        let (_, records) = Wal::open(&path, WalOptions::default()).unwrap();
+        assert_eq!(vec![1, 2], records.iter().map(|r| r.seq).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_wal_rotate() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("wal");
+        let seqs = |records: Vec<WalRecord>| records.iter().map(|r| r.seq).collect::<Vec<_>>();
+
+        let (mut wal, _) = Wal::open(&path, WalOptions::default()).unwrap();
+        wal.append(&insert(1, "a")).unwrap();
+        let rotated = wal.rotate().unwrap();
+        wal.append(&insert(2, "b")).unwrap();
+        assert_eq!(vec![1], seqs(read_records(&rotated)));
+        assert_eq!(vec![2], seqs(read_records(&path)));
+
+        // The snapshot failed, the rotated log keeps growing.
+        wal.rotate().unwrap();
+        wal.append(&insert(3, "c")).unwrap();
+        assert_eq!(vec![1, 2], seqs(read_records(&rotated)));
+        assert_eq!(vec![3], seqs(read_records(&path)));
+    }
+
+    #[test]
+    fn test_wal_reader() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("wal");
+        let (mut wal, _) = Wal::open(&path, WalOptions::default()).unwrap();
+        wal.append(&insert(1, "a")).unwrap();
+        let entry = WalEntry::DropTable {
+            table_name: "other".to_string(),
+        };
+        wal.append(&WalRecord { seq: 2, entry }).unwrap();
+        wal.append(&insert(3, "b")).unwrap();
+        drop(wal);
+        let len = fs::metadata(&path).unwrap().len();
+        OpenOptions::new().write(true).open(&path).unwrap().set_len(len - 3).unwrap();
+
+        // Frames are read without decoding them, up to the torn tail.
+        let mut reader = WalReader::open(&path).unwrap();
+        let mut frames = Vec::new();
+        while reader.advance().unwrap() {
+            frames.push((reader.seq(), reader.table_name().unwrap().to_string()));
+        }
+        assert_eq!(vec![(1, "table".to_string()), (2, "other".to_string())], frames);
+        let record = reader.record().unwrap();
+        assert_eq!((2, "other"), (record.seq, record.entry.table_name()));
+    }
+
+    #[test]
+    fn test_wal_torn_tail_is_truncated() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("wal");
+
+        let (mut wal, _) = Wal::open(&path, WalOptions::default()).unwrap();
+        wal.append(&insert(1, "a")).unwrap();
+        wal.append(&insert(2, "b")).unwrap();
+        drop(wal);
+
+        // Simulate a crash in the middle of the second append.
+        let len = fs::metadata(&path).unwrap().len();
+        OpenOptions::new()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_len(len - 3)
+            .unwrap();
+
+        let (mut wal, records) = Wal::open(&path, WalOptions::default()).unwrap();
+        assert_eq!(1, records.len());
+        wal.append(&insert(2, "c")).unwrap();
+        drop(wal);
+
+        let records = read_records(&path);
+        assert_eq!(2, records.len());
+        match &records[1].entry {
+            WalEntry::InsertRecords { records, .. } => assert_eq!("c", records[0].id),
+            entry => panic!("unexpected entry {:?}", entry),
+        }
+    }
+
+    #[test]
+    fn test_wal_corrupted_entry_is_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("wal");
+
+        let (mut wal, _) = Wal::open(&path, WalOptions::default()).unwrap();
+        wal.append(&insert(1, "a")).unwrap();
+        drop(wal);
+        // A frame with a matching checksum but a payload that is not a record.
+        let payload = [0xff; 16];
+        let mut data = fs::read(&path).unwrap();
+        data.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        data.extend_from_slice(&crc32fast::hash(&payload).to_le_bytes());
+        data.extend_from_slice(&payload);
+        fs::write(&path, &data).unwrap();
+        let e = Wal::open(&path, WalOptions::default()).unwrap_err();
+        assert!(e.to_string().starts_with("Corrupted write-ahead log entry"), "{e}");
+        // Nothing was cut off.
+        assert_eq!(data.len() as u64, fs::metadata(&path).unwrap().len());
+    }
+
+    #[test]
+    fn test_wal_header() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("wal");
+
+        // A crash while the log was created leaves it without its header.
+        fs::write(&path, &MAGIC[..4]).unwrap();
+        let (mut wal, records) = Wal::open(&path, WalOptions::default()).unwrap();
+        assert!(records.is_empty());
+        wal.append(&insert(1, "a")).unwrap();
+        drop(wal);
+        assert_eq!(1, read_records(&path).len());
+
+        let mut other = fs::read(&path).unwrap();
+        other[8] = WAL_VERSION as u8 + 1;
+        fs::write(&path, other).unwrap();
+        assert!(Wal::open(&path, WalOptions::default()).is_err());
+        fs::write(&path, b"not a write-ahead log").unwrap();
+        assert!(Wal::open(&path, WalOptions::default()).is_err());
+    }
+}
@@ -0,0 +1,146 @@
+use crate::database::DBError;
+use http::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::convert::Infallible;
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct BaseHttpResponse<T> {
+    result: T,
+    success: bool,
+    result_code: usize,
+}
+
+impl<T> BaseHttpResponse<T> {
+    pub fn into_result(self) -> T {
+        self.result
+    }
+}
+
+pub fn generate_base_response<T>(
+    result: T,
+    success: bool,
+    result_code: usize,
+) -> BaseHttpResponse<T> {
+    BaseHttpResponse {
+        result,
+        success,
+        result_code,
+    }
+}
+
+/// Error body of a failed request.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub struct ApiError {
+    #[serde(skip)]
+    pub status: StatusCode,
+    /// Stable machine-readable code, e.g. `table_not_found`.
+    pub code: String,
+    /// Human readable description.
+    pub message: String,
+    /// Text of the error variants carrying one, e.g. what was wrong with an `invalid_input`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+impl ApiError {
+    pub fn new(status: StatusCode, code: &str, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            code: code.to_string(),
+            message: message.into(),
+            detail: None,
+        }
+    }
+
+    /// The `DBError` this error was made from, `None` for the errors of the HTTP layer like
+    /// `route_not_found`.
+    pub fn to_db_error(&self) -> Option<DBError> {
+        let detail = |variant: fn(String) -> DBError| {
+            variant(self.detail.clone().unwrap_or_else(|| self.message.clone()))
+        };
+        let e = match self.code.as_str() {
+            "table_already_exists" => DBError::TableAlreadyExists,
+            "table_not_found" => DBError::TableDoesNotExist,
+            "record_not_found" => DBError::RecordDoesNotExist,
+            "record_already_exists" => DBError::RecordExist,
+            "dimension_mismatch" => DBError::DimensionMismatch,
+            "storage_error" => detail(DBError::Storage),
+            "invalid_input" => detail(DBError::InvalidInput),
+            "unauthenticated" => DBError::Unauthenticated,
+            "permission_denied" => detail(DBError::PermissionDenied),
+            "snapshot_not_found" => DBError::SnapshotDoesNotExist,
+            "internal_error" => detail(DBError::Internal),
+            "embedding_error" => detail(DBError::Embedding),
+            "read_only" => DBError::ReadOnly,
+            "changes_unavailable" => DBError::ChangesUnavailable,
+            _ => return None,
+        };
+        Some(e)
+    }
+}
+
+impl From<DBError> for ApiError {
+    fn from(e: DBError) -> Self {
+        let (status, code) = match &e {
+            DBError::TableAlreadyExists => (StatusCode::CONFLICT, "table_already_exists"),
+            DBError::TableDoesNotExist => (StatusCode::NOT_FOUND, "table_not_found"),
+            DBError::RecordDoesNotExist => (StatusCode::NOT_FOUND, "record_not_found"),
+            DBError::RecordExist => (StatusCode::CONFLICT, "record_already_exists"),
+            DBError::DimensionMismatch => (StatusCode::UNPROCESSABLE_ENTITY, "dimension_mismatch"),
+            DBError::Storage(_) => (StatusCode::INTERNAL_SERVER_ERROR, "storage_error"),
+            DBError::InvalidInput(_) => (StatusCode::BAD_REQUEST, "invalid_input"),
+            DBError::Unauthenticated => (StatusCode::UNAUTHORIZED, "unauthenticated"),
+            DBError::PermissionDenied(_) => (StatusCode::FORBIDDEN, "permission_denied"),
+            DBError::SnapshotDoesNotExist => (StatusCode::NOT_FOUND, "snapshot_not_found"),
+            DBError::Internal(_) => (StatusCode::INTERNAL_SERVER_ERROR, "internal_error"),
+            DBError::Embedding(_) => (StatusCode::BAD_GATEWAY, "embedding_error"),
+            DBError::ReadOnly => (StatusCode::FORBIDDEN, "read_only"),
+            DBError::ChangesUnavailable => (StatusCode::GONE, "changes_unavailable"),
+        };
+        let detail = match &e {
+            DBError::Storage(detail)
+            | DBError::InvalidInput(detail)
+            | DBError::PermissionDenied(detail)
+            | DBError::Internal(detail)
+            | DBError::Embedding(detail) => Some(detail.clone()),
+            _ => None,
+        };
+        Self {
+            detail,
+            ..Self::new(status, code, e.to_string())
+        }
+    }
+}
+
+/// For the extractors that never fail, like `()`.
+impl From<Infallible> for ApiError {
+    fn from(e: Infallible) -> Self {
+        match e {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_code_to_db_error() {
+        let errors = [
+            DBError::TableAlreadyExists,
+            DBError::RecordExist,
+            DBError::InvalidInput("alpha 2 is not within [0, 1]".to_string()),
+            DBError::PermissionDenied("reader has Read access".to_string()),
+            DBError::Storage("disk full".to_string()),
+            DBError::ReadOnly,
+        ];
+        for e in errors {
+            let message = e.to_string();
+            let back = ApiError::from(e).to_db_error().unwrap();
+            assert_eq!(message, back.to_string());
+        }
+        let e = ApiError::new(StatusCode::NOT_FOUND, "route_not_found", "No such route");
+        assert!(e.to_db_error().is_none());
+    }
+}
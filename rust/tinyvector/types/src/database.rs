@@ -0,0 +1,378 @@
+use crate::embedding::EmbeddingBinding;
+use crate::metadata::Metadata;
+use crate::similarity::Distance;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// Name queries use for the `embedding` of the records, next to the named vector fields.
+pub const MAIN_FIELD: &str = "embedding";
+
+#[derive(Debug, PartialEq, Eq, thiserror::Error, Serialize, Deserialize)]
+pub enum DBError {
+    /// The table already exists
+    #[error("The table already exists")]
+    TableAlreadyExists,
+    /// The table does not exist
+    #[error("The table does not exist")]
+    TableDoesNotExist,
+    /// The record does not exist
+    #[error("The record does not exist")]
+    RecordDoesNotExist,
+    /// The record does exist
+    #[error("The record does exist")]
+    RecordExist,
+    /// Dimension mismatch
+    #[error("Dimension mismatch")]
+    DimensionMismatch,
+    /// Failed to persist the change
+    #[error("Storage error: {0}")]
+    Storage(String),
+    /// Malformed input
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+    /// The request carries no known API key
+    #[error("Missing or unknown API key")]
+    Unauthenticated,
+    /// The API key lacks the scope or table access
+    #[error("Permission denied: {0}")]
+    PermissionDenied(String),
+    /// The snapshot does not exist
+    #[error("The snapshot does not exist")]
+    SnapshotDoesNotExist,
+    /// A bug, e.g. a panicking request
+    #[error("Internal error: {0}")]
+    Internal(String),
+    /// The embedding provider failed
+    #[error("Embedding error: {0}")]
+    Embedding(String),
+    /// Writes go to the leader while following it
+    #[error("The database is a read-only follower")]
+    ReadOnly,
+    /// The write-ahead log no longer holds the changes to resume from
+    #[error("The changes since this sequence are no longer kept, read the table again")]
+    ChangesUnavailable,
+}
+
+/// Index picked for a table at creation time.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IndexType {
+    /// Brute-force scan over every record.
+    #[default]
+    Flat,
+    /// HNSW graph, only used by queries with the same distance as the graph.
+    Hnsw(HnswConfig),
+}
+
+/// Where a table keeps its records, picked at creation time.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Storage {
+    /// Every record in memory, part of the snapshot.
+    #[default]
+    Memory,
+    /// A memtable flushed into immutable, memory-mapped segment files, scanned exactly.
+    Segments(SegmentConfig),
+}
+
+/// A named vector of the records, scanned exactly, the index and quantization only cover
+/// `embedding`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct VectorField {
+    pub dimension: usize,
+    /// Used by the queries on the field that do not name a distance. With inner product the
+    /// vectors are stored normalized, and only then queried with it.
+    pub distance: Distance,
+}
+
+/// One vector of a query over several fields.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct VectorQuery {
+    /// A named field, or `embedding` for the main vector.
+    #[serde(default = "main_field")]
+    pub field: String,
+    pub embedding: Vec<f32>,
+    /// Share of the field in the combined score.
+    #[serde(default = "default_weight")]
+    pub weight: f32,
+    /// Defaults to the distance of the field, or of the table for `embedding`.
+    #[serde(default)]
+    pub distance: Option<Distance>,
+}
+
+fn main_field() -> String {
+    MAIN_FIELD.to_string()
+}
+
+fn default_weight() -> f32 {
+    1.
+}
+
+/// Tunable parameters of a HNSW index.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "snake_case")]
+pub struct HnswConfig {
+    /// Max neighbours per node on the upper layers, layer 0 keeps `2 * m`.
+    pub m: usize,
+    /// Size of the dynamic candidate list while inserting.
+    pub ef_construction: usize,
+    /// Size of the dynamic candidate list while querying, raised to `top_k` if smaller.
+    pub ef_search: usize,
+    /// The metric the graph is built for, replaced by the distance of the table if it has one.
+    pub distance: Distance,
+}
+
+impl Default for HnswConfig {
+    fn default() -> Self {
+        Self {
+            m: 16,
+            ef_construction: 200,
+            ef_search: 64,
+            distance: Distance::Cosine,
+        }
+    }
+}
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuantizationKind {
+    /// One byte per dimension, linear between the trained min and max of the dimension.
+    Scalar,
+    /// One byte per subspace, the index of the closest of 256 trained centroids.
+    Product { subspaces: usize },
+}
+
+fn default_train_size() -> usize {
+    1000
+}
+
+fn default_rerank_factor() -> usize {
+    4
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct QuantizationConfig {
+    pub kind: QuantizationKind,
+    /// The quantizer is trained in the background once the table holds this many records,
+    /// queries run on the full-precision vectors until then.
+    #[serde(default = "default_train_size")]
+    pub train_size: usize,
+    /// Re-rank `top_k * rerank_factor` quantized candidates with the full-precision vectors.
+    #[serde(default = "default_rerank_factor")]
+    pub rerank_factor: usize,
+}
+
+
+fn default_memtable_size() -> usize {
+    10_000
+}
+
+fn default_max_segments() -> usize {
+    8
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct SegmentConfig {
+    /// Records kept in memory before they are flushed into a new segment.
+    #[serde(default = "default_memtable_size")]
+    pub memtable_size: usize,
+    /// Segments are merged in the background once a table holds more.
+    #[serde(default = "default_max_segments")]
+    pub max_segments: usize,
+}
+
+impl Default for SegmentConfig {
+    fn default() -> Self {
+        Self {
+            memtable_size: default_memtable_size(),
+            max_segments: default_max_segments(),
+        }
+    }
+}
+
+
+/// Settings picked for a table at creation time.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct TableOptions {
+    #[serde(default)]
+    pub index: IndexType,
+    /// Scan quantized vectors instead of the full-precision ones.
+    #[serde(default)]
+    pub quantization: Option<QuantizationConfig>,
+    #[serde(default)]
+    pub storage: Storage,
+    /// Records inserted without `expires_at` expire this many seconds later.
+    #[serde(default)]
+    pub ttl_secs: Option<u64>,
+    /// Metric of the queries that do not name one, see `default_distance`. With inner product
+    /// the embeddings are stored normalized, and only then queried with it.
+    #[serde(default)]
+    pub distance: Option<Distance>,
+}
+
+impl TableOptions {
+    /// The given distance, else the one of the HNSW config, else cosine.
+    pub fn default_distance(&self) -> Distance {
+        match (self.distance, self.index) {
+            (Some(distance), _) => distance,
+            (None, IndexType::Hnsw(config)) => config.distance,
+            (None, IndexType::Flat) => Distance::default(),
+        }
+    }
+}
+
+/// Approximate bytes held by a table.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct MemoryUsage {
+    pub records: usize,
+    /// Full-precision vectors.
+    pub vectors: usize,
+    /// Ids and metadata.
+    pub payload: usize,
+    /// Quantized codes and codebooks.
+    pub quantized: usize,
+    /// HNSW graph along with its copy of the vectors.
+    pub index: usize,
+    /// BM25 postings of the record text.
+    pub text_index: usize,
+    pub total: usize,
+    /// Segment files and the full-precision vectors of trained quantized tables, paged in by
+    /// the OS on demand and not part of `total`.
+    pub mapped: usize,
+}
+
+/// Summary of a table, cheap next to a dump of its records.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct TableStats {
+    pub count: usize,
+    pub dimension: usize,
+    pub index: IndexType,
+    pub quantization: Option<QuantizationConfig>,
+    pub storage: Storage,
+    pub ttl_secs: Option<u64>,
+    pub distance: Distance,
+    pub embedding: Option<EmbeddingBinding>,
+    pub fields: BTreeMap<String, VectorField>,
+    pub memory: MemoryUsage,
+}
+
+/// A page of records ordered by id.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct RecordPage {
+    pub records: Vec<EmbeddingRecord>,
+    /// Pass as `cursor` to get the next page, `None` on the last page.
+    pub next_cursor: Option<String>,
+}
+
+/// A table as `/db/get_table` dumps it, without the index and the quantizer of the server.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct TableContent {
+    pub dimension: usize,
+    pub records: Vec<EmbeddingRecord>,
+    #[serde(default)]
+    pub embedding: Option<EmbeddingBinding>,
+    #[serde(default)]
+    pub fields: BTreeMap<String, VectorField>,
+    #[serde(default)]
+    pub ttl_secs: Option<u64>,
+    #[serde(default)]
+    pub distance: Distance,
+}
+
+/// How the keyword and the vector ranking are merged.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Fusion {
+    /// Reciprocal rank fusion, every ranking adds `1 / (k + rank)`.
+    Rrf { k: f32 },
+    /// `alpha * vector + (1 - alpha) * keyword`, both min-max normalized over the candidates.
+    Weighted { alpha: f32 },
+}
+
+impl Default for Fusion {
+    fn default() -> Self {
+        Fusion::Rrf { k: 60. }
+    }
+}
+
+/// A hybrid query hit, along with the scores of the rankings it was found in.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct HybridRecord {
+    pub record: EmbeddingRecord,
+    /// Fused score, higher is better.
+    pub score: f32,
+    pub vector_score: Option<f32>,
+    pub keyword_score: Option<f32>,
+}
+
+/// A hit of a query over several fields.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct CombinedRecord {
+    pub record: EmbeddingRecord,
+    /// Weighted sum of the field rank keys, normalized to [0, 1] per field when there are
+    /// several, higher is better.
+    pub score: f32,
+    /// Score of each field, in the order of the queries.
+    pub scores: Vec<f32>,
+}
+
+/// A query hit along with the score of the query metric.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct ScoredRecord {
+    pub record: EmbeddingRecord,
+    /// Similarity for cosine and the products, distance for the other metrics.
+    pub score: f32,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct EmbeddingRecord {
+    /// Value of the embedding.
+    pub id: String,
+    /// The embedding, may be left out when the table embeds `text` itself.
+    #[serde(default)]
+    pub embedding: Vec<f32>,
+    /// Payload stored along with the embedding.
+    #[serde(default)]
+    pub metadata: Metadata,
+    /// Text indexed for keyword search.
+    #[serde(default)]
+    pub text: Option<String>,
+    /// Named vectors, by field of the table.
+    #[serde(default)]
+    pub vectors: HashMap<String, Vec<f32>>,
+    /// Unix time in seconds the record expires at, set from the table TTL when absent.
+    #[serde(default)]
+    pub expires_at: Option<u64>,
+}
+
+impl EmbeddingRecord {
+    pub fn is_expired(&self, now: u64) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+
+    /// Copy of the record without its embedding and named vectors.
+    pub fn without_vectors(&self) -> Self {
+        Self {
+            id: self.id.clone(),
+            embedding: Vec::new(),
+            metadata: self.metadata.clone(),
+            text: self.text.clone(),
+            vectors: HashMap::new(),
+            expires_at: self.expires_at,
+        }
+    }
+}
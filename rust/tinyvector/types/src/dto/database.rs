@@ -0,0 +1,271 @@
+use serde::{Deserialize, Serialize};
+use crate::{database::Fusion, similarity::Distance, database::{EmbeddingRecord, TableOptions}, metadata::Filter};
+use crate::database::{ScoredRecord, VectorField, VectorQuery};
+use crate::embedding::EmbeddingBinding;
+use crate::explain::QueryExplain;
+use crate::api::ApiError;
+use std::collections::BTreeMap;
+
+/// Format of a bulk import body.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportFormat {
+    /// One JSON `EmbeddingRecord` per line.
+    #[default]
+    Ndjson,
+    /// A 2-D little endian `f4` or `f8` numpy array, row `i` gets the id `{id_prefix}{i}`.
+    Npy,
+}
+
+/// Encoding of the change stream.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeFormat {
+    /// Server-sent events, the `id` of an event is its `seq`.
+    #[default]
+    Sse,
+    /// One JSON event per line.
+    Ndjson,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct CreateTableRequest {
+    pub table_name: String,
+    pub dimension: usize,
+    /// `index`, `quantization`, `storage`, `ttl_secs` and `distance` sit next to the other
+    /// fields.
+    #[serde(flatten)]
+    pub options: TableOptions,
+    /// Embed the text of records and queries sent without a vector with this model.
+    #[serde(default)]
+    pub embedding: Option<EmbeddingBinding>,
+    /// Named vectors the records may carry next to `embedding`.
+    #[serde(default)]
+    pub fields: BTreeMap<String, VectorField>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct BindEmbeddingRequest {
+    /// `null` unbinds the model.
+    pub embedding: Option<EmbeddingBinding>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct DropTableRequest {
+    pub table_name: String,
+}
+
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct InsertRecordRequest {
+    pub table_name: String,
+    pub record: EmbeddingRecord,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct InsertRecordsRequest {
+    pub table_name: String,
+    pub records: Vec<EmbeddingRecord>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct ImportTableRequest {
+    pub table_name: String,
+}
+
+fn default_import_batch_size() -> usize {
+    1000
+}
+
+/// Query of `/import/:table_name`, the records come as the request body.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct ImportRequest {
+    #[serde(default)]
+    pub format: ImportFormat,
+    /// Replace existing records instead of failing on them.
+    #[serde(default)]
+    pub upsert: bool,
+    /// Records written per write lock and log entry.
+    #[serde(default = "default_import_batch_size")]
+    pub batch_size: usize,
+    /// Id prefix of the npy rows.
+    #[serde(default)]
+    pub id_prefix: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct ImportResponse {
+    /// Records written, the batches before a failure stay in the table.
+    pub imported: usize,
+    /// Why the import stopped early, sent with `success: false` and the status of the error.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<ApiError>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct DeleteRecordRequest {
+    pub table_name: String,
+    pub id: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct QueryRecordRequest {
+    pub table_name: String,
+    /// May be left out for `query_text` on tables bound to an embedding model.
+    #[serde(default)]
+    pub query_embedding: Vec<f32>,
+    #[serde(default)]
+    pub query_text: Option<String>,
+    pub top_k: usize,
+    /// Defaults to the distance of the table.
+    #[serde(default)]
+    pub distance: Option<Distance>,
+    /// Bypass the approximate index and scan every record.
+    #[serde(default)]
+    pub exact: bool,
+    #[serde(default)]
+    pub ef_search: Option<usize>,
+    /// Only records whose metadata matches the filter take part in the top k.
+    #[serde(default)]
+    pub filter: Option<Filter>,
+    /// Drop hits scoring below this, for cosine and the products, rejected for the distances.
+    #[serde(default)]
+    pub min_score: Option<f32>,
+    /// Drop hits farther away than this, for the distances, rejected for the other metrics.
+    #[serde(default)]
+    pub max_distance: Option<f32>,
+    /// Re-rank the hits of a quantized table with the full-precision vectors.
+    #[serde(default)]
+    pub rerank: Option<bool>,
+    /// Report the index used, the candidates scored and the time of every step with the hits.
+    #[serde(default)]
+    pub explain: bool,
+}
+
+/// Hits of `query_record`, along with the report of the query when it asked for `explain`.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(untagged)]
+pub enum QueryRecordResponse {
+    Hits(Vec<ScoredRecord>),
+    Explained {
+        hits: Vec<ScoredRecord>,
+        explain: QueryExplain,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct QueryFieldsRequest {
+    pub table_name: String,
+    /// One vector per field, their weighted scores are summed up.
+    pub vectors: Vec<VectorQuery>,
+    pub top_k: usize,
+    /// Only applies to a single query on `embedding`.
+    #[serde(default)]
+    pub exact: bool,
+    #[serde(default)]
+    pub ef_search: Option<usize>,
+    #[serde(default)]
+    pub filter: Option<Filter>,
+    /// Drop hits whose combined score is below this.
+    #[serde(default)]
+    pub min_score: Option<f32>,
+    #[serde(default)]
+    pub rerank: Option<bool>,
+}
+
+fn default_hybrid_candidates() -> usize {
+    100
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct HybridQueryRequest {
+    pub table_name: String,
+    /// Embedded from `query_text` when left out, on tables bound to an embedding model.
+    #[serde(default)]
+    pub query_embedding: Vec<f32>,
+    /// Keywords matched against the record text with BM25.
+    pub query_text: String,
+    pub top_k: usize,
+    #[serde(default)]
+    pub distance: Option<Distance>,
+    #[serde(default)]
+    pub fusion: Fusion,
+    /// Hits taken from the vector and the keyword ranking before fusing them.
+    #[serde(default = "default_hybrid_candidates")]
+    pub candidates: usize,
+    #[serde(default)]
+    pub exact: bool,
+    #[serde(default)]
+    pub ef_search: Option<usize>,
+    #[serde(default)]
+    pub filter: Option<Filter>,
+    #[serde(default)]
+    pub min_score: Option<f32>,
+    #[serde(default)]
+    pub max_distance: Option<f32>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct QueryTableRequest {
+    pub table_name: String,
+}
+
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct TableRequest {
+    pub table_name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct GetRecordRequest {
+    pub table_name: String,
+    pub id: String,
+}
+
+fn default_page_size() -> usize {
+    100
+}
+
+fn default_include_embedding() -> bool {
+    true
+}
+
+/// Query of `/tables/:table_name/changes`.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct ChangesRequest {
+    /// Resume after this sequence number, the `Last-Event-ID` header of SSE clients works too.
+    /// Only the changes from now on when both are absent.
+    #[serde(default)]
+    pub from_seq: Option<u64>,
+    #[serde(default)]
+    pub format: ChangeFormat,
+}
+
+/// Query of `/tables/:table_name/records`.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct ListRecordsRequest {
+    /// `next_cursor` of the previous page, the first page when absent.
+    #[serde(default)]
+    pub cursor: Option<String>,
+    #[serde(default = "default_page_size")]
+    pub limit: usize,
+    #[serde(default = "default_include_embedding")]
+    pub include_embedding: bool,
+}
@@ -1,5 +1,7 @@
 mod test;
 mod database;
+mod system;
 
 pub use test::*;
 pub use database::*;
+pub use system::*;
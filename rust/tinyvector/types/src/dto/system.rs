@@ -0,0 +1,15 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct RestoreRequest {
+    /// Name of the snapshot as listed by `/system/snapshots`.
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct RestoreResponse {
+    /// Sequence number the database continues from.
+    pub seq: u64,
+}
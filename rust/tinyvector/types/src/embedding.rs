@@ -0,0 +1,16 @@
+use serde::{Deserialize, Serialize};
+
+/// Embedding model a table embeds the text of its records and queries with.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct EmbeddingBinding {
+    pub model: EmbeddingModel,
+}
+
+/// Models of the OpenAI compatible embedding API, named like the API does.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum EmbeddingModel {
+    #[serde(rename = "text-embedding-ada-002")]
+    #[default]
+    TextEmbeddingAda002,
+}
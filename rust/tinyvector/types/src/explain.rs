@@ -0,0 +1,64 @@
+use crate::similarity::Distance;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+/// How the top k of a query was found.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryPlan {
+    /// Every record scored with the full-precision vectors.
+    #[default]
+    Flat,
+    /// Every record scored with the quantized vectors.
+    Quantized,
+    /// Beam search through the HNSW graph.
+    Hnsw,
+    /// Beam search restricted to the records passing the metadata filter.
+    HnswFiltered,
+    /// The filter left few enough records to score them all instead of using the index.
+    ExactFiltered,
+}
+
+/// Time spent in one step of a query.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct Phase {
+    pub name: String,
+    pub micros: u64,
+}
+
+/// Report of a query run with `explain`, filled in along the search.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct QueryExplain {
+    pub plan: QueryPlan,
+    pub distance: Distance,
+    /// Vectors scored, the nodes visited on the bottom layer for the HNSW index.
+    pub candidates: usize,
+    /// Candidates of a quantized scan rescored with the full-precision vectors.
+    pub reranked: usize,
+    /// Records passing the metadata filter, only counted to pick the plan of an indexed table.
+    pub filtered: Option<usize>,
+    /// Segments scanned next to the memtable.
+    pub segments: usize,
+    /// Waiting for the table locks.
+    pub lock_wait_micros: u64,
+    /// Steps of the search in the order they ran.
+    pub phases: Vec<Phase>,
+    /// From the table lookup to the last hit.
+    pub total_micros: u64,
+}
+
+impl QueryExplain {
+    /// Record a step that started at `instant` and just finished.
+    pub fn phase(&mut self, name: &str, instant: Instant) {
+        self.phases.push(Phase {
+            name: name.to_string(),
+            micros: micros(instant.elapsed()),
+        });
+    }
+}
+
+pub fn micros(duration: Duration) -> u64 {
+    duration.as_micros().try_into().unwrap_or(u64::MAX)
+}
@@ -0,0 +1,11 @@
+//! Request and response types of the tinyvector API, shared by the server and its clients
+//! without pulling in the dependencies of the server.
+
+pub mod api;
+pub mod database;
+pub mod dto;
+pub mod embedding;
+pub mod explain;
+pub mod metadata;
+pub mod similarity;
+pub mod system;
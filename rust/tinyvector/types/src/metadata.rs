@@ -0,0 +1,271 @@
+use serde::de::{self, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Payload stored next to an embedding, e.g. source text, document id, tags or timestamps.
+pub type Metadata = HashMap<String, MetadataValue>;
+
+/// A single metadata value.
+///
+/// JSON uses the plain value (`true`, `42`, `"text"`, `["a", "b"]`), while binary formats
+/// like bincode, which can not guess the type, get an explicitly tagged enum.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MetadataValue {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+    List(Vec<MetadataValue>),
+}
+
+/// Tagged representation for the non human readable formats.
+#[derive(Serialize, Deserialize)]
+enum TaggedValue {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+    List(Vec<MetadataValue>),
+}
+
+impl Serialize for MetadataValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if !serializer.is_human_readable() {
+            let tagged = match self.clone() {
+                MetadataValue::Bool(v) => TaggedValue::Bool(v),
+                MetadataValue::Int(v) => TaggedValue::Int(v),
+                MetadataValue::Float(v) => TaggedValue::Float(v),
+                MetadataValue::String(v) => TaggedValue::String(v),
+                MetadataValue::List(v) => TaggedValue::List(v),
+            };
+            return tagged.serialize(serializer);
+        }
+
+        match self {
+            MetadataValue::Bool(v) => serializer.serialize_bool(*v),
+            MetadataValue::Int(v) => serializer.serialize_i64(*v),
+            MetadataValue::Float(v) => serializer.serialize_f64(*v),
+            MetadataValue::String(v) => serializer.serialize_str(v),
+            MetadataValue::List(v) => v.serialize(serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for MetadataValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if !deserializer.is_human_readable() {
+            let value = match TaggedValue::deserialize(deserializer)? {
+                TaggedValue::Bool(v) => MetadataValue::Bool(v),
+                TaggedValue::Int(v) => MetadataValue::Int(v),
+                TaggedValue::Float(v) => MetadataValue::Float(v),
+                TaggedValue::String(v) => MetadataValue::String(v),
+                TaggedValue::List(v) => MetadataValue::List(v),
+            };
+            return Ok(value);
+        }
+
+        deserializer.deserialize_any(MetadataValueVisitor)
+    }
+}
+
+struct MetadataValueVisitor;
+
+impl<'de> Visitor<'de> for MetadataValueVisitor {
+    type Value = MetadataValue;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a bool, number, string or list of them")
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<Self::Value, E> {
+        Ok(MetadataValue::Bool(v))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+        Ok(MetadataValue::Int(v))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+        Ok(i64::try_from(v)
+            .map(MetadataValue::Int)
+            .unwrap_or(MetadataValue::Float(v as f64)))
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Self::Value, E> {
+        Ok(MetadataValue::Float(v))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        Ok(MetadataValue::String(v.to_string()))
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<Self::Value, E> {
+        Ok(MetadataValue::String(v))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut values = Vec::new();
+        while let Some(value) = seq.next_element()? {
+            values.push(value);
+        }
+        Ok(MetadataValue::List(values))
+    }
+}
+
+impl MetadataValue {
+    fn as_f64(&self) -> Option<f64> {
+        match self {
+            MetadataValue::Int(v) => Some(*v as f64),
+            MetadataValue::Float(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    /// Equality where ints and floats compare by numeric value.
+    fn matches(&self, other: &MetadataValue) -> bool {
+        match (self.as_f64(), other.as_f64()) {
+            (Some(a), Some(b)) => a == b,
+            _ => self == other,
+        }
+    }
+
+    /// A scalar is a list of one, so `eq` and `in` also work on tag lists.
+    fn elements(&self) -> &[MetadataValue] {
+        match self {
+            MetadataValue::List(values) => values,
+            value => std::slice::from_ref(value),
+        }
+    }
+}
+
+/// Filter expression over the record metadata.
+///
+/// ```json
+/// {"and": [
+///     {"eq": {"field": "doc_id", "value": "a1"}},
+///     {"range": {"field": "timestamp", "gte": 1700000000}},
+///     {"in": {"field": "tags", "values": ["rust", "db"]}}
+/// ]}
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Filter {
+    /// The field equals `value`, or contains it when the field is a list.
+    Eq { field: String, value: MetadataValue },
+    /// The numeric field lies within the given bounds.
+    Range {
+        field: String,
+        #[serde(default)]
+        gt: Option<f64>,
+        #[serde(default)]
+        gte: Option<f64>,
+        #[serde(default)]
+        lt: Option<f64>,
+        #[serde(default)]
+        lte: Option<f64>,
+    },
+    /// The field equals any of `values`, or shares one with them when the field is a list.
+    In {
+        field: String,
+        values: Vec<MetadataValue>,
+    },
+    And(Vec<Filter>),
+    Or(Vec<Filter>),
+    Not(Box<Filter>),
+}
+
+impl Filter {
+    pub fn matches(&self, metadata: &Metadata) -> bool {
+        match self {
+            Filter::Eq { field, value } => metadata
+                .get(field)
+                .is_some_and(|v| v.elements().iter().any(|e| e.matches(value))),
+            Filter::Range {
+                field,
+                gt,
+                gte,
+                lt,
+                lte,
+            } => {
+                let Some(v) = metadata.get(field).and_then(MetadataValue::as_f64) else {
+                    return false;
+                };
+                let check = |bound: &Option<f64>, ok: &[Ordering]| {
+                    bound.is_none_or(|b| v.partial_cmp(&b).is_some_and(|o| ok.contains(&o)))
+                };
+                check(gt, &[Ordering::Greater])
+                    && check(gte, &[Ordering::Greater, Ordering::Equal])
+                    && check(lt, &[Ordering::Less])
+                    && check(lte, &[Ordering::Less, Ordering::Equal])
+            }
+            Filter::In { field, values } => metadata.get(field).is_some_and(|v| {
+                v.elements()
+                    .iter()
+                    .any(|e| values.iter().any(|value| e.matches(value)))
+            }),
+            Filter::And(filters) => filters.iter().all(|f| f.matches(metadata)),
+            Filter::Or(filters) => filters.iter().any(|f| f.matches(metadata)),
+            Filter::Not(filter) => !filter.matches(metadata),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata() -> Metadata {
+        serde_json::from_str(
+            r#"{"doc_id": "a1", "timestamp": 1700000000, "score": 0.5, "tags": ["rust", "db"], "public": true}"#,
+        )
+        .unwrap()
+    }
+
+    fn filter(json: &str) -> Filter {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn test_metadata_json_and_bincode() {
+        let metadata = metadata();
+        assert_eq!(Some(&MetadataValue::Int(1700000000)), metadata.get("timestamp"));
+        assert_eq!(Some(&MetadataValue::Float(0.5)), metadata.get("score"));
+
+        let data = bincode::serialize(&metadata).unwrap();
+        assert_eq!(metadata, bincode::deserialize::<Metadata>(&data).unwrap());
+
+        let json = serde_json::to_string(&metadata).unwrap();
+        assert_eq!(metadata, serde_json::from_str::<Metadata>(&json).unwrap());
+    }
+
+    #[test]
+    fn test_filter_matches() {
+        let metadata = metadata();
+        let cases = [
+            (r#"{"eq": {"field": "doc_id", "value": "a1"}}"#, true),
+            (r#"{"eq": {"field": "doc_id", "value": "a2"}}"#, false),
+            (r#"{"eq": {"field": "tags", "value": "db"}}"#, true),
+            (r#"{"eq": {"field": "score", "value": 0.5}}"#, true),
+            (r#"{"eq": {"field": "missing", "value": 1}}"#, false),
+            (r#"{"range": {"field": "timestamp", "gte": 1700000000, "lt": 1800000000}}"#, true),
+            (r#"{"range": {"field": "timestamp", "gt": 1700000000}}"#, false),
+            (r#"{"range": {"field": "doc_id", "gt": 0}}"#, false),
+            (r#"{"in": {"field": "tags", "values": ["go", "rust"]}}"#, true),
+            (r#"{"in": {"field": "doc_id", "values": ["b1", "c1"]}}"#, false),
+            (
+                r#"{"and": [{"eq": {"field": "public", "value": true}}, {"in": {"field": "doc_id", "values": ["a1"]}}]}"#,
+                true,
+            ),
+            (
+                r#"{"or": [{"eq": {"field": "public", "value": false}}, {"eq": {"field": "doc_id", "value": "b1"}}]}"#,
+                false,
+            ),
+            (r#"{"not": {"eq": {"field": "doc_id", "value": "b1"}}}"#, true),
+        ];
+        for (json, expected) in cases {
+            assert_eq!(expected, filter(json).matches(&metadata), "{}", json);
+        }
+    }
+}
@@ -0,0 +1,40 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Copy, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Distance {
+    Euclidean,
+    #[default]
+    Cosine,
+    DotProduct,
+    /// Sum of the absolute differences.
+    Manhattan,
+    /// Number of differing bits of binary vectors, any non-zero component is a set bit.
+    Hamming,
+    /// One minus the intersection over the union of the set bits, like `Hamming`.
+    Jaccard,
+    /// Dot product of unit vectors, the query and the records of a table using it by default
+    /// are normalized.
+    InnerProduct,
+}
+
+impl Distance {
+    /// Whether a larger score means a closer match, false for the distances.
+    pub fn higher_is_better(self) -> bool {
+        matches!(self, Distance::Cosine | Distance::DotProduct | Distance::InnerProduct)
+    }
+
+    /// Flip the score so that larger is always closer, applying it twice gives the score back.
+    pub fn rank_key(self, score: f32) -> f32 {
+        if self.higher_is_better() {
+            score
+        } else {
+            -score
+        }
+    }
+
+    /// Whether vectors are normalized before they are scored with this metric.
+    pub fn normalizes(self) -> bool {
+        matches!(self, Distance::InnerProduct)
+    }
+}
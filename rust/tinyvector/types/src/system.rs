@@ -0,0 +1,40 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    Leader,
+    Follower,
+}
+
+/// Body of `/system/replication`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct ReplicationStatus {
+    pub role: Role,
+    /// Sequence number of the last applied mutation.
+    pub seq: u64,
+    /// Followers streaming from this server.
+    pub followers: usize,
+    /// The remaining fields are only set on followers.
+    pub leader: Option<String>,
+    pub connected: Option<bool>,
+    /// Last sequence number the leader reported.
+    pub leader_seq: Option<u64>,
+    /// Mutations of the leader not applied yet.
+    pub lag: Option<u64>,
+    /// Seconds since the follower was last caught up with the leader.
+    pub lag_secs: Option<f64>,
+}
+
+/// A snapshot file of `Snapshots`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct SnapshotInfo {
+    pub name: String,
+    /// Unix time the snapshot was taken at.
+    pub created_at: u64,
+    /// Sequence number of the last mutation it holds.
+    pub seq: u64,
+    pub size: u64,
+}